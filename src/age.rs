@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error as StdError;
+use std::path::Path;
+
+use serde::Serialize;
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+
+use crate::Summary;
+use crate::diagnostics::Diagnostics;
+use crate::git;
+use crate::model::FileModel;
+
+/// Line coverage of code written recently versus older code, going by
+/// `git blame`, so new code can be held to a standard legacy code doesn't
+/// meet yet.
+#[derive(Debug, Serialize)]
+pub struct CommitAge {
+    /// Lines authored this many days ago or less count as recent.
+    pub days: u32,
+    pub recent: Summary,
+    pub older: Summary,
+    /// Files with recent lines.
+    pub files: Vec<FileAge>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileAge {
+    pub name: String,
+    pub recent: Summary,
+    pub older: Summary,
+}
+
+/// Upper bounds (inclusive, in days) of the file age buckets, the last one
+/// taking everything older.
+const AGE_BUCKETS: [(i64, &str); 4] = [
+    (7, "This week"),
+    (30, "This month"),
+    (90, "This quarter"),
+    (i64::MAX, "Older"),
+];
+
+/// Line coverage of files by when they were last changed, going by
+/// `git log`, to see whether it's only new code that's covered.
+#[derive(Debug, Serialize)]
+pub struct FileAgeCoverage {
+    pub buckets: Vec<AgeBucket>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AgeBucket {
+    pub label: String,
+    /// Files last changed in this bucket.
+    pub files: usize,
+    pub lines: Summary,
+}
+
+#[derive(Default)]
+struct Counts {
+    recent: (u64, u64),
+    older: (u64, u64),
+}
+
+impl Counts {
+    fn add(&mut self, other: &Counts) {
+        self.recent.0 += other.recent.0;
+        self.recent.1 += other.recent.1;
+        self.older.0 += other.older.0;
+        self.older.1 += other.older.1;
+    }
+}
+
+/// Blames every file in `repo` (the source directory) and splits its
+/// instrumented lines by age.  Files git doesn't know about are skipped;
+/// uncommitted changes count as recent.
+pub fn commit_age(
+    files: &[FileModel],
+    repo: &Path,
+    days: u32,
+    now: i64,
+    diagnostics: &Diagnostics,
+) -> Result<CommitAge, Box<dyn StdError>> {
+    let cutoff = now - i64::from(days) * 24 * 60 * 60;
+
+    let mut totals = Counts::default();
+    let mut file_ages = vec![];
+    for file in files.iter() {
+        let times = match git::blame_times(repo, &file.filename) {
+            Ok(times) => times,
+            Err(e) => {
+                diagnostics.warn(Some(&file.name), format!("Left out of the commit age report: {}", e));
+                continue;
+            }
+        };
+
+        let mut counts = Counts::default();
+        for line in file.lines.iter().filter(|l| l.mapped) {
+            let time = match usize::try_from(line.line - 1).ok().and_then(|idx| times.get(idx)) {
+                Some(time) => *time,
+                None => continue,
+            };
+            let bucket = match time >= cutoff {
+                true => &mut counts.recent,
+                false => &mut counts.older,
+            };
+            bucket.0 += 1;
+            bucket.1 += (line.count > 0) as u64;
+        }
+
+        totals.add(&counts);
+        if counts.recent.0 > 0 {
+            file_ages.push(FileAge {
+                name: file.name.clone(),
+                recent: Summary::new(counts.recent.0, counts.recent.1),
+                older: Summary::new(counts.older.0, counts.older.1),
+            });
+        }
+    }
+
+    Ok(CommitAge {
+        days,
+        recent: Summary::new(totals.recent.0, totals.recent.1),
+        older: Summary::new(totals.older.0, totals.older.1),
+        files: file_ages,
+    })
+}
+
+/// Buckets every file in `repo` (the source directory) by when it was last
+/// committed to.  Files git doesn't know about are skipped; files with
+/// uncommitted changes count as changed now.
+pub fn file_age(
+    files: &[FileModel],
+    repo: &Path,
+    now: i64,
+    diagnostics: &Diagnostics,
+) -> Result<FileAgeCoverage, Box<dyn StdError>> {
+    let mut modified = git::last_modified(repo)?;
+    for changed in git::changed_files(repo, "HEAD")? {
+        modified.insert(changed, now);
+    }
+
+    // git names files relative to the repository, the export may not
+    let root = repo.canonicalize()?;
+    let modified = files
+        .iter()
+        .filter_map(|file| {
+            let path = Path::new(&file.filename);
+            let relative = match path.is_absolute() {
+                true => path.strip_prefix(&root).ok()?.to_str()?,
+                false => file.filename.as_str(),
+            };
+            match modified.get(relative) {
+                Some(time) => Some((file.filename.clone(), *time)),
+                None => {
+                    diagnostics.warn(Some(&file.name), "Left out of the file age statistics: not committed to git".to_string());
+                    None
+                }
+            }
+        })
+        .collect();
+
+    Ok(bucket_files(files, &modified, now))
+}
+
+/// Sums up the line coverage of `files` by how long ago they were last
+/// changed, given as unix timestamps by their name in the export.  Files
+/// without a time are left out.
+pub fn bucket_files(files: &[FileModel], modified: &HashMap<String, i64>, now: i64) -> FileAgeCoverage {
+    let mut counts = vec![(0, 0, 0); AGE_BUCKETS.len()];
+    for file in files.iter() {
+        let time = match modified.get(&file.filename) {
+            Some(time) => *time,
+            None => continue,
+        };
+        let days = (now - time).max(0) / (24 * 60 * 60);
+        let idx = AGE_BUCKETS.iter().position(|(max, _)| days <= *max).unwrap_or(AGE_BUCKETS.len() - 1);
+        counts[idx].0 += 1;
+        counts[idx].1 += file.summary.lines.count;
+        counts[idx].2 += file.summary.lines.covered;
+    }
+
+    FileAgeCoverage {
+        buckets: AGE_BUCKETS
+            .iter()
+            .zip(counts)
+            .map(|((_, label), (files, count, covered))| AgeBucket {
+                label: label.to_string(),
+                files,
+                lines: Summary::new(count, covered),
+            })
+            .collect(),
+    }
+}
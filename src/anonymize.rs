@@ -0,0 +1,72 @@
+use std::borrow::Cow;
+
+use sha2::{Digest, Sha256};
+
+const DEFAULT_SALT: &str = "cosmoline";
+
+/// Consistently replaces file paths, symbol names, and source text with
+/// salted hashes so that the shape of the coverage data can be shared without
+/// revealing the code itself.  The same salt always produces the same names,
+/// so reports from different runs can still be compared.
+pub struct Anonymizer {
+    salt: Option<String>,
+}
+
+impl Anonymizer {
+    pub fn new(enabled: bool, salt: Option<&str>) -> Self {
+        Self {
+            salt: match enabled {
+                true => Some(salt.unwrap_or(DEFAULT_SALT).to_string()),
+                false => None,
+            },
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.salt.is_some()
+    }
+
+    /// Hashes every component of a path while keeping the separators and any
+    /// file extension intact.
+    pub fn path<'a>(&self, path: &'a str) -> Cow<'a, str> {
+        if !self.is_enabled() {
+            return Cow::Borrowed(path);
+        }
+
+        let components = path
+            .split('/')
+            .map(|component| match component.rsplit_once('.') {
+                _ if component.is_empty() => String::new(),
+                Some((stem, extension)) if !stem.is_empty() => format!("{}.{}", self.hash(stem), extension),
+                _ => self.hash(component),
+            })
+            .collect::<Vec<_>>();
+
+        Cow::Owned(components.join("/"))
+    }
+
+    /// Hashes a symbol name, demangled or not.
+    pub fn symbol<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        match self.is_enabled() {
+            true => Cow::Owned(format!("fn_{}", self.hash(name))),
+            false => Cow::Borrowed(name),
+        }
+    }
+
+    /// Masks the contents of a source line.  Whitespace is kept and every
+    /// other character is replaced one for one so coverage columns still line up.
+    pub fn source_line(&self, line: String) -> String {
+        match self.is_enabled() {
+            true => line.chars().map(|c| if c.is_whitespace() { c } else { 'x' }).collect(),
+            false => line,
+        }
+    }
+
+    fn hash(&self, input: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.salt.as_deref().unwrap_or_default().as_bytes());
+        hasher.update([0u8]);
+        hasher.update(input.as_bytes());
+        hasher.finalize()[..6].iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
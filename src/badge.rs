@@ -0,0 +1,93 @@
+//! Shields.io style "flat" badges rendered as standalone SVG, or described
+//! in the JSON of shields.io's endpoint badges for it to render.
+
+use crate::utils::{color_for_percent, xml_escape};
+
+const CHAR_WIDTH: f64 = 6.5;
+const PADDING: f64 = 10.0;
+
+pub const GREEN: &str = "#4c1";
+pub const YELLOW: &str = "#dfb317";
+pub const RED: &str = "#e05d44";
+pub const GREY: &str = "#9f9f9f";
+
+/// Rough width of `text` in 11px Verdana, which is what shields.io uses.
+fn text_width(text: &str) -> f64 {
+    (text.chars().count() as f64 * CHAR_WIDTH + PADDING).round()
+}
+
+pub fn render(label: &str, message: &str, color: &str) -> String {
+    let label_width = text_width(label);
+    let message_width = text_width(message);
+    let width = label_width + message_width;
+    let (label, message) = (xml_escape(label), xml_escape(message));
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">
+  <title>{label}: {message}</title>
+  <linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>
+  <clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/>
+    <rect width="{width}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text>
+    <text x="{label_x}" y="14">{label}</text>
+    <text x="{message_x}" y="15" fill="#010101" fill-opacity=".3">{message}</text>
+    <text x="{message_x}" y="14">{message}</text>
+  </g>
+</svg>
+"##,
+        width = width,
+        label_width = label_width,
+        message_width = message_width,
+        color = color,
+        label = label,
+        message = message,
+        label_x = label_width / 2.0,
+        message_x = label_width + message_width / 2.0,
+    )
+}
+
+/// Badge showing the change in coverage versus a baseline, e.g. "+0.4%".
+pub fn delta(label: &str, delta: f64) -> String {
+    // Avoid rendering "-0.0%" for tiny regressions
+    let rounded = (delta * 10.0).round() / 10.0;
+
+    let (message, color) = match rounded {
+        d if d > 0.0 => (format!("+{:.1}%", d), GREEN),
+        d if d < 0.0 => (format!("{:.1}%", d), RED),
+        _ => ("±0.0%".to_string(), GREY),
+    };
+
+    render(label, &message, color)
+}
+
+/// Badge showing a coverage percentage, e.g. "87.5%", colored like the
+/// percentages in the report.
+pub fn coverage(label: &str, percent: f64) -> String {
+    render(label, &format!("{:.1}%", percent), coverage_color(percent))
+}
+
+/// The same badge as [`coverage`] in shields.io's
+/// [endpoint](https://shields.io/badges/endpoint-badge) schema, for a README
+/// badge pointing at a hosted copy of it.
+pub fn endpoint(label: &str, percent: f64) -> String {
+    let endpoint = serde_json::json!({
+        "schemaVersion": 1,
+        "label": label,
+        "message": format!("{:.1}%", percent),
+        "color": coverage_color(percent).trim_start_matches('#'),
+    });
+    format!("{:#}\n", endpoint)
+}
+
+fn coverage_color(percent: f64) -> &'static str {
+    match color_for_percent(percent) {
+        "green" => GREEN,
+        "yellow" => YELLOW,
+        _ => RED,
+    }
+}
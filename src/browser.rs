@@ -0,0 +1,30 @@
+//! Opening the report in a browser, for `--open`, the way `cargo doc --open`
+//! does: `$BROWSER` if it's set, otherwise whatever the platform opens files
+//! with.
+
+use std::ffi::OsString;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// The command opening `path`, with `browser` (from `$BROWSER`) if given.
+pub fn command(browser: Option<OsString>, path: &Path) -> Command {
+    let mut command = match browser.filter(|browser| !browser.is_empty()) {
+        Some(browser) => Command::new(browser),
+        None if cfg!(target_os = "macos") => Command::new("open"),
+        None if cfg!(windows) => {
+            // The empty argument is the window title `start` expects first
+            let mut command = Command::new("cmd");
+            command.args(["/C", "start", ""]);
+            command
+        }
+        None => Command::new("xdg-open"),
+    };
+    command.arg(path);
+    command
+}
+
+/// Opens `path` in the browser without waiting for it to be closed.
+pub fn open(path: &Path) -> io::Result<()> {
+    command(std::env::var_os("BROWSER"), path).spawn().map(|_| ())
+}
@@ -0,0 +1,81 @@
+//! `.cosmoline-cache.json`: what each file page of an HTML report was
+//! rendered from, so that rerunning on the same output directory (e.g. from
+//! a watch loop) only renders the pages of files whose source or coverage
+//! changed.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::fs::FileLimiter;
+use crate::manifest;
+use crate::model::{FileModel, ReportModel};
+
+pub const FILENAME: &str = ".cosmoline-cache.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cache {
+    /// Version of cosmoline and whatever else every page depends on, e.g.
+    /// the options it was run with.  Pages cached under other settings are
+    /// rendered again.
+    pub settings: String,
+    /// By page, relative to the output directory.
+    pub pages: BTreeMap<String, CachedPage>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedPage {
+    /// What the page was rendered from, see [`key`].
+    pub key: String,
+    /// Size and SHA-256 of the page as written, for the manifest.
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+impl Cache {
+    pub fn new(settings: &str) -> Self {
+        Self {
+            settings: format!("{} {}", env!("CARGO_PKG_VERSION"), settings),
+            pages: BTreeMap::new(),
+        }
+    }
+
+    /// The cache in `output_path`, or an empty one if there's none, it
+    /// can't be read, or it was written under other settings.
+    pub fn load(output_path: &Path, settings: &str, files: &FileLimiter) -> Self {
+        let empty = Self::new(settings);
+        let cache = files
+            .read(&output_path.join(FILENAME))
+            .ok()
+            .and_then(|contents| serde_json::from_slice::<Self>(&contents).ok());
+        match cache {
+            Some(cache) if cache.settings == empty.settings => cache,
+            _ => empty,
+        }
+    }
+
+    /// The cached page if it was rendered from `key` and is still there.
+    pub fn get(&self, output_path: &Path, page: &str, key: &str) -> Option<&CachedPage> {
+        self.pages
+            .get(page)
+            .filter(|cached| cached.key == key && output_path.join(page).is_file())
+    }
+
+    pub fn save(&self, output_path: &Path, files: &FileLimiter) -> Result<(), Box<dyn std::error::Error>> {
+        files.write(&output_path.join(FILENAME), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// SHA-256 of `source` and the coverage segments of `file`, along with the
+/// rest of the model its page shows.
+pub fn key(model: &ReportModel, file: &FileModel, source: &[u8]) -> String {
+    let mut contents = source.to_vec();
+    contents.push(0);
+    contents.extend(format!(
+        "{:?}\n{:?}\n{:?}\n{:?}\n{:?}",
+        model.metadata.package, file.name, file.summary, file.segments, file.branches,
+    ).into_bytes());
+    manifest::sha256(contents)
+}
@@ -0,0 +1,74 @@
+//! What this build of cosmoline supports, for wrappers that would rather
+//! feature-detect than pin an exact version.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{diff, export, input, manifest};
+
+#[derive(Debug, Serialize)]
+pub struct Capabilities {
+    pub version: &'static str,
+    /// Accepted by `--input-format`.
+    pub input_formats: Vec<String>,
+    /// Accepted by `--output-format`.
+    pub output_formats: Vec<String>,
+    /// Accepted by `--template-engine`.
+    pub template_engines: Vec<&'static str>,
+    /// Templates that can be replaced with `--template-dir`, and whose
+    /// context schema `template-schema` prints.
+    pub templates: Vec<&'static str>,
+    /// Version of the layout of each JSON file cosmoline writes (and of
+    /// `diff`'s JSON output), bumped whenever it changes incompatibly.
+    pub schemas: BTreeMap<&'static str, u32>,
+}
+
+pub fn capabilities() -> Capabilities {
+    let mut output_formats = export::Registry::builtin().names().map(str::to_string).collect::<Vec<_>>();
+    // These need settings from the command line, so they aren't built in
+    output_formats.push("text".to_string());
+    output_formats.push("reachability".to_string());
+    output_formats.push("unique".to_string());
+    if cfg!(feature = "render-html") {
+        output_formats.push("html".to_string());
+    }
+    output_formats.sort();
+
+    let mut template_engines = vec![];
+    if cfg!(feature = "render-html") {
+        template_engines.push("handlebars");
+    }
+    if cfg!(feature = "minijinja") {
+        template_engines.push("minijinja");
+    }
+
+    let mut schemas = BTreeMap::new();
+    schemas.insert("diff", diff::SCHEMA_VERSION);
+    schemas.insert(export::JSON_FILENAME, export::JSON_SCHEMA_VERSION);
+    schemas.insert(export::JSON_SUMMARY_FILENAME, export::JSON_SUMMARY_SCHEMA_VERSION);
+    schemas.insert(export::REACHABILITY_FILENAME, export::REACHABILITY_SCHEMA_VERSION);
+    schemas.insert(export::UNIQUE_FILENAME, export::UNIQUE_SCHEMA_VERSION);
+    #[cfg(feature = "render-html")]
+    schemas.insert(crate::history::HISTORY_FILENAME, crate::history::SCHEMA_VERSION);
+    schemas.insert(manifest::FILENAME, manifest::SCHEMA_VERSION);
+
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        input_formats: input::Registry::builtin().names().map(str::to_string).collect(),
+        output_formats,
+        template_engines,
+        templates: templates(),
+        schemas,
+    }
+}
+
+#[cfg(feature = "render-html")]
+fn templates() -> Vec<&'static str> {
+    crate::render::template_schemas().keys().copied().collect()
+}
+
+#[cfg(not(feature = "render-html"))]
+fn templates() -> Vec<&'static str> {
+    vec![]
+}
@@ -0,0 +1,181 @@
+//! Changed lines that no test ran, for annotating pull requests.
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::Serialize;
+
+use crate::Summary;
+use crate::model::ReportModel;
+
+/// Line coverage of just the lines added or changed in a git range, which is
+/// what a reviewer looks at.  Only populated with `--diff-base`.
+#[derive(Debug, Serialize)]
+pub struct DiffCoverage {
+    /// As given to `git diff`.
+    pub range: String,
+    /// Changed lines with code on them, and how many of those ran.
+    pub summary: Summary,
+    /// Reported files with changed code on them, in the order of the report.
+    pub files: Vec<DiffFile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffFile {
+    /// Path as it appears in the export, which is what review tools expect.
+    #[serde(skip)]
+    pub filename: String,
+    /// Shown name.
+    pub name: String,
+    pub page: String,
+    pub summary: Summary,
+    /// First and last line of each run of consecutive changed lines that
+    /// never ran.
+    pub uncovered: Vec<(i64, i64)>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct UncoveredChange {
+    /// Path relative to the source directory.
+    pub file: String,
+    pub line_start: i64,
+    pub line_end: i64,
+}
+
+/// Runs of consecutive changed lines of reported files that contain code
+/// which never ran.  `changed` is keyed by path relative to the source
+/// directory, e.g. from [`crate::git::changed_lines`].
+pub fn uncovered(model: &ReportModel, changed: &HashMap<String, BTreeSet<i64>>) -> Vec<UncoveredChange> {
+    let mut found: Vec<UncoveredChange> = vec![];
+    for file in model.all_files() {
+        let lines = match changed.get(&file.filename) {
+            Some(lines) => lines,
+            None => continue,
+        };
+
+        let uncovered = file.lines.iter().filter(|l| l.mapped && l.count == 0 && lines.contains(&l.line));
+        for line in uncovered {
+            match found.last_mut() {
+                Some(last) if last.file == file.filename && last.line_end + 1 == line.line => last.line_end = line.line,
+                _ => found.push(UncoveredChange {
+                    file: file.filename.clone(),
+                    line_start: line.line,
+                    line_end: line.line,
+                }),
+            }
+        }
+    }
+    found
+}
+
+/// Line coverage of the changed lines of every reported file.  `changed` is
+/// keyed by path relative to the source directory, as for [`uncovered`].
+pub fn diff_coverage(model: &ReportModel, range: &str, changed: &HashMap<String, BTreeSet<i64>>) -> DiffCoverage {
+    let mut files = vec![];
+    let (mut count, mut covered) = (0, 0);
+    for file in model.all_files() {
+        let lines = match changed.get(&file.filename) {
+            Some(lines) => lines,
+            None => continue,
+        };
+
+        let mut file_count = 0;
+        let mut file_covered = 0;
+        let mut uncovered: Vec<(i64, i64)> = vec![];
+        for line in file.lines.iter().filter(|l| l.mapped && lines.contains(&l.line)) {
+            file_count += 1;
+            if line.count > 0 {
+                file_covered += 1;
+                continue;
+            }
+            match uncovered.last_mut() {
+                Some((_, end)) if *end + 1 == line.line => *end = line.line,
+                _ => uncovered.push((line.line, line.line)),
+            }
+        }
+        if file_count == 0 {
+            continue;
+        }
+
+        count += file_count;
+        covered += file_covered;
+        files.push(DiffFile {
+            filename: file.filename.clone(),
+            name: file.name.clone(),
+            page: file.page.clone(),
+            summary: Summary::new(file_count, file_covered),
+            uncovered,
+        });
+    }
+
+    DiffCoverage { range: range.to_string(), summary: Summary::new(count, covered), files }
+}
+
+#[derive(Serialize)]
+struct DiagnosticResult<'a> {
+    source: Source,
+    severity: &'a str,
+    diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Serialize)]
+struct Source {
+    name: &'static str,
+    url: &'static str,
+}
+
+#[derive(Serialize)]
+struct Diagnostic {
+    message: String,
+    location: Location,
+    code: Code,
+}
+
+#[derive(Serialize)]
+struct Location {
+    path: String,
+    range: Range,
+}
+
+#[derive(Serialize)]
+struct Range {
+    start: Position,
+    end: Position,
+}
+
+#[derive(Serialize)]
+struct Position {
+    line: i64,
+}
+
+#[derive(Serialize)]
+struct Code {
+    value: &'static str,
+}
+
+/// The changes in reviewdog's diagnostic format (`reviewdog -f=rdjson`),
+/// one warning per run of lines.  `path_prefix` is put in front of each path
+/// to make it relative to the repository root, see [`crate::git::show_prefix`].
+pub fn rdjson(changes: &[UncoveredChange], path_prefix: &str) -> String {
+    let result = DiagnosticResult {
+        source: Source { name: env!("CARGO_PKG_NAME"), url: env!("CARGO_PKG_REPOSITORY") },
+        severity: "WARNING",
+        diagnostics: changes
+            .iter()
+            .map(|change| Diagnostic {
+                message: match change.line_start == change.line_end {
+                    true => "Changed line not covered by tests".to_string(),
+                    false => format!("Changed lines {}-{} not covered by tests", change.line_start, change.line_end),
+                },
+                location: Location {
+                    path: format!("{}{}", path_prefix, change.file),
+                    range: Range {
+                        start: Position { line: change.line_start },
+                        end: Position { line: change.line_end },
+                    },
+                },
+                code: Code { value: "uncovered" },
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&result).unwrap()
+}
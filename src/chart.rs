@@ -0,0 +1,220 @@
+//! Inline SVG charts, styled by `style.css`, so reports don't need a charting
+//! library and keep working offline.
+
+use std::f64::consts::PI;
+
+use crate::utils;
+
+/// A series of percentages as a tiny line chart.  Returns `None` when there
+/// aren't enough points to draw a line.
+pub fn sparkline(values: &[f64]) -> Option<String> {
+    const WIDTH: f64 = 60.0;
+    const HEIGHT: f64 = 14.0;
+
+    if values.len() < 2 {
+        return None;
+    }
+
+    let step = WIDTH / (values.len() - 1) as f64;
+    let points = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let v = if v.is_nan() { 0.0 } else { v.clamp(0.0, 100.0) };
+            format!("{:.1},{:.1}", i as f64 * step, HEIGHT - (v / 100.0 * HEIGHT))
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let trend = match values[values.len() - 1] - values[0] {
+        d if d < 0.0 => "red",
+        d if d > 0.0 => "green",
+        _ => "blue",
+    };
+
+    Some(format!(
+        r#"<svg class="sparkline {}" width="{}" height="{}" viewBox="0 0 {} {}"><polyline points="{}"/></svg>"#,
+        trend, WIDTH, HEIGHT, WIDTH, HEIGHT, points
+    ))
+}
+
+/// Labeled values as bars, each with its value above and its label below.
+pub fn bars(bars: &[(&str, u64)]) -> String {
+    const BAR_WIDTH: f64 = 48.0;
+    const HEIGHT: f64 = 120.0;
+    const TEXT: f64 = 14.0;
+
+    let width = BAR_WIDTH * bars.len() as f64;
+    let max = bars.iter().map(|(_, value)| *value).max().unwrap_or(0).max(1) as f64;
+    let mut svg = format!(
+        r#"<svg class="chart" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = HEIGHT + 2.0 * TEXT,
+    );
+    for (i, (label, value)) in bars.iter().enumerate() {
+        let x = i as f64 * BAR_WIDTH;
+        let height = *value as f64 / max * HEIGHT;
+        let top = TEXT + HEIGHT - height;
+        svg.push_str(&format!(
+            r#"<g><title>{label}: {value}</title><rect x="{x:.1}" y="{top:.1}" width="{bar:.1}" height="{height:.1}"/><text x="{mid:.1}" y="{value_y:.1}">{value}</text><text x="{mid:.1}" y="{label_y:.1}">{label}</text></g>"#,
+            label = utils::xml_escape(label),
+            value = value,
+            x = x + 2.0,
+            top = top,
+            bar = BAR_WIDTH - 4.0,
+            height = height,
+            mid = x + BAR_WIDTH / 2.0,
+            value_y = top - 3.0,
+            label_y = TEXT + HEIGHT + TEXT - 3.0,
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// A percentage as a ring filled that far, in `class` (`red`, `yellow`, or
+/// `green`), for headline numbers.
+pub fn donut(percent: f64, class: &str) -> String {
+    const SIZE: f64 = 32.0;
+    const RADIUS: f64 = 13.0;
+
+    let percent = if percent.is_nan() { 0.0 } else { percent.clamp(0.0, 100.0) };
+    let circumference = 2.0 * PI * RADIUS;
+    let filled = percent / 100.0 * circumference;
+    format!(
+        r#"<svg class="donut {class}" width="{size}" height="{size}" viewBox="0 0 {size} {size}"><title>{percent:.1}%</title><circle class="track" cx="{mid}" cy="{mid}" r="{r}"/><circle class="value" cx="{mid}" cy="{mid}" r="{r}" stroke-dasharray="{filled:.2} {circumference:.2}" transform="rotate(-90 {mid} {mid})"/></svg>"#,
+        class = utils::xml_escape(class),
+        size = SIZE,
+        mid = SIZE / 2.0,
+        r = RADIUS,
+        percent = percent,
+        filled = filled,
+        circumference = circumference,
+    )
+}
+
+/// A box of a treemap: a leaf, or a group of smaller boxes.
+#[derive(Debug)]
+pub struct Tile {
+    pub label: String,
+    /// Tooltip.
+    pub title: String,
+    /// Area, relative to the other tiles.
+    pub size: u64,
+    /// `red`, `yellow`, or `green`.
+    pub class: &'static str,
+    /// Where clicking a leaf goes.
+    pub href: Option<String>,
+    pub children: Vec<Tile>,
+}
+
+/// Nested tiles as boxes with areas in proportion to their sizes, laid out
+/// squarified so they stay roughly square.  Groups are outlined with their
+/// label on top and leaves filled in their class.
+pub fn treemap(root: &Tile) -> String {
+    const WIDTH: f64 = 960.0;
+    const HEIGHT: f64 = 600.0;
+
+    let mut svg = format!(r#"<svg class="treemap" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#, w = WIDTH, h = HEIGHT);
+    tile(&mut svg, root, (0.0, 0.0, WIDTH, HEIGHT));
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Rounding may leave a width or height a hair below zero.
+fn positive(value: f64) -> f64 {
+    if value > 0.0 { value } else { 0.0 }
+}
+
+fn tile(svg: &mut String, tile: &Tile, (x, y, width, height): (f64, f64, f64, f64)) {
+    const TEXT: f64 = 12.0;
+    const PADDING: f64 = 2.0;
+
+    let (x, y, width, height) = (positive(x), positive(y), positive(width), positive(height));
+    let label = match !tile.label.is_empty() && width > tile.label.chars().count() as f64 * 6.0 + 2.0 * PADDING && height > TEXT + PADDING {
+        true => format!(r#"<text x="{:.1}" y="{:.1}">{}</text>"#, x + PADDING, y + TEXT - PADDING, utils::xml_escape(&tile.label)),
+        false => String::new(),
+    };
+    let rect = |class: &str| {
+        format!(
+            r#"<title>{}</title><rect class="{}" x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}"/>{}"#,
+            utils::xml_escape(&tile.title), class, x, y, width, height, label,
+        )
+    };
+
+    let children: Vec<_> = tile.children.iter().filter(|c| c.size > 0).collect();
+    if children.is_empty() {
+        let rect = rect(&format!("leaf {}", utils::xml_escape(tile.class)));
+        match tile.href.as_deref() {
+            Some(href) => svg.push_str(&format!(r#"<a href="{}">{}</a>"#, utils::xml_escape(href), rect)),
+            None => svg.push_str(&format!("<g>{}</g>", rect)),
+        }
+        return;
+    }
+
+    svg.push_str(&format!("<g>{}", rect(&format!("group {}", utils::xml_escape(tile.class)))));
+    let top = if label.is_empty() { PADDING } else { TEXT + PADDING };
+    let inner = (x + PADDING, y + top, positive(width - 2.0 * PADDING), positive(height - top - PADDING));
+    let sizes: Vec<_> = children.iter().map(|c| c.size as f64).collect();
+    for (child, rect) in children.iter().zip(squarify(&sizes, inner)) {
+        self::tile(svg, child, rect);
+    }
+    svg.push_str("</g>");
+}
+
+/// Splits a rectangle into one per size, in the given order, adding sizes
+/// to a row along the shorter side for as long as that makes the row's
+/// worst aspect ratio better.
+fn squarify(sizes: &[f64], (mut x, mut y, mut width, mut height): (f64, f64, f64, f64)) -> Vec<(f64, f64, f64, f64)> {
+    let total: f64 = sizes.iter().sum();
+    if total <= 0.0 || width <= 0.0 || height <= 0.0 {
+        return sizes.iter().map(|_| (x, y, 0.0, 0.0)).collect();
+    }
+    let scale = width * height / total;
+    let areas: Vec<_> = sizes.iter().map(|size| size * scale).collect();
+
+    let worst = |row: &[f64], side: f64| {
+        let sum: f64 = row.iter().sum();
+        let max = row.iter().cloned().fold(0.0, f64::max);
+        let min = row.iter().cloned().fold(f64::INFINITY, f64::min);
+        f64::max(side * side * max / (sum * sum), sum * sum / (side * side * min))
+    };
+
+    let mut rects = Vec::with_capacity(areas.len());
+    let mut start = 0;
+    while start < areas.len() {
+        let side = width.min(height);
+        let mut end = start + 1;
+        while end < areas.len() && worst(&areas[start..=end], side) <= worst(&areas[start..end], side) {
+            end += 1;
+        }
+
+        let row = &areas[start..end];
+        let sum: f64 = row.iter().sum();
+        if width >= height {
+            // A column along the left
+            let column = if height > 0.0 { sum / height } else { 0.0 };
+            let mut top = y;
+            for area in row {
+                let h = if column > 0.0 { area / column } else { 0.0 };
+                rects.push((x, top, column, h));
+                top += h;
+            }
+            x += column;
+            width = positive(width - column);
+        } else {
+            // A row along the top
+            let row_height = if width > 0.0 { sum / width } else { 0.0 };
+            let mut left = x;
+            for area in row {
+                let w = if row_height > 0.0 { area / row_height } else { 0.0 };
+                rects.push((left, y, w, row_height));
+                left += w;
+            }
+            y += row_height;
+            height = positive(height - row_height);
+        }
+        start = end;
+    }
+    rects
+}
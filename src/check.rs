@@ -0,0 +1,327 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{FileCoverageSummary, Summary};
+use crate::model::FileModel;
+use crate::utils::{self, xml_escape};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metric {
+    Lines,
+    Functions,
+    Branches,
+}
+
+/// What a threshold that isn't met does to the run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    /// Reported, but the run still succeeds.
+    Warn,
+    /// Reported, and the run fails.
+    Fail,
+}
+
+/// Minimum coverage percentage for a single metric.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Threshold {
+    pub metric: Metric,
+    pub minimum: f64,
+    pub level: Level,
+}
+
+/// Coverage levels a metric is colored by: red below `medium`, yellow below
+/// `high`, and green from there up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Goal {
+    pub medium: f64,
+    pub high: f64,
+}
+
+/// A goal for each metric, since they naturally sit at different levels.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Goals {
+    pub lines: Goal,
+    pub functions: Goal,
+    pub branches: Goal,
+}
+
+/// The result of evaluating one threshold against one file (or the totals).
+#[derive(Debug)]
+pub struct Outcome<'a> {
+    pub name: &'a str,
+    pub threshold: Threshold,
+    pub actual: f64,
+    /// Nothing to measure, e.g. a file without any branches.
+    pub skipped: bool,
+}
+
+pub const TOTAL: &str = "total";
+
+/// Exit status when a threshold that fails the run isn't met, so CI can tell
+/// coverage that's too low from a run that broke.
+pub const EXIT_BELOW_THRESHOLD: i32 = 2;
+
+impl Metric {
+    pub fn summary<'a>(&self, summary: &'a FileCoverageSummary) -> &'a Summary {
+        match self {
+            Metric::Lines => &summary.lines,
+            Metric::Functions => &summary.functions,
+            Metric::Branches => &summary.branches,
+        }
+    }
+}
+
+impl Default for Goal {
+    fn default() -> Self {
+        Self { medium: utils::YELLOW_PERCENT, high: utils::GREEN_PERCENT }
+    }
+}
+
+impl Goal {
+    pub fn color(&self, percent: f64) -> &'static str {
+        utils::color_between(percent, self.medium, self.high)
+    }
+}
+
+impl FromStr for Goal {
+    type Err = String;
+
+    /// Parses `medium,high`, e.g. `75,90`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |value: &str| value.trim().parse::<f64>().map_err(|e| format!("Invalid goal `{}': {}", s, e));
+        let (medium, high) = s.split_once(',').ok_or_else(|| format!("Invalid goal `{}', expected medium,high", s))?;
+        let (medium, high) = (parse(medium)?, parse(high)?);
+        if medium > high {
+            return Err(format!("Invalid goal `{}', medium is above high", s));
+        }
+        Ok(Self { medium, high })
+    }
+}
+
+impl Goals {
+    pub fn get(&self, metric: Metric) -> Goal {
+        match metric {
+            Metric::Lines => self.lines,
+            Metric::Functions => self.functions,
+            Metric::Branches => self.branches,
+        }
+    }
+}
+
+impl Threshold {
+    /// Parses a minimum given as a percent, or as `medium` or `high` to use
+    /// the metric's goal.
+    pub fn parse(metric: Metric, minimum: &str, level: Level, goals: &Goals) -> Result<Self, String> {
+        let minimum = match minimum {
+            "medium" => goals.get(metric).medium,
+            "high" => goals.get(metric).high,
+            percent => percent.parse().map_err(|e| format!("Invalid {} threshold `{}': {}", metric, percent, e))?,
+        };
+        Ok(Self { metric, minimum, level })
+    }
+}
+
+impl Metric {
+    fn noun(&self) -> &'static str {
+        match self {
+            Metric::Lines => "line",
+            Metric::Functions => "function",
+            Metric::Branches => "branch",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Level::Warn => "warn",
+            Level::Fail => "fail",
+        })
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Metric::Lines => "lines",
+            Metric::Functions => "functions",
+            Metric::Branches => "branches",
+        })
+    }
+}
+
+impl<'a> Outcome<'a> {
+    pub fn passed(&self) -> bool {
+        self.skipped || self.actual >= self.threshold.minimum
+    }
+
+    /// Not met, and the threshold fails the run.
+    pub fn failed(&self) -> bool {
+        !self.passed() && self.threshold.level == Level::Fail
+    }
+
+    /// Not met, but the threshold only warns.
+    pub fn warned(&self) -> bool {
+        !self.passed() && self.threshold.level == Level::Warn
+    }
+
+    /// The file (or the total) with the threshold, e.g. `total (fail under
+    /// 80.0%)`, so a file checked against a warning and a failing threshold
+    /// of the same metric gets two distinct test cases.
+    pub fn case_name(&self) -> String {
+        format!("{} ({} under {:.1}%)", self.name, self.threshold.level, self.threshold.minimum)
+    }
+
+    pub fn message(&self) -> String {
+        format!(
+            "{} coverage for {} is {:.1}%, {} {:.1}%",
+            self.threshold.metric.noun(),
+            self.name,
+            self.actual,
+            if self.passed() { "meeting" } else { "below" },
+            self.threshold.minimum
+        )
+    }
+}
+
+fn outcome<'a>(name: &'a str, summary: &FileCoverageSummary, threshold: Threshold) -> Outcome<'a> {
+    let summary = threshold.metric.summary(summary);
+    Outcome {
+        name,
+        threshold,
+        actual: summary.percent,
+        skipped: summary.count == 0,
+    }
+}
+
+/// Evaluates every threshold against the totals, which is all that decides
+/// whether the run fails.
+pub fn evaluate<'a>(thresholds: &[Threshold], totals: &FileCoverageSummary) -> Vec<Outcome<'a>> {
+    thresholds.iter().map(|threshold| outcome(TOTAL, totals, *threshold)).collect()
+}
+
+/// Evaluates every threshold against each file, for `--per-file-thresholds`.
+pub fn evaluate_files<'a>(thresholds: &[Threshold], files: &'a [FileModel]) -> Vec<Outcome<'a>> {
+    thresholds
+        .iter()
+        .flat_map(|threshold| files.iter().map(move |file| outcome(&file.name, &file.summary, *threshold)))
+        .collect()
+}
+
+/// One sentence on what failed the run, e.g. `Coverage too low: line
+/// coverage is below 80.0% for the total and 3 files`, or nothing if every
+/// threshold that fails the run was met.
+pub fn failure_summary(outcomes: &[Outcome]) -> Option<String> {
+    // Each threshold with whether the total failed it and how many files did
+    let mut failed: Vec<(Threshold, bool, usize)> = vec![];
+    for outcome in outcomes.iter().filter(|o| o.failed()) {
+        let index = match failed.iter().position(|(threshold, _, _)| *threshold == outcome.threshold) {
+            Some(index) => index,
+            None => {
+                failed.push((outcome.threshold, false, 0));
+                failed.len() - 1
+            }
+        };
+        match outcome.name == TOTAL {
+            true => failed[index].1 = true,
+            false => failed[index].2 += 1,
+        }
+    }
+    if failed.is_empty() {
+        return None;
+    }
+
+    let clauses = failed
+        .iter()
+        .map(|(threshold, total, files)| {
+            let files = match files {
+                0 => None,
+                1 => Some("1 file".to_string()),
+                n => Some(format!("{} files", n)),
+            };
+            let which = match (total, files) {
+                (true, Some(files)) => format!("the total and {}", files),
+                (true, None) => "the total".to_string(),
+                (false, Some(files)) => files,
+                (false, None) => unreachable!(),
+            };
+            format!("{} coverage is below {:.1}% for {}", threshold.metric.noun(), threshold.minimum, which)
+        })
+        .collect::<Vec<_>>();
+    Some(format!("Coverage too low: {}", clauses.join("; ")))
+}
+
+/// One line per threshold that wasn't met.  Under GitHub Actions these are
+/// workflow commands, which show up as annotations on the run.
+pub fn annotations(outcomes: &[Outcome], github: bool) -> Vec<String> {
+    outcomes
+        .iter()
+        .filter(|o| !o.passed())
+        .map(|o| {
+            let level = match o.threshold.level {
+                Level::Warn => "warning",
+                Level::Fail => "error",
+            };
+            match github {
+                // Workflow commands treat `%` as the start of an escape
+                true => format!("::{} title=Coverage below threshold::{}", level, o.message().replace('%', "%25")),
+                false => format!("{}: {}", level, o.message()),
+            }
+        })
+        .collect()
+}
+
+/// Renders outcomes as JUnit XML with one test suite per metric and one test
+/// case per threshold on the totals, plus one per file when those were
+/// evaluated (`--per-file-thresholds`), so CI systems can show failures at
+/// file granularity.
+/// Thresholds that only warn never fail a test case; their message goes to
+/// the case's output instead.
+pub fn junit(outcomes: &[Outcome]) -> String {
+    let failures = outcomes.iter().filter(|o| o.failed()).count();
+    let skipped = outcomes.iter().filter(|o| o.skipped).count();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"cosmoline\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">\n",
+        outcomes.len(),
+        failures,
+        skipped
+    ));
+
+    for metric in [Metric::Lines, Metric::Functions, Metric::Branches].iter() {
+        let suite = outcomes.iter().filter(|o| o.threshold.metric == *metric).collect::<Vec<_>>();
+        if suite.is_empty() {
+            continue;
+        }
+
+        xml.push_str(&format!(
+            "  <testsuite name=\"coverage.{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">\n",
+            metric,
+            suite.len(),
+            suite.iter().filter(|o| o.failed()).count(),
+            suite.iter().filter(|o| o.skipped).count()
+        ));
+
+        for outcome in suite {
+            xml.push_str(&format!(
+                "    <testcase classname=\"coverage.{}\" name=\"{}\">",
+                metric,
+                xml_escape(&outcome.case_name())
+            ));
+            if outcome.skipped {
+                xml.push_str("<skipped/>");
+            } else if outcome.failed() {
+                xml.push_str(&format!("<failure message=\"{}\"/>", xml_escape(&outcome.message())));
+            } else if outcome.warned() {
+                xml.push_str(&format!("<system-out>{}</system-out>", xml_escape(&outcome.message())));
+            }
+            xml.push_str("</testcase>\n");
+        }
+
+        xml.push_str("  </testsuite>\n");
+    }
+
+    xml.push_str("</testsuites>\n");
+    xml
+}
@@ -0,0 +1,142 @@
+//! Settings that don't fit on the command line, read from `cosmoline.toml`,
+//! and defaults for the ones that do, which can also be set by `COSMOLINE_*`
+//! environment variables.
+
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::path::Path;
+
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+use serde::Deserialize;
+
+#[cfg(feature = "render-html")]
+use crate::render::{PostProcessor, PostProcessorConfig, Titles};
+
+/// Looked for in the working directory when `--config` isn't given.
+pub const FILENAME: &str = "cosmoline.toml";
+
+/// Environment variables starting with this set command line arguments.
+pub const ENV_PREFIX: &str = "COSMOLINE_";
+
+/// Where the config file is when `--config` isn't given.
+pub const CONFIG_VAR: &str = "COSMOLINE_CONFIG";
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    /// Applied to every source line of the file pages, in order.
+    #[cfg(feature = "render-html")]
+    #[serde(default)]
+    pub postprocess: Vec<PostProcessorConfig>,
+    /// Formats of the page titles and breadcrumbs.
+    #[cfg(feature = "render-html")]
+    #[serde(default)]
+    pub titles: Titles,
+    /// Any other key is a command line argument by its long name, e.g.
+    /// `fail-under-lines = 80`, `include = ["src/**"]`, or `sidebar = true`.
+    #[serde(flatten)]
+    pub args: BTreeMap<String, toml::Value>,
+}
+
+impl Config {
+    /// Reads `path`, or `cosmoline.toml` if there is one.  Without either
+    /// everything is left at its default.
+    pub fn load(path: Option<&Path>) -> Result<Self, Box<dyn StdError>> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(FILENAME).is_file() => Path::new(FILENAME),
+            None => return Ok(Self::default()),
+        };
+        let contents = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&contents).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// The command line arguments set by the `COSMOLINE_*` variables in
+    /// `vars`, e.g. `COSMOLINE_FAIL_UNDER_LINES=80` for `--fail-under-lines
+    /// 80`.  `true` and `false` turn switches on and off, arguments that can
+    /// be repeated take a line per value, and empty variables are left out.
+    /// So are variables that aren't named after an argument in `names` (see
+    /// [`Config::canonical`]), since other tools may use the prefix too.
+    pub fn from_env(vars: impl IntoIterator<Item = (String, String)>, names: &BTreeMap<String, String>) -> Self {
+        let args = vars
+            .into_iter()
+            .filter(|(var, value)| var.starts_with(ENV_PREFIX) && var != CONFIG_VAR && !value.is_empty())
+            .filter_map(|(var, value)| {
+                let name = match names.get(&var[ENV_PREFIX.len()..].to_ascii_lowercase().replace('_', "-")) {
+                    Some(name) => name.clone(),
+                    None => {
+                        warn!("Ignoring {}, there's no such argument", var);
+                        return None;
+                    }
+                };
+                let value = match value.as_str() {
+                    "true" => toml::Value::Boolean(true),
+                    "false" => toml::Value::Boolean(false),
+                    _ if value.contains('\n') => toml::Value::Array(
+                        value.lines().filter(|line| !line.is_empty()).map(|line| toml::Value::String(line.to_string())).collect(),
+                    ),
+                    _ => toml::Value::String(value),
+                };
+                Some((name, value))
+            })
+            .collect();
+        // The other fields only exist with `render-html`
+        #[allow(clippy::needless_update)]
+        Self { args, ..Self::default() }
+    }
+
+    /// Renames the arguments set by an alias, e.g. `include-glob`, after the
+    /// argument, e.g. `include`, so [`Config::args`] can tell whether the
+    /// command line gave it.  `names` has the name of each argument by every
+    /// long name and alias it goes by; unknown names are kept for the command
+    /// line to reject.
+    pub fn canonical(mut self, names: &BTreeMap<String, String>) -> Self {
+        self.args = std::mem::take(&mut self.args)
+            .into_iter()
+            .map(|(name, value)| (names.get(&name).cloned().unwrap_or(name), value))
+            .collect();
+        self
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, Box<dyn StdError>> {
+        let config: Self = toml::from_str(contents)?;
+        #[cfg(feature = "render-html")]
+        config.titles.validate()?;
+        Ok(config)
+    }
+
+    /// The command line arguments the file sets, leaving out the ones
+    /// `given` already, so the command line wins.
+    pub fn args(&self, given: impl Fn(&str) -> bool) -> Result<Vec<String>, String> {
+        let mut args = vec![];
+        for (name, value) in self.args.iter().filter(|(name, _)| !given(name)) {
+            if name == "config" {
+                return Err("`config' can only be given on the command line".to_string());
+            }
+            let values = match value {
+                toml::Value::Boolean(true) => {
+                    args.push(format!("--{}", name));
+                    continue;
+                }
+                toml::Value::Boolean(false) => continue,
+                toml::Value::Array(values) => values.iter().collect(),
+                value => vec![value],
+            };
+            for value in values {
+                let value = match value {
+                    toml::Value::String(value) => value.clone(),
+                    toml::Value::Integer(value) => value.to_string(),
+                    toml::Value::Float(value) => value.to_string(),
+                    _ => return Err(format!("`{}' should be a string, a number, a boolean, or a list of them", name)),
+                };
+                args.push(format!("--{}={}", name, value));
+            }
+        }
+        Ok(args)
+    }
+
+    #[cfg(feature = "render-html")]
+    pub fn postprocessors(&self) -> Result<Vec<Box<dyn PostProcessor>>, Box<dyn StdError>> {
+        self.postprocess.iter().map(PostProcessorConfig::build).collect()
+    }
+}
@@ -1,14 +1,24 @@
+// Mirrors the llvm-cov export schema; not every field is consumed by the renderers.
+#![allow(dead_code)]
+
 #[allow(unused)]
 use log::{error, warn, info, debug, trace};
 
+use std::borrow::Cow;
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+
 use rustc_demangle::demangle;
-use serde::{Deserialize, Deserializer};
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
+use serde_json::value::RawValue;
 
 use crate::utils::deser_from_str;
 
 #[derive(Debug, Deserialize)]
-pub(crate) struct CoverageMapping<'a> {
+pub struct CoverageMapping<'a> {
     #[serde(borrow)]
     pub files: Vec<FileCoverage<'a>>,
 
@@ -18,8 +28,8 @@ pub(crate) struct CoverageMapping<'a> {
     pub totals: FileCoverageSummary,
 }
 
-#[derive(Debug)]
-pub(crate) struct FileBranch {
+#[derive(Clone, Debug)]
+pub struct FileBranch {
     pub line_start: i64,
     pub column_start: i64,
     pub line_end: i64,
@@ -32,7 +42,7 @@ pub(crate) struct FileBranch {
 }
 
 #[derive(Debug, Deserialize)]
-pub(crate) struct FileCoverage<'a> {
+pub struct FileCoverage<'a> {
     pub branches: Vec<FileBranch>,
     pub expansions: Vec<FileExpansion<'a>>,
     pub filename: &'a str,
@@ -40,8 +50,8 @@ pub(crate) struct FileCoverage<'a> {
     pub summary: FileCoverageSummary,
 }
 
-#[derive(Debug, Deserialize)]
-pub(crate) struct FileCoverageSummary {
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FileCoverageSummary {
     pub branches: Summary,
     pub functions: Summary,
     pub instantiations: Summary,
@@ -50,13 +60,13 @@ pub(crate) struct FileCoverageSummary {
 }
 
 #[derive(Debug, Deserialize)]
-pub(crate) struct FileExpansion<'a> {
+pub struct FileExpansion<'a> {
     #[serde(borrow)]
     pub filenames: Vec<&'a str>,
 }
 
-#[derive(Debug)]
-pub(crate) struct FileSegment {
+#[derive(Clone, Debug)]
+pub struct FileSegment {
     pub line: i64,
     pub col: i64,
     pub count: i64,
@@ -66,7 +76,7 @@ pub(crate) struct FileSegment {
 }
 
 #[derive(Debug, Deserialize)]
-pub(crate) struct FunctionCoverage<'a> {
+pub struct FunctionCoverage<'a> {
     pub name: &'a str,
 
     pub count: i64,
@@ -77,8 +87,8 @@ pub(crate) struct FunctionCoverage<'a> {
     pub filenames: Vec<&'a str>,
 }
 
-#[derive(Debug)]
-pub(crate) struct Region {
+#[derive(Clone, Debug)]
+pub struct Region {
     pub line_start: i64,
     pub column_start: i64,
     pub line_end: i64,
@@ -89,17 +99,17 @@ pub(crate) struct Region {
     pub region_kind: i64,
 }
 
-#[derive(Debug, Deserialize)]
-pub(crate) struct Summary {
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Summary {
     pub count: u64,
     pub covered: u64,
-    #[serde(rename = "notcovered")]
+    #[serde(rename = "notcovered", skip_serializing_if = "Option::is_none")]
     pub not_covered: Option<u64>,
     pub percent: f64,
 }
 
 #[derive(Debug, Deserialize)]
-pub(crate) struct SummaryReport<'a> {
+pub struct SummaryReport<'a> {
     #[serde(rename = "type")]
     pub report_type: &'a str,
 
@@ -158,10 +168,128 @@ impl<'de> Deserialize<'de> for FileSegment {
     }
 }
 
+impl Summary {
+    pub fn new(count: u64, covered: u64) -> Self {
+        Self {
+            count,
+            covered,
+            not_covered: Some(count - covered),
+            percent: match count {
+                0 => 0.0,
+                _ => covered as f64 * 100.0 / count as f64,
+            },
+        }
+    }
+
+    /// Adds up several summaries and recomputes the percentage.
+    pub fn total<'s>(summaries: impl Iterator<Item = &'s Summary>) -> Self {
+        let (count, covered) = summaries.fold((0, 0), |(count, covered), s| (count + s.count, covered + s.covered));
+        Self::new(count, covered)
+    }
+}
+
+impl FileCoverageSummary {
+    /// Adds up every metric of several summaries.
+    pub fn total<'s>(summaries: impl Iterator<Item = &'s FileCoverageSummary> + Clone) -> Self {
+        Self {
+            branches: Summary::total(summaries.clone().map(|s| &s.branches)),
+            functions: Summary::total(summaries.clone().map(|s| &s.functions)),
+            instantiations: Summary::total(summaries.clone().map(|s| &s.instantiations)),
+            lines: Summary::total(summaries.clone().map(|s| &s.lines)),
+            regions: Summary::total(summaries.map(|s| &s.regions)),
+        }
+    }
+}
+
+/// Execution count for a single source line, derived from the segments the
+/// same way `llvm-cov` does.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LineCoverage {
+    pub line: i64,
+    pub count: i64,
+    /// Whether the line contains instrumented code at all.
+    pub mapped: bool,
+    /// More than one region starts on this line.
+    pub has_multiple_regions: bool,
+}
+
+impl<'a> FileCoverage<'a> {
+    /// Per-line execution counts, starting from line 1 through the last line
+    /// with a segment.  Mirrors llvm's `LineCoverageIterator`.
+    pub fn line_coverage(&self) -> Vec<LineCoverage> {
+        let is_start_of_region = |s: &FileSegment| !s.is_gap_region && s.has_count && s.is_region_entry;
+
+        let last_line = match self.segments.last() {
+            Some(segment) => segment.line,
+            None => return vec![],
+        };
+        let first_line = self.segments[0].line;
+
+        let mut lines = (1..first_line).map(|line| LineCoverage { line, ..Default::default() }).collect::<Vec<_>>();
+        let mut wrapped: Option<&FileSegment> = None;
+        let mut next = 0;
+
+        for line in first_line..=last_line {
+            let start = next;
+            while next < self.segments.len() && self.segments[next].line == line {
+                next += 1;
+            }
+            let line_segments = &self.segments[start..next];
+
+            let region_starts = line_segments.iter().filter(|s| is_start_of_region(s)).count();
+            let start_of_skipped_region = line_segments
+                .first()
+                .map(|s| !s.has_count && s.is_region_entry)
+                .unwrap_or(false);
+
+            // Newer llvm releases also treat any counted region entry (including
+            // gap regions) as mapping the line.
+            let mapped = (!start_of_skipped_region
+                && (wrapped.map(|w| w.has_count).unwrap_or(false) || region_starts > 0))
+                || line_segments.iter().any(|s| s.is_region_entry && s.has_count);
+
+            let mut count = 0;
+            if mapped {
+                count = wrapped.map(|w| w.count).unwrap_or(0);
+                for segment in line_segments.iter().filter(|s| is_start_of_region(s)) {
+                    count = count.max(segment.count);
+                }
+            }
+
+            lines.push(LineCoverage {
+                line,
+                count,
+                mapped,
+                has_multiple_regions: region_starts > 1,
+            });
+
+            if let Some(last) = line_segments.last() {
+                wrapped = Some(last);
+            }
+        }
+
+        lines
+    }
+}
+
 impl<'a> FunctionCoverage<'a> {
     pub fn demangle(&self) -> String {
         format!("{:#}", demangle(self.name))
     }
+
+    /// The crate a function comes from, taken from the leading path segment of
+    /// the demangled name, e.g. `alloc` for `<alloc::vec::Vec<T> as Drop>::drop`.
+    /// Returns `None` for names that don't start with a path (e.g. `<&str as ...>`).
+    pub fn crate_name(&self) -> Option<String> {
+        let demangled = self.demangle();
+        let path = demangled.trim_start_matches('<');
+        let (segment, _) = path.split_once("::")?;
+
+        match !segment.is_empty() && segment.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            true => Some(segment.to_string()),
+            false => None,
+        }
+    }
 }
 
 impl From<[Value; 8]> for Region {
@@ -188,3 +316,199 @@ impl<'de> Deserialize<'de> for Region {
         Ok(Self::from(data))
     }
 }
+
+/// A file or a function of an export as [`stream_export`] reads it: its
+/// JSON, to be parsed (borrowing from it) only if it's wanted.
+#[derive(Debug)]
+pub enum ExportEntry<'a> {
+    File(&'a RawValue),
+    Function(&'a RawValue),
+}
+
+/// What an export says about itself besides its data.
+#[derive(Debug, Default)]
+pub struct ExportHeader {
+    pub report_type: String,
+    pub version: String,
+}
+
+/// Reads the JSON written by `llvm-cov export` one file and one function at
+/// a time, handing each to `each` along with the index of the mapping it's
+/// in.  Only the entry being handled is in memory, rather than the whole
+/// export and everything parsed from it as with [`SummaryReport`], so
+/// exports larger than memory can still be picked apart.  The totals of the
+/// mappings are skipped.
+///
+/// This is what `cosmoline repro` reads exports with.  Rendering a report
+/// doesn't, it parses the whole export into a [`SummaryReport`].
+pub fn stream_export<R, F>(reader: R, mut each: F) -> Result<ExportHeader, Box<dyn StdError>>
+where
+    R: io::Read,
+    F: FnMut(usize, ExportEntry) -> Result<(), Box<dyn StdError>>,
+{
+    let mut deserializer = serde_json::Deserializer::from_reader(io::BufReader::new(reader));
+    let header = ExportSeed { each: &mut each }.deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(header)
+}
+
+/// The top level of an export.
+struct ExportSeed<'f, F> {
+    each: &'f mut F,
+}
+
+/// `data`, the list of mappings.
+struct DataSeed<'f, F> {
+    each: &'f mut F,
+}
+
+/// One mapping, the `index`th.
+struct MappingSeed<'f, F> {
+    each: &'f mut F,
+    index: usize,
+}
+
+/// The `files` or the `functions` of a mapping.
+struct EntriesSeed<'f, F> {
+    each: &'f mut F,
+    index: usize,
+    functions: bool,
+}
+
+impl<'de, F> DeserializeSeed<'de> for ExportSeed<'_, F>
+where
+    F: FnMut(usize, ExportEntry) -> Result<(), Box<dyn StdError>>,
+{
+    type Value = ExportHeader;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F> Visitor<'de> for ExportSeed<'_, F>
+where
+    F: FnMut(usize, ExportEntry) -> Result<(), Box<dyn StdError>>,
+{
+    type Value = ExportHeader;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an llvm-cov export")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut header = ExportHeader::default();
+        while let Some(key) = map.next_key::<Cow<str>>()? {
+            match key.as_ref() {
+                "type" => header.report_type = map.next_value()?,
+                "version" => header.version = map.next_value()?,
+                "data" => map.next_value_seed(DataSeed { each: &mut *self.each })?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(header)
+    }
+}
+
+impl<'de, F> DeserializeSeed<'de> for DataSeed<'_, F>
+where
+    F: FnMut(usize, ExportEntry) -> Result<(), Box<dyn StdError>>,
+{
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, F> Visitor<'de> for DataSeed<'_, F>
+where
+    F: FnMut(usize, ExportEntry) -> Result<(), Box<dyn StdError>>,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of coverage mappings")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut index = 0;
+        while seq.next_element_seed(MappingSeed { each: &mut *self.each, index })?.is_some() {
+            index += 1;
+        }
+        Ok(())
+    }
+}
+
+impl<'de, F> DeserializeSeed<'de> for MappingSeed<'_, F>
+where
+    F: FnMut(usize, ExportEntry) -> Result<(), Box<dyn StdError>>,
+{
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F> Visitor<'de> for MappingSeed<'_, F>
+where
+    F: FnMut(usize, ExportEntry) -> Result<(), Box<dyn StdError>>,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a coverage mapping")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        while let Some(key) = map.next_key::<Cow<str>>()? {
+            let functions = match key.as_ref() {
+                "files" => false,
+                "functions" => true,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                    continue;
+                }
+            };
+            map.next_value_seed(EntriesSeed { each: &mut *self.each, index: self.index, functions })?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de, F> DeserializeSeed<'de> for EntriesSeed<'_, F>
+where
+    F: FnMut(usize, ExportEntry) -> Result<(), Box<dyn StdError>>,
+{
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, F> Visitor<'de> for EntriesSeed<'_, F>
+where
+    F: FnMut(usize, ExportEntry) -> Result<(), Box<dyn StdError>>,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of files or functions")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        // Read into a buffer of its own, dropped once it's been handled
+        while let Some(raw) = seq.next_element::<Box<RawValue>>()? {
+            let entry = match self.functions {
+                true => ExportEntry::Function(&raw),
+                false => ExportEntry::File(&raw),
+            };
+            (self.each)(self.index, entry).map_err(de::Error::custom)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Warnings and errors collected while building a report, bucketed by the
+/// file they concern.  Collecting them instead of logging as they happen
+/// keeps them together (and in order) however the work is scheduled, and
+/// lets the report show them on its warnings page and in the manifest.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    buckets: Mutex<BTreeMap<Option<String>, Vec<Diagnostic>>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub message: String,
+}
+
+/// Every diagnostic about one file, or about the report as a whole.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Bucket {
+    /// Shown name of the file, `None` for the report as a whole.
+    pub file: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn warn(&self, file: Option<&str>, message: impl Into<String>) {
+        self.push(file, Level::Warning, message.into());
+    }
+
+    pub fn error(&self, file: Option<&str>, message: impl Into<String>) {
+        self.push(file, Level::Error, message.into());
+    }
+
+    /// Repeats of something already collected about the file, e.g. from
+    /// rendering it again for another view, are left out.
+    fn push(&self, file: Option<&str>, level: Level, message: String) {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets.entry(file.map(str::to_string)).or_default();
+        if !bucket.iter().any(|d| d.level == level && d.message == message) {
+            bucket.push(Diagnostic { level, message });
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        let buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        buckets.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Everything collected so far, the report as a whole first and then by
+    /// file name.
+    pub fn buckets(&self) -> Vec<Bucket> {
+        let buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        buckets
+            .iter()
+            .map(|(file, diagnostics)| Bucket { file: file.clone(), diagnostics: diagnostics.clone() })
+            .collect()
+    }
+}
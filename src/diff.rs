@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::io::IsTerminal;
+
+use clap::ArgMatches;
+use serde::Serialize;
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+
+use crate::{CoverageMapping, Summary, utils};
+use crate::check::{self, Goals, Level, Metric, Outcome, Threshold};
+use crate::input::{InputFormat, LlvmJson};
+use crate::merge::{self, MergeMode};
+
+/// Version of the layout of `cosmoline diff --format json`.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Line coverage of a single file in the old and new exports.  `None` means
+/// the file wasn't present in that export.
+#[derive(Debug, Serialize)]
+pub struct FileDelta<'a> {
+    pub name: &'a str,
+    pub old: Option<f64>,
+    pub new: Option<f64>,
+}
+
+/// What the new export is checked against.
+#[derive(Debug, Default)]
+pub struct Limits {
+    /// Minimum line coverage of the total and of every file.
+    pub fail_under_lines: Option<Threshold>,
+    /// How many percentage points the line coverage of the total or of a
+    /// file may drop by.
+    pub max_decrease: Option<f64>,
+}
+
+/// The outcome of checking one limit against the total or one file.
+#[derive(Debug, Serialize)]
+pub struct Verdict<'a> {
+    pub name: &'a str,
+    /// `fail-under-lines` or `max-decrease`.
+    pub check: &'static str,
+    pub limit: f64,
+    /// New line coverage, or how far it dropped.
+    pub actual: f64,
+    pub passed: bool,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+struct JsonDelta<'a> {
+    #[serde(flatten)]
+    delta: &'a FileDelta<'a>,
+    /// New minus old, in percentage points.
+    change: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    version: u32,
+    total: JsonDelta<'a>,
+    /// Every file in either export, by name.
+    files: Vec<JsonDelta<'a>>,
+    /// Files only in the new export.
+    added: Vec<&'a str>,
+    /// Files only in the old export.
+    removed: Vec<&'a str>,
+    verdicts: &'a [Verdict<'a>],
+    /// Whether every verdict passed.
+    passed: bool,
+}
+
+impl<'a> FileDelta<'a> {
+    pub fn delta(&self) -> Option<f64> {
+        match (self.old, self.new) {
+            (Some(old), Some(new)) => Some(new - old),
+            _ => None,
+        }
+    }
+}
+
+/// Pairs up the reported files of two exports by filename.
+pub fn compare<'a>(old: &CoverageMapping<'a>, new: &CoverageMapping<'a>) -> Vec<FileDelta<'a>> {
+    let mut files: BTreeMap<&str, FileDelta> = BTreeMap::new();
+
+    for file in old.files.iter().filter(|f| utils::is_source_file(f.filename)) {
+        files.entry(file.filename)
+            .or_insert(FileDelta { name: file.filename, old: None, new: None })
+            .old = Some(file.summary.lines.percent);
+    }
+
+    for file in new.files.iter().filter(|f| utils::is_source_file(f.filename)) {
+        files.entry(file.filename)
+            .or_insert(FileDelta { name: file.filename, old: None, new: None })
+            .new = Some(file.summary.lines.percent);
+    }
+
+    files.into_values().collect()
+}
+
+/// Line coverage of all the files [`compare`] pairs up, in either export.
+/// llvm's totals would count the files left out of the comparison too.
+pub fn total<'a>(old: &CoverageMapping<'a>, new: &CoverageMapping<'a>) -> FileDelta<'a> {
+    let lines = |mapping: &CoverageMapping| {
+        let files = mapping.files.iter().filter(|f| utils::is_source_file(f.filename));
+        Summary::total(files.map(|f| &f.summary.lines)).percent
+    };
+    FileDelta { name: "Total", old: Some(lines(old)), new: Some(lines(new)) }
+}
+
+/// Checks the total and every file present in the new export against the
+/// limits, in that order.
+pub fn verdicts<'a>(deltas: &'a [FileDelta<'a>], total: &'a FileDelta<'a>, limits: &Limits) -> Vec<Verdict<'a>> {
+    let mut verdicts = vec![];
+    let files = || Some(total).into_iter().chain(deltas.iter());
+
+    if let Some(threshold) = limits.fail_under_lines {
+        for delta in files() {
+            let actual = match delta.new {
+                Some(new) => new,
+                None => continue,
+            };
+            let outcome = Outcome { name: delta.name, threshold, actual, skipped: false };
+            verdicts.push(Verdict {
+                name: delta.name,
+                check: "fail-under-lines",
+                limit: threshold.minimum,
+                actual,
+                passed: outcome.passed(),
+                message: outcome.message(),
+            });
+        }
+    }
+
+    if let Some(limit) = limits.max_decrease {
+        for delta in files() {
+            let decrease = match delta.delta() {
+                Some(d) => -d,
+                None => continue,
+            };
+            let passed = decrease <= limit;
+            verdicts.push(Verdict {
+                name: delta.name,
+                check: "max-decrease",
+                limit,
+                actual: decrease,
+                passed,
+                message: format!(
+                    "line coverage for {} {} by {:.1} points, {} {:.1}",
+                    delta.name,
+                    if decrease > 0.0 { "dropped" } else { "changed" },
+                    decrease.max(0.0),
+                    if passed { "within" } else { "more than" },
+                    limit,
+                ),
+            });
+        }
+    }
+
+    verdicts
+}
+
+/// The deltas and verdicts as JSON, for bots and dashboards.
+pub fn json(deltas: &[FileDelta], total: &FileDelta, verdicts: &[Verdict]) -> Result<String, serde_json::Error> {
+    let json_delta = |delta| JsonDelta { delta, change: delta.delta() };
+    let document = Document {
+        version: SCHEMA_VERSION,
+        total: json_delta(total),
+        files: deltas.iter().map(json_delta).collect(),
+        added: deltas.iter().filter(|d| d.old.is_none()).map(|d| d.name).collect(),
+        removed: deltas.iter().filter(|d| d.new.is_none()).map(|d| d.name).collect(),
+        verdicts,
+        passed: verdicts.iter().all(|v| v.passed),
+    };
+    serde_json::to_string_pretty(&document)
+}
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const DIM: &str = "\x1b[2m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+fn percent(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.1}%", v)).unwrap_or_else(|| "-".to_string())
+}
+
+/// Formats the deltas as an aligned table with arrows and (optionally) ANSI colors.
+pub fn terminal(deltas: &[FileDelta], total: &FileDelta, color: bool) -> String {
+    let paint = |code: &str, text: String| match color {
+        true => format!("{}{}{}", code, text, RESET),
+        false => text,
+    };
+
+    let width = deltas.iter().map(|d| d.name.chars().count()).chain(Some(total.name.len())).max().unwrap_or(0);
+    let mut output = paint(BOLD, format!("{:width$}  {:>7}     {:>7}  {}", "File", "Old", "New", "Change", width = width));
+    output.push('\n');
+
+    for delta in deltas.iter().chain(Some(total)) {
+        let (code, change) = match (delta.old, delta.new, delta.delta()) {
+            (None, Some(_), _) => (GREEN, "new".to_string()),
+            (Some(_), None, _) => (DIM, "removed".to_string()),
+            (_, _, Some(d)) if d >= 0.05 => (GREEN, format!("↑ +{:.1}%", d)),
+            (_, _, Some(d)) if d <= -0.05 => (RED, format!("↓ {:.1}%", d)),
+            _ => (DIM, "=".to_string()),
+        };
+
+        let line = format!(
+            "{:width$}  {:>7}  →  {:>7}  {}",
+            delta.name,
+            percent(delta.old),
+            percent(delta.new),
+            change,
+            width = width
+        );
+        output.push_str(&paint(code, line));
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Every mapping of an export, e.g. one per test binary, summed into one.
+pub fn read(contents: &str) -> Result<CoverageMapping<'_>, Box<dyn StdError>> {
+    Ok(merge::combine(LlvmJson.read(contents)?, MergeMode::Sum))
+}
+
+/// Entry point for `cosmoline diff`.
+pub fn run(matches: &ArgMatches) -> Result<(), Box<dyn StdError>> {
+    let old_filename = matches.value_of("old").unwrap();
+    let new_filename = matches.value_of("new").unwrap();
+
+    info!("Reading old llvm JSON from: {}", old_filename);
+    let old_contents = std::fs::read_to_string(old_filename)?;
+    let old = read(&old_contents)?;
+
+    info!("Reading new llvm JSON from: {}", new_filename);
+    let new_contents = std::fs::read_to_string(new_filename)?;
+    let new = read(&new_contents)?;
+
+    let deltas = compare(&old, &new);
+    let total = total(&old, &new);
+
+    let color = match matches.value_of("color").unwrap() {
+        "always" => true,
+        "never" => false,
+        _ => std::io::stdout().is_terminal(),
+    };
+
+    let limits = Limits {
+        fail_under_lines: matches
+            .value_of("fail-under-lines")
+            .map(|minimum| Threshold::parse(Metric::Lines, minimum, Level::Fail, &Goals::default()))
+            .transpose()?,
+        max_decrease: matches.value_of("max-decrease").map(str::parse).transpose()?,
+    };
+    let verdicts = verdicts(&deltas, &total, &limits);
+
+    match matches.value_of("format").unwrap() {
+        "terminal" => {
+            print!("{}", terminal(&deltas, &total, color));
+            for verdict in verdicts.iter().filter(|v| !v.passed) {
+                eprintln!("error: {}", verdict.message);
+            }
+        }
+        "json" => println!("{}", json(&deltas, &total, &verdicts)?),
+        format => unreachable!("Unknown diff format {}", format),
+    }
+
+    // Exits like a run with thresholds, so CI can tell coverage that's too
+    // low from a diff that broke
+    match verdicts.iter().filter(|v| !v.passed).count() {
+        0 => Ok(()),
+        failed => {
+            eprintln!("{} coverage checks failed", failed);
+            std::process::exit(check::EXIT_BELOW_THRESHOLD);
+        }
+    }
+}
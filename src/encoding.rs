@@ -0,0 +1,88 @@
+//! Decoding source files that aren't (all) UTF-8, e.g. legacy C sources in a
+//! mixed project.
+
+use std::str::FromStr;
+
+/// How source files are decoded before they're rendered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SourceEncoding {
+    /// Invalid sequences are shown as U+FFFD rather than dropped.
+    Utf8,
+    /// ISO 8859-1: every byte is the code point of the same value.
+    Latin1,
+    /// Go by the byte order mark, then by whether the file looks like UTF-16
+    /// or is valid UTF-8, falling back to Latin-1.
+    #[default]
+    Auto,
+}
+
+impl FromStr for SourceEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "utf-8" | "utf8" => Ok(SourceEncoding::Utf8),
+            "latin1" | "latin-1" | "iso-8859-1" => Ok(SourceEncoding::Latin1),
+            "auto" => Ok(SourceEncoding::Auto),
+            _ => Err(format!("Unknown source encoding `{}', expected utf-8, latin1, or auto", s)),
+        }
+    }
+}
+
+const UTF8_BOM: &[u8] = &[0xef, 0xbb, 0xbf];
+const UTF16LE_BOM: &[u8] = &[0xff, 0xfe];
+const UTF16BE_BOM: &[u8] = &[0xfe, 0xff];
+
+/// Decodes a source file.  Never fails: whatever can't be decoded is
+/// replaced, so every line of the file still shows up.
+pub fn decode(bytes: &[u8], encoding: SourceEncoding) -> String {
+    match encoding {
+        SourceEncoding::Utf8 => utf8(bytes),
+        SourceEncoding::Latin1 => latin1(bytes),
+        SourceEncoding::Auto => {
+            if bytes.starts_with(UTF8_BOM) {
+                utf8(bytes)
+            } else if let Some(rest) = bytes.strip_prefix(UTF16LE_BOM) {
+                utf16(rest, u16::from_le_bytes)
+            } else if let Some(rest) = bytes.strip_prefix(UTF16BE_BOM) {
+                utf16(rest, u16::from_be_bytes)
+            } else if let Some(from_bytes) = guess_utf16(bytes) {
+                utf16(bytes, from_bytes)
+            } else if std::str::from_utf8(bytes).is_ok() {
+                utf8(bytes)
+            } else {
+                latin1(bytes)
+            }
+        }
+    }
+}
+
+fn utf8(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes)).into_owned()
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| *b as char).collect()
+}
+
+fn utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    let units = bytes.chunks_exact(2).map(|pair| from_bytes([pair[0], pair[1]])).collect::<Vec<_>>();
+    String::from_utf16_lossy(&units)
+}
+
+/// Mostly ASCII text in UTF-16 has a NUL in every other byte, which source
+/// in any single byte encoding practically never does.
+fn guess_utf16(bytes: &[u8]) -> Option<fn([u8; 2]) -> u16> {
+    let pairs = bytes.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+
+    let even = bytes.iter().step_by(2).filter(|b| **b == 0).count();
+    let odd = bytes.iter().skip(1).step_by(2).filter(|b| **b == 0).count();
+    match (even * 2 > pairs, odd * 2 > pairs) {
+        (false, true) => Some(u16::from_le_bytes),
+        (true, false) => Some(u16::from_be_bytes),
+        _ => None,
+    }
+}
@@ -0,0 +1,77 @@
+use std::error::Error as StdError;
+use std::path::Path;
+
+use crate::model::ReportModel;
+
+#[cfg(feature = "render-html")]
+mod html;
+#[cfg(feature = "render-html")]
+pub use html::*;
+
+mod lcov;
+pub use lcov::*;
+
+mod cobertura;
+pub use cobertura::*;
+
+mod github;
+pub use github::*;
+
+mod json;
+pub use json::*;
+
+mod markdown;
+pub use markdown::*;
+
+mod reachability;
+pub use reachability::*;
+
+mod text;
+pub use text::*;
+
+mod unique;
+pub use unique::*;
+
+/// An output format.  Exporters are handed the finished report model and
+/// write whatever files they produce into the output directory.
+pub trait Exporter {
+    /// Name the format is selected by with `--output-format`.
+    fn name(&self) -> &str;
+
+    fn export(&self, model: &ReportModel, output_path: &Path) -> Result<(), Box<dyn StdError>>;
+}
+
+/// Exporters keyed by name.
+#[derive(Default)]
+pub struct Registry<'a> {
+    exporters: Vec<Box<dyn Exporter + 'a>>,
+}
+
+impl<'a> Registry<'a> {
+    /// A registry holding every built-in format that doesn't need any
+    /// configuration, i.e. everything but HTML.
+    pub fn builtin() -> Self {
+        let mut registry = Self::default();
+        registry.register(Lcov);
+        registry.register(Cobertura);
+        registry.register(Json);
+        registry.register(JsonSummary);
+        registry.register(GithubAnnotations::default());
+        registry.register(Markdown::default());
+        registry
+    }
+
+    /// Adds an exporter, replacing any previously registered under the same name.
+    pub fn register(&mut self, exporter: impl Exporter + 'a) {
+        self.exporters.retain(|e| e.name() != exporter.name());
+        self.exporters.push(Box::new(exporter));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&(dyn Exporter + 'a)> {
+        self.exporters.iter().find(|e| e.name() == name).map(|e| e.as_ref())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.exporters.iter().map(|e| e.name())
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::Summary;
+use crate::model::{FileModel, ReportModel};
+use crate::utils::xml_escape;
+use super::Exporter;
+
+/// Cobertura XML (`cobertura.xml`), consumed by GitLab and Jenkins among
+/// others.  Files are grouped into packages by directory.
+pub struct Cobertura;
+
+/// Cobertura wants rates in the range 0..=1 rather than percentages.
+fn rate(summary: &Summary) -> String {
+    match summary.count {
+        0 => "1".to_string(),
+        _ => format!("{:.4}", summary.covered as f64 / summary.count as f64),
+    }
+}
+
+impl Cobertura {
+    fn class(model: &ReportModel, file: &FileModel, out: &mut String) -> std::fmt::Result {
+        let name = file.name.rsplit('/').next().unwrap_or(&file.name);
+        writeln!(
+            out,
+            r#"        <class name="{}" filename="{}" line-rate="{}" branch-rate="{}" complexity="0">"#,
+            xml_escape(name),
+            xml_escape(&file.name),
+            rate(&file.summary.lines),
+            rate(&file.summary.branches),
+        )?;
+
+        writeln!(out, "          <methods>")?;
+        for function in model.file_functions(file) {
+            let hit = (function.count > 0) as u8;
+            writeln!(
+                out,
+                r#"            <method name="{}" signature="" line-rate="{}" branch-rate="1" complexity="0">"#,
+                xml_escape(&function.name),
+                hit,
+            )?;
+            if let Some(region) = function.regions.first() {
+                writeln!(out, "              <lines>")?;
+                writeln!(out, r#"                <line number="{}" hits="{}" branch="false"/>"#, region.line_start, function.count)?;
+                writeln!(out, "              </lines>")?;
+            }
+            writeln!(out, "            </method>")?;
+        }
+        writeln!(out, "          </methods>")?;
+
+        // (covered, total) branch arms per line
+        let mut branches: BTreeMap<i64, (u64, u64)> = BTreeMap::new();
+        for branch in file.branches.iter() {
+            let entry = branches.entry(branch.line_start).or_default();
+            entry.0 += (branch.execution_count > 0) as u64 + (branch.false_execution_count > 0) as u64;
+            entry.1 += 2;
+        }
+
+        writeln!(out, "          <lines>")?;
+        for line in file.lines.iter().filter(|l| l.mapped) {
+            match branches.get(&line.line) {
+                Some((covered, total)) => writeln!(
+                    out,
+                    r#"            <line number="{}" hits="{}" branch="true" condition-coverage="{}% ({}/{})"/>"#,
+                    line.line,
+                    line.count,
+                    covered * 100 / total,
+                    covered,
+                    total,
+                )?,
+                None => writeln!(out, r#"            <line number="{}" hits="{}" branch="false"/>"#, line.line, line.count)?,
+            }
+        }
+        writeln!(out, "          </lines>")?;
+
+        writeln!(out, "        </class>")
+    }
+
+    fn document(model: &ReportModel) -> Result<String, std::fmt::Error> {
+        let mut packages: BTreeMap<&str, Vec<&FileModel>> = BTreeMap::new();
+        for file in model.all_files() {
+            let package = file.name.rsplit_once('/').map(|(dir, _)| dir).unwrap_or(".");
+            packages.entry(package).or_default().push(file);
+        }
+
+        #[cfg(feature = "render-html")]
+        let timestamp = model.metadata.input_mtime.timestamp();
+        // Built without timestamps, but the DTD wants one
+        #[cfg(not(feature = "render-html"))]
+        let timestamp = 0;
+
+        let mut out = String::new();
+        writeln!(out, r#"<?xml version="1.0" ?>"#)?;
+        writeln!(out, r#"<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">"#)?;
+        writeln!(
+            out,
+            r#"<coverage line-rate="{}" branch-rate="{}" lines-covered="{}" lines-valid="{}" branches-covered="{}" branches-valid="{}" complexity="0" version="{} {}" timestamp="{}">"#,
+            rate(&model.totals.lines),
+            rate(&model.totals.branches),
+            model.totals.lines.covered,
+            model.totals.lines.count,
+            model.totals.branches.covered,
+            model.totals.branches.count,
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+            timestamp,
+        )?;
+        writeln!(out, "  <sources>")?;
+        writeln!(out, "    <source>.</source>")?;
+        writeln!(out, "  </sources>")?;
+        writeln!(out, "  <packages>")?;
+        for (name, files) in packages {
+            let summary = crate::FileCoverageSummary::total(files.iter().map(|f| &f.summary));
+            writeln!(
+                out,
+                r#"    <package name="{}" line-rate="{}" branch-rate="{}" complexity="0">"#,
+                xml_escape(name),
+                rate(&summary.lines),
+                rate(&summary.branches),
+            )?;
+            writeln!(out, "      <classes>")?;
+            for file in files {
+                Self::class(model, file, &mut out)?;
+            }
+            writeln!(out, "      </classes>")?;
+            writeln!(out, "    </package>")?;
+        }
+        writeln!(out, "  </packages>")?;
+        writeln!(out, "</coverage>")?;
+
+        Ok(out)
+    }
+}
+
+impl Exporter for Cobertura {
+    fn name(&self) -> &str {
+        "cobertura"
+    }
+
+    fn export(&self, model: &ReportModel, output_path: &Path) -> Result<(), Box<dyn StdError>> {
+        std::fs::write(output_path.join("cobertura.xml"), Self::document(model)?)?;
+        Ok(())
+    }
+}
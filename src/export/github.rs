@@ -0,0 +1,97 @@
+use std::error::Error as StdError;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::model::ReportModel;
+use super::Exporter;
+
+pub const GITHUB_ANNOTATIONS_FILENAME: &str = "github-annotations.json";
+
+/// Changed lines that never ran as GitHub Actions workflow commands, printed
+/// so they show up inline on the pull request diff, and as the annotations
+/// of a check run (`github-annotations.json`) for posting through the Checks
+/// API.  Needs `--diff-base`.
+#[derive(Default)]
+pub struct GithubAnnotations {
+    path_prefix: String,
+}
+
+/// One entry of `output.annotations` of a check run.
+#[derive(Serialize)]
+struct CheckAnnotation {
+    path: String,
+    start_line: i64,
+    end_line: i64,
+    annotation_level: &'static str,
+    title: &'static str,
+    message: String,
+}
+
+const TITLE: &str = "Uncovered change";
+
+/// Workflow commands treat `%`, and in properties `:` and `,`, as special.
+fn escape_property(value: &str) -> String {
+    escape_message(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+fn escape_message(value: &str) -> String {
+    value.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+impl GithubAnnotations {
+    /// Put in front of each path to make it relative to the repository root,
+    /// see [`crate::git::show_prefix`].
+    pub fn path_prefix(mut self, path_prefix: impl Into<String>) -> Self {
+        self.path_prefix = path_prefix.into();
+        self
+    }
+
+    fn annotations(&self, model: &ReportModel) -> Result<Vec<CheckAnnotation>, Box<dyn StdError>> {
+        let diff = model.diff.as_ref().ok_or("The github-annotations output format needs --diff-base")?;
+        Ok(diff.files
+            .iter()
+            .flat_map(|file| file.uncovered.iter().map(move |(start, end)| (file, *start, *end)))
+            .map(|(file, start_line, end_line)| CheckAnnotation {
+                path: format!("{}{}", self.path_prefix, file.filename),
+                start_line,
+                end_line,
+                annotation_level: "warning",
+                title: TITLE,
+                message: match start_line == end_line {
+                    true => "Line not covered".to_string(),
+                    false => format!("Lines {}-{} not covered", start_line, end_line),
+                },
+            })
+            .collect())
+    }
+
+    /// The workflow commands, one per run of changed lines that never ran.
+    pub fn commands(&self, model: &ReportModel) -> Result<String, Box<dyn StdError>> {
+        let mut out = String::new();
+        for annotation in self.annotations(model)? {
+            out.push_str(&format!(
+                "::warning file={},line={},endLine={},title={}::{}\n",
+                escape_property(&annotation.path),
+                annotation.start_line,
+                annotation.end_line,
+                escape_property(annotation.title),
+                escape_message(&annotation.message),
+            ));
+        }
+        Ok(out)
+    }
+}
+
+impl Exporter for GithubAnnotations {
+    fn name(&self) -> &str {
+        "github-annotations"
+    }
+
+    fn export(&self, model: &ReportModel, output_path: &Path) -> Result<(), Box<dyn StdError>> {
+        print!("{}", self.commands(model)?);
+        let annotations = self.annotations(model)?;
+        std::fs::write(output_path.join(GITHUB_ANNOTATIONS_FILENAME), serde_json::to_string_pretty(&annotations)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,709 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error as StdError;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+use rayon::prelude::*;
+
+use crate::anonymize::Anonymizer;
+use crate::cache::{self, Cache, CachedPage};
+use crate::check::{Goals, Threshold};
+use crate::diagnostics::Diagnostics;
+use crate::encoding::SourceEncoding;
+use crate::freshness;
+use crate::fs::FileLimiter;
+use crate::history::History;
+use crate::manifest::{self, Manifest};
+use crate::model::{FileModel, ReportModel};
+use crate::render::{self, Assets, Hosting, RenderCompare, RenderContext, RenderDiffCoverage, RenderDirectory, Highlighter, Palette, Sidebar, RenderFile, RenderFlaky, RenderFunction, RenderFunctionPage, RenderIndex, RenderLegend, RenderNotFound, RenderOmitted, RenderPublicApi, RenderSearch, RenderSingleFile, RenderStats, RenderTreemap, RenderWarnings, PostProcessor, Tab, Templates, Titles};
+use crate::utils;
+use super::Exporter;
+
+/// The HTML report: an index, a page per file, the functions page, the
+/// shared stylesheet, and a manifest of everything written.
+pub struct Html<'a> {
+    templates: Templates<'a>,
+    assets: Assets,
+    anonymizer: &'a Anonymizer,
+    input_path: &'a Path,
+    source_encoding: SourceEncoding,
+    history: Option<(&'a History, usize)>,
+    changed_files: Option<HashSet<String>>,
+    incremental: Option<String>,
+    thresholds: Vec<Threshold>,
+    goals: Goals,
+    palette: Palette,
+    print_of_record: bool,
+    hosting: Hosting,
+    labels: Vec<(String, ReportModel)>,
+    compare: Option<(String, String)>,
+    diagnostics: Diagnostics,
+    files: FileLimiter,
+    size_budget: Option<u64>,
+    postprocessors: Vec<Box<dyn PostProcessor>>,
+    highlighter: Option<Highlighter>,
+    titles: Titles,
+    sidebar: bool,
+    single_file: bool,
+    preview: Option<usize>,
+}
+
+impl<'a> Html<'a> {
+    pub fn new(assets: Assets, anonymizer: &'a Anonymizer, input_path: &'a Path) -> Result<Self, Box<dyn StdError>> {
+        Ok(Self {
+            templates: Templates::builtin()?,
+            assets,
+            anonymizer,
+            input_path,
+            source_encoding: SourceEncoding::default(),
+            history: None,
+            changed_files: None,
+            incremental: None,
+            thresholds: vec![],
+            goals: Goals::default(),
+            palette: Palette::default(),
+            print_of_record: false,
+            hosting: Hosting::default(),
+            labels: vec![],
+            compare: None,
+            diagnostics: Diagnostics::default(),
+            files: FileLimiter::default(),
+            size_budget: None,
+            postprocessors: vec![],
+            highlighter: None,
+            titles: Titles::default(),
+            sidebar: false,
+            single_file: false,
+            preview: None,
+        })
+    }
+
+    /// How the source files are decoded.
+    pub fn source_encoding(mut self, source_encoding: SourceEncoding) -> Self {
+        self.source_encoding = source_encoding;
+        self
+    }
+
+    /// Render a sparkline of the last `runs` runs next to each file.
+    pub fn history(mut self, history: &'a History, runs: usize) -> Self {
+        self.history = Some((history, runs));
+        self
+    }
+
+    /// Only render pages for these files (as named in the export).
+    pub fn changed_files(mut self, changed_files: HashSet<String>) -> Self {
+        self.changed_files = Some(changed_files);
+        self
+    }
+
+    /// Only render the pages of files whose source or coverage changed since
+    /// the pages in the output directory were rendered, going by the
+    /// `.cosmoline-cache.json` written there.  `settings` is whatever else
+    /// the pages depend on, e.g. the command line and the config file, so
+    /// that changing it renders every page again.  So do changes to the
+    /// custom templates.
+    pub fn incremental(mut self, settings: &str) -> Self {
+        self.incremental = Some(settings.to_string());
+        self
+    }
+
+    /// Thresholds the run is checked against, explained on the legend page.
+    pub fn thresholds(mut self, thresholds: Vec<Threshold>) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Per-metric goals the percentages are colored by.
+    pub fn goals(mut self, goals: Goals) -> Self {
+        self.goals = goals;
+        self
+    }
+
+    /// Colors coverage is shown in.
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Print the pages black on white, with coverage told apart by patterns
+    /// rather than colors, e.g. as evidence for a certification.
+    pub fn print_of_record(mut self, print_of_record: bool) -> Self {
+        self.print_of_record = print_of_record;
+        self
+    }
+
+    /// Extras for publishing the report on a public static host.
+    pub fn hosting(mut self, hosting: Hosting) -> Self {
+        self.hosting = hosting;
+        self
+    }
+
+    /// Separate views of the report, one per labeled input.  The main model
+    /// becomes the union view, and each view gets its own index in a tab.
+    pub fn labels(mut self, labels: Vec<(String, ReportModel)>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Also render every file with the counts of two of the labels side by
+    /// side, in a tab of its own.
+    pub fn compare_labels(mut self, left: &str, right: &str) -> Self {
+        self.compare = Some((left.to_string(), right.to_string()));
+        self
+    }
+
+    /// Problems found before exporting, e.g. while building the model, to
+    /// show next to the ones found while rendering.
+    pub fn diagnostics(mut self, diagnostics: Diagnostics) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Templates to render the pages with instead of the built-in ones.
+    pub fn templates(mut self, templates: Templates<'a>) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    /// Most files read or written at the same time.
+    pub fn max_open_files(mut self, max_open_files: usize) -> Self {
+        self.files = FileLimiter::new(max_open_files);
+        self
+    }
+
+    /// Warn when the report takes up more than this many bytes.
+    pub fn size_budget(mut self, bytes: u64) -> Self {
+        self.size_budget = Some(bytes);
+        self
+    }
+
+    /// Run each source line of the file pages through these, in order.
+    pub fn postprocessors(mut self, postprocessors: Vec<Box<dyn PostProcessor>>) -> Self {
+        self.postprocessors = postprocessors;
+        self
+    }
+
+    /// Syntax highlight the source on the file pages, showing coverage by
+    /// background color instead.
+    pub fn highlight(mut self, highlighter: Highlighter) -> Self {
+        self.highlighter = Some(highlighter);
+        self
+    }
+
+    /// Formats of the page titles and breadcrumbs.
+    pub fn titles(mut self, titles: Titles) -> Self {
+        self.titles = titles;
+        self
+    }
+
+    /// Show a tree of every directory and file with its coverage next to
+    /// every page.
+    pub fn sidebar(mut self) -> Self {
+        self.sidebar = true;
+        self
+    }
+
+    /// Write the whole report as a single `index.html`, with the styles
+    /// inlined and the source of every file in a collapsed section, e.g. to
+    /// attach to a CI run or send by email.
+    pub fn single_file(mut self) -> Self {
+        self.single_file = true;
+        self
+    }
+
+    /// Only render the pages of the `worst` least covered files, the index,
+    /// and the pages summing up the whole report, for a first look at a
+    /// large export.  Links to the pages left out lead to one saying so.
+    pub fn preview(mut self, worst: usize) -> Self {
+        self.preview = Some(worst);
+        self
+    }
+
+    /// The `worst` files with the lowest line coverage, the most lines that
+    /// never ran first among equals, by name in the export.
+    fn worst_files(model: &ReportModel, worst: usize) -> HashSet<&str> {
+        let mut files = model.all_files().filter(|file| file.summary.lines.count > 0).collect::<Vec<_>>();
+        files.sort_by(|a, b| {
+            let uncovered = |file: &FileModel| file.summary.lines.count - file.summary.lines.covered;
+            a.summary.lines.percent
+                .total_cmp(&b.summary.lines.percent)
+                .then_with(|| uncovered(b).cmp(&uncovered(a)))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        files.into_iter().take(worst).map(|file| file.filename.as_str()).collect()
+    }
+
+    /// Points out what's taking up the space and what would make the report
+    /// smaller.
+    fn over_budget(&self, sizes: &BTreeMap<String, u64>, budget: u64) -> String {
+        let total = sizes.values().sum::<u64>();
+        let mut message = format!(
+            "The report takes up {}B, more than the budget of {}B.",
+            utils::human_count(total as i64),
+            utils::human_count(budget as i64),
+        );
+        if let Some((kind, bytes)) = sizes.iter().max_by_key(|(_, bytes)| **bytes) {
+            message.push_str(&format!(" Most of it ({}B) is {} pages.", utils::human_count(*bytes as i64), kind));
+            if kind == "file" {
+                message.push_str(" Render only the files that changed with --only-changed, or leave some out with --exclude.");
+            }
+        }
+        if self.assets.inlines_any() {
+            message.push_str(" --no-inline-css and --no-inline-js share page styles and scripts instead of repeating them on every page.");
+        }
+        message.push_str(" If only the numbers are needed, --output-format json,markdown writes just the summaries.");
+        message
+    }
+
+    /// Collects the custom templates that failed and were stood in for by
+    /// the built-in ones, to show on the warnings page.
+    fn template_fallbacks(&self) {
+        for (name, fallback) in self.templates.fallbacks() {
+            self.diagnostics.error(None, format!(
+                "Custom template `{}' failed to render {} pages, which were rendered with the built-in one instead: {}",
+                name, fallback.pages, fallback.error,
+            ));
+        }
+    }
+
+    fn label_page(label: &str, page: &str) -> String {
+        // `@` never shows up in file pages, so views can't clash with them
+        format!("{}@{}", utils::escape_filename(label), page)
+    }
+
+    fn compare_name(&self) -> Option<String> {
+        self.compare.as_ref().map(|(left, right)| format!("{} vs {}", left, right))
+    }
+
+    /// Renders and writes the page of one file, unless only changed files
+    /// are rendered and it isn't one of them, or the page in the cache is
+    /// still up to date.  Called from the thread pool.
+    fn file_page(&self, model: &ReportModel, file: &FileModel, ctx: &RenderContext, output_path: &Path, previewed: Option<&HashSet<&str>>, cache: Option<&Cache>) -> std::io::Result<FilePage> {
+        if previewed.is_some_and(|previewed| !previewed.contains(file.filename.as_str())) {
+            return Ok(FilePage::Skipped);
+        }
+
+        if let Some(changed) = self.changed_files.as_ref() {
+            if !changed.contains(&file.filename) {
+                debug!("Skipping unchanged file {}", file.filename);
+                // Keep linking to a page left over from a previous full run
+                return Ok(match output_path.join(&file.page).exists() {
+                    true => FilePage::Kept,
+                    false => FilePage::Skipped,
+                });
+            }
+        }
+
+        let key = cache
+            .and_then(|_| self.files.read(&self.input_path.join(&file.filename)).ok())
+            .map(|source| cache::key(model, file, &source));
+        if let (Some(cache), Some(key)) = (cache, key.as_deref()) {
+            if let Some(cached) = cache.get(output_path, &file.page, key) {
+                debug!("Keeping the page of unchanged file {}", file.filename);
+                // The views still need it to tell whether theirs are the same
+                let output = match self.labels.is_empty() {
+                    true => None,
+                    false => self.files.read(&output_path.join(&file.page)).ok().and_then(|page| String::from_utf8(page).ok()),
+                };
+                return Ok(FilePage::Cached { page: cached.clone(), output });
+            }
+        }
+
+        let output = match RenderFile::new(model, file, &ctx.at(&file.page)).render() {
+            Ok(output) => output,
+            Err(e) => {
+                self.diagnostics.error(Some(&file.name), format!("No page rendered: {}", e));
+                return Ok(FilePage::Skipped);
+            }
+        };
+
+        write_page(&self.files, output_path, &file.page, &output)?;
+        Ok(FilePage::Written {
+            bytes: output.len() as u64,
+            sha256: manifest::sha256(&output),
+            key,
+            output: match self.labels.is_empty() {
+                true => None,
+                false => Some(output),
+            },
+        })
+    }
+
+    /// Writes the report as one page, and a manifest listing just it.
+    fn export_single_file(&self, model: &ReportModel, ctx: &RenderContext, output_path: &Path) -> Result<(), Box<dyn StdError>> {
+        let stylesheet = render::stylesheet(&self.templates, self.palette, self.print_of_record)?;
+        let output = RenderSingleFile::new(model, ctx, stylesheet).render()?;
+        self.files.write(&output_path.join("index.html"), &output)?;
+        info!("Report size: {}B", utils::human_count(output.len() as i64));
+
+        if let Some(budget) = self.size_budget.filter(|budget| output.len() as u64 > *budget) {
+            warn!("The report takes up {}B, more than the budget of {}B", utils::human_count(output.len() as i64), utils::human_count(budget as i64));
+        }
+        // There's no warnings page to link to, only the manifest
+        self.template_fallbacks();
+        if !self.diagnostics.is_empty() {
+            warn!("{} problems generating the report, see {}", self.diagnostics.len(), output_path.join(manifest::FILENAME).display());
+        }
+
+        let manifest = Manifest {
+            files: vec!["index.html".to_string()],
+            sizes: BTreeMap::from([("single_file".to_string(), output.len() as u64)]),
+            sources: freshness::source_hashes(model, self.input_path, &self.files),
+            hashes: BTreeMap::from([("index.html".to_string(), manifest::sha256(&output))]),
+            pages: BTreeMap::new(),
+            diagnostics: self.diagnostics.buckets(),
+        };
+        self.files.write(&output_path.join(manifest::FILENAME), serde_json::to_string_pretty(&manifest)?)?;
+
+        Ok(())
+    }
+
+    /// The pages among `written` whose names were cut short to fit in a
+    /// filename, with the shown name of the file or function on each.
+    fn shortened_pages(&self, model: &ReportModel, written: &[String]) -> BTreeMap<String, String> {
+        let mut shown = HashMap::new();
+        let views = self.labels.iter().map(|(label, _)| label.clone()).chain(self.compare_name()).collect::<Vec<_>>();
+        for file in model.all_files().filter(|file| utils::is_shortened(&file.page)) {
+            shown.insert(file.page.clone(), &file.name);
+            for view in views.iter() {
+                shown.insert(Self::label_page(view, &file.page), &file.name);
+            }
+        }
+        for function in model.functions.iter() {
+            let page = RenderFunctionPage::page(function);
+            if utils::is_shortened(&page) {
+                shown.insert(page, &function.name);
+            }
+        }
+
+        written
+            .iter()
+            .filter(|page| utils::is_shortened(page))
+            .filter_map(|page| Some((page.clone(), shown.get(page)?.to_string())))
+            .collect()
+    }
+
+    fn tabs(&self, ctx: &RenderContext, active: Option<&str>) -> Vec<Tab> {
+        if self.labels.is_empty() {
+            return vec![];
+        }
+
+        let mut tabs = vec![Tab { label: "All".into(), href: ctx.link("index.html"), active: active.is_none() }];
+        for (label, _) in self.labels.iter() {
+            tabs.push(Tab {
+                label: label.clone(),
+                href: ctx.link(&Self::label_page(label, "index.html")),
+                active: active == Some(label.as_str()),
+            });
+        }
+        if let Some(name) = self.compare_name() {
+            tabs.push(Tab {
+                href: ctx.link(&Self::label_page(&name, "index.html")),
+                active: active == Some(name.as_str()),
+                label: name,
+            });
+        }
+        tabs
+    }
+}
+
+/// What became of the page of a file.
+enum FilePage {
+    /// Not rendered, and not linked to.
+    Skipped,
+    /// Not rendered, but left over from a previous run.
+    Kept,
+    /// Rendered and written, keeping what was written if the views need it,
+    /// and what it was rendered from if it's cached.
+    Written { bytes: u64, sha256: String, key: Option<String>, output: Option<String> },
+    /// Not rendered, the page in the cache is still up to date.
+    Cached { page: CachedPage, output: Option<String> },
+}
+
+/// Writes `contents` to `name` below `output_path`, making the directories
+/// it goes in.
+fn write_page(files: &FileLimiter, output_path: &Path, name: &str, contents: &str) -> std::io::Result<()> {
+    let path = output_path.join(name);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    files.write(&path, contents)
+}
+
+impl<'a> Exporter for Html<'a> {
+    fn name(&self) -> &str {
+        "html"
+    }
+
+    fn export(&self, model: &ReportModel, output_path: &Path) -> Result<(), Box<dyn StdError>> {
+        let sidebar = self.sidebar.then(|| Sidebar::new(model, &self.goals));
+        let ctx = RenderContext {
+            templates: &self.templates,
+            assets: &self.assets,
+            anonymizer: self.anonymizer,
+            input_path: self.input_path,
+            source_encoding: self.source_encoding,
+            hosting: &self.hosting,
+            goals: &self.goals,
+            palette: self.palette,
+            diagnostics: &self.diagnostics,
+            files: &self.files,
+            postprocessors: &self.postprocessors,
+            highlighter: self.highlighter.as_ref(),
+            titles: &self.titles,
+            sidebar: sidebar.as_ref(),
+            page: "index.html".to_string(),
+            depth: 0,
+        };
+
+        if self.single_file {
+            return self.export_single_file(model, &ctx, output_path);
+        }
+
+        // File pages are rendered and written on a thread pool, the rest of
+        // the bookkeeping happens in order afterwards
+        let started = Instant::now();
+        let all_files = model.all_files().collect::<Vec<_>>();
+        let previewed = self.preview.map(|worst| Self::worst_files(model, worst));
+        let settings = self.incremental.as_ref().map(|settings| format!("{} {}", settings, self.templates.fingerprint()));
+        let cache = settings.as_deref().map(|settings| Cache::load(output_path, settings, &self.files));
+        let file_pages = all_files
+            .par_iter()
+            .map(|file| self.file_page(model, file, &ctx, output_path, previewed.as_ref(), cache.as_ref()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        // Time spent on each kind of page, rendering and writing it
+        let mut timings: BTreeMap<String, Duration> = BTreeMap::new();
+        timings.insert("file".to_string(), started.elapsed());
+
+        let mut written = vec![];
+        let mut sizes: BTreeMap<String, u64> = BTreeMap::new();
+        let mut hashes = BTreeMap::new();
+        // Kept to find view pages identical to the union's
+        let mut rendered = HashMap::new();
+        let mut pages = HashSet::new();
+        let mut next_cache = settings.as_deref().map(Cache::new);
+        for (file, file_page) in all_files.iter().zip(file_pages) {
+            let (page, output) = match file_page {
+                FilePage::Skipped => continue,
+                FilePage::Kept => {
+                    pages.insert(file.page.clone());
+                    continue;
+                }
+                FilePage::Written { bytes, sha256, key, output } => {
+                    let page = CachedPage { key: key.unwrap_or_default(), bytes, sha256 };
+                    (page, output)
+                }
+                FilePage::Cached { page, output } => (page, output),
+            };
+
+            written.push(file.page.clone());
+            *sizes.entry("file".to_string()).or_default() += page.bytes;
+            hashes.insert(file.page.clone(), page.sha256.clone());
+            pages.insert(file.page.clone());
+            if let Some(output) = output {
+                rendered.insert(file.filename.as_str(), output);
+            }
+            if let Some(next_cache) = next_cache.as_mut().filter(|_| !page.key.is_empty()) {
+                next_cache.pages.insert(file.page.clone(), page);
+            }
+        }
+
+        // Each page is rendered right before it's written, so it took the
+        // time since the last one was written
+        let mut lap = Instant::now();
+        let mut write = |kind: &str, name: &str, contents: &str| -> std::io::Result<()> {
+            write_page(&self.files, output_path, name, contents)?;
+            written.push(name.to_string());
+            *sizes.entry(kind.to_string()).or_default() += contents.len() as u64;
+            hashes.insert(name.to_string(), manifest::sha256(contents));
+            *timings.entry(kind.to_string()).or_default() += lap.elapsed();
+            lap = Instant::now();
+            Ok(())
+        };
+
+        // Views go first so the index can count their diagnostics
+        for (label, labeled) in self.labels.iter() {
+            let mut links = HashMap::new();
+            let mut label_pages = pages.clone();
+            for file in labeled.all_files() {
+                let page = Self::label_page(label, &file.page);
+                let output = match RenderFile::new(labeled, file, &ctx.at(&page)).render() {
+                    Ok(output) => output,
+                    Err(e) => {
+                        self.diagnostics.error(Some(&file.name), format!("No page rendered for {}: {}", label, e));
+                        continue;
+                    }
+                };
+                if rendered.get(file.filename.as_str()) == Some(&output) {
+                    continue;
+                }
+
+                write("file", &page, &output)?;
+                label_pages.insert(page.clone());
+                links.insert(file.filename.clone(), page);
+            }
+
+            let render = RenderIndex::new(labeled, &ctx)
+                .links(links)
+                .pages(label_pages)
+                .tabs(self.tabs(&ctx, Some(label)));
+            write("index", &Self::label_page(label, "index.html"), &render.render()?)?;
+        }
+
+        if let (Some((left, right)), Some(name)) = (self.compare.as_ref(), self.compare_name()) {
+            let labeled = |label: &str| {
+                self.labels.iter().find(|(l, _)| l == label).map(|(_, m)| m).ok_or_else(|| format!("No input labeled `{}' to compare", label))
+            };
+            let (left_model, right_model) = (labeled(left)?, labeled(right)?);
+            let mut links = HashMap::new();
+            let mut compare_pages = HashSet::new();
+            for file in model.all_files() {
+                let page = Self::label_page(&name, &file.page);
+                let output = match RenderCompare::new(model, file, (left, left_model), (right, right_model), &ctx.at(&page)).render() {
+                    Ok(output) => output,
+                    Err(e) => {
+                        self.diagnostics.error(Some(&file.name), format!("No page rendered for {}: {}", name, e));
+                        continue;
+                    }
+                };
+                write("compare", &page, &output)?;
+                compare_pages.insert(page.clone());
+                links.insert(file.filename.clone(), page);
+            }
+
+            let render = RenderIndex::new(model, &ctx)
+                .links(links)
+                .pages(compare_pages)
+                .tabs(self.tabs(&ctx, Some(&name)));
+            write("index", &Self::label_page(&name, "index.html"), &render.render()?)?;
+        }
+
+        // Everything the preview leaves out links to the page saying so
+        let mut omitted = HashMap::new();
+        if let Some(previewed) = previewed.as_ref() {
+            write("omitted", RenderOmitted::PAGE, &RenderOmitted::new(model, &ctx, previewed.len()).render()?)?;
+            pages.insert(RenderOmitted::PAGE.to_string());
+            for file in all_files.iter().filter(|file| !previewed.contains(file.filename.as_str())) {
+                omitted.insert(file.filename.clone(), RenderOmitted::PAGE.to_string());
+            }
+        } else {
+            for dir in render::directories(model) {
+                let page = format!("{}/index.html", dir);
+                write("directory", &page, &RenderDirectory::new(model, &dir, &ctx.at(&page)).render()?)?;
+            }
+        }
+
+        {
+            let mut search = RenderSearch::new(model, &ctx).pages(pages.clone()).links(omitted.clone());
+            if previewed.is_some() {
+                search = search.omit_function_pages();
+            }
+            write("search", RenderSearch::PAGE, &search.render()?)?;
+            write("search", RenderSearch::INDEX, &search.index()?)?;
+        }
+
+        {
+            let mut render = RenderIndex::new(model, &ctx)
+                .pages(pages.clone())
+                .links(omitted.clone())
+                .tabs(self.tabs(&ctx, None));
+            if previewed.is_none() {
+                render = render.directories();
+            }
+            if let Some((history, runs)) = self.history {
+                render = render.history(history, runs);
+            }
+
+            write("index", "index.html", &render.render()?)?;
+        }
+
+        write("assets", "style.css", &render::stylesheet(&self.templates, self.palette, self.print_of_record)?)?;
+        match previewed.is_some() {
+            true => write("functions", "functions.html", &RenderFunction::new(model, &ctx).omit_function_pages().render()?)?,
+            false => {
+                write("functions", "functions.html", &RenderFunction::new(model, &ctx).render()?)?;
+                for function in model.functions.iter() {
+                    let page = RenderFunctionPage::page(function);
+                    write("function", &page, &RenderFunctionPage::new(model, function, &ctx.at(&page)).pages(&pages).render()?)?;
+                }
+            }
+        }
+        write("legend", "legend.html", &RenderLegend::new(model, &ctx).thresholds(&self.thresholds).render()?)?;
+
+        write("stats", "stats.html", &RenderStats::new(model, &ctx).render()?)?;
+        write("treemap", "treemap.html", &RenderTreemap::new(model, &ctx).pages(pages).links(omitted).render()?)?;
+
+        if model.flaky.is_some() {
+            write("flaky", "flaky.html", &RenderFlaky::new(model, &ctx).render()?)?;
+        }
+
+        if model.public_api.is_some() {
+            write("public_api", "public_api.html", &RenderPublicApi::new(model, &ctx).render()?)?;
+        }
+
+        if model.diff.is_some() {
+            write("diff", "diff.html", &RenderDiffCoverage::new(model, &ctx).render()?)?;
+        }
+
+        if self.hosting.not_found_page {
+            write("not_found", "404.html", &RenderNotFound::new(model, &ctx).render()?)?;
+        }
+
+        if self.hosting.robots_txt {
+            write("robots", "robots.txt", "User-agent: *\nDisallow: /\n")?;
+        }
+
+        for (filename, size, sha256) in self.assets.write_all(output_path, &self.files)? {
+            *sizes.entry("assets".to_string()).or_default() += size;
+            hashes.insert(filename.clone(), sha256);
+            written.push(filename);
+        }
+
+        // The warnings page is small enough not to matter, but it has to
+        // come last to show this
+        if let Some(budget) = self.size_budget.filter(|budget| sizes.values().sum::<u64>() > *budget) {
+            self.diagnostics.warn(None, self.over_budget(&sizes, budget));
+        }
+
+        self.template_fallbacks();
+        if !self.diagnostics.is_empty() {
+            warn!("{} problems generating the report, see {}", self.diagnostics.len(), output_path.join("warnings.html").display());
+            let output = RenderWarnings::new(model, &ctx).render()?;
+            self.files.write(&output_path.join("warnings.html"), &output)?;
+            written.push("warnings.html".to_string());
+            *sizes.entry("warnings".to_string()).or_default() += output.len() as u64;
+            hashes.insert("warnings.html".to_string(), manifest::sha256(&output));
+        }
+
+        let by_kind = sizes
+            .iter()
+            .map(|(kind, bytes)| format!("{} {}B", kind, utils::human_count(*bytes as i64)))
+            .collect::<Vec<_>>();
+        info!("Report size: {}B ({})", utils::human_count(sizes.values().sum::<u64>() as i64), by_kind.join(", "));
+        let by_kind = timings
+            .iter()
+            .map(|(kind, took)| format!("{} {:.3}s", kind, took.as_secs_f64()))
+            .collect::<Vec<_>>();
+        info!("Render time by kind of page: {}", by_kind.join(", "));
+
+        let pages = self.shortened_pages(model, &written);
+        let manifest = Manifest {
+            files: written,
+            sizes,
+            sources: freshness::source_hashes(model, self.input_path, &self.files),
+            hashes,
+            pages,
+            diagnostics: self.diagnostics.buckets(),
+        };
+        self.files.write(&output_path.join(manifest::FILENAME), serde_json::to_string_pretty(&manifest)?)?;
+
+        if let Some(next_cache) = next_cache {
+            next_cache.save(output_path, &self.files)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::{FileCoverageSummary, Summary};
+use crate::model::ReportModel;
+use super::Exporter;
+
+pub const JSON_FILENAME: &str = "coverage.json";
+/// Version of the layout of `coverage.json`.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+pub const JSON_SUMMARY_FILENAME: &str = "coverage-summary.json";
+/// Version of the layout of `coverage-summary.json`.
+pub const JSON_SUMMARY_SCHEMA_VERSION: u32 = 1;
+
+/// The report model as JSON (`coverage.json`): per-file, per-directory, and
+/// per-crate summaries, the function list, and totals, after filtering and
+/// merging.
+pub struct Json;
+
+impl Exporter for Json {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn export(&self, model: &ReportModel, output_path: &Path) -> Result<(), Box<dyn StdError>> {
+        std::fs::write(output_path.join(JSON_FILENAME), serde_json::to_string_pretty(model)?)?;
+        Ok(())
+    }
+}
+
+/// Just the line, function, and branch counts of every reported file and
+/// the totals (`coverage-summary.json`), after filtering and merging, for
+/// tools that want the numbers without the rest of the model.
+pub struct JsonSummary;
+
+#[derive(Serialize)]
+struct Counts {
+    count: u64,
+    covered: u64,
+    percent: f64,
+}
+
+#[derive(Serialize)]
+struct Metrics {
+    lines: Counts,
+    functions: Counts,
+    branches: Counts,
+}
+
+#[derive(Serialize)]
+struct SummaryDocument<'a> {
+    version: u32,
+    totals: Metrics,
+    /// By name as shown in the report.
+    files: BTreeMap<&'a str, Metrics>,
+}
+
+impl From<&Summary> for Counts {
+    fn from(summary: &Summary) -> Self {
+        Self { count: summary.count, covered: summary.covered, percent: summary.percent }
+    }
+}
+
+impl From<&FileCoverageSummary> for Metrics {
+    fn from(summary: &FileCoverageSummary) -> Self {
+        Self {
+            lines: (&summary.lines).into(),
+            functions: (&summary.functions).into(),
+            branches: (&summary.branches).into(),
+        }
+    }
+}
+
+impl Exporter for JsonSummary {
+    fn name(&self) -> &str {
+        "json-summary"
+    }
+
+    fn export(&self, model: &ReportModel, output_path: &Path) -> Result<(), Box<dyn StdError>> {
+        let document = SummaryDocument {
+            version: JSON_SUMMARY_SCHEMA_VERSION,
+            totals: (&model.totals).into(),
+            files: model.files.iter().map(|file| (file.name.as_str(), (&file.summary).into())).collect(),
+        };
+        std::fs::write(output_path.join(JSON_SUMMARY_FILENAME), serde_json::to_string(&document)?)?;
+        Ok(())
+    }
+}
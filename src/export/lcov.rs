@@ -0,0 +1,69 @@
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::model::{FileModel, ReportModel};
+use super::Exporter;
+
+/// LCOV tracefile (`lcov.info`), as read by genhtml, codecov, coveralls and
+/// most editor plugins.
+pub struct Lcov;
+
+impl Lcov {
+    fn record(model: &ReportModel, file: &FileModel, out: &mut String) -> std::fmt::Result {
+        writeln!(out, "TN:")?;
+        writeln!(out, "SF:{}", file.name)?;
+
+        let functions = model.file_functions(file).collect::<Vec<_>>();
+        for function in functions.iter() {
+            let line = function.regions.first().map(|r| r.line_start).unwrap_or(0);
+            writeln!(out, "FN:{},{}", line, function.name)?;
+        }
+        for function in functions.iter() {
+            writeln!(out, "FNDA:{},{}", function.count, function.name)?;
+        }
+        writeln!(out, "FNF:{}", functions.len())?;
+        writeln!(out, "FNH:{}", functions.iter().filter(|f| f.count > 0).count())?;
+
+        // Each llvm branch has a true and a false arm; number the branches on
+        // a line in the order they appear.
+        let mut branches: BTreeMap<i64, usize> = BTreeMap::new();
+        let mut arms_hit = 0;
+        for branch in file.branches.iter() {
+            let index = branches.entry(branch.line_start).or_default();
+            for count in [branch.execution_count, branch.false_execution_count].iter() {
+                writeln!(out, "BRDA:{},0,{},{}", branch.line_start, *index, count)?;
+                arms_hit += (*count > 0) as usize;
+                *index += 1;
+            }
+        }
+        writeln!(out, "BRF:{}", file.branches.len() * 2)?;
+        writeln!(out, "BRH:{}", arms_hit)?;
+
+        let lines = file.lines.iter().filter(|l| l.mapped).collect::<Vec<_>>();
+        for line in lines.iter() {
+            writeln!(out, "DA:{},{}", line.line, line.count)?;
+        }
+        writeln!(out, "LF:{}", lines.len())?;
+        writeln!(out, "LH:{}", lines.iter().filter(|l| l.count > 0).count())?;
+
+        writeln!(out, "end_of_record")
+    }
+}
+
+impl Exporter for Lcov {
+    fn name(&self) -> &str {
+        "lcov"
+    }
+
+    fn export(&self, model: &ReportModel, output_path: &Path) -> Result<(), Box<dyn StdError>> {
+        let mut out = String::new();
+        for file in model.all_files() {
+            Self::record(model, file, &mut out)?;
+        }
+
+        std::fs::write(output_path.join("lcov.info"), out)?;
+        Ok(())
+    }
+}
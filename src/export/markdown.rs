@@ -0,0 +1,133 @@
+use std::error::Error as StdError;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::check::{self, Threshold};
+use crate::model::ReportModel;
+use crate::Summary;
+use super::Exporter;
+
+/// A Markdown summary table (`coverage.md`), handy for pull request comments
+/// and CI job summaries.
+#[derive(Default)]
+pub struct Markdown {
+    thresholds: Vec<Threshold>,
+}
+
+fn cell(summary: &Summary) -> String {
+    match summary.count {
+        0 => "–".to_string(),
+        _ => format!("{:.1}% ({}/{})", summary.percent, summary.covered, summary.count),
+    }
+}
+
+impl Markdown {
+    /// Thresholds the run is checked against; any that aren't met are listed
+    /// below the table.
+    pub fn thresholds(mut self, thresholds: Vec<Threshold>) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    fn document(&self, model: &ReportModel) -> Result<String, std::fmt::Error> {
+        let mut out = String::new();
+
+        match model.metadata.package.as_ref() {
+            Some(package) => writeln!(out, "# Code Coverage for {}", package)?,
+            None => writeln!(out, "# Code Coverage Report")?,
+        }
+        writeln!(out)?;
+        writeln!(out, "| File | Lines | Functions | Branches |")?;
+        writeln!(out, "|:-----|------:|----------:|---------:|")?;
+        for file in model.all_files() {
+            writeln!(
+                out,
+                "| `{}` | {} | {} | {} |",
+                file.name,
+                cell(&file.summary.lines),
+                cell(&file.summary.functions),
+                cell(&file.summary.branches),
+            )?;
+        }
+        writeln!(
+            out,
+            "| **Total** | **{}** | **{}** | **{}** |",
+            cell(&model.totals.lines),
+            cell(&model.totals.functions),
+            cell(&model.totals.branches),
+        )?;
+
+        if let Some(age) = model.commit_age.as_ref() {
+            writeln!(out)?;
+            writeln!(out, "## Lines by Commit Age")?;
+            writeln!(out)?;
+            writeln!(out, "| File | Last {} Days | Older |", age.days)?;
+            writeln!(out, "|:-----|------:|------:|")?;
+            for file in age.files.iter() {
+                writeln!(out, "| `{}` | {} | {} |", file.name, cell(&file.recent), cell(&file.older))?;
+            }
+            writeln!(out, "| **Total** | **{}** | **{}** |", cell(&age.recent), cell(&age.older))?;
+        }
+
+        if !model.dropped_functions.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "## Functions No Longer Run")?;
+            writeln!(out)?;
+            writeln!(out, "These ran in the baseline but not in this run, which usually means tests were deleted or disabled.")?;
+            writeln!(out)?;
+            for function in model.dropped_functions.iter() {
+                let renamed = match function.renamed_from.as_ref() {
+                    Some(old_name) => format!(", was `{}`", old_name),
+                    None => String::new(),
+                };
+                match function.file.as_ref() {
+                    Some(file) => writeln!(out, "- `{}` in `{}` ({} hits before{})", function.name, file, function.baseline_count, renamed)?,
+                    None => writeln!(out, "- `{}` ({} hits before{})", function.name, function.baseline_count, renamed)?,
+                }
+            }
+        }
+
+        if !model.renamed_functions.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "## Renamed Functions")?;
+            writeln!(out)?;
+            for function in model.renamed_functions.iter() {
+                match function.file.as_ref() {
+                    Some(file) => writeln!(out, "- `{}` → `{}` in `{}` ({} → {} hits)", function.old_name, function.name, file, function.baseline_count, function.count)?,
+                    None => writeln!(out, "- `{}` → `{}` ({} → {} hits)", function.old_name, function.name, function.baseline_count, function.count)?,
+                }
+            }
+        }
+
+        if !self.thresholds.is_empty() {
+            let outcomes = check::evaluate(&self.thresholds, &model.totals);
+            writeln!(out)?;
+            writeln!(out, "## Thresholds")?;
+            writeln!(out)?;
+            let unmet = outcomes.iter().filter(|o| !o.passed()).collect::<Vec<_>>();
+            if unmet.is_empty() {
+                writeln!(out, "All {} checks passed.", outcomes.len())?;
+            }
+            for outcome in unmet {
+                let marker = match outcome.failed() {
+                    true => "❌",
+                    false => "⚠️",
+                };
+                writeln!(out, "- {} {}", marker, outcome.message())?;
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+impl Exporter for Markdown {
+    fn name(&self) -> &str {
+        "markdown"
+    }
+
+    fn export(&self, model: &ReportModel, output_path: &Path) -> Result<(), Box<dyn StdError>> {
+        std::fs::write(output_path.join("coverage.md"), self.document(model)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error as StdError;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::model::ReportModel;
+use super::Exporter;
+
+pub const REACHABILITY_FILENAME: &str = "reachability.json";
+/// Version of the layout of `reachability.json`.
+pub const REACHABILITY_SCHEMA_VERSION: u32 = 1;
+
+/// Which labeled inputs (test suites) executed each instrumented line
+/// (`reachability.json`), for mutation testing tools to skip mutants on
+/// lines nothing reaches and to only run the suites that reach the rest.
+pub struct Reachability {
+    labels: Vec<String>,
+    /// Lines each label executed, by file name in the export.
+    hits: Vec<HashMap<String, HashSet<i64>>>,
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    version: u32,
+    labels: &'a [String],
+    /// Every instrumented line of every reported file, with the labels that
+    /// executed it; an empty list means it's unreached.
+    files: BTreeMap<&'a str, BTreeMap<i64, Vec<&'a str>>>,
+}
+
+impl Reachability {
+    /// One report model per label, as built for `--no-merge`.
+    pub fn new(labels: &[(String, ReportModel)]) -> Self {
+        let hits = labels
+            .iter()
+            .map(|(_, model)| {
+                model.files
+                    .iter()
+                    .map(|file| {
+                        let lines = file.lines.iter().filter(|l| l.mapped && l.count > 0).map(|l| l.line).collect();
+                        (file.filename.clone(), lines)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            labels: labels.iter().map(|(label, _)| label.clone()).collect(),
+            hits,
+        }
+    }
+}
+
+impl Exporter for Reachability {
+    fn name(&self) -> &str {
+        "reachability"
+    }
+
+    fn export(&self, model: &ReportModel, output_path: &Path) -> Result<(), Box<dyn StdError>> {
+        let files = model.files
+            .iter()
+            .map(|file| {
+                let lines = file.lines
+                    .iter()
+                    .filter(|l| l.mapped)
+                    .map(|l| {
+                        let labels = self.labels
+                            .iter()
+                            .zip(self.hits.iter())
+                            .filter(|(_, hits)| hits.get(&file.filename).is_some_and(|lines| lines.contains(&l.line)))
+                            .map(|(label, _)| label.as_str())
+                            .collect();
+                        (l.line, labels)
+                    })
+                    .collect();
+                (file.name.as_str(), lines)
+            })
+            .collect();
+
+        let document = Document { version: REACHABILITY_SCHEMA_VERSION, labels: &self.labels, files };
+        std::fs::write(output_path.join(REACHABILITY_FILENAME), serde_json::to_string_pretty(&document)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use std::error::Error as StdError;
+use std::fmt::Write;
+use std::path::{Component, Path, PathBuf};
+
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+
+use crate::anonymize::Anonymizer;
+use crate::encoding::{self, SourceEncoding};
+use crate::fs::FileLimiter;
+use crate::model::{FileModel, ReportModel};
+use super::Exporter;
+
+/// Directory inside the output directory the annotated sources go into.
+pub const TEXT_DIR: &str = "text";
+
+const LINE_NUMBER_WIDTH: usize = 5;
+const COUNT_WIDTH: usize = 7;
+
+/// Annotated sources the way `llvm-cov show -format=text` writes them: the
+/// line number and count in a gutter, and a `^count` marker under each
+/// region that ran a different number of times than its line.  One file per
+/// source, e.g. `text/src/lib.rs.txt`, to diff between runs.
+pub struct Text<'a> {
+    anonymizer: &'a Anonymizer,
+    input_path: &'a Path,
+    source_encoding: SourceEncoding,
+    files: FileLimiter,
+}
+
+impl<'a> Text<'a> {
+    pub fn new(anonymizer: &'a Anonymizer, input_path: &'a Path) -> Self {
+        Self {
+            anonymizer,
+            input_path,
+            source_encoding: SourceEncoding::default(),
+            files: FileLimiter::default(),
+        }
+    }
+
+    /// How the source files are decoded.
+    pub fn source_encoding(mut self, source_encoding: SourceEncoding) -> Self {
+        self.source_encoding = source_encoding;
+        self
+    }
+
+    /// Most files read or written at the same time.
+    pub fn max_open_files(mut self, max_open_files: usize) -> Self {
+        self.files = FileLimiter::new(max_open_files);
+        self
+    }
+
+    fn annotate(&self, file: &FileModel) -> Result<String, Box<dyn StdError>> {
+        let source = encoding::decode(&self.files.read(&self.input_path.join(&file.filename))?, self.source_encoding);
+
+        let mut out = String::new();
+        for (idx, text) in source.lines().enumerate() {
+            let line_number = idx as i64 + 1;
+            let coverage = file.lines.get(idx).filter(|l| l.mapped);
+
+            let count = coverage.map(|l| format_count(l.count)).unwrap_or_default();
+            writeln!(
+                out,
+                "{:>line_width$}|{:>count_width$}|{}",
+                line_number,
+                count,
+                self.anonymizer.source_line(text.to_string()),
+                line_width = LINE_NUMBER_WIDTH,
+                count_width = COUNT_WIDTH,
+            )?;
+
+            let line_count = match coverage {
+                Some(line) => line.count,
+                None => continue,
+            };
+            let markers = file
+                .segments
+                .iter()
+                .filter(|s| s.line == line_number && s.is_region_entry && s.has_count && !s.is_gap_region)
+                .filter(|s| s.count != line_count)
+                .collect::<Vec<_>>();
+            if markers.is_empty() {
+                continue;
+            }
+
+            // Same as llvm: the marker's caret sits under the region's column
+            let mut marker_line = " ".repeat(LINE_NUMBER_WIDTH + COUNT_WIDTH + 2);
+            let mut column = 1;
+            for segment in markers {
+                let col = segment.col as usize;
+                if col > column {
+                    marker_line.push_str(&" ".repeat(col - column));
+                }
+                let count = format_count(segment.count);
+                column = col + 1 + count.len();
+                write!(marker_line, "^{}", count)?;
+            }
+            writeln!(out, "{}", marker_line)?;
+        }
+
+        Ok(out)
+    }
+}
+
+/// Counts with more than three digits are shortened to three significant
+/// digits and a suffix, e.g. `1.23k`, like llvm-cov does.
+fn format_count(count: i64) -> String {
+    let digits = count.to_string();
+    if digits.len() <= 3 {
+        return digits;
+    }
+
+    let int_len = match digits.len() % 3 {
+        0 => 3,
+        len => len,
+    };
+    let mut short = digits[..int_len].to_string();
+    if int_len != 3 {
+        short.push('.');
+        short.push_str(&digits[int_len..3]);
+    }
+    short.push(" kMGTPE".as_bytes()[(digits.len() - 1) / 3] as char);
+    short
+}
+
+/// Where a file's annotated source goes, mirroring its path in the report.
+/// Anything that could end up outside the directory is left out.
+fn text_path(name: &str) -> PathBuf {
+    let mut path = PathBuf::from(TEXT_DIR);
+    for component in Path::new(name).components() {
+        if let Component::Normal(part) = component {
+            path.push(part);
+        }
+    }
+    path.set_file_name(format!("{}.txt", path.file_name().unwrap_or_default().to_string_lossy()));
+    path
+}
+
+impl<'a> Exporter for Text<'a> {
+    fn name(&self) -> &str {
+        "text"
+    }
+
+    fn export(&self, model: &ReportModel, output_path: &Path) -> Result<(), Box<dyn StdError>> {
+        for file in model.all_files() {
+            let annotated = match self.annotate(file) {
+                Ok(annotated) => annotated,
+                Err(e) => {
+                    warn!("Skipping the annotated source of {}: {}", file.name, e);
+                    continue;
+                }
+            };
+
+            let path = output_path.join(text_path(&file.name));
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            self.files.write(&path, annotated)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error as StdError;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::model::ReportModel;
+use super::Exporter;
+
+pub const UNIQUE_FILENAME: &str = "unique.json";
+/// Version of the layout of `unique.json`.
+pub const UNIQUE_SCHEMA_VERSION: u32 = 1;
+
+/// What each labeled input (a test binary, usually) covers that none of the
+/// others do (`unique.json`), so CI can run the tests contributing the most
+/// first, or shard by contribution.
+pub struct Unique {
+    tests: Vec<UniqueCoverage>,
+}
+
+#[derive(Serialize)]
+struct UniqueCoverage {
+    label: String,
+    /// Lines only this label ran.
+    unique_lines: usize,
+    /// Files only this label ran any line of.
+    files: Vec<String>,
+    /// Functions only this label ran.
+    functions: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    version: u32,
+    /// Most unique lines first.
+    tests: &'a [UniqueCoverage],
+}
+
+impl Unique {
+    /// One report model per label, as built for `--no-merge`.
+    pub fn new(labels: &[(String, ReportModel)]) -> Self {
+        // Lines, files and functions by the labels that ran them
+        let mut lines: BTreeMap<(&str, i64), BTreeSet<usize>> = BTreeMap::new();
+        let mut files: BTreeMap<&str, BTreeSet<usize>> = BTreeMap::new();
+        let mut functions: BTreeMap<&str, BTreeSet<usize>> = BTreeMap::new();
+        for (idx, (_, model)) in labels.iter().enumerate() {
+            for file in model.files.iter() {
+                for line in file.lines.iter().filter(|l| l.mapped && l.count > 0) {
+                    lines.entry((&file.name, line.line)).or_default().insert(idx);
+                    files.entry(&file.name).or_default().insert(idx);
+                }
+            }
+            for function in model.functions.iter().filter(|f| f.count > 0) {
+                functions.entry(&function.name).or_default().insert(idx);
+            }
+        }
+
+        let only = |ran: &BTreeSet<usize>, idx: usize| ran.len() == 1 && ran.contains(&idx);
+        let mut tests: Vec<_> = labels
+            .iter()
+            .enumerate()
+            .map(|(idx, (label, _))| UniqueCoverage {
+                label: label.clone(),
+                unique_lines: lines.values().filter(|ran| only(ran, idx)).count(),
+                files: files.iter().filter(|(_, ran)| only(ran, idx)).map(|(name, _)| name.to_string()).collect(),
+                functions: functions.iter().filter(|(_, ran)| only(ran, idx)).map(|(name, _)| name.to_string()).collect(),
+            })
+            .collect();
+        tests.sort_by_key(|test| std::cmp::Reverse(test.unique_lines));
+
+        Self { tests }
+    }
+}
+
+impl Exporter for Unique {
+    fn name(&self) -> &str {
+        "unique"
+    }
+
+    fn export(&self, _model: &ReportModel, output_path: &Path) -> Result<(), Box<dyn StdError>> {
+        let document = Document { version: UNIQUE_SCHEMA_VERSION, tests: &self.tests };
+        std::fs::write(output_path.join(UNIQUE_FILENAME), serde_json::to_string_pretty(&document)?)?;
+        Ok(())
+    }
+}
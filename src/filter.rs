@@ -0,0 +1,253 @@
+//! Which files from the export end up in the report, and in which section.
+//! Every file goes through the same stages in order: include globs, exclude
+//! globs, ignore files, third-party routing, and per-crate mapping.  The
+//! first stage that rejects a file decides, and [`Filter::decide`] records
+//! what each stage made of it so `--explain-filter` can show why.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+
+use crate::utils;
+
+/// Looked for in the source directory.
+pub const IGNORE_FILENAME: &str = ".cosmolineignore";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    Include,
+    Exclude,
+    IgnoreFile,
+    ThirdParty,
+    Crate,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Stage::Include => "include",
+            Stage::Exclude => "exclude",
+            Stage::IgnoreFile => "ignore file",
+            Stage::ThirdParty => "third-party",
+            Stage::Crate => "crate",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Excluded(Stage),
+    /// A file of the project, attributed to a crate by `--crate-dir`.
+    Project { crate_name: Option<String> },
+    /// Third party code, reported in its own section.
+    Dependency,
+}
+
+#[derive(Debug)]
+pub struct Decision {
+    pub verdict: Verdict,
+    /// What each stage the file got to made of it.
+    pub steps: Vec<(Stage, String)>,
+}
+
+impl Decision {
+    pub fn is_reported(&self) -> bool {
+        !matches!(self.verdict, Verdict::Excluded(_))
+    }
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (stage, note) in self.steps.iter() {
+            writeln!(f, "{:>12}: {}", stage.to_string(), note)?;
+        }
+        match &self.verdict {
+            Verdict::Excluded(stage) => write!(f, "Excluded by the {} stage", stage),
+            Verdict::Project { crate_name: Some(name) } => write!(f, "Reported as part of crate {}", name),
+            Verdict::Project { crate_name: None } => write!(f, "Reported"),
+            Verdict::Dependency => write!(f, "Reported as a dependency"),
+        }
+    }
+}
+
+/// Globs along with where they came from, to explain matches.
+#[derive(Debug)]
+struct Patterns {
+    origin: String,
+    globs: Vec<String>,
+    set: GlobSet,
+}
+
+impl Patterns {
+    fn new(origin: impl Into<String>, globs: Vec<String>) -> Result<Self, Box<dyn StdError>> {
+        let mut builder = GlobSetBuilder::new();
+        for glob in globs.iter() {
+            builder.add(GlobBuilder::new(glob).literal_separator(true).build()?);
+        }
+        Ok(Self { origin: origin.into(), globs, set: builder.build()? })
+    }
+
+    /// The first glob matching `filename`.
+    fn matching(&self, filename: &str) -> Option<&str> {
+        self.set.matches(filename).first().map(|idx| self.globs[*idx].as_str())
+    }
+}
+
+/// The filter pipeline.  By default only files below `src/` are reported.
+#[derive(Debug)]
+pub struct Filter {
+    include: Patterns,
+    exclude: Option<Patterns>,
+    ignore: Vec<Patterns>,
+    include_deps: bool,
+    /// Crate names by directory, longest directory first.
+    crates: Vec<(String, String)>,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            include: Patterns::new("the default", vec!["src/**".to_string()]).unwrap(),
+            exclude: None,
+            ignore: vec![],
+            include_deps: false,
+            crates: vec![],
+        }
+    }
+}
+
+impl Filter {
+    /// Only report files matching one of these globs instead of everything
+    /// below `src/`.
+    pub fn include<S: AsRef<str>>(mut self, globs: &[S]) -> Result<Self, Box<dyn StdError>> {
+        let globs = globs.iter().map(|g| g.as_ref().to_string()).collect();
+        self.include = Patterns::new("--include", globs)?;
+        Ok(self)
+    }
+
+    /// Leave out files matching any of these globs.
+    pub fn exclude<S: AsRef<str>>(mut self, globs: &[S]) -> Result<Self, Box<dyn StdError>> {
+        let globs = globs.iter().map(|g| g.as_ref().to_string()).collect();
+        self.exclude = Some(Patterns::new("--exclude", globs)?);
+        Ok(self)
+    }
+
+    /// Leave out files matching the patterns in an ignore file: one glob per
+    /// line, `#` starts a comment.  Like in `.gitignore` a pattern without a
+    /// slash matches at any depth, and one ending in a slash matches
+    /// everything below that directory.
+    pub fn ignore_file(mut self, path: &Path) -> Result<Self, Box<dyn StdError>> {
+        let contents = std::fs::read_to_string(path)?;
+        let mut globs = vec![];
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('!') {
+                return Err(format!("{}: negated patterns like `{}' aren't supported", path.display(), line).into());
+            }
+
+            let (pattern, glob) = match line.strip_suffix('/') {
+                Some(dir) => (dir, format!("{}/**", dir)),
+                None => (line, line.to_string()),
+            };
+            globs.push(match glob.strip_prefix('/') {
+                Some(anchored) => anchored.to_string(),
+                None if !pattern.contains('/') => format!("**/{}", glob),
+                None => glob,
+            });
+        }
+        self.ignore.push(Patterns::new(path.display().to_string(), globs)?);
+        Ok(self)
+    }
+
+    /// Report registry and git dependencies from `CARGO_HOME` in their own
+    /// section rather than leaving them out.
+    pub fn include_deps(mut self, include_deps: bool) -> Self {
+        self.include_deps = include_deps;
+        self
+    }
+
+    /// Attribute the files below `dir` to the crate `name`, e.g. the members
+    /// of a workspace.
+    pub fn crate_dir(mut self, name: &str, dir: &str) -> Self {
+        let dir = dir.trim_end_matches('/');
+        self.crates.push((name.to_string(), format!("{}/", dir)));
+        self.crates.sort_by_key(|(_, dir)| std::cmp::Reverse(dir.len()));
+        self
+    }
+
+    pub fn is_reported(&self, filename: &str) -> bool {
+        self.decide(filename).is_reported()
+    }
+
+    /// Runs `filename` (as named in the export) through every stage.
+    pub fn decide(&self, filename: &str) -> Decision {
+        let mut steps = vec![];
+        let dependency = utils::dependency_path(filename).is_some();
+
+        match self.include.matching(filename) {
+            Some(glob) => steps.push((Stage::Include, format!("matches `{}' from {}", glob, self.include.origin))),
+            None if dependency && self.include_deps => {
+                steps.push((Stage::Include, "is a dependency and --include-deps is set".to_string()))
+            }
+            None => {
+                let globs = self.include.globs.join("', `");
+                let hint = match dependency {
+                    true => "; it's a dependency from CARGO_HOME, see --include-deps",
+                    false => "",
+                };
+                steps.push((Stage::Include, format!("matches none of `{}' from {}{}", globs, self.include.origin, hint)));
+                return Decision { verdict: Verdict::Excluded(Stage::Include), steps };
+            }
+        }
+
+        if let Some(exclude) = self.exclude.as_ref() {
+            if let Some(glob) = exclude.matching(filename) {
+                steps.push((Stage::Exclude, format!("matches `{}' from {}", glob, exclude.origin)));
+                return Decision { verdict: Verdict::Excluded(Stage::Exclude), steps };
+            }
+            steps.push((Stage::Exclude, "matches no --exclude glob".to_string()));
+        }
+
+        for ignore in self.ignore.iter() {
+            if let Some(glob) = ignore.matching(filename) {
+                steps.push((Stage::IgnoreFile, format!("matches `{}' from {}", glob, ignore.origin)));
+                return Decision { verdict: Verdict::Excluded(Stage::IgnoreFile), steps };
+            }
+            steps.push((Stage::IgnoreFile, format!("matches nothing in {}", ignore.origin)));
+        }
+
+        if dependency {
+            if !self.include_deps {
+                steps.push((Stage::ThirdParty, "is a dependency from CARGO_HOME; pass --include-deps to report it".to_string()));
+                return Decision { verdict: Verdict::Excluded(Stage::ThirdParty), steps };
+            }
+            steps.push((Stage::ThirdParty, "is a dependency from CARGO_HOME, reported in its own section".to_string()));
+            return Decision { verdict: Verdict::Dependency, steps };
+        }
+        steps.push((Stage::ThirdParty, "is part of the project".to_string()));
+
+        let crate_name = self.crates.iter().find(|(_, dir)| filename.starts_with(dir.as_str())).map(|(name, dir)| {
+            steps.push((Stage::Crate, format!("is below {}, which belongs to crate {}", dir, name)));
+            name.clone()
+        });
+        if crate_name.is_none() && !self.crates.is_empty() {
+            steps.push((Stage::Crate, "is below no --crate-dir".to_string()));
+        }
+
+        Decision { verdict: Verdict::Project { crate_name }, steps }
+    }
+}
+
+/// Parses a `--crate-dir` value, `name=dir`.
+pub fn parse_crate_dir(value: &str) -> Result<(&str, &str), String> {
+    match value.split_once('=') {
+        Some((name, dir)) if !name.is_empty() && !dir.is_empty() => Ok((name, dir)),
+        _ => Err(format!("Expected `name=dir', got `{}'", value)),
+    }
+}
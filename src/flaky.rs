@@ -0,0 +1,63 @@
+//! Regions that ran in some runs of the same tests but not in others, which
+//! points at tests depending on timing, random seeds, or the environment.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::model::ReportModel;
+use crate::uncovered::{self, Position};
+
+#[derive(Debug, Serialize)]
+pub struct FlakyCoverage {
+    /// Number of runs compared.
+    pub runs: usize,
+    /// By file and position.
+    pub regions: Vec<FlakyRegion>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FlakyRegion {
+    /// Shown name of the file.
+    pub file: String,
+    /// Page of the file in the merged report, if it's reported.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+    pub line_start: i64,
+    pub column_start: i64,
+    pub line_end: i64,
+    pub column_end: i64,
+    /// Function the region belongs to.
+    pub function: String,
+    /// Number of runs the region ran in, more than none but fewer than all.
+    pub covered_runs: usize,
+}
+
+/// Compares one model per run of the same tests.  A region missing from a
+/// run entirely, e.g. a generic that wasn't instantiated there, counts as not
+/// having run in it.
+pub fn compare(merged: &ReportModel, runs: &[ReportModel]) -> FlakyCoverage {
+    let mut counts: BTreeMap<Position, (usize, &str)> = BTreeMap::new();
+    for run in runs.iter() {
+        for (position, (hit, function)) in uncovered::region_hits(run) {
+            counts.entry(position).or_insert((0, function)).0 += hit as usize;
+        }
+    }
+
+    let regions = counts
+        .into_iter()
+        .filter(|(_, (covered_runs, _))| *covered_runs > 0 && *covered_runs < runs.len())
+        .map(|((file, line_start, column_start, line_end, column_end), (covered_runs, function))| FlakyRegion {
+            file: file.to_string(),
+            page: merged.all_files().find(|f| f.name == file).map(|f| f.page.clone()),
+            line_start,
+            column_start,
+            line_end,
+            column_end,
+            function: function.to_string(),
+            covered_runs,
+        })
+        .collect();
+
+    FlakyCoverage { runs: runs.len(), regions }
+}
@@ -0,0 +1,78 @@
+//! Whether the sources on disk are the ones the coverage data was recorded
+//! against.  An export from before the sources were edited still renders,
+//! but its counts end up on the wrong lines.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::encoding::{self, SourceEncoding};
+use crate::fs::FileLimiter;
+use crate::input::END_OF_LINE;
+use crate::manifest::{self, Manifest};
+use crate::model::{FileModel, ReportModel};
+
+/// What about `source` doesn't fit the coverage data of `file`, if anything.
+/// Only the shape can be compared: the export doesn't record what the
+/// sources looked like.
+pub fn mismatch(file: &FileModel, source: &str) -> Option<String> {
+    let lines = source.lines().collect::<Vec<_>>();
+
+    if let Some(last) = file.segments.iter().map(|s| s.line).max() {
+        if last as usize > lines.len() {
+            return Some(format!(
+                "the coverage data refers to line {} but the source has {} lines; it was probably edited after the export",
+                last,
+                lines.len()
+            ));
+        }
+    }
+
+    // Columns count bytes, and region ends are exclusive so a region can end
+    // one past the last one.  Line based formats (LCOV, Cobertura) close
+    // every region at the end of its line, whatever its length.
+    file.segments.iter().filter(|s| s.col != END_OF_LINE).find_map(|s| {
+        let length = (s.line as usize).checked_sub(1).and_then(|idx| lines.get(idx))?.len();
+        match s.col as usize > length + 1 {
+            true => Some(format!(
+                "the coverage data refers to column {} of line {}, which is {} bytes long; the source was probably edited after the export",
+                s.col, s.line, length
+            )),
+            false => None,
+        }
+    })
+}
+
+/// Every reported file whose source doesn't fit its coverage data, by shown
+/// name.  Sources that can't be read are left out.
+pub fn stale_sources(model: &ReportModel, input_path: &Path, source_encoding: SourceEncoding, files: &FileLimiter) -> Vec<(String, String)> {
+    model
+        .all_files()
+        .filter_map(|file| {
+            let source = files.read(&input_path.join(&file.filename)).ok()?;
+            let problem = mismatch(file, &encoding::decode(&source, source_encoding))?;
+            Some((file.name.clone(), problem))
+        })
+        .collect()
+}
+
+/// SHA-256 of each reported file's source, by shown name.  Sources that
+/// can't be read are left out.
+pub fn source_hashes(model: &ReportModel, input_path: &Path, files: &FileLimiter) -> BTreeMap<String, String> {
+    model
+        .all_files()
+        .filter_map(|file| {
+            let source = files.read(&input_path.join(&file.filename)).ok()?;
+            Some((file.name.clone(), manifest::sha256(&source)))
+        })
+        .collect()
+}
+
+/// Files whose source changed since `manifest` recorded it, by shown name.
+/// Files the manifest doesn't know about are left out.
+pub fn changed_since(manifest: &Manifest, hashes: &BTreeMap<String, String>) -> Vec<(String, String)> {
+    hashes
+        .iter()
+        .filter(|(name, hash)| manifest.sources.get(*name).is_some_and(|recorded| recorded != *hash))
+        .map(|(name, _)| (name.clone(), "the source changed since the manifest was written".to_string()))
+        .collect()
+}
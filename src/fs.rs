@@ -0,0 +1,99 @@
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+
+/// How often an operation that ran out of file descriptors is retried.
+const RETRIES: u32 = 5;
+/// Wait before the first retry, doubled for each one after that.
+const BACKOFF: Duration = Duration::from_millis(10);
+
+/// Nanoseconds spent writing through any [`FileLimiter`], summed over the
+/// threads doing it.
+static WRITING: AtomicU64 = AtomicU64::new(0);
+
+/// Time spent writing through any [`FileLimiter`] so far, summed over the
+/// threads doing it.
+pub fn time_writing() -> Duration {
+    Duration::from_nanos(WRITING.load(Ordering::Relaxed))
+}
+
+/// Bounds how many files the report has open at once, and backs off and
+/// retries when the OS runs out of file descriptors anyway (e.g. because
+/// something else in the process holds a lot of them).
+#[derive(Debug)]
+pub struct FileLimiter {
+    open: Mutex<usize>,
+    closed: Condvar,
+    max_open: usize,
+}
+
+impl Default for FileLimiter {
+    fn default() -> Self {
+        Self::new(64)
+    }
+}
+
+impl FileLimiter {
+    pub fn new(max_open: usize) -> Self {
+        Self {
+            open: Mutex::new(0),
+            closed: Condvar::new(),
+            max_open: max_open.max(1),
+        }
+    }
+
+    pub fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.limit(|| std::fs::read(path))
+    }
+
+    pub fn write(&self, path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+        let start = Instant::now();
+        let result = self.limit(|| std::fs::write(path, contents.as_ref()));
+        WRITING.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        result
+    }
+
+    fn limit<T>(&self, op: impl Fn() -> io::Result<T>) -> io::Result<T> {
+        {
+            let mut open = self.open.lock().unwrap_or_else(|e| e.into_inner());
+            while *open >= self.max_open {
+                open = self.closed.wait(open).unwrap_or_else(|e| e.into_inner());
+            }
+            *open += 1;
+        }
+
+        let mut result = op();
+        let mut backoff = BACKOFF;
+        for _ in 0..RETRIES {
+            match &result {
+                Err(e) if out_of_descriptors(e) => {
+                    debug!("Out of file descriptors, retrying in {:?}", backoff);
+                    std::thread::sleep(backoff);
+                    backoff *= 2;
+                    result = op();
+                }
+                _ => break,
+            }
+        }
+
+        *self.open.lock().unwrap_or_else(|e| e.into_inner()) -= 1;
+        self.closed.notify_one();
+
+        result
+    }
+}
+
+fn out_of_descriptors(e: &io::Error) -> bool {
+    match e.raw_os_error() {
+        // EMFILE and ENFILE
+        Some(23) | Some(24) => cfg!(unix),
+        // ERROR_TOO_MANY_OPEN_FILES
+        Some(4) => cfg!(windows),
+        _ => false,
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::error::Error as StdError;
+use std::path::Path;
+use std::process::Command;
+
+#[cfg(feature = "render-html")]
+use chrono::{DateTime, FixedOffset};
+
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+
+/// Runs git in `repo` and returns its stdout.
+fn git(repo: &Path, args: &[&str]) -> Result<String, Box<dyn StdError>> {
+    debug!("Running git {} in `{}'", args.join(" "), repo.display());
+
+    let output = Command::new("git").current_dir(repo).args(args).output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// When `HEAD` of the checkout `repo` is in was committed.
+#[cfg(feature = "render-html")]
+pub fn commit_time(repo: &Path) -> Result<DateTime<FixedOffset>, Box<dyn StdError>> {
+    let stdout = git(repo, &["log", "-1", "--format=%cI", "HEAD"])?;
+    Ok(DateTime::parse_from_rfc3339(stdout.trim())?)
+}
+
+/// Where `repo` is within its checkout, e.g. `crates/foo/`, or nothing at the
+/// top.  Paths relative to `repo` need it in front to be relative to the
+/// repository root, as review tools expect.
+pub fn show_prefix(repo: &Path) -> Result<String, Box<dyn StdError>> {
+    let stdout = git(repo, &["rev-parse", "--show-prefix"])?;
+    Ok(stdout.trim_end_matches('\n').to_string())
+}
+
+/// Files touched in `range` (anything `git diff` accepts, e.g. `main...HEAD`)
+/// relative to `repo`, which is expected to be the source directory.
+pub fn changed_files(repo: &Path, range: &str) -> Result<HashSet<String>, Box<dyn StdError>> {
+    let stdout = git(repo, &["diff", "--name-only", "--relative", range, "--"])?;
+    Ok(stdout.lines().map(str::to_string).collect())
+}
+
+/// Lines added or changed in `range`, by file relative to `repo`.
+pub fn changed_lines(repo: &Path, range: &str) -> Result<HashMap<String, BTreeSet<i64>>, Box<dyn StdError>> {
+    let stdout = git(
+        repo,
+        &["diff", "--unified=0", "--no-color", "--no-ext-diff", "--src-prefix=a/", "--dst-prefix=b/", "--relative", range, "--"],
+    )?;
+    parse_changed_lines(&stdout)
+}
+
+/// Reads the new side of each hunk of a `git diff --unified=0`.
+pub fn parse_changed_lines(diff: &str) -> Result<HashMap<String, BTreeSet<i64>>, Box<dyn StdError>> {
+    let mut changed: HashMap<String, BTreeSet<i64>> = HashMap::new();
+    let mut file = None;
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            // Deleted files have no new side
+            file = path.strip_prefix("b/").map(str::to_string);
+        } else if let (Some(hunk), Some(file)) = (line.strip_prefix("@@ "), file.as_ref()) {
+            // @@ -start[,count] +start[,count] @@
+            let new = hunk
+                .split(' ')
+                .find_map(|range| range.strip_prefix('+'))
+                .ok_or_else(|| format!("Malformed hunk header `{}'", line))?;
+            let (start, count) = match new.split_once(',') {
+                Some((start, count)) => (start.parse::<i64>()?, count.parse::<i64>()?),
+                None => (new.parse::<i64>()?, 1),
+            };
+            changed.entry(file.clone()).or_default().extend(start..start + count);
+        }
+    }
+    Ok(changed)
+}
+
+/// When each file under `repo` was last committed to, as a unix timestamp,
+/// by path relative to `repo`.
+pub fn last_modified(repo: &Path) -> Result<HashMap<String, i64>, Box<dyn StdError>> {
+    let stdout = git(repo, &["log", "--format=%x01%ct", "--name-only", "--no-renames", "--relative", "HEAD", "--"])?;
+    parse_last_modified(&stdout)
+}
+
+/// Reads a `git log --format=%x01%ct --name-only`, newest commit first.
+pub fn parse_last_modified(log: &str) -> Result<HashMap<String, i64>, Box<dyn StdError>> {
+    let mut modified = HashMap::new();
+    let mut time = None;
+    for line in log.lines() {
+        if let Some(commit_time) = line.strip_prefix('\u{1}') {
+            time = Some(commit_time.parse::<i64>()?);
+        } else if let (false, Some(time)) = (line.is_empty(), time) {
+            modified.entry(line.to_string()).or_insert(time);
+        }
+    }
+    Ok(modified)
+}
+
+/// When each line of `file` (relative to `repo`) was authored, as a unix
+/// timestamp.  Uncommitted lines get the current time.
+pub fn blame_times(repo: &Path, file: &str) -> Result<Vec<i64>, Box<dyn StdError>> {
+    let stdout = git(repo, &["blame", "--line-porcelain", "--", file])?;
+
+    let mut times = vec![];
+    let mut author_time = 0;
+    for line in stdout.lines() {
+        if let Some(time) = line.strip_prefix("author-time ") {
+            author_time = time.parse()?;
+        } else if line.starts_with('\t') {
+            // Every source line is preceded by its full commit info
+            times.push(author_time);
+        }
+    }
+
+    Ok(times)
+}
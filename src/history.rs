@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, Utc};
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+use serde::{Deserialize, Serialize};
+
+use crate::FileCoverageSummary;
+use crate::model::FileModel;
+
+pub const HISTORY_FILENAME: &str = "history.json";
+/// Version of the layout of `history.json`.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Coverage totals from previous runs, kept in the output directory so that
+/// trends can be rendered alongside the current snapshot.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct History {
+    /// [`SCHEMA_VERSION`] of the layout the store was written with; stores
+    /// from before the field was written are version 1.
+    #[serde(default = "first_version")]
+    pub version: u32,
+    pub runs: Vec<Run>,
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+/// How much of the history store to keep.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Retention {
+    /// Runs recorded within the last n days.
+    Days(i64),
+    /// The last n runs.
+    Runs(usize),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Run {
+    pub timestamp: DateTime<Utc>,
+    pub lines_percent: f64,
+    pub functions_percent: f64,
+    pub files: BTreeMap<String, FileRun>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct FileRun {
+    pub lines_percent: f64,
+    pub functions_percent: f64,
+}
+
+impl FromStr for Retention {
+    type Err = String;
+
+    /// Parses e.g. `90d`, `12w`, or a bare run count like `30`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid history retention `{}', expected e.g. 90d, 12w, or 30", s);
+
+        match s.char_indices().last() {
+            Some((idx, 'd')) => s[..idx].parse::<u32>().map(|d| Retention::Days(d.into())).map_err(|_| invalid()),
+            Some((idx, 'w')) => s[..idx].parse::<u32>().map(|w| Retention::Days(i64::from(w) * 7)).map_err(|_| invalid()),
+            Some(_) => s.parse().map(Retention::Runs).map_err(|_| invalid()),
+            None => Err(invalid()),
+        }
+    }
+}
+
+impl Run {
+    /// True when the coverage numbers (but not necessarily the timestamps) are identical.
+    fn same_coverage(&self, other: &Run) -> bool {
+        self.lines_percent == other.lines_percent
+            && self.functions_percent == other.functions_percent
+            && self.files == other.files
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self { version: SCHEMA_VERSION, runs: Vec::new() }
+    }
+}
+
+impl History {
+    pub fn path(output_path: &Path) -> PathBuf {
+        output_path.join(HISTORY_FILENAME)
+    }
+
+    /// Loads the history store, or starts a new one if there isn't one yet.
+    pub fn load(output_path: &Path) -> Result<Self, Box<dyn StdError>> {
+        let path = Self::path(output_path);
+        match path.exists() {
+            true => {
+                debug!("Reading history from `{}'", path.display());
+                let contents = std::fs::read_to_string(&path)?;
+                let history: Self = serde_json::from_str(&contents)?;
+                if history.version != SCHEMA_VERSION {
+                    return Err(format!(
+                        "`{}' is history version {}, this build reads version {}",
+                        path.display(),
+                        history.version,
+                        SCHEMA_VERSION
+                    )
+                    .into());
+                }
+                Ok(history)
+            }
+            false => {
+                info!("Starting new history at `{}'", path.display());
+                Ok(Self::default())
+            }
+        }
+    }
+
+    pub fn save(&self, output_path: &Path) -> Result<(), Box<dyn StdError>> {
+        std::fs::write(Self::path(output_path), serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Appends the current run, keyed by the files' shown names.  Pass the
+    /// same `now` to [`History::prune`].
+    pub fn record(&mut self, files: &[FileModel], totals: &FileCoverageSummary, now: DateTime<Utc>) {
+        self.runs.push(Run {
+            timestamp: now,
+            lines_percent: totals.lines.percent,
+            functions_percent: totals.functions.percent,
+            files: files
+                .iter()
+                .map(|f| {
+                    (f.name.clone(), FileRun {
+                        lines_percent: f.summary.lines.percent,
+                        functions_percent: f.summary.functions.percent,
+                    })
+                })
+                .collect(),
+        });
+    }
+
+    /// Drops runs outside of the retention window and compacts the remainder
+    /// by collapsing consecutive runs with unchanged coverage into the most
+    /// recent one.  The newest run, usually the one just recorded, is always
+    /// kept.  Returns the number of runs removed.
+    pub fn prune(&mut self, retention: Retention, now: DateTime<Utc>) -> usize {
+        let before = self.runs.len();
+
+        let mut compacted: Vec<Run> = Vec::with_capacity(self.runs.len());
+        for run in self.runs.drain(..) {
+            match compacted.last_mut() {
+                Some(last) if last.same_coverage(&run) => *last = run,
+                _ => compacted.push(run),
+            }
+        }
+        self.runs = compacted;
+
+        let newest = self.runs.pop();
+        match retention {
+            Retention::Days(days) => {
+                let cutoff = now - Duration::days(days);
+                self.runs.retain(|run| run.timestamp >= cutoff);
+            }
+            Retention::Runs(count) => {
+                let skip = self.runs.len().saturating_sub(count.saturating_sub(1));
+                self.runs.drain(..skip);
+            }
+        }
+        self.runs.extend(newest);
+
+        before - self.runs.len()
+    }
+
+    /// Line coverage for a single file over (at most) the last `count` runs.
+    /// Runs in which the file didn't appear are skipped.
+    pub fn file_lines_percent(&self, filename: &str, count: usize) -> Vec<f64> {
+        let skip = self.runs.len().saturating_sub(count);
+        self.runs
+            .iter()
+            .skip(skip)
+            .filter_map(|run| run.files.get(filename))
+            .map(|f| f.lines_percent)
+            .collect()
+    }
+}
@@ -0,0 +1,122 @@
+use std::error::Error as StdError;
+use std::path::Path;
+
+use crate::{CoverageMapping, FileCoverage, FileCoverageSummary, FileSegment, FunctionCoverage, Region};
+
+mod llvm;
+pub use llvm::*;
+
+mod lcov;
+pub use lcov::*;
+
+mod cobertura;
+pub use cobertura::*;
+
+/// A coverage data format that can be read into coverage mappings.
+pub trait InputFormat {
+    /// Name the format is selected by with `--input-format`.
+    fn name(&self) -> &str;
+
+    /// Whether a file looks like this format, judging by its extension or
+    /// its contents.
+    fn detect(&self, path: &Path, contents: &str) -> bool;
+
+    fn read<'a>(&self, contents: &'a str) -> Result<Vec<CoverageMapping<'a>>, Box<dyn StdError>>;
+}
+
+/// Input formats keyed by name.
+#[derive(Default)]
+pub struct Registry {
+    formats: Vec<Box<dyn InputFormat>>,
+}
+
+impl Registry {
+    pub fn builtin() -> Self {
+        let mut registry = Self::default();
+        registry.register(LlvmJson);
+        registry.register(Lcov);
+        registry.register(Cobertura);
+        registry
+    }
+
+    /// Adds a format, replacing any previously registered under the same name.
+    pub fn register(&mut self, format: impl InputFormat + 'static) {
+        self.formats.retain(|f| f.name() != format.name());
+        self.formats.push(Box::new(format));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn InputFormat> {
+        self.formats.iter().find(|f| f.name() == name).map(|f| f.as_ref())
+    }
+
+    /// The first registered format that recognizes the file.
+    pub fn detect(&self, path: &Path, contents: &str) -> Option<&dyn InputFormat> {
+        self.formats.iter().find(|f| f.detect(path, contents)).map(|f| f.as_ref())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.formats.iter().map(|f| f.name())
+    }
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| extensions.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+        .unwrap_or(false)
+}
+
+/// Column used to close a region at the end of its line when a format only
+/// records line granularity.
+pub const END_OF_LINE: i64 = i64::MAX;
+
+/// Line based formats only tell us how often each line ran, so each line
+/// becomes a region spanning the whole line.
+fn line_segments(mut lines: Vec<(i64, i64)>) -> Vec<FileSegment> {
+    lines.sort_unstable();
+    lines.dedup_by_key(|(line, _)| *line);
+
+    lines
+        .into_iter()
+        .flat_map(|(line, count)| {
+            vec![
+                FileSegment { line, col: 1, count, has_count: true, is_region_entry: true, is_gap_region: false },
+                FileSegment { line, col: END_OF_LINE, count: 0, has_count: false, is_region_entry: false, is_gap_region: false },
+            ]
+        })
+        .collect()
+}
+
+/// A function known only by the line it starts on.
+fn line_function<'a>(name: &'a str, filename: &'a str, line: i64, count: i64) -> FunctionCoverage<'a> {
+    FunctionCoverage {
+        name,
+        count,
+        regions: vec![Region {
+            line_start: line,
+            column_start: 1,
+            line_end: line,
+            column_end: END_OF_LINE,
+            execution_count: count,
+            file_id: 0,
+            expanded_file_id: 0,
+            region_kind: 0,
+        }],
+        filenames: vec![filename],
+    }
+}
+
+/// Fills in the summaries llvm would have computed for the files and totals.
+fn summarize<'a>(mut files: Vec<FileCoverage<'a>>, functions: Vec<FunctionCoverage<'a>>) -> CoverageMapping<'a> {
+    for file in files.iter_mut() {
+        file.summary = crate::merge::summarize(file, &functions);
+    }
+    let totals = FileCoverageSummary::total(files.iter().map(|f| &f.summary));
+
+    CoverageMapping { files, functions, totals }
+}
+
+/// An empty summary, replaced by `summarize`.
+fn empty_summary() -> FileCoverageSummary {
+    FileCoverageSummary::total(std::iter::empty())
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::path::Path;
+
+use crate::{CoverageMapping, FileBranch, FileCoverage};
+use super::{empty_summary, has_extension, line_function, line_segments, summarize, InputFormat};
+
+/// Cobertura XML as written by `grcov`, `cargo tarpaulin`, and our own
+/// cobertura exporter.
+///
+/// Only the handful of elements and attributes we need are picked out, so
+/// attribute values are used as is; entities in file or method names are not
+/// decoded.
+pub struct Cobertura;
+
+#[derive(Default)]
+struct Class {
+    lines: Vec<(i64, i64)>,
+    branches: Vec<FileBranch>,
+}
+
+fn number(value: &str) -> Result<i64, Box<dyn StdError>> {
+    value.parse().map_err(|e| format!("Invalid number `{}': {}", value, e).into())
+}
+
+/// Branch arms for a `condition-coverage="50% (1/2)"` attribute.  Cobertura
+/// only records how many arms ran, not how often, so covered arms count once.
+fn branches(line: i64, condition_coverage: &str) -> Result<Vec<FileBranch>, Box<dyn StdError>> {
+    let ratio = condition_coverage
+        .split_once('(')
+        .and_then(|(_, rest)| rest.strip_suffix(')'))
+        .and_then(|ratio| ratio.split_once('/'))
+        .ok_or_else(|| format!("Malformed condition-coverage `{}'", condition_coverage))?;
+    let (covered, total) = (number(ratio.0)?, number(ratio.1)?);
+
+    let arm = |idx: i64| (idx < covered) as i64;
+    Ok((0..(total + 1) / 2)
+        .map(|pair| FileBranch {
+            line_start: line,
+            column_start: 1,
+            line_end: line,
+            column_end: super::END_OF_LINE,
+            execution_count: arm(pair * 2),
+            false_execution_count: arm(pair * 2 + 1),
+            file_id: 0,
+            expanded_file_id: 0,
+            region_kind: 4,
+        })
+        .collect())
+}
+
+/// The `class`, `method`, and `line` tags in `contents`, in order: whether
+/// it's a closing tag, its name, and whatever follows the name up to `>`.
+fn tags(contents: &str) -> impl Iterator<Item = (bool, &str, &str)> {
+    contents.match_indices('<').filter_map(move |(start, _)| {
+        let rest = &contents[start + 1..];
+        let (closing, rest) = match rest.strip_prefix('/') {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+        let name_len = rest.find(|c: char| !is_name_char(c) || c == '-').unwrap_or(rest.len());
+        let name = &rest[..name_len];
+        if !["class", "method", "line"].contains(&name) {
+            return None;
+        }
+        let end = rest.find('>')?;
+        Some((closing, name, &rest[name_len..end]))
+    })
+}
+
+/// The `name="value"` pairs in what follows a tag's name.
+fn attributes(tag: &str) -> HashMap<&str, &str> {
+    let mut attrs = HashMap::new();
+    let mut rest = tag;
+    while let Some(eq) = rest.find("=\"") {
+        let name_start = rest[..eq]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_name_char(*c))
+            .last()
+            .map_or(eq, |(idx, _)| idx);
+        let value = &rest[eq + 2..];
+        let end = match value.find('"') {
+            Some(end) => end,
+            None => break,
+        };
+        if name_start < eq {
+            attrs.insert(&rest[name_start..eq], &value[..end]);
+        }
+        rest = &value[end + 1..];
+    }
+    attrs
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+impl InputFormat for Cobertura {
+    fn name(&self) -> &str {
+        "cobertura"
+    }
+
+    fn detect(&self, path: &Path, contents: &str) -> bool {
+        has_extension(path, &["xml"]) || contents.contains("<coverage")
+    }
+
+    fn read<'a>(&self, contents: &'a str) -> Result<Vec<CoverageMapping<'a>>, Box<dyn StdError>> {
+        let mut classes: Vec<(&'a str, Class)> = vec![];
+        let mut class_index: HashMap<&'a str, usize> = HashMap::new();
+        let mut functions = vec![];
+
+        let mut class: Option<usize> = None;
+        // Name and first line of the method being read
+        let mut method: Option<(&'a str, Option<(i64, i64)>)> = None;
+
+        for (closing, name, rest) in tags(contents) {
+            let self_closing = rest.trim_end().ends_with('/');
+            let attrs = attributes(rest);
+
+            match (name, closing) {
+                ("class", false) if self_closing => (),
+                ("class", false) => {
+                    let filename = *attrs.get("filename").ok_or("class without a filename")?;
+                    let idx = *class_index.entry(filename).or_insert_with(|| {
+                        classes.push((filename, Class::default()));
+                        classes.len() - 1
+                    });
+                    class = Some(idx);
+                }
+                ("class", true) => class = None,
+                ("method", false) if self_closing => (),
+                ("method", false) => {
+                    method = Some((attrs.get("name").copied().unwrap_or_default(), None));
+                }
+                ("method", true) => {
+                    if let (Some((name, Some((line, hits)))), Some(idx)) = (method.take(), class) {
+                        functions.push(line_function(name, classes[idx].0, line, hits));
+                    }
+                }
+                ("line", false) => {
+                    let line = number(attrs.get("number").ok_or("line without a number")?)?;
+                    let hits = number(attrs.get("hits").ok_or("line without hits")?)?;
+
+                    if let Some((_, first)) = method.as_mut() {
+                        first.get_or_insert((line, hits));
+                    } else if let Some(idx) = class {
+                        let class = &mut classes[idx].1;
+                        class.lines.push((line, hits));
+                        if let Some(condition_coverage) = attrs.get("condition-coverage") {
+                            class.branches.extend(branches(line, condition_coverage)?);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let files = classes
+            .into_iter()
+            .map(|(filename, class)| FileCoverage {
+                branches: class.branches,
+                expansions: vec![],
+                filename,
+                segments: line_segments(class.lines),
+                summary: empty_summary(),
+            })
+            .collect();
+
+        Ok(vec![summarize(files, functions)])
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::path::Path;
+
+use crate::{CoverageMapping, FileBranch, FileCoverage, FunctionCoverage};
+use super::{empty_summary, has_extension, line_function, line_segments, summarize, InputFormat};
+
+/// LCOV tracefiles as written by `lcov`, `grcov`, and `cargo llvm-cov --lcov`.
+pub struct Lcov;
+
+/// Everything collected between `SF:` and `end_of_record`.
+#[derive(Default)]
+struct Record<'a> {
+    filename: &'a str,
+    /// Function name to (first line, count)
+    functions: BTreeMap<&'a str, (i64, i64)>,
+    /// (line, block) to the counts of each branch arm
+    branches: BTreeMap<(i64, &'a str), Vec<i64>>,
+    lines: Vec<(i64, i64)>,
+}
+
+fn number(value: &str, line: usize) -> Result<i64, Box<dyn StdError>> {
+    value
+        .trim()
+        .parse()
+        .map_err(|e| format!("Invalid number `{}' on line {}: {}", value, line, e).into())
+}
+
+impl<'a> Record<'a> {
+    fn finish(self, files: &mut Vec<FileCoverage<'a>>, functions: &mut Vec<FunctionCoverage<'a>>) {
+        for (name, (line, count)) in self.functions {
+            functions.push(line_function(name, self.filename, line, count));
+        }
+
+        // llvm branches have exactly two arms, so split wider branches up
+        let mut branches = vec![];
+        for ((line, _), arms) in self.branches {
+            for pair in arms.chunks(2) {
+                branches.push(FileBranch {
+                    line_start: line,
+                    column_start: 1,
+                    line_end: line,
+                    column_end: super::END_OF_LINE,
+                    execution_count: pair[0],
+                    false_execution_count: pair.get(1).copied().unwrap_or(0),
+                    file_id: 0,
+                    expanded_file_id: 0,
+                    region_kind: 4,
+                });
+            }
+        }
+
+        files.push(FileCoverage {
+            branches,
+            expansions: vec![],
+            filename: self.filename,
+            segments: line_segments(self.lines),
+            summary: empty_summary(),
+        });
+    }
+}
+
+impl InputFormat for Lcov {
+    fn name(&self) -> &str {
+        "lcov"
+    }
+
+    fn detect(&self, path: &Path, contents: &str) -> bool {
+        has_extension(path, &["info", "lcov"])
+            || contents.lines().take(2).any(|l| l.starts_with("TN:") || l.starts_with("SF:"))
+    }
+
+    fn read<'a>(&self, contents: &'a str) -> Result<Vec<CoverageMapping<'a>>, Box<dyn StdError>> {
+        let mut files = vec![];
+        let mut functions = vec![];
+        let mut record: Option<Record<'a>> = None;
+
+        for (idx, line) in contents.lines().enumerate() {
+            let line_no = idx + 1;
+            let line = line.trim();
+            let (key, value) = match line.split_once(':') {
+                Some(pair) => pair,
+                None if line == "end_of_record" => {
+                    match record.take() {
+                        Some(record) => record.finish(&mut files, &mut functions),
+                        None => return Err(format!("end_of_record without SF on line {}", line_no).into()),
+                    }
+                    continue;
+                }
+                None => continue,
+            };
+
+            if key == "SF" {
+                record = Some(Record { filename: value, ..Default::default() });
+                continue;
+            }
+
+            let record = match record.as_mut() {
+                Some(record) => record,
+                None => continue,
+            };
+
+            match key {
+                // FN:<line>,<name> or FN:<line>,<end line>,<name>
+                "FN" => {
+                    let (line, name) = value.split_once(',').ok_or_else(|| format!("Malformed FN on line {}", line_no))?;
+                    let name = match name.split_once(',') {
+                        Some((end, rest)) if end.parse::<i64>().is_ok() => rest,
+                        _ => name,
+                    };
+                    record.functions.entry(name).or_default().0 = number(line, line_no)?;
+                }
+                "FNDA" => {
+                    let (count, name) = value.split_once(',').ok_or_else(|| format!("Malformed FNDA on line {}", line_no))?;
+                    record.functions.entry(name).or_default().1 = number(count, line_no)?;
+                }
+                "BRDA" => {
+                    let fields = value.split(',').collect::<Vec<_>>();
+                    if fields.len() != 4 {
+                        return Err(format!("Malformed BRDA on line {}", line_no).into());
+                    }
+                    // `-` marks a branch whose line never ran
+                    let taken = match fields[3] {
+                        "-" => 0,
+                        taken => number(taken, line_no)?,
+                    };
+                    record.branches.entry((number(fields[0], line_no)?, fields[1])).or_default().push(taken);
+                }
+                "DA" => {
+                    let mut fields = value.split(',');
+                    let line = number(fields.next().unwrap_or_default(), line_no)?;
+                    let count = number(fields.next().ok_or_else(|| format!("Malformed DA on line {}", line_no))?, line_no)?;
+                    record.lines.push((line, count));
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(record) = record {
+            record.finish(&mut files, &mut functions);
+        }
+
+        Ok(vec![summarize(files, functions)])
+    }
+}
@@ -0,0 +1,23 @@
+use std::error::Error as StdError;
+use std::path::Path;
+
+use crate::{CoverageMapping, SummaryReport};
+use super::{has_extension, InputFormat};
+
+/// JSON written by `llvm-cov export`.
+pub struct LlvmJson;
+
+impl InputFormat for LlvmJson {
+    fn name(&self) -> &str {
+        "llvm"
+    }
+
+    fn detect(&self, path: &Path, contents: &str) -> bool {
+        has_extension(path, &["json"]) || contents.trim_start().starts_with('{')
+    }
+
+    fn read<'a>(&self, contents: &'a str) -> Result<Vec<CoverageMapping<'a>>, Box<dyn StdError>> {
+        let report: SummaryReport = serde_json::from_str(contents)?;
+        Ok(report.data)
+    }
+}
@@ -0,0 +1,52 @@
+//! Generates code coverage reports for rust projects from `llvm-cov export`
+//! JSON.  The `cosmoline` binary puts its pipeline together from the
+//! modules of this crate itself, with far more options than
+//! [`report::CoverageReport`] offers; that's for build scripts and xtasks that
+//! just want a report.  The parts most useful to other tools are the
+//! [`model`] that every output format is built from and the
+//! [`export::Exporter`] trait for adding formats of your own.
+
+pub mod coverage_data;
+pub use coverage_data::*;
+
+pub mod age;
+pub mod anonymize;
+pub mod badge;
+pub mod browser;
+pub mod cache;
+pub mod capabilities;
+pub mod changes;
+pub mod chart;
+pub mod check;
+pub mod config;
+pub mod diagnostics;
+pub mod diff;
+pub mod encoding;
+pub mod export;
+pub mod filter;
+pub mod flaky;
+pub mod freshness;
+pub mod fs;
+pub mod git;
+#[cfg(feature = "render-html")]
+pub mod history;
+pub mod input;
+pub mod manifest;
+pub mod merge;
+pub mod model;
+pub mod profile;
+pub mod public_api;
+#[cfg(feature = "render-html")]
+pub mod render;
+pub mod report;
+pub mod renames;
+pub mod repro;
+pub mod stats;
+#[cfg(feature = "render-html")]
+pub mod time;
+pub mod treemap;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod uncovered;
+pub mod utils;
+pub mod verify;
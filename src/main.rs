@@ -1,77 +1,628 @@
-#![feature(destructuring_assignment)]
-
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 #[allow(unused)]
 use log::{error, warn, info, debug, trace};
 
-use clap::{crate_name, crate_version, App, Arg};
+use clap::{crate_name, crate_version, App, AppSettings, Arg, SubCommand};
 use env_logger::{Builder, Env};
-use handlebars::{self as hbs, Handlebars};
-use serde::Serialize;
 
-mod coverage_data;
-use coverage_data::*;
+use cosmoline::{age, anonymize, badge, browser, capabilities, changes, check, config, diagnostics, diff, export, filter, flaky, freshness, fs, git, history, input, manifest, merge, model, profile, public_api, render, report, repro, time, uncovered, utils, verify};
 
-mod render;
-mod utils;
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(debug_assertions)]
+    Builder::from_env(Env::default().default_filter_or("info,cosmoline=debug"))
+        .format_timestamp(None)
+        .init();
 
-fn setup_handlebars<'a>() -> Result<Handlebars<'a>, Box<dyn std::error::Error>> {
-    let mut handlebars = Handlebars::new();
+    #[cfg(not(debug_assertions))]
+    Builder::from_env(Env::default().default_filter_or("off"))
+        .format_timestamp(None)
+        .init();
 
-    handlebars.register_helper("strftime",
-      Box::new(|h: &hbs::Helper, _r: &hbs::Handlebars, _: &hbs::Context, _rc: &mut hbs::RenderContext, out: &mut dyn hbs::Output| -> hbs::HelperResult {
-          let time_arg : &str = h.param(0).ok_or(hbs::RenderError::new("time param not found"))?.value().as_str().unwrap();
-          let format_arg : &str = h.param(1).ok_or(hbs::RenderError::new("format param not found"))?.value().as_str().unwrap();
+    let given = app(false).get_matches();
 
-          let time = chrono::DateTime::parse_from_rfc3339(time_arg).map_err(|e| hbs::RenderError::new(e.to_string()))?;
+    match given.subcommand() {
+        ("diff", Some(diff_matches)) => return diff::run(diff_matches),
+        ("repro", Some(repro_matches)) => return repro::run(repro_matches),
+        ("verify", Some(verify_matches)) => return verify::run(verify_matches),
+        ("template-schema", Some(schema_matches)) => return template_schema(schema_matches),
+        #[cfg(feature = "tui")]
+        ("tui", Some(tui_matches)) => return cosmoline::tui::run(tui_matches),
+        _ => (),
+    }
 
-          out.write(
-            &format!("{}", time.format(format_arg))
-          ).map_err(|e| hbs::RenderError::new(e.to_string()))
-      }));
+    // Settings from the environment and then the config file go after the
+    // command line, for the arguments it doesn't give
+    let vars = std::env::vars_os().filter_map(|(var, value)| Some((var.into_string().ok()?, value.into_string().ok()?)));
+    let names = long_names(&app(false));
+    let env = config::Config::from_env(vars, &names);
+    let env_args = env.args(|name| given.occurrences_of(name) > 0)?;
+    let config_path = given.value_of_os("config").or(std::env::var_os(config::CONFIG_VAR).as_deref()).map(PathBuf::from);
+    let config = config::Config::load(config_path.as_deref())?.canonical(&names);
+    let config_args = config.args(|name| given.occurrences_of(name) > 0 || env.args.contains_key(name))?;
+    let extra_args = env_args.into_iter().chain(config_args).collect::<Vec<_>>();
+    let matches = match app(true).get_matches_from_safe(std::env::args_os().chain(extra_args.iter().map(Into::into))) {
+        Ok(matches) => matches,
+        Err(e) => {
+            if !extra_args.is_empty() {
+                eprintln!("Settings from the environment and the config file were passed as: {}", extra_args.join(" "));
+            }
+            e.exit()
+        }
+    };
 
-    let index_template_str = include_str!("../template/index.html.hbs");
-    handlebars.register_template_string("index", index_template_str)?;
+    if matches.is_present("capabilities") {
+        println!("{}", serde_json::to_string_pretty(&capabilities::capabilities())?);
+        return Ok(());
+    }
 
-    let file_template_str = include_str!("../template/file.html.hbs");
-    handlebars.register_template_string("file", file_template_str)?;
+    if let Some(filename) = matches.value_of("explain-filter") {
+        let source_path = Path::new(matches.value_of("source-prefix").unwrap_or("."));
+        println!("{}\n{}", filename, file_filter(&matches, source_path)?.decide(filename));
+        return Ok(());
+    }
 
-    let funcs_template_str = include_str!("../template/functions.html.hbs");
-    handlebars.register_template_string("functions", funcs_template_str)?;
+    let mut profile = profile::Profile::start();
 
-    let style_source = include_str!("../template/style.css");
-    handlebars.register_template_string("style", style_source)?;
+    let input_filenames = matches.values_of("input").unwrap().collect::<Vec<_>>();
+    let input_path = match matches.value_of("source-prefix") {
+        Some(prefix) => Path::new(prefix),
+        None => Path::new(input_filenames[0]).parent().unwrap()
+    };
 
-    Ok(handlebars)
-}
+    let output_directory = matches.value_of("output-directory").unwrap();
+    let output_path = Path::new(output_directory);
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    #[cfg(debug_assertions)]
-    Builder::from_env(Env::default().default_filter_or("info,cosmoline=debug"))
-        .format_timestamp(None)
-        .init();
+    let package = matches.value_of("package-name");
 
-    #[cfg(not(debug_assertions))]
-    Builder::from_env(Env::default().default_filter_or("off"))
-        .format_timestamp(None)
-        .init();
+    let assets = render::Assets::new(
+        !matches.is_present("no-inline-css"),
+        !matches.is_present("no-inline-js"),
+    );
+
+    let anonymizer = anonymize::Anonymizer::new(
+        matches.is_present("anonymize"),
+        matches.value_of("anonymize-salt"),
+    );
+
+    let mut file_contents = vec![];
+    for input_filename in input_filenames.iter() {
+        info!("Reading coverage data from: {}", input_filename);
+        file_contents.push(std::fs::read_to_string(input_filename)?);
+    }
+
+    let labels = match matches.values_of("label") {
+        Some(labels) => labels.collect::<Vec<_>>(),
+        None => input_filenames
+            .iter()
+            .map(|filename| Path::new(filename).file_stem().and_then(|stem| stem.to_str()).unwrap_or(filename))
+            .collect(),
+    };
+    if labels.len() != input_filenames.len() {
+        return Err(format!("Got {} labels for {} inputs", labels.len(), input_filenames.len()).into());
+    }
+
+    let input_formats = input::Registry::builtin();
+    let read_input = |input_filename: &str, contents| {
+        let format = match matches.value_of("input-format") {
+            Some(name) => input_formats.get(name),
+            None => input_formats.detect(Path::new(input_filename), contents),
+        };
+        let format = format.ok_or_else(|| format!("Unrecognized input format for `{}', try --input-format", input_filename))?;
+        debug!("Reading {} as {}", input_filename, format.name());
+        format.read(contents)
+    };
+
+    let mut mappings = vec![];
+    for (input_filename, contents) in input_filenames.iter().zip(file_contents.iter()) {
+        mappings.extend(read_input(input_filename, contents)?);
+    }
+
+    let baseline_contents = match matches.value_of("baseline") {
+        Some(baseline_filename) => {
+            info!("Reading baseline from: {}", baseline_filename);
+            Some((baseline_filename, std::fs::read_to_string(baseline_filename)?))
+        }
+        None => None,
+    };
+    let baseline = match baseline_contents.as_ref() {
+        Some((baseline_filename, contents)) => Some(read_input(baseline_filename, contents)?),
+        None => None,
+    };
 
-    let matches = App::new(crate_name!())
+    report::create_output_dir(output_path)?;
+    profile.lap("parse");
+
+    info!("{} reports", mappings.len());
+    let merge_mode: merge::MergeMode = matches.value_of("merge-mode").unwrap().parse()?;
+    let coverage = merge::combine(mappings, merge_mode);
+
+    let timezone = match matches.is_present("utc") {
+        true => time::Timezone::Utc,
+        false => matches.value_of("timezone").unwrap().parse()?,
+    };
+    // Resolved once so every model built below gives the same time
+    let timestamp = match matches.value_of("timestamp-source").unwrap().parse()? {
+        time::TimestampSource::Mtime => time::TimestampSource::Mtime,
+        source => time::TimestampSource::Fixed(source.resolve(input_path)?),
+    };
+
+    let options = model::ModelOptions {
+        package,
+        filter: file_filter(&matches, input_path)?,
+        // Crate names are always underscored in symbols
+        function_crates: matches
+            .values_of("function-crate")
+            .map(|crates| crates.map(|c| c.replace('-', "_")).collect::<Vec<_>>())
+            .unwrap_or_default(),
+        dedup: match matches.value_of("dedup-functions").unwrap() {
+            "none" => None,
+            mode => Some(mode.parse()?),
+        },
+        fold_async: !matches.is_present("no-fold-async"),
+        fold_closures: !matches.is_present("no-fold-closures"),
+        page_layout: matches.value_of("page-layout").unwrap().parse()?,
+        timezone,
+        timestamp,
+    };
+    let mut model = model::ReportModel::build(&coverage, &options, &anonymizer, input_path)?;
+    profile.lap("model");
+
+    let baseline = match baseline {
+        Some(mappings) => {
+            let baseline = model::ReportModel::build(&merge::combine(mappings, merge_mode), &options, &anonymizer, input_path)?;
+            model.compare_baseline(&baseline);
+            if !model.dropped_functions.is_empty() {
+                warn!("{} functions ran in the baseline but not anymore", model.dropped_functions.len());
+            }
+            if !model.renamed_functions.is_empty() {
+                info!("{} functions were renamed since the baseline", model.renamed_functions.len());
+            }
+            Some(baseline)
+        }
+        None => None,
+    };
+
+    let no_merge = matches.is_present("no-merge");
+    let mut labeled = vec![];
+    // These formats are made of the labeled models alone
+    let by_label = matches.values_of("output-format").unwrap().any(|format| format == "reachability" || format == "unique");
+    if no_merge || by_label {
+        // Inputs sharing a label are merged into the same view
+        let mut grouped: Vec<(&str, Vec<_>)> = vec![];
+        for ((input_filename, contents), label) in input_filenames.iter().zip(file_contents.iter()).zip(labels.iter()) {
+            let read = read_input(input_filename, contents)?;
+            match grouped.iter_mut().find(|(existing, _)| existing == label) {
+                Some((_, mappings)) => mappings.extend(read),
+                None => grouped.push((label, read)),
+            }
+        }
+
+        for (label, mappings) in grouped {
+            let coverage = merge::combine(mappings, merge_mode);
+            labeled.push((label.to_string(), model::ReportModel::build(&coverage, &options, &anonymizer, input_path)?));
+        }
+    }
+
+    if matches.is_present("flaky") {
+        if input_filenames.len() < 2 {
+            return Err("--flaky needs at least two inputs to compare".into());
+        }
+        let mut runs = vec![];
+        for (input_filename, contents) in input_filenames.iter().zip(file_contents.iter()) {
+            let coverage = merge::combine(read_input(input_filename, contents)?, merge_mode);
+            runs.push(model::ReportModel::build(&coverage, &options, &anonymizer, input_path)?);
+        }
+        let flaky = flaky::compare(&model, &runs);
+        match flaky.regions.len() {
+            0 => info!("Every region ran in all {} runs or in none", flaky.runs),
+            n => warn!("{} regions ran in only some of the {} runs", n, flaky.runs),
+        }
+        model.flaky = Some(flaky);
+    }
+
+    let diagnostics = diagnostics::Diagnostics::default();
+
+    if let Some(days) = matches.value_of("commit-age") {
+        let commit_age = age::commit_age(&model.files, input_path, days.parse()?, chrono::Utc::now().timestamp(), &diagnostics)?;
+        info!(
+            "Lines from the last {} days: {:.1}% covered, older lines: {:.1}%",
+            commit_age.days, commit_age.recent.percent, commit_age.older.percent
+        );
+        model.commit_age = Some(commit_age);
+    }
+
+    if matches.is_present("file-age") {
+        let file_age = age::file_age(&model.files, input_path, chrono::Utc::now().timestamp(), &diagnostics)?;
+        for bucket in file_age.buckets.iter().filter(|b| b.files > 0) {
+            info!("Files changed {}: {:.1}% of lines covered", bucket.label.to_lowercase(), bucket.lines.percent);
+        }
+        model.file_age = Some(file_age);
+    }
+
+    if let Some(path) = matches.value_of("public-api") {
+        let json = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let public_api = public_api::public_api(&model, &public_api::parse_rustdoc(&json)?, &anonymizer);
+        info!(
+            "Public API: {} of {} functions hit ({:.1}%)",
+            public_api.summary.covered, public_api.summary.count, public_api.summary.percent
+        );
+        model.public_api = Some(public_api);
+    }
+
+    let changed_lines = match matches.value_of("diff-base") {
+        Some(range) => {
+            let changed = git::changed_lines(input_path, range)?;
+            let diff = changes::diff_coverage(&model, range, &changed);
+            info!(
+                "Changed lines in {}: {} of {} hit ({:.1}%)",
+                range, diff.summary.covered, diff.summary.count, diff.summary.percent
+            );
+            model.diff = Some(diff);
+            Some((range, changed))
+        }
+        None => None,
+    };
+
+    // Where the source directory is within the repository, for the paths
+    // review tools attach findings by
+    let path_prefix = match changed_lines.is_some() {
+        true => git::show_prefix(input_path)?,
+        false => String::new(),
+    };
+
+    let changed_files = match matches.value_of("only-changed") {
+        Some(range) => {
+            let changed = git::changed_files(input_path, range)?;
+            info!("{} files changed in {}", changed.len(), range);
+            Some(changed)
+        }
+        None => None,
+    };
+
+    let history = match matches.is_present("history") {
+        true => {
+            let mut history = history::History::load(output_path)?;
+            let now = chrono::Utc::now();
+            history.record(&model.files, &model.totals, now);
+            if let Some(keep) = matches.value_of("history-keep") {
+                let pruned = history.prune(keep.parse()?, now);
+                info!("Pruned {} runs from history", pruned);
+            }
+            history.save(output_path)?;
+            Some(history)
+        }
+        false => None,
+    };
+
+    let mut goals = check::Goals::default();
+    for (arg, goal) in [
+        ("goal-lines", &mut goals.lines),
+        ("goal-functions", &mut goals.functions),
+        ("goal-branches", &mut goals.branches),
+    ].iter_mut() {
+        if let Some(value) = matches.value_of(arg) {
+            **goal = value.parse()?;
+        }
+    }
+
+    let fail_level = match matches.is_present("soft-fail") {
+        true => check::Level::Warn,
+        false => check::Level::Fail,
+    };
+    let mut thresholds = vec![];
+    for (arg, metric, level) in [
+        ("fail-under-lines", check::Metric::Lines, fail_level),
+        ("fail-under-functions", check::Metric::Functions, fail_level),
+        ("fail-under-branches", check::Metric::Branches, fail_level),
+        ("warn-under-lines", check::Metric::Lines, check::Level::Warn),
+        ("warn-under-functions", check::Metric::Functions, check::Level::Warn),
+        ("warn-under-branches", check::Metric::Branches, check::Level::Warn),
+    ].iter() {
+        if let Some(minimum) = matches.value_of(arg) {
+            thresholds.push(check::Threshold::parse(*metric, minimum, *level, &goals)?);
+        }
+    }
+
+    let hosting = render::Hosting {
+        noindex: matches.is_present("noindex"),
+        robots_txt: matches.is_present("robots-txt"),
+        not_found_page: matches.is_present("not-found-page"),
+        base_url: matches.value_of("base-url").map(|url| match url.ends_with('/') {
+            true => url.to_string(),
+            false => format!("{}/", url),
+        }),
+        link_style: matches.value_of("link-style").unwrap().parse()?,
+    };
+
+    let base_url = hosting.base_url.clone();
+
+    let source_encoding = matches.value_of("source-encoding").unwrap().parse()?;
+    let max_open_files = matches.value_of("max-open-files").unwrap().parse()?;
+
+    let require_fresh = matches.is_present("require-fresh-sources");
+    let mut stale = vec![];
+    if require_fresh {
+        stale = freshness::stale_sources(&model, input_path, source_encoding, &fs::FileLimiter::new(max_open_files));
+    }
+    if let Some(path) = matches.value_of("source-manifest") {
+        let recorded: manifest::Manifest = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let hashes = freshness::source_hashes(&model, input_path, &fs::FileLimiter::new(max_open_files));
+        for (name, problem) in freshness::changed_since(&recorded, &hashes) {
+            diagnostics.warn(Some(&name), format!("Stale source: {}", problem));
+            stale.push((name, problem));
+        }
+    }
+    if require_fresh && !stale.is_empty() {
+        for (name, problem) in stale.iter() {
+            error!("{}: {}", name, problem);
+        }
+        return Err(format!("{} source files don't fit their coverage data", stale.len()).into());
+    }
+
+    // Built for these exports alone without --no-merge
+    let reachability = export::Reachability::new(&labeled);
+    let unique = export::Unique::new(&labeled);
+    if !no_merge {
+        labeled.clear();
+    }
+
+    let mut html = export::Html::new(assets, &anonymizer, input_path)?
+        .thresholds(thresholds.clone())
+        .goals(goals)
+        .palette(matches.value_of("palette").unwrap().parse()?)
+        .print_of_record(matches.is_present("print-of-record"))
+        .source_encoding(source_encoding)
+        .hosting(hosting)
+        .labels(labeled)
+        .diagnostics(diagnostics)
+        .max_open_files(max_open_files);
+    if let Some(mut compared) = matches.values_of("compare-labels") {
+        html = html.compare_labels(compared.next().unwrap(), compared.next().unwrap());
+    }
+    html = html.postprocessors(config.postprocessors()?).titles(config.titles.clone());
+    if let Some(budget) = matches.value_of("size-budget") {
+        html = html.size_budget(utils::parse_size(budget)?);
+    }
+    let mut templates = render::Templates::builtin()?.timezone(timezone);
+    if let Some(dir) = matches.value_of("template-dir") {
+        let engine = matches.value_of("template-engine").unwrap().parse()?;
+        templates = templates.custom_dir(Path::new(dir), engine)?;
+    }
+    html = html.templates(templates);
+    if !matches.is_present("no-highlight") {
+        html = html.highlight(render::Highlighter::new());
+    }
+    if matches.is_present("sidebar") {
+        html = html.sidebar();
+    }
+    if matches.is_present("single-file") {
+        html = html.single_file();
+    }
+    if matches.is_present("preview") {
+        html = html.preview(matches.value_of("preview-files").unwrap().parse()?);
+    }
+    if let Some(history) = history.as_ref() {
+        let runs = matches.value_of("sparkline-runs").unwrap().parse()?;
+        html = html.history(history, runs);
+    }
+    if let Some(changed) = changed_files {
+        html = html.changed_files(changed);
+    }
+    if matches.is_present("incremental") {
+        // Any change to the options renders every page again, including the
+        // sections of the config file that aren't arguments
+        let args = std::env::args_os().skip(1).map(|arg| arg.to_string_lossy().into_owned()).chain(extra_args.iter().cloned());
+        let settings = format!("{} {:?} {:?}", args.collect::<Vec<_>>().join(" "), config.postprocess, config.titles);
+        html = html.incremental(&settings);
+    }
+
+    let mut exporters = export::Registry::builtin();
+    exporters.register(html);
+    exporters.register(export::Text::new(&anonymizer, input_path).source_encoding(source_encoding).max_open_files(max_open_files));
+    exporters.register(export::Markdown::default().thresholds(thresholds.clone()));
+    exporters.register(export::GithubAnnotations::default().path_prefix(path_prefix.as_str()));
+    exporters.register(reachability);
+    exporters.register(unique);
+
+    let selected = matches
+        .values_of("output-format")
+        .unwrap()
+        .map(|format| {
+            exporters.get(format).ok_or_else(|| {
+                format!(
+                    "Unknown output format `{}', expected one of: {}",
+                    format,
+                    exporters.names().collect::<Vec<_>>().join(", ")
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    profile.lap("setup");
+
+    let mut wrote_html = false;
+    for exporter in selected {
+        info!("Writing {} output", exporter.name());
+        exporter.export(&model, output_path)?;
+        wrote_html |= exporter.name() == "html";
+        profile.lap(&format!("render {}", exporter.name()));
+    }
+    info!("Spent {:.3}s writing files, summed over the threads writing them", fs::time_writing().as_secs_f64());
+
+    if let (Some(level), true) = (matches.value_of("check-links"), wrote_html) {
+        let manifest = verify::manifest(output_path)?;
+        let dead = verify::dead_links(output_path, &verify::pages(output_path, &manifest), base_url.as_deref())?;
+        let prefix = match level {
+            "fail" => "error",
+            _ => "warning",
+        };
+        for link in dead.iter() {
+            eprintln!("{}: {}", prefix, link);
+        }
+        match dead.len() {
+            0 => info!("Every link leads somewhere"),
+            n if level == "fail" => return Err(format!("{} dead links in the report", n).into()),
+            n => warn!("{} dead links in the report", n),
+        }
+    }
+
+    let mut outcomes = check::evaluate(&thresholds, &model.totals);
+    if matches.is_present("per-file-thresholds") {
+        outcomes.extend(check::evaluate_files(&thresholds, &model.files));
+    }
+    for annotation in check::annotations(&outcomes, std::env::var_os("GITHUB_ACTIONS").is_some()) {
+        println!("{}", annotation);
+    }
+
+    if let Some(junit_path) = matches.value_of("junit-report") {
+        std::fs::write(junit_path, check::junit(&outcomes))?;
+        info!("Threshold results written to {}", junit_path);
+    }
+
+    if let Some(csv_path) = matches.value_of("uncovered-csv") {
+        let regions = uncovered::regions(&model);
+        std::fs::write(csv_path, uncovered::csv(&regions))?;
+        info!("{} uncovered regions written to {}", regions.len(), csv_path);
+    }
+
+    if let (Some(rdjson_path), Some((range, changed))) = (matches.value_of("reviewdog"), changed_lines.as_ref()) {
+        let uncovered = changes::uncovered(&model, changed);
+        std::fs::write(rdjson_path, changes::rdjson(&uncovered, &path_prefix))?;
+        info!("{} uncovered changes since {} written to {}", uncovered.len(), range, rdjson_path);
+    }
+
+    if let Some(badge_path) = matches.value_of("badge") {
+        std::fs::write(badge_path, badge::coverage("coverage", model.totals.lines.percent))?;
+        info!("Coverage badge written to {}", badge_path);
+    }
+
+    if let Some(endpoint_path) = matches.value_of("badge-endpoint") {
+        std::fs::write(endpoint_path, badge::endpoint("coverage", model.totals.lines.percent))?;
+        info!("Coverage badge endpoint written to {}", endpoint_path);
+    }
+
+    if let (Some(badge_path), Some(baseline)) = (matches.value_of("delta-badge"), baseline.as_ref()) {
+        let delta = model.totals.lines.percent - baseline.totals.lines.percent;
+        info!("Line coverage changed by {:.2}% versus baseline", delta);
+        std::fs::write(badge_path, badge::delta("coverage Δ", delta))?;
+    }
+
+    if matches.is_present("profile-self") {
+        profile.finish();
+        eprintln!("{}", profile.summary());
+        std::fs::write(output_path.join(profile::FILENAME), serde_json::to_string_pretty(&profile)?)?;
+    }
+
+    println!("Report written to {}", output_path.display());
+
+    if matches.is_present("open") && wrote_html {
+        let index = output_path.join("index.html");
+        if let Err(e) = browser::open(&index) {
+            eprintln!("warning: couldn't open {}: {}", index.display(), e);
+        }
+    }
+
+    if let Some(summary) = check::failure_summary(&outcomes) {
+        eprintln!("{}", summary);
+        std::process::exit(check::EXIT_BELOW_THRESHOLD);
+    }
+
+    Ok(())
+}
+
+/// The name of every argument of `app` by each of its long names, aliases
+/// included, e.g. `include` by `include` and `include-glob`.  clap 2 doesn't
+/// list them publicly, but its parser's fields are public and won't change
+/// anymore.
+fn long_names(app: &App) -> BTreeMap<String, String> {
+    let flags = app.p.flags.iter().map(|flag| (flag.b.name, &flag.s));
+    let opts = app.p.opts.iter().map(|opt| (opt.b.name, &opt.s));
+    let mut names = BTreeMap::new();
+    for (name, switched) in flags.chain(opts) {
+        let aliases = switched.aliases.iter().flatten().map(|(alias, _)| *alias);
+        for long in switched.long.into_iter().chain(aliases) {
+            names.insert(long.to_string(), name.to_string());
+        }
+    }
+    names
+}
+
+/// The command line.  Without `strict` nothing is required, to find out
+/// which arguments were given before the config file fills in the rest.
+fn app<'a, 'b>(strict: bool) -> App<'a, 'b> {
+    let app = App::new(crate_name!())
         .version(crate_version!())
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(
-            Arg::with_name("input")
+            required(strict, Arg::with_name("input")
                 .short("i")
                 .long("input")
                 .takes_value(true)
-                .required(true),
+                .multiple(true)
+                .number_of_values(1)
+                .help("Coverage export to report on; several (e.g. unit, doc, and integration tests) are merged into one report")),
+        )
+        .arg(
+            Arg::with_name("input-format")
+                .long("input-format")
+                .takes_value(true)
+                .possible_values(&["llvm", "lcov", "cobertura"])
+                .help("Format of the input files; detected from the extension or contents by default")
+        )
+        .arg(
+            Arg::with_name("label")
+                .long("label")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Label for each input, in the same order (defaults to the file name)")
+        )
+        .arg(
+            Arg::with_name("no-merge")
+                .long("no-merge")
+                .help("Also render a view per label, tabbed next to the merged view of every input")
+        )
+        .arg(
+            Arg::with_name("compare-labels")
+                .long("compare-labels")
+                .takes_value(true)
+                .number_of_values(2)
+                .use_delimiter(true)
+                .value_names(&["left", "right"])
+                .requires("no-merge")
+                .help("Render the counts of two labels side by side in a tab of their own, e.g. unit,fuzz")
+        )
+        .arg(
+            Arg::with_name("flaky")
+                .long("flaky")
+                .help("Treat the inputs as repeats of the same test run and list regions that ran in only some of them")
+        )
+        .arg(
+            Arg::with_name("merge-mode")
+                .long("merge-mode")
+                .takes_value(true)
+                .possible_values(&["sum", "max", "latest"])
+                .default_value("sum")
+                .help("How execution counts are combined when merging several exports")
         )
         .arg(
-            Arg::with_name("output")
+            required(strict, Arg::with_name("output-directory")
                 .short("o")
                 .long("output-directory")
+                .takes_value(true)),
+        )
+        .arg(
+            Arg::with_name("output-format")
+                .long("output-format")
+                .alias("emit")
                 .takes_value(true)
-                .required(true),
+                .multiple(true)
+                .number_of_values(1)
+                .use_delimiter(true)
+                .default_value("html")
+                .help("Formats to write to the output directory: html, lcov, cobertura, json, json-summary, github-annotations, markdown, reachability, text, or unique (may be repeated)")
         )
         .arg(
             Arg::with_name("source-prefix")
@@ -79,113 +630,630 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .long("source-prefix")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("source-encoding")
+                .long("source-encoding")
+                .takes_value(true)
+                .possible_values(&["utf-8", "latin1", "auto"])
+                .default_value("auto")
+                .help("How source files are decoded; auto goes by the byte order mark and falls back from UTF-8 to Latin-1")
+        )
         .arg(
             Arg::with_name("package-name")
                 .short("n")
                 .long("package-name")
                 .takes_value(true)
         )
-        .get_matches();
-
-    let handlebars = setup_handlebars()?;
-
-    let input_filename = matches.value_of("input").unwrap();
-    let input_path = match matches.value_of("source-prefix") {
-        Some(prefix) => Path::new(prefix),
-        None => Path::new(input_filename).parent().unwrap()
-    };
+        .arg(
+            Arg::with_name("no-inline-css")
+                .long("no-inline-css")
+                .help("Write page specific CSS to external files instead of inline <style> blocks")
+        )
+        .arg(
+            Arg::with_name("no-inline-js")
+                .long("no-inline-js")
+                .help("Write page specific JavaScript to external files instead of inline <script> blocks")
+        )
+        .arg(
+            Arg::with_name("no-highlight")
+                .long("no-highlight")
+                .help("Don't syntax highlight the source, color it by coverage alone")
+        )
+        .arg(
+            Arg::with_name("noindex")
+                .long("noindex")
+                .help("Ask search engines not to index the report with a robots meta tag on every page")
+        )
+        .arg(
+            Arg::with_name("robots-txt")
+                .long("robots-txt")
+                .help("Write a robots.txt turning all crawlers away (only honored at the root of a site)")
+        )
+        .arg(
+            Arg::with_name("not-found-page")
+                .long("not-found-page")
+                .help("Write a 404.html for static hosts, linking back to the index")
+        )
+        .arg(
+            Arg::with_name("base-url")
+                .long("base-url")
+                .takes_value(true)
+                .value_name("url")
+                .help("URL the report is published at, e.g. https://example.com/coverage/; used for absolute links")
+        )
+        .arg(
+            Arg::with_name("link-style")
+                .long("link-style")
+                .takes_value(true)
+                .possible_values(&["relative", "absolute"])
+                .default_value("relative")
+                .help("Link between pages relatively, or absolutely under --base-url (the site root without one)")
+        )
+        .arg(
+            Arg::with_name("page-layout")
+                .long("page-layout")
+                .takes_value(true)
+                .possible_values(&["flat", "tree"])
+                .default_value("flat")
+                .help("Name file pages after their paths next to the index, or mirror the source tree with an index.html per directory")
+        )
+        .arg(
+            Arg::with_name("sidebar")
+                .long("sidebar")
+                .help("Show a collapsible tree of every directory and file with its coverage next to every page")
+        )
+        .arg(
+            Arg::with_name("single-file")
+                .long("single-file")
+                .conflicts_with_all(&["no-inline-css", "no-merge", "sidebar"])
+                .help("Write the whole report as one index.html with the styles inlined and every file in a collapsed section")
+        )
+        .arg(
+            Arg::with_name("preview")
+                .long("preview")
+                .conflicts_with_all(&["single-file", "no-merge", "sidebar", "only-changed"])
+                .help("Only render the pages of the least covered files besides the index and summary pages, for a first look at a large export")
+        )
+        .arg(
+            Arg::with_name("preview-files")
+                .long("preview-files")
+                .takes_value(true)
+                .value_name("n")
+                .default_value("20")
+                .help("Number of files --preview renders pages for")
+        )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .takes_value(true)
+                .value_name("file")
+                .help("Read settings from this file rather than $COSMOLINE_CONFIG or ./cosmoline.toml")
+        )
+        .arg(
+            Arg::with_name("size-budget")
+                .long("size-budget")
+                .takes_value(true)
+                .value_name("size")
+                .help("Warn when the HTML report takes up more than this, e.g. 50M")
+        )
+        .arg(
+            Arg::with_name("template-dir")
+                .long("template-dir")
+                .takes_value(true)
+                .value_name("dir")
+                .help("Directory of custom templates replacing the built-in ones of the same name, e.g. index.html.hbs")
+        )
+        .arg(
+            Arg::with_name("template-engine")
+                .long("template-engine")
+                .takes_value(true)
+                .possible_values(&["handlebars", "minijinja"])
+                .default_value("handlebars")
+                .help("What the custom templates are written for; minijinja needs the minijinja feature")
+        )
+        .arg(
+            Arg::with_name("timezone")
+                .long("timezone")
+                .takes_value(true)
+                .default_value("local")
+                .help("Show times in this timezone: local, utc, or an offset like +02:00")
+        )
+        .arg(
+            Arg::with_name("timestamp-source")
+                .long("timestamp-source")
+                .takes_value(true)
+                .default_value("mtime")
+                .value_name("source")
+                .help("What the report gives as the time of the data: mtime of the input, commit time of HEAD, now, or fixed=<rfc3339>")
+        )
+        .arg(
+            Arg::with_name("utc")
+                .long("utc")
+                .help("Show times in UTC, the same as --timezone utc")
+        )
+        .arg(
+            Arg::with_name("max-open-files")
+                .long("max-open-files")
+                .takes_value(true)
+                .default_value("64")
+                .help("Most source and output files open at the same time")
+        )
+        .arg(
+            Arg::with_name("anonymize")
+                .long("anonymize")
+                .help("Replace file paths, function names, and source text with salted hashes")
+        )
+        .arg(
+            Arg::with_name("anonymize-salt")
+                .long("anonymize-salt")
+                .takes_value(true)
+                .requires("anonymize")
+                .help("Salt used when hashing names for --anonymize")
+        )
+        .arg(
+            Arg::with_name("history")
+                .long("history")
+                .help("Record this run in a history store in the output directory and render per-file trends")
+        )
+        .arg(
+            Arg::with_name("sparkline-runs")
+                .long("sparkline-runs")
+                .takes_value(true)
+                .default_value("10")
+                .help("Number of runs shown in each per-file sparkline")
+        )
+        .arg(
+            Arg::with_name("history-keep")
+                .long("history-keep")
+                .takes_value(true)
+                .requires("history")
+                .help("Prune the history store to recent runs, e.g. 90d, 12w, or a run count like 30")
+        )
+        .arg(
+            Arg::with_name("only-changed")
+                .long("only-changed")
+                .takes_value(true)
+                .value_name("git-range")
+                .help("Only render pages for files changed in the given git range (the index is always rendered)")
+        )
+        .arg(
+            Arg::with_name("incremental")
+                .long("incremental")
+                .conflicts_with_all(&["single-file", "sidebar"])
+                .help("Only render pages for files whose source or coverage changed since the last run, going by .cosmoline-cache.json in the output directory")
+        )
+        .arg(
+            Arg::with_name("open")
+                .long("open")
+                .help("Open the HTML report in a browser once it's written, with $BROWSER if set")
+        )
+        .arg(
+            Arg::with_name("profile-self")
+                .long("profile-self")
+                .help("Print how long parsing, building the model, and each output took, and the peak memory use, and write them to cosmoline-profile.json in the output directory")
+        )
+        .arg(
+            Arg::with_name("require-fresh-sources")
+                .long("require-fresh-sources")
+                .help("Fail if a source file doesn't fit its coverage data, e.g. because it was edited after the export")
+        )
+        .arg(
+            Arg::with_name("source-manifest")
+                .long("source-manifest")
+                .takes_value(true)
+                .value_name("file")
+                .help("Treat sources whose hash differs from the one in this manifest.json as stale")
+        )
+        .arg(
+            Arg::with_name("diff-base")
+                .long("diff-base")
+                .takes_value(true)
+                .value_name("git-range")
+                .help("Report coverage of just the lines changed in `git diff <git-range>' on diff.html, also what --reviewdog looks at")
+        )
+        .arg(
+            Arg::with_name("reviewdog")
+                .long("reviewdog")
+                .takes_value(true)
+                .value_name("file")
+                .requires("diff-base")
+                .help("Write changed lines that never ran as reviewdog diagnostics (rdjson)")
+        )
+        .arg(
+            Arg::with_name("commit-age")
+                .long("commit-age")
+                .takes_value(true)
+                .value_name("days")
+                .help("Compare line coverage of code written in the last <days> days against older code, using git blame")
+        )
+        .arg(
+            Arg::with_name("file-age")
+                .long("file-age")
+                .help("Show line coverage of files by when they were last changed on the statistics page, using git log")
+        )
+        .arg(
+            Arg::with_name("public-api")
+                .long("public-api")
+                .takes_value(true)
+                .value_name("file")
+                .help("Report coverage of the public functions listed in this rustdoc JSON separately")
+        )
+        .arg(
+            Arg::with_name("baseline")
+                .long("baseline")
+                .takes_value(true)
+                .help("Export to compare this run against, in any input format")
+        )
+        .arg(
+            Arg::with_name("badge")
+                .long("badge")
+                .takes_value(true)
+                .value_name("path")
+                .help("Write an SVG badge showing the total line coverage")
+        )
+        .arg(
+            Arg::with_name("badge-endpoint")
+                .long("badge-endpoint")
+                .takes_value(true)
+                .value_name("path")
+                .help("Write the total line coverage as shields.io endpoint badge JSON")
+        )
+        .arg(
+            Arg::with_name("delta-badge")
+                .long("delta-badge")
+                .takes_value(true)
+                .requires("baseline")
+                .help("Write an SVG badge showing the change in line coverage versus the baseline")
+        )
+        .arg(
+            Arg::with_name("palette")
+                .long("palette")
+                .takes_value(true)
+                .possible_values(&["red-green", "blue-orange", "monochrome"])
+                .default_value("red-green")
+                .help("Colors coverage is shown in; the others also hatch code that never ran")
+        )
+        .arg(
+            Arg::with_name("print-of-record")
+                .long("print-of-record")
+                .help("Print the pages in black and white, with coverage told apart by patterns")
+        )
+        .arg(
+            Arg::with_name("goal-lines")
+                .long("goal-lines")
+                .takes_value(true)
+                .value_name("medium,high")
+                .help("Line coverage shown in yellow and in green [default: 75,90]")
+        )
+        .arg(
+            Arg::with_name("goal-functions")
+                .long("goal-functions")
+                .takes_value(true)
+                .value_name("medium,high")
+                .help("Function coverage shown in yellow and in green [default: 75,90]")
+        )
+        .arg(
+            Arg::with_name("goal-branches")
+                .long("goal-branches")
+                .takes_value(true)
+                .value_name("medium,high")
+                .help("Branch coverage shown in yellow and in green [default: 75,90]")
+        )
+        .arg(
+            Arg::with_name("fail-under-lines")
+                .long("fail-under-lines")
+                .takes_value(true)
+                .value_name("percent")
+                .help("Minimum line coverage, checked against the totals (see --per-file-thresholds); medium or high use the goal")
+        )
+        .arg(
+            Arg::with_name("fail-under-functions")
+                .long("fail-under-functions")
+                .takes_value(true)
+                .value_name("percent")
+                .help("Minimum function coverage, checked against the totals (see --per-file-thresholds); medium or high use the goal")
+        )
+        .arg(
+            Arg::with_name("fail-under-branches")
+                .long("fail-under-branches")
+                .takes_value(true)
+                .value_name("percent")
+                .help("Minimum branch coverage, checked against the totals (see --per-file-thresholds); medium or high use the goal")
+        )
+        .arg(
+            Arg::with_name("warn-under-lines")
+                .long("warn-under-lines")
+                .takes_value(true)
+                .value_name("percent")
+                .help("Line coverage below which a warning is reported without failing the run")
+        )
+        .arg(
+            Arg::with_name("warn-under-functions")
+                .long("warn-under-functions")
+                .takes_value(true)
+                .value_name("percent")
+                .help("Function coverage below which a warning is reported without failing the run")
+        )
+        .arg(
+            Arg::with_name("warn-under-branches")
+                .long("warn-under-branches")
+                .takes_value(true)
+                .value_name("percent")
+                .help("Branch coverage below which a warning is reported without failing the run")
+        )
+        .arg(
+            Arg::with_name("soft-fail")
+                .long("soft-fail")
+                .help("Report --fail-under thresholds that aren't met as warnings and exit successfully")
+        )
+        .arg(
+            Arg::with_name("per-file-thresholds")
+                .long("per-file-thresholds")
+                .help("Check the thresholds against every file as well as the totals")
+        )
+        .arg(
+            Arg::with_name("check-links")
+                .long("check-links")
+                .takes_value(true)
+                .possible_values(&["warn", "fail"])
+                .help("Check that every link on the pages of the report leads to a page (and anchor) of it, warning or failing if not")
+        )
+        .arg(
+            Arg::with_name("junit-report")
+                .long("junit-report")
+                .takes_value(true)
+                .help("Write threshold results as JUnit XML, one test case per threshold for the totals, and per file with --per-file-thresholds")
+        )
+        .arg(
+            Arg::with_name("uncovered-csv")
+                .long("uncovered-csv")
+                .takes_value(true)
+                .value_name("file")
+                .help("Write every region of code that never ran as CSV")
+        )
+        .arg(
+            Arg::with_name("dedup-functions")
+                .long("dedup-functions")
+                .takes_value(true)
+                .possible_values(&["none", "sum", "max", "latest"])
+                .default_value("sum")
+                .help("How to combine counts of functions listed more than once under the same name and file")
+        )
+        .arg(
+            Arg::with_name("no-fold-async")
+                .long("no-fold-async")
+                .help("List the body of each async fn as a closure of its own rather than as part of the fn")
+        )
+        .arg(
+            Arg::with_name("no-fold-closures")
+                .long("no-fold-closures")
+                .help("List closures as functions of their own rather than as part of the function they're written in")
+        )
+        .arg(
+            Arg::with_name("function-crate")
+                .long("function-crate")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Only list functions from this crate on the functions page (may be repeated)")
+        )
+        .arg(
+            Arg::with_name("include-deps")
+                .long("include-deps")
+                .help("Include registry and git dependencies from CARGO_HOME in their own section")
+        )
+        .arg(
+            Arg::with_name("include")
+                .long("include")
+                .visible_alias("include-glob")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("glob")
+                .help("Only report files matching this glob instead of everything below src/ (may be repeated)")
+        )
+        .arg(
+            Arg::with_name("exclude")
+                .long("exclude")
+                .visible_alias("exclude-glob")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("glob")
+                .help("Leave out files matching this glob (may be repeated)")
+        )
+        .arg(
+            Arg::with_name("ignore-file")
+                .long("ignore-file")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("path")
+                .help("Leave out files matching the patterns in this file, besides .cosmolineignore in the source directory (may be repeated)")
+        )
+        .arg(
+            Arg::with_name("crate-dir")
+                .long("crate-dir")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("name=dir")
+                .help("Attribute the files below a directory to a crate (may be repeated)")
+        )
+        .arg(
+            Arg::with_name("capabilities")
+                .long("capabilities")
+                .help("Print the supported input and output formats, template engines, and schema versions as JSON, and exit")
+        )
+        .arg(
+            Arg::with_name("explain-filter")
+                .long("explain-filter")
+                .takes_value(true)
+                .value_name("path")
+                .help("Explain why a file (as named in the export) is reported or left out, and exit")
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Compare line coverage between two llvm JSON exports")
+                .arg(Arg::with_name("old").required(true).index(1))
+                .arg(Arg::with_name("new").required(true).index(2))
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["terminal", "json"])
+                        .default_value("terminal")
+                        .help("Print a table, or the deltas and verdicts as JSON for bots and dashboards")
+                )
+                .arg(
+                    Arg::with_name("fail-under-lines")
+                        .long("fail-under-lines")
+                        .takes_value(true)
+                        .value_name("percent")
+                        .help("Fail when the new line coverage of the total or of any file is below this")
+                )
+                .arg(
+                    Arg::with_name("max-decrease")
+                        .long("max-decrease")
+                        .takes_value(true)
+                        .value_name("points")
+                        .help("Fail when the line coverage of the total or of any file drops by more than this many percentage points")
+                )
+                .arg(
+                    Arg::with_name("color")
+                        .long("color")
+                        .takes_value(true)
+                        .possible_values(&["auto", "always", "never"])
+                        .default_value("auto")
+                        .help("Colorize terminal output; CI logs usually need `always'")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("Check a report against its manifest.json, and that every link in it leads somewhere")
+                .arg(Arg::with_name("report").required(true).index(1).value_name("report-dir"))
+                .arg(
+                    Arg::with_name("base-url")
+                        .long("base-url")
+                        .takes_value(true)
+                        .value_name("url")
+                        .help("URL the report was rendered for with --base-url; absolute links under it are checked too")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("template-schema")
+                .about("Print the JSON schema of the context each page template is rendered with")
+                .arg(
+                    Arg::with_name("template")
+                        .index(1)
+                        .possible_values(&["index", "file", "functions", "legend", "not_found", "warnings"])
+                        .help("Only print the schema for this template")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("repro")
+                .about("Cut an llvm JSON export down to a single file to attach to a bug report")
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .takes_value(true)
+                        .required(true)
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .long("file")
+                        .takes_value(true)
+                        .required(true)
+                        .help("File to keep, as named in the export or the report (e.g. src/foo.rs)")
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output-directory")
+                        .takes_value(true)
+                        .required(true)
+                )
+                .arg(
+                    Arg::with_name("with-source")
+                        .long("with-source")
+                        .help("Also copy the source file into the output directory")
+                )
+                .arg(
+                    Arg::with_name("source-prefix")
+                        .short("p")
+                        .long("source-prefix")
+                        .takes_value(true)
+                )
+        );
 
-    let output_directory = matches.value_of("output").unwrap();
-    let output_path = Path::new(output_directory);
+    #[cfg(feature = "tui")]
+    let app = app.subcommand(
+        SubCommand::with_name("tui")
+            .about("Browse the files, functions, and annotated sources of a report in the terminal")
+            .arg(
+                Arg::with_name("input")
+                    .short("i")
+                    .long("input")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .required(true)
+                    .help("Coverage export to browse; several are merged")
+            )
+            .arg(
+                Arg::with_name("source-prefix")
+                    .short("p")
+                    .long("source-prefix")
+                    .takes_value(true)
+                    .help("Directory the paths in the export are relative to, the one of the first input by default")
+            )
+    );
 
-    let package = matches.value_of("package-name");
+    app
+}
 
-    info!("Reading llvm JSON from: {}", input_filename);
-    let mut file_contents = std::fs::read_to_string(input_filename)?;
-    let summary_report: SummaryReport = serde_json::from_str(&mut file_contents)?;
-
-    {
-        match output_path.exists() {
-            true => {
-                let metadata = std::fs::metadata(output_directory)?;
-                if metadata.file_type().is_dir() {
-                    info!("Output directory exists at `{}'", output_path.display());
-                } else {
-                    error!("Non-directory exists at output `{}'", output_path.display());
-                }
-            }
-            false => {
-                // Make output directory
-                std::fs::create_dir(output_path)?;
-                info!(
-                    "Created missing output directory `{}'",
-                    output_path.display()
-                );
-            }
-        }
+fn required<'a, 'b>(strict: bool, arg: Arg<'a, 'b>) -> Arg<'a, 'b> {
+    match strict {
+        true => arg.required_unless_one(&["explain-filter", "capabilities"]),
+        false => arg,
     }
+}
 
-    info!("{} reports", summary_report.data.len());
-    let file_coverage = summary_report.data[0]
-        .files
-        .iter()
-        .filter(|x| x.filename.starts_with("src/"))
-        .collect::<Vec<_>>();
-
-    for file in file_coverage.iter() {
-        use render::RenderFile;
-        let render = RenderFile::new(file, package, input_path, &handlebars);
-        let output = render.render()?;
-
-        let sanitized = utils::sanitize_filename(file.filename);
-        std::fs::write(output_path.join(sanitized), &*output)?;
+/// The file filter pipeline configured on the command line.
+fn file_filter(matches: &clap::ArgMatches, source_path: &Path) -> Result<filter::Filter, Box<dyn std::error::Error>> {
+    let mut filter = filter::Filter::default().include_deps(matches.is_present("include-deps"));
+    if let Some(globs) = matches.values_of("include") {
+        filter = filter.include(&globs.collect::<Vec<_>>())?;
     }
-
-    {
-        use render::RenderIndex;
-        let render = RenderIndex::new(&file_coverage, &summary_report.data[0].totals, package, input_path, &handlebars);
-
-        std::fs::write(
-            output_path.join("index.html"),
-            render.render()?,
-        )?;
+    if let Some(globs) = matches.values_of("exclude") {
+        filter = filter.exclude(&globs.collect::<Vec<_>>())?;
     }
 
-    // style.css
-    {
-        #[derive(Serialize)]
-        struct Context {}
-
-        let context = Context {};
-
-        std::fs::write(
-            output_path.join("style.css"),
-            handlebars.render("style", &context)?,
-        )?;
+    let default_ignore = source_path.join(filter::IGNORE_FILENAME);
+    if default_ignore.is_file() {
+        filter = filter.ignore_file(&default_ignore)?;
+    }
+    for path in matches.values_of("ignore-file").into_iter().flatten() {
+        filter = filter.ignore_file(Path::new(path))?;
     }
 
-    {
-        let func_coverage = summary_report.data[0]
-            .functions
-            .iter()
-            .filter(|f| {
-                f.filenames
-                    .iter()
-                    .filter(|x| x.starts_with("src/"))
-                    .collect::<Vec<_>>()
-                    .len()
-                    > 0
-            })
-            .collect::<Vec<_>>();
-        use render::RenderFunction;
-        let render = RenderFunction::new(&func_coverage, package, input_path, &handlebars);
-        std::fs::write(
-            output_path.join("functions.html"),
-            render.render()?,
-        )?;
+    for value in matches.values_of("crate-dir").into_iter().flatten() {
+        let (name, dir) = filter::parse_crate_dir(value)?;
+        filter = filter.crate_dir(name, dir);
     }
 
-    println!("Report written to {}/index.html", output_path.display());
+    Ok(filter)
+}
 
+/// Prints the template context schemas, all of them keyed by template name or
+/// just the one asked for.
+fn template_schema(matches: &clap::ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let schemas = render::template_schemas();
+    let output = match matches.value_of("template") {
+        Some(template) => serde_json::to_string_pretty(&schemas[template])?,
+        None => serde_json::to_string_pretty(&schemas)?,
+    };
+    println!("{}", output);
     Ok(())
 }
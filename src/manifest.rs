@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::diagnostics::Bucket;
+
+pub const FILENAME: &str = "manifest.json";
+/// Version of the layout of `manifest.json`.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// `manifest.json`: what an HTML report consists of and what went wrong
+/// while generating it, for tools that post-process or check reports.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Every file written, relative to the output directory.
+    pub files: Vec<String>,
+    /// Bytes written by kind of page (`file`, `index`, `assets`, ...), not
+    /// counting the manifest itself.
+    #[serde(default)]
+    pub sizes: BTreeMap<String, u64>,
+    /// SHA-256 of each reported source file as it was read, by shown name,
+    /// to tell later whether a report still matches the sources.
+    #[serde(default)]
+    pub sources: BTreeMap<String, String>,
+    /// SHA-256 of each file written, by name, to tell later whether the
+    /// report was cut short or changed.
+    #[serde(default)]
+    pub hashes: BTreeMap<String, String>,
+    /// Pages whose names were cut short to fit in a filename, with the
+    /// shown name of the file or function each of them is about.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pages: BTreeMap<String, String>,
+    pub diagnostics: Vec<Bucket>,
+}
+
+/// Hex SHA-256 of `contents`, as the manifest records it.
+pub fn sha256(contents: impl AsRef<[u8]>) -> String {
+    Sha256::digest(contents.as_ref()).iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+
+use crate::{
+    CoverageMapping, FileBranch, FileCoverage, FileCoverageSummary, FileSegment,
+    FunctionCoverage, Region, Summary,
+};
+
+/// How execution counts from several exports are combined.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MergeMode {
+    /// Add counts together.  Shared fixtures get counted more than once.
+    Sum,
+    /// Take the highest count, i.e. "was this ever covered".
+    Max,
+    /// Take the count from the last export that has the item.
+    Latest,
+}
+
+impl MergeMode {
+    pub fn combine(&self, old: i64, new: i64) -> i64 {
+        match self {
+            MergeMode::Sum => old + new,
+            MergeMode::Max => old.max(new),
+            MergeMode::Latest => new,
+        }
+    }
+}
+
+impl FromStr for MergeMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sum" => Ok(MergeMode::Sum),
+            "max" => Ok(MergeMode::Max),
+            "latest" => Ok(MergeMode::Latest),
+            _ => Err(format!("Unknown merge mode `{}', expected sum, max, or latest", s)),
+        }
+    }
+}
+
+/// Merges the mappings read from one or more exports, leaving a single one
+/// untouched: it keeps the summaries llvm wrote, which can count a few more
+/// lines than the ones [`merge`] recomputes (see [`summarize`]) whatever the
+/// mode.
+pub fn combine<'a>(mut mappings: Vec<CoverageMapping<'a>>, mode: MergeMode) -> CoverageMapping<'a> {
+    match mappings.len() {
+        1 => mappings.pop().unwrap(),
+        _ => merge(mappings, mode),
+    }
+}
+
+/// Merges several coverage mappings (from one or more exports) into one,
+/// combining segment, branch, and function counts with `mode` and then
+/// recomputing every summary from the merged data.
+pub fn merge<'a>(mappings: Vec<CoverageMapping<'a>>, mode: MergeMode) -> CoverageMapping<'a> {
+    let mut files: Vec<FileCoverage<'a>> = vec![];
+    let mut file_index: HashMap<&'a str, usize> = HashMap::new();
+    let mut functions: Vec<FunctionCoverage<'a>> = vec![];
+    let mut function_index: HashMap<&'a str, usize> = HashMap::new();
+
+    for mapping in mappings {
+        for file in mapping.files {
+            match file_index.get(file.filename) {
+                Some(idx) => merge_file(&mut files[*idx], file, mode),
+                None => {
+                    file_index.insert(file.filename, files.len());
+                    files.push(file);
+                }
+            }
+        }
+
+        for function in mapping.functions {
+            match function_index.get(function.name) {
+                Some(idx) => merge_function(&mut functions[*idx], function, mode),
+                None => {
+                    function_index.insert(function.name, functions.len());
+                    functions.push(function);
+                }
+            }
+        }
+    }
+
+    for file in files.iter_mut() {
+        file.summary = summarize(file, &functions);
+    }
+
+    let totals = FileCoverageSummary::total(files.iter().map(|f| &f.summary));
+
+    CoverageMapping { files, functions, totals }
+}
+
+fn merge_file<'a>(into: &mut FileCoverage<'a>, other: FileCoverage<'a>, mode: MergeMode) {
+    let mut segments: BTreeMap<(i64, i64), FileSegment> =
+        into.segments.drain(..).map(|s| ((s.line, s.col), s)).collect();
+    for segment in other.segments {
+        match segments.get_mut(&(segment.line, segment.col)) {
+            Some(existing) => {
+                existing.count = mode.combine(existing.count, segment.count);
+                existing.has_count |= segment.has_count;
+                existing.is_region_entry |= segment.is_region_entry;
+                existing.is_gap_region &= segment.is_gap_region;
+            }
+            None => {
+                segments.insert((segment.line, segment.col), segment);
+            }
+        }
+    }
+    into.segments = segments.into_values().collect();
+
+    let branch_key = |b: &FileBranch| (b.line_start, b.column_start, b.line_end, b.column_end, b.file_id);
+    let mut branches: BTreeMap<_, FileBranch> = into.branches.drain(..).map(|b| (branch_key(&b), b)).collect();
+    for branch in other.branches {
+        match branches.get_mut(&branch_key(&branch)) {
+            Some(existing) => {
+                existing.execution_count = mode.combine(existing.execution_count, branch.execution_count);
+                existing.false_execution_count = mode.combine(existing.false_execution_count, branch.false_execution_count);
+            }
+            None => {
+                branches.insert(branch_key(&branch), branch);
+            }
+        }
+    }
+    into.branches = branches.into_values().collect();
+
+    into.expansions.extend(other.expansions);
+}
+
+fn region_key(r: &Region) -> (i64, i64, i64, i64, i64) {
+    (r.line_start, r.column_start, r.line_end, r.column_end, r.file_id)
+}
+
+fn merge_function<'a>(into: &mut FunctionCoverage<'a>, other: FunctionCoverage<'a>, mode: MergeMode) {
+    into.count = mode.combine(into.count, other.count);
+    merge_regions(&mut into.regions, other.regions, mode);
+}
+
+/// Combines the counts of regions at the same location, adding the ones only
+/// `other` has.
+pub(crate) fn merge_regions(into: &mut Vec<Region>, other: Vec<Region>, mode: MergeMode) {
+    for region in other {
+        match into.iter_mut().find(|r| region_key(r) == region_key(&region)) {
+            Some(existing) => existing.execution_count = mode.combine(existing.execution_count, region.execution_count),
+            None => into.push(region),
+        }
+    }
+}
+
+/// Recomputes a file's summary from its segments, branches, and the functions
+/// defined in it.  Lines are counted the way `llvm-cov show` does, so lines
+/// llvm's own summary counts twice (e.g. shared by a function and its
+/// closures) are counted once.
+pub(crate) fn summarize(file: &FileCoverage, functions: &[FunctionCoverage]) -> FileCoverageSummary {
+    const CODE_REGION: i64 = 0;
+
+    let lines = file.line_coverage();
+    let mapped = lines.iter().filter(|l| l.mapped).count() as u64;
+    let hit = lines.iter().filter(|l| l.mapped && l.count > 0).count() as u64;
+
+    let branch_count = file.branches.len() as u64 * 2;
+    let branch_hit = file
+        .branches
+        .iter()
+        .map(|b| (b.execution_count > 0) as u64 + (b.false_execution_count > 0) as u64)
+        .sum();
+
+    // Instantiations of the same generic function share a start location
+    let instantiations = functions
+        .iter()
+        .filter(|f| f.filenames.first() == Some(&file.filename))
+        .collect::<Vec<_>>();
+    let mut groups: BTreeMap<(i64, i64), Vec<&FunctionCoverage>> = BTreeMap::new();
+    for function in instantiations.iter() {
+        let start = function.regions.first().map(|r| (r.line_start, r.column_start)).unwrap_or((0, 0));
+        groups.entry(start).or_default().push(function);
+    }
+
+    let functions_hit = groups.values().filter(|g| g.iter().any(|f| f.count > 0)).count() as u64;
+    let instantiations_hit = instantiations.iter().filter(|f| f.count > 0).count() as u64;
+
+    let (mut region_count, mut region_hit) = (0, 0);
+    for group in groups.values() {
+        let mut regions: BTreeMap<_, bool> = BTreeMap::new();
+        for region in group.iter().flat_map(|f| f.regions.iter()).filter(|r| r.region_kind == CODE_REGION && r.file_id == 0) {
+            *regions.entry(region_key(region)).or_default() |= region.execution_count > 0;
+        }
+        region_count += regions.len() as u64;
+        region_hit += regions.values().filter(|hit| **hit).count() as u64;
+    }
+
+    FileCoverageSummary {
+        branches: Summary::new(branch_count, branch_hit),
+        functions: Summary::new(groups.len() as u64, functions_hit),
+        instantiations: Summary::new(instantiations.len() as u64, instantiations_hit),
+        lines: Summary::new(mapped, hit),
+        regions: Summary::new(region_count, region_hit),
+    }
+}
@@ -0,0 +1,643 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+
+#[cfg(feature = "render-html")]
+use chrono::{DateTime, FixedOffset};
+use schemars::JsonSchema;
+use serde::Serialize;
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+
+use crate::{CoverageMapping, FileBranch, FileCoverageSummary, FileSegment, LineCoverage, Region, Summary, utils};
+use crate::age::{CommitAge, FileAgeCoverage};
+use crate::anonymize::Anonymizer;
+use crate::changes::DiffCoverage;
+use crate::filter::{Filter, Verdict};
+use crate::flaky::FlakyCoverage;
+use crate::merge::{self, MergeMode};
+use crate::public_api::PublicApi;
+use crate::renames;
+#[cfg(feature = "render-html")]
+use crate::time::{TimestampSource, Timezone};
+
+/// Everything the renderers and exporters need, computed once from the
+/// (merged) coverage mapping.  Unlike the raw llvm structures the model owns
+/// its data and has filtering, naming, and anonymization already applied.
+#[derive(Debug, Serialize)]
+pub struct ReportModel {
+    /// Reported project files.
+    pub files: Vec<FileModel>,
+    /// Third party files, only populated with `--include-deps`.
+    pub dependencies: Vec<FileModel>,
+    /// Rolled up totals for every directory containing a reported file.
+    pub dirs: Vec<DirModel>,
+    /// Rolled up function totals per originating crate.
+    pub crates: Vec<CrateModel>,
+    /// Reported functions, sorted by name.
+    pub functions: Vec<FunctionModel>,
+    /// Rolled up function totals per impl block.
+    pub impls: Vec<ImplModel>,
+    /// Functions that ran in the baseline but not in this run, only
+    /// populated by `compare_baseline`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dropped_functions: Vec<DroppedFunction>,
+    /// Functions renamed since the baseline, only populated by
+    /// `compare_baseline`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub renamed_functions: Vec<RenamedFunction>,
+    /// Coverage of recent versus older code, only populated with
+    /// `--commit-age`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_age: Option<CommitAge>,
+    /// Coverage of files by when they were last changed, only populated
+    /// with `--file-age`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_age: Option<FileAgeCoverage>,
+    /// Coverage of the public functions, only populated with
+    /// `--public-api`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_api: Option<PublicApi>,
+    /// Regions that ran in only some of the inputs, only populated with
+    /// `--flaky`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flaky: Option<FlakyCoverage>,
+    /// Coverage of the lines changed in a git range, only populated with
+    /// `--diff-base`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<DiffCoverage>,
+    pub totals: FileCoverageSummary,
+    pub metadata: Metadata,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileModel {
+    /// Path as it appears in the export, used to locate the source file.
+    #[serde(skip)]
+    pub filename: String,
+    /// Path shown in the report.
+    pub name: String,
+    /// Filename of the rendered page, relative to the output directory.
+    pub page: String,
+    /// Crate the file belongs to, only known with `--crate-dir`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crate_name: Option<String>,
+    pub summary: FileCoverageSummary,
+    #[serde(skip)]
+    pub segments: Vec<FileSegment>,
+    #[serde(skip)]
+    pub branches: Vec<FileBranch>,
+    #[serde(skip)]
+    pub lines: Vec<LineCoverage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DirModel {
+    pub name: String,
+    pub files: usize,
+    pub summary: FileCoverageSummary,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CrateModel {
+    pub name: String,
+    pub functions: Summary,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImplModel {
+    /// E.g. `impl Area for Circle`, or `impl Circle` for inherent methods.
+    pub name: String,
+    pub functions: Summary,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FunctionModel {
+    /// Demangled (and possibly anonymized) name.
+    pub name: String,
+    /// Mangled symbol of the first symbol folded into this entry, hashed
+    /// like the name under `--anonymize`.
+    #[serde(skip)]
+    pub symbol: String,
+    /// Shown name of the file the function is defined in.
+    pub file: Option<String>,
+    pub crate_name: Option<String>,
+    /// The impl block the function is a method of, named like in
+    /// [`ImplModel`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub impl_block: Option<String>,
+    /// The generic function this is an instantiation of, e.g.
+    /// `sample::apply` for `sample::apply::<u8>`.  Only known from v0
+    /// symbol names, legacy ones leave out the generic arguments.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generic: Option<String>,
+    pub count: i64,
+    /// Number of symbols folded into this entry.
+    pub symbols: usize,
+    /// Closures and async fn bodies folded into this entry.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<FunctionPart>,
+    #[serde(skip)]
+    pub regions: Vec<Region>,
+}
+
+/// A closure or async fn body as it was listed in the export.
+#[derive(Debug, Serialize)]
+pub struct FunctionPart {
+    /// Demangled (and possibly anonymized) name.
+    pub name: String,
+    pub count: i64,
+}
+
+/// A function that ran in the baseline but not in this run, a strong sign of
+/// deleted or disabled tests.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DroppedFunction {
+    pub name: String,
+    pub file: Option<String>,
+    /// Execution count in the baseline.
+    pub baseline_count: i64,
+    /// Name in the baseline, if the function was renamed since.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renamed_from: Option<String>,
+}
+
+/// A function only in the baseline matched up with one only in this run by
+/// [`renames`](crate::renames), rather than reported as removed and added.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RenamedFunction {
+    /// Name in the baseline.
+    pub old_name: String,
+    /// Name in this run.
+    pub name: String,
+    pub file: Option<String>,
+    /// Execution count in the baseline.
+    pub baseline_count: i64,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Metadata {
+    pub package: Option<String>,
+    /// When the data is from, as set by `--timestamp-source`, in the
+    /// timezone the report is shown in.
+    #[cfg(feature = "render-html")]
+    pub input_mtime: DateTime<FixedOffset>,
+}
+
+/// Knobs that control what ends up in the model.
+#[derive(Debug)]
+pub struct ModelOptions<'a> {
+    pub package: Option<&'a str>,
+    /// Which files are reported, and in which section.
+    pub filter: Filter,
+    /// Only keep functions from these crates (all when empty).
+    pub function_crates: Vec<String>,
+    /// Fold functions with the same demangled name and file.
+    pub dedup: Option<MergeMode>,
+    /// Fold the bodies of async fns into the fn.
+    pub fold_async: bool,
+    /// Fold closures into the function they're written in.
+    pub fold_closures: bool,
+    /// How the pages of the files are named.
+    pub page_layout: PageLayout,
+    /// What the timestamps are given in.
+    #[cfg(feature = "render-html")]
+    pub timezone: Timezone,
+    /// Where the time the data is from comes from.
+    #[cfg(feature = "render-html")]
+    pub timestamp: TimestampSource,
+}
+
+/// Where the pages of the files go in the output directory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PageLayout {
+    /// All next to the index, named after their paths, e.g.
+    /// `src_render_file.rs.html`.
+    #[default]
+    Flat,
+    /// In directories mirroring the sources, e.g. `src/render/file.rs.html`,
+    /// each with an `index.html` of its own.
+    Tree,
+}
+
+impl FromStr for PageLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flat" => Ok(PageLayout::Flat),
+            "tree" => Ok(PageLayout::Tree),
+            _ => Err(format!("Unknown page layout `{}', expected flat or tree", s)),
+        }
+    }
+}
+
+impl<'a> Default for ModelOptions<'a> {
+    fn default() -> Self {
+        Self {
+            package: None,
+            filter: Filter::default(),
+            function_crates: vec![],
+            dedup: Some(MergeMode::Sum),
+            fold_async: true,
+            fold_closures: true,
+            page_layout: PageLayout::default(),
+            #[cfg(feature = "render-html")]
+            timezone: Timezone::default(),
+            #[cfg(feature = "render-html")]
+            timestamp: TimestampSource::default(),
+        }
+    }
+}
+
+impl ReportModel {
+    pub fn build(
+        coverage: &CoverageMapping,
+        options: &ModelOptions,
+        anonymizer: &Anonymizer,
+        input_path: &Path,
+    ) -> std::io::Result<Self> {
+        let file_model = |f: &crate::FileCoverage| {
+            let name = anonymizer.path(utils::report_name(f.filename)).into_owned();
+            FileModel {
+                filename: f.filename.to_string(),
+                page: match options.page_layout {
+                    PageLayout::Flat => utils::sanitize_filename(&name),
+                    PageLayout::Tree => utils::tree_page(&name),
+                },
+                name,
+                crate_name: None,
+                summary: f.summary.clone(),
+                segments: f.segments.clone(),
+                branches: f.branches.clone(),
+                lines: f.line_coverage(),
+            }
+        };
+
+        let verdicts = coverage
+            .files
+            .iter()
+            .map(|f| (f.filename, options.filter.decide(f.filename).verdict))
+            .collect::<HashMap<_, _>>();
+
+        let mut files = vec![];
+        let mut dependencies = vec![];
+        for f in coverage.files.iter() {
+            match &verdicts[f.filename] {
+                Verdict::Project { crate_name } => files.push(FileModel { crate_name: crate_name.clone(), ..file_model(f) }),
+                Verdict::Dependency => dependencies.push(file_model(f)),
+                Verdict::Excluded(_) => (),
+            }
+        }
+
+        let is_reported = |filename: &str| match verdicts.get(filename) {
+            Some(verdict) => !matches!(verdict, Verdict::Excluded(_)),
+            None => options.filter.is_reported(filename),
+        };
+
+        let mut functions: Vec<FunctionModel> = vec![];
+        // Demangled names before anonymization, by index into `functions`
+        let mut names: Vec<String> = vec![];
+        let mut canonical: HashMap<(String, Option<&str>), usize> = HashMap::new();
+
+        for f in coverage.functions.iter() {
+            if !f.filenames.iter().any(|x| is_reported(x)) {
+                continue;
+            }
+
+            let crate_name = f.crate_name();
+            if !options.function_crates.is_empty()
+                && !crate_name.as_ref().map(|c| options.function_crates.contains(c)).unwrap_or(false)
+            {
+                continue;
+            }
+
+            let name = f.demangle();
+            let filename = f.filenames.first().copied();
+
+            if let Some(mode) = options.dedup {
+                let key = (name.clone(), filename);
+                if let Some(idx) = canonical.get(&key) {
+                    let function = &mut functions[*idx];
+                    function.count = mode.combine(function.count, f.count);
+                    function.symbols += 1;
+                    merge::merge_regions(&mut function.regions, f.regions.clone(), mode);
+                    continue;
+                }
+                canonical.insert(key, functions.len());
+            }
+
+            names.push(name.clone());
+            functions.push(FunctionModel {
+                name: anonymizer.symbol(&name).into_owned(),
+                symbol: anonymizer.symbol(f.name).into_owned(),
+                file: filename.map(|x| anonymizer.path(utils::report_name(x)).into_owned()),
+                crate_name,
+                impl_block: impl_block(&name).map(|block| anonymizer.symbol(&block).into_owned()),
+                generic: generic_base(&name).map(|base| anonymizer.symbol(&base).into_owned()),
+                count: f.count,
+                symbols: 1,
+                parts: vec![],
+                regions: f.regions.clone(),
+            });
+        }
+
+        let mut totals = coverage.totals.clone();
+        for (name, covered) in Self::fold_closures(&mut functions, &names, options) {
+            let file = files.iter_mut().chain(dependencies.iter_mut()).find(|f| Some(&f.name) == name.as_ref());
+            for summary in file.map(|f| &mut f.summary).into_iter().chain(Some(&mut totals)) {
+                summary.functions = Summary::new(
+                    summary.functions.count.saturating_sub(1),
+                    summary.functions.covered.saturating_sub(covered),
+                );
+            }
+        }
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let dirs = Self::roll_up_dirs(files.iter().chain(dependencies.iter()));
+
+        let mut crates: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+        for function in functions.iter() {
+            let entry = crates.entry(function.crate_name.as_deref().unwrap_or("(unknown)")).or_default();
+            entry.0 += 1;
+            entry.1 += (function.count > 0) as u64;
+        }
+        let crates = crates
+            .into_iter()
+            .map(|(name, (count, covered))| CrateModel { name: name.to_string(), functions: Summary::new(count, covered) })
+            .collect();
+
+        let mut impls: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+        for function in functions.iter() {
+            if let Some(block) = function.impl_block.as_deref() {
+                let entry = impls.entry(block).or_default();
+                entry.0 += 1;
+                entry.1 += (function.count > 0) as u64;
+            }
+        }
+        let impls = impls
+            .into_iter()
+            .map(|(name, (count, covered))| ImplModel { name: name.to_string(), functions: Summary::new(count, covered) })
+            .collect();
+
+        // Only read for the timestamp
+        #[cfg(not(feature = "render-html"))]
+        let _ = input_path;
+
+        Ok(Self {
+            files,
+            dependencies,
+            dirs,
+            crates,
+            functions,
+            impls,
+            dropped_functions: vec![],
+            renamed_functions: vec![],
+            commit_age: None,
+            file_age: None,
+            public_api: None,
+            flaky: None,
+            diff: None,
+            totals,
+            metadata: Metadata {
+                package: options.package.map(str::to_string),
+                #[cfg(feature = "render-html")]
+                input_mtime: options.timezone.convert(options.timestamp.resolve(input_path)?),
+            },
+        })
+    }
+
+    /// Folds closures into the function they're written in, and the bodies
+    /// of async fns into the fn.  The compiler splits each async fn into the
+    /// fn itself, which only covers the signature and returns a future, and a
+    /// closure holding the body (`fetch::{closure#0}`).  Closures written
+    /// inside a function start inside it, so a closure starting after the end
+    /// of its parent is the body of an async fn.  An async fn counts as run
+    /// only if its body ran, i.e. the future was polled; a function with
+    /// closures counts its own calls.
+    ///
+    /// Returns the file of each function folded into and by how much its
+    /// covered function count went down, to fix up the summaries.
+    fn fold_closures(functions: &mut Vec<FunctionModel>, names: &[String], options: &ModelOptions) -> Vec<(Option<String>, u64)> {
+        let index = names
+            .iter()
+            .zip(functions.iter())
+            .enumerate()
+            .map(|(idx, (name, f))| ((name.as_str(), f.file.as_deref()), idx))
+            .collect::<HashMap<_, _>>();
+
+        let mut children = vec![];
+        for (idx, name) in names.iter().enumerate() {
+            let parent = match closure_parent(name).and_then(|parent| index.get(&(parent, functions[idx].file.as_deref()))) {
+                Some(parent) => *parent,
+                None => continue,
+            };
+            let parent_end = functions[parent].regions.iter().filter(|r| r.file_id == 0).map(|r| (r.line_end, r.column_end)).max();
+            let start = functions[idx].regions.iter().filter(|r| r.file_id == 0).map(|r| (r.line_start, r.column_start)).min();
+            let is_async_body = match (parent_end, start) {
+                (Some(parent_end), Some(start)) => start >= parent_end,
+                _ => false,
+            };
+            if (is_async_body && options.fold_async) || (!is_async_body && options.fold_closures) {
+                children.push((idx, parent, is_async_body));
+            }
+        }
+        // Nested closures go first, so whatever was folded into them moves
+        // along to their parent
+        children.sort_by_key(|(idx, _, _)| std::cmp::Reverse(names[*idx].matches("::").count()));
+
+        let mut folded = vec![];
+        for (child, parent, is_async_body) in children.iter().copied() {
+            let regions = functions[child].regions.clone();
+            let parts = std::mem::take(&mut functions[child].parts);
+            let (count, symbols) = (functions[child].count, functions[child].symbols);
+            let part = FunctionPart { name: functions[child].name.clone(), count };
+
+            let function = &mut functions[parent];
+            let covered_before = (function.count > 0) as u64 + (count > 0) as u64;
+            if is_async_body {
+                function.count = count;
+            }
+            function.symbols += symbols;
+            function.parts.push(part);
+            function.parts.extend(parts);
+            function.regions.extend(regions);
+            folded.push((function.file.clone(), covered_before - (function.count > 0) as u64));
+        }
+
+        let children = children.into_iter().map(|(child, _, _)| child).collect::<HashSet<_>>();
+        let mut idx = 0;
+        functions.retain(|_| {
+            idx += 1;
+            !children.contains(&(idx - 1))
+        });
+        for function in functions.iter_mut() {
+            function.parts.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+
+        folded
+    }
+
+    fn roll_up_dirs<'f>(files: impl Iterator<Item = &'f FileModel>) -> Vec<DirModel> {
+        let mut dirs: BTreeMap<&str, Vec<&FileCoverageSummary>> = BTreeMap::new();
+
+        for file in files {
+            let mut name = file.name.as_str();
+            while let Some((parent, _)) = name.rsplit_once('/') {
+                dirs.entry(parent).or_default().push(&file.summary);
+                name = parent;
+            }
+        }
+
+        dirs.into_iter()
+            .map(|(name, summaries)| DirModel {
+                name: name.to_string(),
+                files: summaries.len(),
+                summary: FileCoverageSummary::total(summaries.into_iter()),
+            })
+            .collect()
+    }
+
+    /// Records the functions that ran in `baseline` but not in this run, and
+    /// the ones renamed since.  Functions missing on either side were added
+    /// or removed rather than left untested, so they don't count unless
+    /// they're matched up as a rename.
+    pub fn compare_baseline(&mut self, baseline: &ReportModel) {
+        fn key(f: &FunctionModel) -> (&str, Option<&str>) {
+            (f.name.as_str(), f.file.as_deref())
+        }
+        let mut baseline_counts: HashMap<(&str, Option<&str>), i64> = HashMap::new();
+        for f in baseline.functions.iter() {
+            let count = baseline_counts.entry(key(f)).or_default();
+            *count = (*count).max(f.count);
+        }
+        let current = self.functions.iter().map(key).collect::<HashSet<_>>();
+
+        // One entry per name on each side, as with the counts
+        let mut seen = HashSet::new();
+        let removed = baseline.functions
+            .iter()
+            .filter(|f| !current.contains(&key(f)) && seen.insert(key(f)))
+            .collect::<Vec<_>>();
+        let added = self.functions
+            .iter()
+            .filter(|f| !baseline_counts.contains_key(&key(f)) && seen.insert(key(f)))
+            .collect::<Vec<_>>();
+        let renamed = renames::pair(&removed, &added)
+            .into_iter()
+            .map(|(old, new)| RenamedFunction {
+                old_name: removed[old].name.clone(),
+                name: added[new].name.clone(),
+                file: added[new].file.clone(),
+                baseline_count: baseline_counts[&key(removed[old])],
+                count: added[new].count,
+            })
+            .collect::<Vec<_>>();
+        let old_names = renamed
+            .iter()
+            .map(|r| ((r.name.as_str(), r.file.as_deref()), r.old_name.as_str()))
+            .collect::<HashMap<_, _>>();
+
+        let mut seen = HashSet::new();
+        self.dropped_functions = self
+            .functions
+            .iter()
+            .filter(|f| f.count == 0)
+            .filter_map(|f| {
+                let key = key(f);
+                let old_name = old_names.get(&key).copied();
+                let baseline_key = (old_name.unwrap_or(key.0), key.1);
+                let baseline_count = *baseline_counts.get(&baseline_key).filter(|count| **count > 0)?;
+                match seen.insert(key) {
+                    true => Some(DroppedFunction {
+                        name: f.name.clone(),
+                        file: f.file.clone(),
+                        baseline_count,
+                        renamed_from: old_name.map(str::to_string),
+                    }),
+                    false => None,
+                }
+            })
+            .collect();
+        self.renamed_functions = renamed;
+    }
+
+    /// Functions defined in the given (shown) file.
+    pub fn file_functions<'m>(&'m self, file: &'m FileModel) -> impl Iterator<Item = &'m FunctionModel> {
+        self.functions.iter().filter(move |f| f.file.as_deref() == Some(file.name.as_str()))
+    }
+
+    /// Every file that gets its own page.
+    pub fn all_files(&self) -> impl Iterator<Item = &FileModel> {
+        self.files.iter().chain(self.dependencies.iter())
+    }
+}
+
+/// The function a closure is defined in, e.g. `fetch` for `fetch::{closure#0}`
+/// (or `fetch::{{closure}}` with legacy symbol names).
+fn closure_parent(name: &str) -> Option<&str> {
+    let (parent, last) = name.rsplit_once("::")?;
+    match last == "{{closure}}" || (last.starts_with("{closure#") && last.ends_with('}')) {
+        true => Some(parent),
+        false => None,
+    }
+}
+
+/// The name of a generic function without its generic arguments, e.g.
+/// `sample::apply` for `sample::apply::<u8>`.  `None` if there are none.
+fn generic_base(name: &str) -> Option<String> {
+    let mut base = String::with_capacity(name.len());
+    let mut rest = name;
+    while let Some(start) = rest.find("::<") {
+        base.push_str(&rest[..start]);
+        let mut depth = 0;
+        let mut end = None;
+        for (idx, c) in rest[start + 2..].char_indices() {
+            match c {
+                '<' => depth += 1,
+                // Not the arrow of a fn pointer type
+                '>' if !rest[..start + 2 + idx].ends_with('-') => depth -= 1,
+                _ => (),
+            }
+            if depth == 0 {
+                end = Some(start + 2 + idx + 1);
+                break;
+            }
+        }
+        rest = &rest[end?..];
+    }
+
+    match base.is_empty() {
+        true => None,
+        false => Some(base + rest),
+    }
+}
+
+/// The impl block a method belongs to, from its demangled name:
+/// `<Circle as Area>::area` is in `impl Area for Circle` and `<Circle>::new`
+/// in `impl Circle`.  Legacy symbol names spell inherent methods like free
+/// functions (`Circle::new`), so those aren't recognized.
+fn impl_block(name: &str) -> Option<String> {
+    let mut depth = 0;
+    let mut split = None;
+    for (idx, c) in name.char_indices() {
+        match c {
+            '<' => depth += 1,
+            // Not the arrow of a fn pointer type
+            '>' if !name[..idx].ends_with('-') => depth -= 1,
+            ' ' if depth == 1 && name[idx..].starts_with(" as ") && split.is_none() => split = Some(idx),
+            _ => (),
+        }
+        if depth == 0 {
+            if idx == 0 || !name[idx + 1..].starts_with("::") {
+                return None;
+            }
+            return Some(match split {
+                Some(split) => format!("impl {} for {}", &name[split + 4..idx], &name[1..split]),
+                None => format!("impl {}", &name[1..idx]),
+            });
+        }
+    }
+    None
+}
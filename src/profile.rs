@@ -0,0 +1,100 @@
+//! How long each phase of a run took and how much memory it peaked at.  The
+//! timings are always logged, `--profile-self` prints them along with the
+//! memory use, to see where the time goes on large reports.
+
+use std::time::{Duration, Instant};
+
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+use serde::Serialize;
+
+use crate::fs;
+
+/// Written into the output directory.
+pub const FILENAME: &str = "cosmoline-profile.json";
+
+#[derive(Debug, Serialize)]
+pub struct Phase {
+    pub name: String,
+    pub seconds: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Profile {
+    /// In the order they ran, e.g. `parse`, `model`, and `render html`.
+    pub phases: Vec<Phase>,
+    /// Time spent writing report files, summed over the threads doing it,
+    /// so it overlaps the render phases.
+    pub write_seconds: f64,
+    pub total_seconds: f64,
+    /// Peak resident set size in bytes, where the OS tells.
+    pub peak_rss: Option<u64>,
+    #[serde(skip)]
+    start: Instant,
+    #[serde(skip)]
+    lap: Instant,
+}
+
+impl Profile {
+    pub fn start() -> Self {
+        let now = Instant::now();
+        Self {
+            phases: vec![],
+            write_seconds: 0.0,
+            total_seconds: 0.0,
+            peak_rss: None,
+            start: now,
+            lap: now,
+        }
+    }
+
+    /// Ends the phase `name`, which began when the last one ended, and logs
+    /// how long it took.
+    pub fn lap(&mut self, name: &str) -> Duration {
+        let now = Instant::now();
+        let took = now - self.lap;
+        self.lap = now;
+        info!("Phase {} took {:.3}s", name, took.as_secs_f64());
+        self.phases.push(Phase { name: name.to_string(), seconds: took.as_secs_f64() });
+        took
+    }
+
+    /// Fills in the totals and the peak memory use so far.
+    pub fn finish(&mut self) {
+        self.write_seconds = fs::time_writing().as_secs_f64();
+        self.total_seconds = self.start.elapsed().as_secs_f64();
+        self.peak_rss = peak_rss();
+    }
+
+    /// One line per phase and total, as printed with `--profile-self`.
+    pub fn summary(&self) -> String {
+        let mut lines = self.phases
+            .iter()
+            .map(|phase| format!("{:>12}: {:.3}s", phase.name, phase.seconds))
+            .collect::<Vec<_>>();
+        lines.push(format!("{:>12}: {:.3}s (across threads)", "write", self.write_seconds));
+        lines.push(format!("{:>12}: {:.3}s", "total", self.total_seconds));
+        lines.push(format!("{:>12}: {}", "peak RSS", match self.peak_rss {
+            Some(bytes) => format!("{}B", crate::utils::human_count(bytes as i64)),
+            None => "unknown".to_string(),
+        }));
+        lines.join("\n")
+    }
+}
+
+/// The peak resident set size of this process in bytes.  Only known on
+/// Linux.
+pub fn peak_rss() -> Option<u64> {
+    parse_peak_rss(&std::fs::read_to_string("/proc/self/status").ok()?)
+}
+
+/// The `VmHWM` line of `/proc/self/status`, in bytes.
+pub fn parse_peak_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let mut fields = line["VmHWM:".len()..].split_whitespace();
+    let value = fields.next()?.parse::<u64>().ok()?;
+    match fields.next() {
+        Some("kB") => Some(value * 1024),
+        _ => None,
+    }
+}
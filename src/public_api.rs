@@ -0,0 +1,137 @@
+use std::error::Error as StdError;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+
+use crate::Summary;
+use crate::anonymize::Anonymizer;
+use crate::model::ReportModel;
+
+/// Function coverage of just the public functions and methods of the crate,
+/// going by its rustdoc JSON (`cargo +nightly rustdoc -- -Z unstable-options
+/// --output-format json`).
+#[derive(Debug, Serialize)]
+pub struct PublicApi {
+    /// Public functions in the order of their files and lines.
+    pub items: Vec<ApiItem>,
+    pub summary: Summary,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiItem {
+    /// Demangled name of the covering function, or the item's path if no
+    /// function was instrumented for it.
+    pub name: String,
+    /// Shown name of the file the item is defined in.
+    pub file: String,
+    pub line: i64,
+    /// Execution count, `None` if the item wasn't instrumented at all, e.g.
+    /// a generic function that was never instantiated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<i64>,
+}
+
+/// A function as documented by rustdoc.
+#[derive(Debug)]
+pub struct DocItem {
+    pub path: String,
+    /// Relative to the directory rustdoc was run in.
+    pub filename: String,
+    pub line_start: i64,
+    pub line_end: i64,
+}
+
+/// Public functions and methods of the documented crate that have a body.
+/// Reads both the current format (`inner: {"function": ..}`) and the older
+/// one with a separate `kind`.
+pub fn parse_rustdoc(json: &str) -> Result<Vec<DocItem>, Box<dyn StdError>> {
+    let doc: Value = serde_json::from_str(json)?;
+    let index = doc["index"].as_object().ok_or("not rustdoc JSON: no index")?;
+
+    let mut items = vec![];
+    for (id, item) in index.iter() {
+        if item["crate_id"].as_u64() != Some(0) {
+            continue;
+        }
+        let function = match (&item["inner"]["function"], item["kind"].as_str()) {
+            (function @ Value::Object(_), _) => function,
+            (_, Some("function")) | (_, Some("method")) => &item["inner"],
+            _ => continue,
+        };
+        if function["has_body"] == Value::Bool(false) {
+            continue;
+        }
+        // Trait impl methods are "default", and as public as the trait
+        if !matches!(item["visibility"].as_str(), Some("public") | Some("default")) {
+            continue;
+        }
+        let span = &item["span"];
+        let (filename, line_start, line_end) = match (span["filename"].as_str(), span["begin"][0].as_i64(), span["end"][0].as_i64()) {
+            (Some(filename), Some(start), Some(end)) => (filename, start, end),
+            _ => continue,
+        };
+        let path = match doc["paths"][id]["path"].as_array() {
+            Some(path) => path.iter().filter_map(Value::as_str).collect::<Vec<_>>().join("::"),
+            None => item["name"].as_str().unwrap_or_default().to_string(),
+        };
+        items.push(DocItem { path, filename: filename.to_string(), line_start, line_end });
+    }
+    Ok(items)
+}
+
+/// Matches every item to the functions that start first within its span,
+/// so fns and closures nested in it aren't counted for it.  An item covered by
+/// several functions, e.g. instances of a generic, counts what they ran
+/// together.
+pub fn public_api(model: &ReportModel, items: &[DocItem], anonymizer: &Anonymizer) -> PublicApi {
+    let starts: Vec<_> = model.functions
+        .iter()
+        .filter_map(|f| Some((f, f.regions.iter().filter(|r| r.file_id == 0).map(|r| r.line_start).min()?)))
+        .collect();
+
+    let files: Vec<_> = items
+        .iter()
+        .map(|item| model.files.iter().find(|f| Path::new(&f.filename).ends_with(&item.filename)))
+        .collect();
+
+    let counts = items.iter().zip(files.iter()).map(|(item, file)| {
+        let within: Vec<_> = starts
+            .iter()
+            .filter(|(f, start)| {
+                file.is_some_and(|file| f.file.as_ref() == Some(&file.name))
+                    && item.line_start <= *start && *start <= item.line_end
+            })
+            .collect();
+        let first = within.iter().map(|(_, start)| *start).min()?;
+        let mut matched = within.into_iter().filter(|(_, start)| *start == first).map(|(f, _)| f);
+        let function = matched.next()?;
+        Some((function.name.clone(), function.count + matched.map(|f| f.count).sum::<i64>()))
+    });
+
+    let mut api_items: Vec<_> = items
+        .iter()
+        .zip(files.iter())
+        .zip(counts)
+        .map(|((item, file), count)| {
+            let file = match file {
+                Some(file) => file.name.clone(),
+                None => anonymizer.path(&item.filename).into_owned(),
+            };
+            let (name, count) = match count {
+                Some((name, count)) => (name, Some(count)),
+                None => (anonymizer.symbol(&item.path).into_owned(), None),
+            };
+            ApiItem { name, file, line: item.line_start, count }
+        })
+        .collect();
+    api_items.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+
+    let covered = api_items.iter().filter(|item| item.count.unwrap_or(0) > 0).count();
+    PublicApi {
+        summary: Summary::new(api_items.len() as u64, covered as u64),
+        items: api_items,
+    }
+}
@@ -0,0 +1,95 @@
+//! Matching functions that were renamed between a baseline and this run, so
+//! that a rename reads as one function with a new name rather than one
+//! removed and one new (and often uncovered) function.
+//!
+//! Candidates are paired within the file they're defined in.  Each pair is
+//! scored on how alike the demangled names are and on where the function's
+//! regions start and how many there are, then the best scoring pairs are
+//! taken greedily.
+
+use std::collections::HashSet;
+
+use crate::model::FunctionModel;
+
+/// Pairs scoring below this are left as a removal and an addition.
+pub const MIN_SCORE: f64 = 0.6;
+
+/// How alike two strings are, from 0 (nothing in common) to 1 (equal), as
+/// one minus their Levenshtein distance over the length of the longer one.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (a.chars().collect::<Vec<_>>(), b.chars().collect::<Vec<_>>());
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+/// The first line of the function's own regions, and how many there are.
+fn position(function: &FunctionModel) -> Option<(i64, usize)> {
+    let own = function.regions.iter().filter(|r| r.file_id == 0);
+    let first = own.clone().map(|r| r.line_start).min()?;
+    Some((first, own.count()))
+}
+
+/// How likely it is that `new` is `old` renamed, from 0 to 1.  Functions in
+/// different files are never matched.
+pub fn score(old: &FunctionModel, new: &FunctionModel) -> f64 {
+    if old.file != new.file {
+        return 0.0;
+    }
+
+    // The leaf name changes, the path to it usually doesn't
+    let leaf = |name: &str| name.rsplit("::").next().unwrap_or(name).to_string();
+    let names = (similarity(&old.name, &new.name) + similarity(&leaf(&old.name), &leaf(&new.name))) / 2.0;
+
+    let place = match (position(old), position(new)) {
+        (Some((old_line, old_regions)), Some((new_line, new_regions))) => {
+            let lines = 1.0 / (1.0 + (old_line - new_line).abs() as f64 / 10.0);
+            let regions = old_regions.min(new_regions) as f64 / old_regions.max(new_regions) as f64;
+            (lines + regions) / 2.0
+        }
+        _ => 0.0,
+    };
+
+    (names + place) / 2.0
+}
+
+/// Pairs up functions only in the baseline with functions only in this run,
+/// returning the indices of each pair into `old` and `new`.  Every function
+/// is in at most one pair.
+pub fn pair(old: &[&FunctionModel], new: &[&FunctionModel]) -> Vec<(usize, usize)> {
+    let mut candidates = vec![];
+    for (i, o) in old.iter().enumerate() {
+        for (j, n) in new.iter().enumerate() {
+            let score = score(o, n);
+            if score >= MIN_SCORE {
+                candidates.push((score, i, j));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then((a.1, a.2).cmp(&(b.1, b.2))));
+
+    let (mut old_taken, mut new_taken) = (HashSet::new(), HashSet::new());
+    let mut pairs = vec![];
+    for (_, i, j) in candidates {
+        if !old_taken.contains(&i) && !new_taken.contains(&j) {
+            old_taken.insert(i);
+            new_taken.insert(j);
+            pairs.push((i, j));
+        }
+    }
+    pairs.sort_unstable();
+    pairs
+}
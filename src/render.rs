@@ -1,8 +1,397 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use handlebars::Handlebars;
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Serialize;
+
+use crate::anonymize::Anonymizer;
+use crate::check::Goals;
+use crate::diagnostics::Diagnostics;
+use crate::encoding::SourceEncoding;
+use crate::fs::FileLimiter;
+
 mod index;
-pub(crate) use index::*;
+pub use index::*;
 
 mod file;
-pub(crate) use file::*;
+pub use file::*;
+
+mod compare;
+pub use compare::*;
+
+mod diff_coverage;
+pub use diff_coverage::*;
+
+mod directory;
+pub use directory::*;
+
+mod flaky;
+pub use flaky::*;
+
+pub mod helpers;
+pub use helpers::Timezone;
+
+mod highlight;
+pub use highlight::*;
 
 mod function;
-pub(crate) use function::*;
+pub use function::*;
+
+mod function_page;
+pub use function_page::*;
+
+mod legend;
+pub use legend::*;
+
+mod not_found;
+pub use not_found::*;
+
+mod omitted;
+pub use omitted::*;
+
+mod public_api;
+pub use public_api::*;
+
+mod search;
+pub use search::*;
+
+mod sidebar;
+pub use sidebar::*;
+
+mod single_file;
+pub use single_file::*;
+
+mod stats;
+pub use stats::*;
+
+mod titles;
+pub use titles::*;
+
+mod treemap;
+pub use treemap::*;
+
+mod warnings;
+pub use warnings::*;
+
+mod assets;
+pub use assets::*;
+
+mod templates;
+pub use templates::*;
+
+mod postprocess;
+pub use postprocess::*;
+
+/// Settings for reports published on a public static host.
+#[derive(Clone, Debug, Default)]
+pub struct Hosting {
+    /// Ask search engines not to index any page.
+    pub noindex: bool,
+    /// Write a `robots.txt` turning all crawlers away.
+    pub robots_txt: bool,
+    /// Write a `404.html` linking back to the index.
+    pub not_found_page: bool,
+    /// URL the report is served from, ending in a slash.
+    pub base_url: Option<String>,
+    /// How pages link to each other.
+    pub link_style: LinkStyle,
+}
+
+impl Hosting {
+    /// Prefix for absolute links: the base URL, or the root of the site.
+    pub fn absolute_root(&self) -> &str {
+        self.base_url.as_deref().unwrap_or("/")
+    }
+}
+
+/// Relative links keep working wherever the report is copied, but break
+/// behind proxies that serve it at a path the pages don't know about (e.g.
+/// without the trailing slash).  Absolute links always go through
+/// `base_url`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LinkStyle {
+    #[default]
+    Relative,
+    Absolute,
+}
+
+impl FromStr for LinkStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "relative" => Ok(LinkStyle::Relative),
+            "absolute" => Ok(LinkStyle::Absolute),
+            _ => Err(format!("Unknown link style `{}', expected relative or absolute", s)),
+        }
+    }
+}
+
+/// Colors coverage is shown in.  The palettes besides the default hatch code
+/// that never ran as well, for readers who can't tell the colors apart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Palette {
+    #[default]
+    RedGreen,
+    BlueOrange,
+    Monochrome,
+}
+
+impl Palette {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::RedGreen => "red-green",
+            Palette::BlueOrange => "blue-orange",
+            Palette::Monochrome => "monochrome",
+        }
+    }
+
+    /// What code that ran looks like, for the legend.
+    pub fn covered(&self) -> &'static str {
+        match self {
+            Palette::RedGreen => "green",
+            Palette::BlueOrange => "blue",
+            Palette::Monochrome => "grey",
+        }
+    }
+
+    /// What code that never ran looks like, for the legend.
+    pub fn uncovered(&self) -> &'static str {
+        match self {
+            Palette::RedGreen => "red",
+            Palette::BlueOrange => "orange and hatched",
+            Palette::Monochrome => "white and hatched",
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "red-green" => Ok(Palette::RedGreen),
+            "blue-orange" => Ok(Palette::BlueOrange),
+            "monochrome" => Ok(Palette::Monochrome),
+            _ => Err(format!("Unknown palette `{}', expected red-green, blue-orange, or monochrome", s)),
+        }
+    }
+}
+
+/// `style.css`, with the colors of `palette`, and black and white print
+/// styles with `print_of_record`.
+pub fn stylesheet(templates: &Templates, palette: Palette, print_of_record: bool) -> Result<String, Box<dyn std::error::Error>> {
+    #[derive(Serialize)]
+    struct PaletteContext {
+        palette: &'static str,
+    }
+
+    let mut style = templates.render("style", &())?;
+    if palette != Palette::default() {
+        style.push('\n');
+        style.push_str(&templates.render("palette_css", &PaletteContext { palette: palette.name() })?);
+    }
+    if print_of_record {
+        style.push('\n');
+        style.push_str(&templates.render("print_css", &())?);
+    }
+    Ok(style)
+}
+
+/// Values every page template uses.
+#[derive(Serialize, JsonSchema)]
+pub struct PageContext {
+    noindex: bool,
+    /// Prepended to every link to another page of the report.
+    root: String,
+    /// Page title as configured, the template's own if absent.
+    page_title: Option<String>,
+    /// Header text after the link back to the index as configured, the
+    /// template's own if absent.
+    breadcrumb: Option<String>,
+    /// Every directory and file with its coverage, with `--sidebar`.
+    sidebar: Option<Vec<NavNode>>,
+}
+
+/// Everything a renderer needs besides the report model itself.
+#[derive(Clone)]
+pub struct RenderContext<'a> {
+    pub templates: &'a Templates<'a>,
+    pub assets: &'a Assets,
+    pub anonymizer: &'a Anonymizer,
+    /// Directory the source files are read from.
+    pub input_path: &'a Path,
+    pub source_encoding: SourceEncoding,
+    pub hosting: &'a Hosting,
+    /// What the percentages are colored by.
+    pub goals: &'a Goals,
+    /// What they're colored in.
+    pub palette: Palette,
+    /// Where problems found while rendering are collected.
+    pub diagnostics: &'a Diagnostics,
+    /// Every file is read and written through this.
+    pub files: &'a FileLimiter,
+    /// Applied to every rendered source line, in order.
+    pub postprocessors: &'a [Box<dyn PostProcessor>],
+    /// Syntax highlights the source on the file pages, if set.
+    pub highlighter: Option<&'a Highlighter>,
+    pub titles: &'a Titles,
+    /// Shown next to every page, if set.
+    pub sidebar: Option<&'a Sidebar>,
+    /// The page being rendered, relative to the index.
+    pub page: String,
+    /// How many directories below the index the page being rendered is, for
+    /// relative links.
+    pub depth: usize,
+}
+
+impl<'a> RenderContext<'a> {
+    pub fn page(&self, package: Option<&str>, title: Title) -> PageContext {
+        let (page_title, breadcrumb) = self.titles.format(package, &title);
+        PageContext {
+            noindex: self.hosting.noindex,
+            root: self.root(),
+            page_title,
+            breadcrumb,
+            sidebar: self.sidebar.map(|sidebar| sidebar.nav(self)),
+        }
+    }
+
+    /// For rendering `page`, a path relative to the index.
+    pub fn at(&self, page: &str) -> Self {
+        Self { page: page.to_string(), depth: page.matches('/').count(), ..self.clone() }
+    }
+
+    /// Link to another page of the report.
+    pub fn link(&self, page: &str) -> String {
+        format!("{}{}", self.root(), page)
+    }
+
+    fn root(&self) -> String {
+        match self.hosting.link_style {
+            LinkStyle::Relative => "../".repeat(self.depth),
+            LinkStyle::Absolute => self.hosting.absolute_root().to_string(),
+        }
+    }
+}
+
+/// JSON schemas of the contexts the page templates are rendered with, by
+/// template name, for authors of custom templates.
+pub fn template_schemas() -> BTreeMap<&'static str, RootSchema> {
+    let mut schemas = BTreeMap::new();
+    schemas.insert("index", RenderIndex::context_schema());
+    schemas.insert("file", RenderFile::context_schema());
+    schemas.insert("compare", RenderCompare::context_schema());
+    schemas.insert("diff_coverage", RenderDiffCoverage::context_schema());
+    schemas.insert("directory", RenderDirectory::context_schema());
+    schemas.insert("flaky", RenderFlaky::context_schema());
+    schemas.insert("function", RenderFunctionPage::context_schema());
+    schemas.insert("functions", RenderFunction::context_schema());
+    schemas.insert("legend", RenderLegend::context_schema());
+    schemas.insert("not_found", RenderNotFound::context_schema());
+    schemas.insert("omitted", RenderOmitted::context_schema());
+    schemas.insert("public_api", RenderPublicApi::context_schema());
+    schemas.insert("search", RenderSearch::context_schema());
+    schemas.insert("single_file", RenderSingleFile::context_schema());
+    schemas.insert("stats", RenderStats::context_schema());
+    schemas.insert("treemap", RenderTreemap::context_schema());
+    schemas.insert("warnings", RenderWarnings::context_schema());
+    schemas
+}
+
+/// Handlebars registry with every template and helper the HTML report uses.
+pub fn handlebars<'a>() -> Result<Handlebars<'a>, Box<dyn std::error::Error>> {
+    let mut handlebars = Handlebars::new();
+    // Partials like the index rows are laid out in the templates themselves,
+    // so the output doesn't depend on the handlebars version indenting them
+    handlebars.set_prevent_indent(true);
+    helpers::register(&mut handlebars, Timezone::default());
+
+    let index_template_str = include_str!("../template/index.html.hbs");
+    handlebars.register_template_string("index", index_template_str)?;
+
+    let file_template_str = include_str!("../template/file.html.hbs");
+    handlebars.register_template_string("file", file_template_str)?;
+
+    let compare_template_str = include_str!("../template/compare.html.hbs");
+    handlebars.register_template_string("compare", compare_template_str)?;
+
+    let directory_template_str = include_str!("../template/directory.html.hbs");
+    handlebars.register_template_string("directory", directory_template_str)?;
+
+    let diff_coverage_template_str = include_str!("../template/diff_coverage.html.hbs");
+    handlebars.register_template_string("diff_coverage", diff_coverage_template_str)?;
+
+    let function_template_str = include_str!("../template/function.html.hbs");
+    handlebars.register_template_string("function", function_template_str)?;
+
+    let funcs_template_str = include_str!("../template/functions.html.hbs");
+    handlebars.register_template_string("functions", funcs_template_str)?;
+
+    let flaky_template_str = include_str!("../template/flaky.html.hbs");
+    handlebars.register_template_string("flaky", flaky_template_str)?;
+
+    let legend_template_str = include_str!("../template/legend.html.hbs");
+    handlebars.register_template_string("legend", legend_template_str)?;
+
+    let not_found_template_str = include_str!("../template/404.html.hbs");
+    handlebars.register_template_string("not_found", not_found_template_str)?;
+
+    let omitted_template_str = include_str!("../template/omitted.html.hbs");
+    handlebars.register_template_string("omitted", omitted_template_str)?;
+
+    let public_api_template_str = include_str!("../template/public_api.html.hbs");
+    handlebars.register_template_string("public_api", public_api_template_str)?;
+
+    let search_template_str = include_str!("../template/search.html.hbs");
+    handlebars.register_template_string("search", search_template_str)?;
+
+    let single_file_template_str = include_str!("../template/single_file.html.hbs");
+    handlebars.register_template_string("single_file", single_file_template_str)?;
+
+    let stats_template_str = include_str!("../template/stats.html.hbs");
+    handlebars.register_template_string("stats", stats_template_str)?;
+
+    let treemap_template_str = include_str!("../template/treemap.html.hbs");
+    handlebars.register_template_string("treemap", treemap_template_str)?;
+
+    let warnings_template_str = include_str!("../template/warnings.html.hbs");
+    handlebars.register_template_string("warnings", warnings_template_str)?;
+
+    let file_css_str = include_str!("../template/file.css.hbs");
+    handlebars.register_template_string("file_css", file_css_str)?;
+
+    let index_css_str = include_str!("../template/index.css.hbs");
+    handlebars.register_template_string("index_css", index_css_str)?;
+
+    let index_js_str = include_str!("../template/index.js");
+    handlebars.register_template_string("index_js", index_js_str)?;
+
+    let compare_css_str = include_str!("../template/compare.css.hbs");
+    handlebars.register_template_string("compare_css", compare_css_str)?;
+
+    let funcs_css_str = include_str!("../template/functions.css.hbs");
+    handlebars.register_template_string("functions_css", funcs_css_str)?;
+
+    let funcs_js_str = include_str!("../template/functions.js");
+    handlebars.register_template_string("functions_js", funcs_js_str)?;
+
+    let search_js_str = include_str!("../template/search.js");
+    handlebars.register_template_string("search_js", search_js_str)?;
+
+    let sidebar_str = include_str!("../template/sidebar.html.hbs");
+    handlebars.register_template_string("sidebar", sidebar_str)?;
+
+    let palette_css_str = include_str!("../template/palette.css.hbs");
+    handlebars.register_template_string("palette_css", palette_css_str)?;
+
+    let print_css_str = include_str!("../template/print.css.hbs");
+    handlebars.register_template_string("print_css", print_css_str)?;
+
+    let style_source = include_str!("../template/style.css");
+    handlebars.register_template_string("style", style_source)?;
+
+    Ok(handlebars)
+}
@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::fs::FileLimiter;
+use crate::manifest;
+
+/// Page specific CSS and JS.  By default these are inlined into the page, but
+/// they can also be written out as separate content-addressed files so that the
+/// report can be served behind a strict Content-Security-Policy.
+pub struct Assets {
+    inline_css: bool,
+    inline_js: bool,
+    external: Mutex<BTreeMap<String, String>>,
+}
+
+/// Either the contents of an asset to inline, or a link to the external file.
+#[derive(Serialize, JsonSchema)]
+pub struct Asset {
+    inline: Option<String>,
+    href: Option<String>,
+}
+
+impl Assets {
+    pub fn new(inline_css: bool, inline_js: bool) -> Self {
+        Self {
+            inline_css,
+            inline_js,
+            external: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn stylesheet(&self, name: &str, contents: String) -> Asset {
+        self.asset(self.inline_css, name, "css", contents)
+    }
+
+    pub fn script(&self, name: &str, contents: String) -> Asset {
+        self.asset(self.inline_js, name, "js", contents)
+    }
+
+    fn asset(&self, inline: bool, name: &str, extension: &str, contents: String) -> Asset {
+        if inline {
+            return Asset { inline: Some(contents), href: None };
+        }
+
+        let digest = Sha256::digest(contents.as_bytes());
+        let hash = digest[..8].iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        let filename = format!("{}-{}.{}", name, hash, extension);
+
+        self.external.lock().unwrap_or_else(|e| e.into_inner()).insert(filename.clone(), contents);
+
+        Asset { inline: None, href: Some(filename) }
+    }
+
+    /// Whether any assets are inlined into every page using them.
+    pub fn inlines_any(&self) -> bool {
+        self.inline_css || self.inline_js
+    }
+
+    /// Writes every external asset requested so far into the output
+    /// directory, returning their filenames, sizes, and SHA-256.
+    pub fn write_all(&self, output_path: &Path, files: &FileLimiter) -> io::Result<Vec<(String, u64, String)>> {
+        let mut written = vec![];
+        for (filename, contents) in self.external.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            files.write(&output_path.join(filename), contents)?;
+            written.push((filename.clone(), contents.len() as u64, manifest::sha256(contents)));
+        }
+        Ok(written)
+    }
+}
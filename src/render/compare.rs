@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Serialize;
+
+use crate::encoding;
+use crate::freshness;
+use crate::utils;
+use crate::model::{FileModel, ReportModel};
+use super::{Asset, PageContext, RenderContext, Title};
+
+const CODE_REGION: i64 = 0;
+
+/// A line of source with the execution counts of both labels, if it contains
+/// code
+#[derive(Serialize, JsonSchema)]
+struct ComparedLine {
+    /// 1-based line number.
+    line: usize,
+    left: Option<i64>,
+    right: Option<i64>,
+    /// `both`, `left`, `right`, or `neither`, going by which label ran the
+    /// line; absent on lines without code.
+    ran_in: Option<&'static str>,
+    /// The escaped source with a span around each code region, colored by
+    /// which label ran it and starting with the count of each, and whatever
+    /// the post-processors made of it, to be included as is.
+    html: String,
+}
+
+/// A code region of the file with the count of each label.
+struct ComparedRegion {
+    start: (i64, i64),
+    /// One past the last character, as in the coverage data.
+    end: (i64, i64),
+    left: i64,
+    right: i64,
+}
+
+/// What the `compare` template is rendered with.
+#[derive(Serialize, JsonSchema)]
+struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
+    package: Option<&'a str>,
+    filename: &'a str,
+    /// Why the source doesn't seem to be the one the coverage data was
+    /// recorded against, if it doesn't.
+    stale: Option<String>,
+    left: &'a str,
+    right: &'a str,
+    page_style: Asset,
+    /// Lines only the left or only the right label ran.
+    left_only: usize,
+    right_only: usize,
+    contents: Vec<ComparedLine>,
+}
+
+/// A file with the counts of two labeled inputs side by side, to see which
+/// suite exercises what.
+pub struct RenderCompare<'a> {
+    model: &'a ReportModel,
+    file: &'a FileModel,
+    left: (&'a str, &'a ReportModel),
+    right: (&'a str, &'a ReportModel),
+    ctx: &'a RenderContext<'a>,
+}
+
+/// Execution counts of the code regions of `file` in `model`, by start and
+/// end position.
+fn region_counts(model: &ReportModel, file: &FileModel) -> BTreeMap<(i64, i64, i64, i64), i64> {
+    let mut counts = BTreeMap::new();
+    for function in model.functions.iter().filter(|f| f.file.as_ref() == Some(&file.name)) {
+        for region in function.regions.iter().filter(|r| r.region_kind == CODE_REGION && r.file_id == 0) {
+            *counts.entry((region.line_start, region.column_start, region.line_end, region.column_end)).or_default() += region.execution_count;
+        }
+    }
+    counts
+}
+
+/// `both`, `left`, `right`, or `neither`, and the class coloring it.
+fn ran_in(left: i64, right: i64) -> (&'static str, &'static str) {
+    match (left > 0, right > 0) {
+        (true, true) => ("both", "green"),
+        (true, false) => ("left", "yellow"),
+        (false, true) => ("right", "yellow"),
+        (false, false) => ("neither", "red"),
+    }
+}
+
+impl ComparedRegion {
+    fn contains(&self, position: (i64, i64)) -> bool {
+        self.start <= position && position < self.end
+    }
+}
+
+impl<'a> RenderCompare<'a> {
+    /// `file` is from `model`, the merged report; the labels are matched to
+    /// it by the file name in the export.
+    pub fn new(
+        model: &'a ReportModel,
+        file: &'a FileModel,
+        left: (&'a str, &'a ReportModel),
+        right: (&'a str, &'a ReportModel),
+        ctx: &'a RenderContext<'a>,
+    ) -> Self {
+        Self {
+            model, file, left, right, ctx
+        }
+    }
+
+    /// JSON schema of what the `compare` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let source_path = self.ctx.input_path.join(&self.file.filename);
+        let input = encoding::decode(&self.ctx.files.read(&source_path)?, self.ctx.source_encoding);
+        let stale = freshness::mismatch(self.file, &input);
+
+        let labeled = |model: &'a ReportModel| model.all_files().find(|f| f.filename == self.file.filename);
+        let (left_file, right_file) = (labeled(self.left.1), labeled(self.right.1));
+        let count = |file: Option<&FileModel>, idx: usize| {
+            file.and_then(|f| f.lines.get(idx)).filter(|l| l.mapped).map(|l| l.count)
+        };
+
+        let left_regions = left_file.map(|f| region_counts(self.left.1, f)).unwrap_or_default();
+        let right_regions = right_file.map(|f| region_counts(self.right.1, f)).unwrap_or_default();
+        // Outer regions before the ones nested in them
+        let mut regions = left_regions
+            .keys()
+            .chain(right_regions.keys().filter(|p| !left_regions.contains_key(p)))
+            .map(|position| {
+                let (line_start, column_start, line_end, column_end) = *position;
+                ComparedRegion {
+                    start: (line_start, column_start),
+                    end: (line_end, column_end),
+                    left: left_regions.get(position).copied().unwrap_or(0),
+                    right: right_regions.get(position).copied().unwrap_or(0),
+                }
+            })
+            .collect::<Vec<_>>();
+        regions.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+
+        let contents: Vec<_> = input
+            .lines()
+            .enumerate()
+            .map(|(idx, text)| {
+                let (left, right) = (count(left_file, idx), count(right_file, idx));
+                let ran = match (left, right) {
+                    (None, None) => None,
+                    (left, right) => Some(ran_in(left.unwrap_or(0), right.unwrap_or(0)).0),
+                };
+                let text = self.ctx.anonymizer.source_line(text.to_string());
+                let html = self.annotate(idx as i64 + 1, &text, &regions);
+                ComparedLine {
+                    line: idx + 1,
+                    left,
+                    right,
+                    ran_in: ran,
+                    html: self.ctx.postprocessors.iter().fold(html, |html, p| p.process(&html)),
+                }
+            })
+            .collect();
+
+        let page = format!("{}: {} vs {}", self.file.name, self.left.0, self.right.0);
+        let context = Context {
+            page: self.ctx.page(self.model.metadata.package.as_deref(), Title::file(&page, self.file)),
+            package: self.model.metadata.package.as_deref(),
+            filename: &self.file.name,
+            stale,
+            left: self.left.0,
+            right: self.right.0,
+            page_style: self.ctx.assets.stylesheet("compare", self.ctx.templates.render("compare_css", &())?),
+            left_only: contents.iter().filter(|l| l.ran_in == Some("left")).count(),
+            right_only: contents.iter().filter(|l| l.ran_in == Some("right")).count(),
+            contents,
+        };
+
+        self.ctx.templates.render("compare", &context)
+    }
+
+    /// The escaped `text` of line `row`, split wherever a region starts or
+    /// ends, with each part in a span for the innermost region around it.
+    /// The part a region starts with begins with the counts of both labels.
+    fn annotate(&self, row: i64, text: &str, regions: &[ComparedRegion]) -> String {
+        let chars = text.chars().collect::<Vec<_>>();
+        let end_column = chars.len() as i64 + 1;
+        let on_line = regions.iter().filter(|r| r.start.0 <= row && row <= r.end.0).collect::<Vec<_>>();
+
+        let mut columns = vec![1, end_column];
+        for region in on_line.iter() {
+            if region.start.0 == row {
+                columns.push(region.start.1.clamp(1, end_column));
+            }
+            if region.end.0 == row {
+                columns.push(region.end.1.clamp(1, end_column));
+            }
+        }
+        columns.sort_unstable();
+        columns.dedup();
+
+        let mut html = String::new();
+        for part in columns.windows(2) {
+            let (from, to) = (part[0], part[1]);
+            let text = handlebars::html_escape(&chars[(from - 1) as usize..(to - 1) as usize].iter().collect::<String>());
+            // The last one to start is the innermost
+            let region = match on_line.iter().rev().find(|r| r.contains((row, from))) {
+                Some(region) => region,
+                None => {
+                    html.push_str(&text);
+                    continue;
+                }
+            };
+            let (ran, class) = ran_in(region.left, region.right);
+            html.push_str(&format!(
+                r#"<span class="region {}" data-ran-in="{}" data-left="{}" data-right="{}">"#,
+                class, ran, region.left, region.right,
+            ));
+            if region.start == (row, from) {
+                html.push_str(&format!(
+                    r#"<span class="counts" title="{} {}, {} {}">{}&#x2009;|&#x2009;{}</span>"#,
+                    handlebars::html_escape(self.left.0), region.left,
+                    handlebars::html_escape(self.right.0), region.right,
+                    utils::human_count(region.left), utils::human_count(region.right),
+                ));
+            }
+            html.push_str(&text);
+            html.push_str("</span>");
+        }
+        html
+    }
+}
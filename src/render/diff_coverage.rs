@@ -0,0 +1,92 @@
+use std::error::Error as StdError;
+
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Serialize;
+
+use crate::model::ReportModel;
+use super::{PageContext, RenderContext, Title};
+
+#[derive(Serialize, JsonSchema)]
+struct File<'a> {
+    pub name: &'a str,
+    pub link: String,
+    pub lines_instrumented: u64,
+    pub lines_hit: u64,
+    pub lines_hit_percent: String,
+    pub hit_class: &'static str,
+    /// Runs of changed lines that never ran, e.g. `12` or `14-17`.
+    pub uncovered: Vec<String>,
+}
+
+/// What the `diff_coverage` template is rendered with.
+#[derive(Serialize, JsonSchema)]
+struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
+    package: Option<&'a str>,
+    range: &'a str,
+    lines_instrumented: u64,
+    lines_hit: u64,
+    lines_hit_percent: String,
+    /// `red`, `yellow`, or `green` against the line goal.
+    hit_class: &'static str,
+    /// Files with changed lines that never ran first.
+    files: Vec<File<'a>>,
+}
+
+/// Lists the changed lines that never ran, only rendered with
+/// `--diff-base`.
+pub struct RenderDiffCoverage<'a> {
+    model: &'a ReportModel,
+    ctx: &'a RenderContext<'a>,
+}
+
+impl<'a> RenderDiffCoverage<'a> {
+    pub fn new(model: &'a ReportModel, ctx: &'a RenderContext<'a>) -> Self {
+        Self {
+            model, ctx
+        }
+    }
+
+    /// JSON schema of what the `diff_coverage` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let diff = self.model.diff.as_ref().ok_or("no changes to render")?;
+
+        let mut files = diff.files.iter().collect::<Vec<_>>();
+        files.sort_by_key(|file| file.uncovered.is_empty());
+
+        let context = Context {
+            page: self.ctx.page(self.model.metadata.package.as_deref(), Title::report("Diff Coverage", self.model)),
+            package: self.model.metadata.package.as_deref(),
+            range: &diff.range,
+            lines_instrumented: diff.summary.count,
+            lines_hit: diff.summary.covered,
+            lines_hit_percent: format!("{:.1}", diff.summary.percent),
+            hit_class: self.ctx.goals.lines.color(diff.summary.percent),
+            files: files
+                .into_iter()
+                .map(|file| File {
+                    name: &file.name,
+                    link: self.ctx.link(&file.page),
+                    lines_instrumented: file.summary.count,
+                    lines_hit: file.summary.covered,
+                    lines_hit_percent: format!("{:.1}", file.summary.percent),
+                    hit_class: self.ctx.goals.lines.color(file.summary.percent),
+                    uncovered: file.uncovered
+                        .iter()
+                        .map(|(start, end)| match start == end {
+                            true => start.to_string(),
+                            false => format!("{}-{}", start, end),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        };
+
+        self.ctx.templates.render("diff_coverage", &context)
+    }
+}
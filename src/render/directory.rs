@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Serialize;
+
+use crate::{FileCoverageSummary, Summary};
+use crate::check::Goal;
+use crate::model::{FileModel, ReportModel};
+use super::{PageContext, RenderContext, Title};
+
+/// A directory with `--page-layout tree`, by the path of its page and its
+/// shown name.
+pub(crate) struct Directory<'a> {
+    /// Relative to the index, e.g. `src/render`.
+    pub path: String,
+    /// Last component of the directory's shown path.
+    pub name: &'a str,
+    /// Of every reported file under it.
+    pub summary: FileCoverageSummary,
+}
+
+impl Directory<'_> {
+    pub fn page(&self) -> String {
+        format!("{}/index.html", self.path)
+    }
+}
+
+/// The (shown) path of a file split like its page is with
+/// `--page-layout tree`.
+pub(crate) fn components(file: &FileModel) -> Vec<&str> {
+    file.name.split(['/', '\\']).filter(|c| !c.is_empty()).collect()
+}
+
+/// The directories right under `dir` (the path of its page, empty for the
+/// index), and the files in it.  The pages of files are all next to the
+/// index with the flat layout, so there are none then.
+pub(crate) fn entries<'a>(model: &'a ReportModel, dir: &str) -> (Vec<Directory<'a>>, Vec<&'a FileModel>) {
+    let parent: Vec<_> = dir.split('/').filter(|c| !c.is_empty()).collect();
+
+    let mut dirs: BTreeMap<&str, (&str, Vec<&FileCoverageSummary>)> = BTreeMap::new();
+    let mut files = vec![];
+    for file in model.files.iter() {
+        let pages: Vec<_> = file.page.split('/').collect();
+        if pages.len() <= parent.len() || pages[..parent.len()] != parent[..] {
+            continue;
+        }
+        match pages.len() - parent.len() {
+            1 => files.push(file),
+            _ => {
+                let name = components(file).get(parent.len()).copied().unwrap_or(pages[parent.len()]);
+                dirs.entry(pages[parent.len()]).or_insert((name, vec![])).1.push(&file.summary);
+            }
+        }
+    }
+
+    let dirs = dirs
+        .into_iter()
+        .map(|(page, (name, summaries))| Directory {
+            path: parent.iter().chain(std::iter::once(&page)).copied().collect::<Vec<_>>().join("/"),
+            name,
+            summary: FileCoverageSummary::total(summaries.into_iter()),
+        })
+        .collect();
+    (dirs, files)
+}
+
+/// Every directory a reported file's page is in, e.g. `src` and
+/// `src/render` for `src/render/file.rs.html`.
+pub(crate) fn directories(model: &ReportModel) -> Vec<String> {
+    let mut dirs: Vec<_> = model.files
+        .iter()
+        .flat_map(|file| {
+            let pages: Vec<_> = file.page.split('/').collect();
+            (1..pages.len()).map(move |len| pages[..len].join("/"))
+        })
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+/// Covered and instrumented, or nothing to measure.
+#[derive(Serialize, JsonSchema)]
+struct Metric {
+    covered: u64,
+    count: u64,
+    /// Absent when there's nothing to measure.
+    percent: Option<String>,
+    hit_class: Option<&'static str>,
+}
+
+impl Metric {
+    fn new(summary: &Summary, goal: &Goal) -> Self {
+        let measured = summary.count > 0;
+        Self {
+            covered: summary.covered,
+            count: summary.count,
+            percent: measured.then(|| format!("{:.1}", summary.percent)),
+            hit_class: measured.then(|| goal.color(summary.percent)),
+        }
+    }
+}
+
+/// A subdirectory or a file.
+#[derive(Serialize, JsonSchema)]
+struct Entry<'a> {
+    name: &'a str,
+    link: String,
+    directory: bool,
+    lines: Metric,
+    functions: Metric,
+    branches: Metric,
+}
+
+/// A directory above the one shown.
+#[derive(Serialize, JsonSchema)]
+struct Crumb<'a> {
+    name: &'a str,
+    link: String,
+}
+
+/// What the `directory` template is rendered with.
+#[derive(Serialize, JsonSchema)]
+struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
+    package: Option<&'a str>,
+    /// Last component of the directory's path.
+    directory: &'a str,
+    /// The directories it's in, outermost first.
+    parents: Vec<Crumb<'a>>,
+    /// Every file under the directory.
+    totals: Entry<'a>,
+    /// Subdirectories first, then files, each by name.
+    entries: Vec<Entry<'a>>,
+}
+
+/// The page of a directory with `--page-layout tree`, with the totals of
+/// each of its subdirectories and files, like genhtml writes them.
+pub struct RenderDirectory<'a> {
+    model: &'a ReportModel,
+    /// Path of the directory's page relative to the index.
+    path: &'a str,
+    ctx: &'a RenderContext<'a>,
+}
+
+impl<'a> RenderDirectory<'a> {
+    /// `ctx` should be at the directory's page, `path/index.html`.
+    pub fn new(model: &'a ReportModel, path: &'a str, ctx: &'a RenderContext<'a>) -> Self {
+        Self {
+            model, path, ctx
+        }
+    }
+
+    /// JSON schema of what the `directory` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
+    fn entry(&self, name: &'a str, link: String, directory: bool, summary: &FileCoverageSummary) -> Entry<'a> {
+        let goals = self.ctx.goals;
+        Entry {
+            name,
+            link,
+            directory,
+            lines: Metric::new(&summary.lines, &goals.lines),
+            functions: Metric::new(&summary.functions, &goals.functions),
+            branches: Metric::new(&summary.branches, &goals.branches),
+        }
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let pages: Vec<_> = self.path.split('/').collect();
+        let prefix = format!("{}/", self.path);
+        // Shown names of the directory and the ones it's in, from any file
+        // under it
+        let names = self.model.files
+            .iter()
+            .find(|f| f.page.starts_with(&prefix))
+            .map(components)
+            .filter(|names| names.len() > pages.len())
+            .ok_or_else(|| format!("No reported files under {}", self.path))?;
+        let name = names[pages.len() - 1];
+        let parents = (1..pages.len())
+            .map(|len| Crumb { name: names[len - 1], link: self.ctx.link(&format!("{}/index.html", pages[..len].join("/"))) })
+            .collect();
+
+        let (dirs, files) = entries(self.model, self.path);
+        let summary = FileCoverageSummary::total(dirs.iter().map(|d| &d.summary).chain(files.iter().map(|f| &f.summary)));
+        let mut listed: Vec<_> = dirs.iter().map(|d| self.entry(d.name, self.ctx.link(&d.page()), true, &d.summary)).collect();
+        listed.extend(files.iter().map(|f| {
+            let name = components(f).last().copied().unwrap_or(&f.name);
+            self.entry(name, self.ctx.link(&f.page), false, &f.summary)
+        }));
+
+        let context = Context {
+            page: self.ctx.page(
+                self.model.metadata.package.as_deref(),
+                Title { page: name, file: None, percent: summary.lines.percent },
+            ),
+            package: self.model.metadata.package.as_deref(),
+            directory: name,
+            parents,
+            totals: self.entry("Total", self.ctx.link(&format!("{}/index.html", self.path)), false, &summary),
+            entries: listed,
+        };
+
+        self.ctx.templates.render("directory", &context)
+    }
+}
@@ -1,19 +1,20 @@
+use std::collections::HashSet;
 use std::error::Error as StdError;
-use std::fs::File;
-use std::path::Path;
-use std::io::{BufRead, BufReader};
 
-use handlebars::Handlebars;
+use schemars::{schema::RootSchema, JsonSchema};
 use serde::Serialize;
 use log::{debug, trace};
 
-use crate::{FileCoverage, utils};
+use crate::encoding;
+use crate::freshness;
+use crate::utils;
+use crate::model::{FileModel, ReportModel};
+use super::{Asset, PageContext, RenderContext, Title};
 
-pub(crate) struct RenderFile<'a> {
-    file: &'a FileCoverage<'a>,
-    package: Option<&'a str>,
-    input_path: &'a Path,
-    handlebars: &'a Handlebars<'a>
+pub struct RenderFile<'a> {
+    model: &'a ReportModel,
+    file: &'a FileModel,
+    ctx: &'a RenderContext<'a>,
 }
 
 /// Collapsed segment with start and stop points
@@ -24,16 +25,47 @@ struct Seg {
     pub start_row: i64,
     pub stop_row: i64,
     pub count: i64,
+    pub gap: bool,
 }
 
-/// Render context
+/// Page stylesheet context
 #[derive(Serialize)]
+pub(super) struct StyleContext {
+    pub max_line_len: usize,
+    pub line_count_width: usize,
+}
+
+/// A line of source with its execution count, if it contains code
+#[derive(Serialize, JsonSchema)]
+pub(super) struct SourceLine {
+    /// 1-based line number.
+    line: usize,
+    count: Option<i64>,
+    /// Whether the line ran but has code regions on it that didn't, e.g.
+    /// the error branch of a `?` or the right of a `&&`.
+    partial: bool,
+    /// The escaped source with a span around each region, and whatever the
+    /// post-processors made of it, to be included as is.
+    html: String,
+}
+
+/// What the `file` template is rendered with.
+#[derive(Serialize, JsonSchema)]
 struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
     package: Option<&'a str>,
     filename: &'a str,
-    contents: Vec<String>,
-    max_line_len: usize,
-    line_count_width: usize,
+    /// Why the source doesn't seem to be the one the coverage data was
+    /// recorded against, if it doesn't.
+    stale: Option<String>,
+    /// Whether the source is syntax highlighted, with coverage shown by
+    /// background color instead of text color.
+    highlighted: bool,
+    page_style: Asset,
+    contents: Vec<SourceLine>,
+    /// Lines that ran only in part, which line coverage alone doesn't show.
+    partial_lines: usize,
     lines_instrumented: u64,
     lines_hit: u64,
     lines_hit_percent: String,
@@ -43,34 +75,86 @@ struct Context<'a> {
     functions_hit_percent: String,
 }
 
+/// The source of a file marked up with its regions, as shown on its page
+/// and in the single file report.
+pub(super) struct Annotated {
+    pub stale: Option<String>,
+    pub highlighted: bool,
+    pub max_line_len: usize,
+    pub line_count_width: usize,
+    pub contents: Vec<SourceLine>,
+}
+
 impl<'a> RenderFile<'a> {
-    pub fn new(file: &'a FileCoverage<'a>, package: Option<&'a str>, input_path: &'a Path, handlebars: &'a Handlebars<'a>) -> Self {
+    pub fn new(model: &'a ReportModel, file: &'a FileModel, ctx: &'a RenderContext<'a>) -> Self {
         Self {
-            file, package, input_path, handlebars
+            model, file, ctx
         }
     }
 
+    /// JSON schema of what the `file` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
     pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let annotated = self.annotate()?;
+        let page_style = self.ctx.templates.render("file_css", &StyleContext {
+            max_line_len: annotated.max_line_len,
+            line_count_width: annotated.line_count_width,
+        })?;
+
+        let context = Context {
+            page: self.ctx.page(self.model.metadata.package.as_deref(), Title::file(&self.file.name, self.file)),
+            package: self.model.metadata.package.as_deref(),
+            filename: &self.file.name,
+            stale: annotated.stale,
+            highlighted: annotated.highlighted,
+            page_style: self.ctx.assets.stylesheet("file", page_style),
+            partial_lines: annotated.contents.iter().filter(|line| line.partial).count(),
+            contents: annotated.contents,
+            lines_instrumented: self.file.summary.lines.count,
+            lines_hit: self.file.summary.lines.covered,
+            lines_hit_percent: format!("{:.1}", self.file.summary.lines.percent),
+            functions_instrumented: self.file.summary.functions.count,
+            functions_hit: self.file.summary.functions.covered,
+            functions_hit_percent: format!("{:.1}", self.file.summary.functions.percent),
+        };
+
+        self.ctx.templates.render("file", &context)
+    }
+
+    pub(super) fn annotate(&self) -> Result<Annotated, Box<dyn StdError>> {
         use utils::InsertAtCharacter;
 
-        debug!("Input: {:?}", self.input_path.join(self.file.filename));
+        let source_path = self.ctx.input_path.join(&self.file.filename);
+        debug!("Input: {:?}", source_path);
         trace!("{:#?}\n\n", self.file);
 
-        let input = File::open(self.input_path.join(self.file.filename))?;
-        let input_reader = BufReader::new(input);
-        let mut lines: Vec<String> = input_reader.lines().filter_map(Result::ok).collect();
+        let input = encoding::decode(&self.ctx.files.read(&source_path)?, self.ctx.source_encoding);
+        let stale = freshness::mismatch(self.file, &input);
+        if let Some(problem) = stale.as_ref() {
+            self.ctx.diagnostics.warn(Some(&self.file.name), format!("Stale source: {}", problem));
+        }
+        let mut lines: Vec<String> = input
+            .lines()
+            .map(|line| self.ctx.anonymizer.source_line(line.to_string()))
+            .collect();
         let max_line_len: usize = lines.iter().map(|l| l.len()).max().unwrap();
         let line_count_width: usize = ((lines.len() as f64).log10() + 1_f64).floor() as usize;
+        // Tokenized before the segment placeholders go in
+        let highlights = self.ctx.highlighter.and_then(|h| h.runs(&self.file.filename, &lines));
         let mut segments = vec![];
 
         for segment in self.file.segments.iter() {
-            if segment.is_region_entry == true {
+            if segment.is_region_entry {
                 segments.push(Seg {
                     start_col: segment.col,
                     stop_col: segment.col,
                     start_row: segment.line,
                     stop_row: segment.line,
                     count: segment.count,
+                    gap: segment.is_gap_region,
                 })
             } else {
                 segments.last_mut().unwrap().stop_col = segment.col;
@@ -80,36 +164,39 @@ impl<'a> RenderFile<'a> {
         }
 
         let segments: Vec<Seg> = segments.into_iter().rev().collect();
+        let uncovered_rows = segments
+            .iter()
+            .filter(|segment| !segment.gap && segment.count == 0)
+            .flat_map(|segment| segment.start_row..=segment.stop_row)
+            .collect::<HashSet<_>>();
 
         for (seg_idx, segment) in segments.iter().enumerate() {
+            if segment.stop_row as usize > lines.len() {
+                continue;
+            }
+            let kind = match segment.gap {
+                true => "gap",
+                false => "code",
+            };
+            // A segment spanning several lines gets a span on each of them
+            let start_segment = |row: i64| format!("{{{{ start_segment {} {} {} {} }}}}", seg_idx, segment.count, row, kind);
+
             if segment.start_row == segment.stop_row {
                 let line_index = segment.start_row as usize - 1;
                 lines[line_index].insert_at_char(segment.stop_col as usize, "{{ end_segment }}");
-                lines[line_index].insert_at_char(
-                    segment.start_col as usize,
-                    &format!("{{{{ start_segment {} {} }}}}", seg_idx, segment.count),
-                );
+                lines[line_index].insert_at_char(segment.start_col as usize, &start_segment(segment.start_row));
             } else {
                 let start_idx = segment.start_row as usize - 1;
                 lines[start_idx].push_str("{{ end_segment }}");
-                lines[start_idx].insert_at_char(
-                    segment.start_col as usize,
-                    &format!("{{{{ start_segment {} {} }}}}", seg_idx, segment.count),
-                );
+                lines[start_idx].insert_at_char(segment.start_col as usize, &start_segment(segment.start_row));
 
                 let stop_idx = segment.stop_row as usize - 1;
                 lines[stop_idx].insert_at_char(segment.stop_col as usize, "{{ end_segment }}");
 
-                lines[segment.stop_row as usize - 1].insert_at_char(
-                    0,
-                    &format!("{{{{ start_segment {} {} }}}}", seg_idx, segment.count),
-                );
+                lines[segment.stop_row as usize - 1].insert_at_char(0, &start_segment(segment.stop_row));
                 for i in (segment.start_row + 1)..(segment.stop_row) {
                     lines[i as usize - 1].push_str("{{ end_segment }}");
-                    lines[i as usize - 1].insert_at_char(
-                        0,
-                        &format!("{{{{ start_segment {} {} }}}}", seg_idx, segment.count),
-                    );
+                    lines[i as usize - 1].insert_at_char(0, &start_segment(i));
                 }
             }
             trace!("{:?}", segment)
@@ -119,31 +206,44 @@ impl<'a> RenderFile<'a> {
             trace!("{:5}: {}", i, line)
         }
 
-        let context = Context {
-            package: self.package,
-            filename: self.file.filename,
-            contents: lines,
-            max_line_len,
-            line_count_width,
-            lines_instrumented: self.file.summary.lines.count,
-            lines_hit: self.file.summary.lines.covered,
-            lines_hit_percent: format!("{:.1}", self.file.summary.lines.percent),
-            functions_instrumented: self.file.summary.functions.count,
-            functions_hit: self.file.summary.functions.covered,
-            functions_hit_percent: format!("{:.1}", self.file.summary.functions.percent),
+        let re = regex::Regex::new(r#"\{\{ start_segment (\d+) (\d+) (\d+) (\w+) \}\}"#)?;
+        let to_html = |idx: usize, text: &str| {
+            let html = match highlights.as_ref().and_then(|h| h.get(idx)) {
+                Some(runs) => super::markup(text, runs),
+                None => handlebars::html_escape(text),
+            };
+            let html = html.replace("{{ end_segment }}", "</span>");
+            let html = re.replace_all(&html, |caps: &regex::Captures| {
+                format!(
+                    r#"<span class='hit' title="{count} hits" data-line="{line}" data-count="{count}" data-covered="{covered}" data-region-kind="{kind}" data-segment-index="{index}">"#,
+                    index = &caps[1],
+                    count = &caps[2],
+                    line = &caps[3],
+                    kind = &caps[4],
+                    covered = &caps[2] != "0",
+                )
+            });
+            self.ctx.postprocessors.iter().fold(html.into_owned(), |html, p| p.process(&html))
         };
 
-        let re = regex::Regex::new(r#"\{\{ start_segment (\d+) (\d+) \}\}"#)?;
-        let output = self.handlebars
-            .render("file", &context)?
-            .replace("{{ end_segment }}", "</span>");
-
-        let output = re.replace_all(
-            &output,
-            r#"<span class='hit' title="${2} hits" data-count=${2} data-segment-index=${1}>"#,
-        );
-
-        Ok(output.to_string())
-
+        Ok(Annotated {
+            stale,
+            highlighted: highlights.is_some(),
+            max_line_len,
+            line_count_width,
+            contents: lines
+                .into_iter()
+                .enumerate()
+                .map(|(idx, text)| {
+                    let count = self.file.lines.get(idx).filter(|l| l.mapped).map(|l| l.count);
+                    SourceLine {
+                        line: idx + 1,
+                        count,
+                        partial: count.unwrap_or(0) > 0 && uncovered_rows.contains(&(idx as i64 + 1)),
+                        html: to_html(idx, &text),
+                    }
+                })
+                .collect(),
+        })
     }
 }
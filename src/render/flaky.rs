@@ -0,0 +1,75 @@
+use std::error::Error as StdError;
+
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Serialize;
+
+use crate::model::ReportModel;
+use super::{PageContext, RenderContext, Title};
+
+#[derive(Serialize, JsonSchema)]
+struct Region<'a> {
+    pub file: &'a str,
+    /// Page of the file, if it was rendered.
+    pub page: Option<&'a str>,
+    pub line_start: i64,
+    pub column_start: i64,
+    pub line_end: i64,
+    pub column_end: i64,
+    pub function: &'a str,
+    pub covered_runs: usize,
+}
+
+/// What the `flaky` template is rendered with.
+#[derive(Serialize, JsonSchema)]
+struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
+    package: Option<&'a str>,
+    runs: usize,
+    regions: Vec<Region<'a>>,
+}
+
+/// Lists the regions that ran in only some of the runs, only rendered with
+/// `--flaky`.
+pub struct RenderFlaky<'a> {
+    model: &'a ReportModel,
+    ctx: &'a RenderContext<'a>,
+}
+
+impl<'a> RenderFlaky<'a> {
+    pub fn new(model: &'a ReportModel, ctx: &'a RenderContext<'a>) -> Self {
+        Self {
+            model, ctx
+        }
+    }
+
+    /// JSON schema of what the `flaky` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let flaky = self.model.flaky.as_ref().ok_or("no runs to compare")?;
+
+        let context = Context {
+            page: self.ctx.page(self.model.metadata.package.as_deref(), Title::report("Flaky Coverage", self.model)),
+            package: self.model.metadata.package.as_deref(),
+            runs: flaky.runs,
+            regions: flaky.regions
+                .iter()
+                .map(|r| Region {
+                    file: &r.file,
+                    page: r.page.as_deref(),
+                    line_start: r.line_start,
+                    column_start: r.column_start,
+                    line_end: r.line_end,
+                    column_end: r.column_end,
+                    function: &r.function,
+                    covered_runs: r.covered_runs,
+                })
+                .collect(),
+        };
+
+        self.ctx.templates.render("flaky", &context)
+    }
+}
@@ -1,51 +1,168 @@
+use std::collections::HashMap;
 use std::error::Error as StdError;
+use schemars::{schema::RootSchema, JsonSchema};
 use serde::Serialize;
-use crate::FunctionCoverage;
+use crate::model::{DroppedFunction, FunctionModel, FunctionPart, RenamedFunction, ReportModel};
+use super::{Asset, PageContext, RenderContext, RenderFunctionPage, RenderOmitted, Title};
 
-use handlebars::Handlebars;
-use std::path::Path;
+#[derive(Serialize, JsonSchema)]
+struct Function<'a> {
+    pub name: &'a str,
+    /// Page with the regions of the function.
+    pub link: String,
+    pub count: i64,
+    /// Number of symbols folded into this entry.
+    pub symbols: usize,
+    /// Closures and async fn bodies folded into this entry.
+    pub parts: Vec<Part<'a>>,
+    /// For a generic function, each of its instantiations, in which case
+    /// the count and symbols are theirs summed up.
+    pub instantiations: Vec<Function<'a>>,
+}
 
-#[derive(Serialize)]
-struct Function {
-    pub name: String,
+#[derive(Serialize, JsonSchema)]
+struct Part<'a> {
+    pub name: &'a str,
     pub count: i64,
 }
 
-#[derive(Serialize)]
+impl<'a> From<&'a FunctionPart> for Part<'a> {
+    fn from(part: &'a FunctionPart) -> Self {
+        Self { name: &part.name, count: part.count }
+    }
+}
+
+/// The functions of one impl block.
+#[derive(Serialize, JsonSchema)]
+struct Impl<'a> {
+    /// E.g. `impl Area for Circle`, or `impl Circle` for inherent methods.
+    pub name: &'a str,
+    pub functions_instrumented: u64,
+    pub functions_hit: u64,
+    pub functions_hit_percent: String,
+    /// `red`, `yellow`, or `green` against the function goal.
+    pub hit_class: &'static str,
+    pub functions: Vec<Function<'a>>,
+}
+
+/// What the `functions` template is rendered with.
+#[derive(Serialize, JsonSchema)]
 struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
     package: Option<&'a str>,
-    functions: Vec<Function>,
+    page_style: Asset,
+    page_script: Asset,
+    functions: Vec<Function<'a>>,
+    impls: Vec<Impl<'a>>,
+    dropped: &'a [DroppedFunction],
+    renamed: &'a [RenamedFunction],
 }
 
-pub(crate) struct RenderFunction<'a> {
-    func_coverage: &'a [&'a FunctionCoverage<'a>],
-    package: Option<&'a str>,
-    // input_path: &'a Path,
-    handlebars: &'a Handlebars<'a>,
+pub struct RenderFunction<'a> {
+    model: &'a ReportModel,
+    ctx: &'a RenderContext<'a>,
+    function_pages: bool,
 }
 
 impl<'a> RenderFunction<'a> {
-    pub fn new(func_coverage: &'a[&'a FunctionCoverage], package: Option<&'a str>, _input_path: &'a Path, handlebars: &'a Handlebars<'a>) -> Self {
+    pub fn new(model: &'a ReportModel, ctx: &'a RenderContext<'a>) -> Self {
         Self {
-            func_coverage, package, handlebars
+            model, ctx, function_pages: true
         }
     }
 
+    /// Link every function to the page saying it was left out of the
+    /// preview instead of to its own.
+    pub fn omit_function_pages(mut self) -> Self {
+        self.function_pages = false;
+        self
+    }
+
+    /// JSON schema of what the `functions` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
     pub fn render(&self) -> Result<String, Box<dyn StdError>> {
-        let mut functions: Vec<Function> = self.func_coverage
+        let function = |f: &'a FunctionModel| Function {
+            name: &f.name,
+            link: match self.function_pages {
+                true => self.ctx.link(&RenderFunctionPage::page(f)),
+                false => self.ctx.link(RenderOmitted::PAGE),
+            },
+            count: f.count,
+            symbols: f.symbols,
+            parts: f.parts.iter().map(Part::from).collect(),
+            instantiations: vec![],
+        };
+        let functions = group_instantiations(self.model.functions.iter(), function);
+
+        let impls = self.model.impls
             .iter()
-            .map(|f| Function {
-                name: f.demangle(),
-                count: f.count,
+            .map(|block| Impl {
+                name: &block.name,
+                functions_instrumented: block.functions.count,
+                functions_hit: block.functions.covered,
+                functions_hit_percent: format!("{:.1}", block.functions.percent),
+                hit_class: self.ctx.goals.functions.color(block.functions.percent),
+                functions: group_instantiations(
+                    self.model.functions.iter().filter(|f| f.impl_block.as_deref() == Some(block.name.as_str())),
+                    function,
+                ),
             })
             .collect();
-        functions.sort_by(|a, b| a.name.partial_cmp(&b.name).unwrap());
 
         let context = Context {
-            package: self.package,
-            functions
+            page: self.ctx.page(self.model.metadata.package.as_deref(), Title::report("Function Coverage", self.model)),
+            package: self.model.metadata.package.as_deref(),
+            page_style: self.ctx.assets.stylesheet("functions", self.ctx.templates.render("functions_css", &())?),
+            page_script: self.ctx.assets.script("functions", self.ctx.templates.render("functions_js", &())?),
+            functions,
+            impls,
+            dropped: &self.model.dropped_functions,
+            renamed: &self.model.renamed_functions,
+        };
+
+        self.ctx.templates.render("functions", &context)
+    }
+}
+
+/// One entry per function, with the instantiations of a generic function
+/// (in the same file) gathered under its name where the first of them is.
+fn group_instantiations<'a>(
+    functions: impl Iterator<Item = &'a FunctionModel>,
+    function: impl Fn(&'a FunctionModel) -> Function<'a>,
+) -> Vec<Function<'a>> {
+    let mut entries: Vec<Function<'a>> = vec![];
+    let mut generics: HashMap<(&str, Option<&str>), usize> = HashMap::new();
+
+    for f in functions {
+        let base = match f.generic.as_deref() {
+            Some(base) => base,
+            None => {
+                entries.push(function(f));
+                continue;
+            }
         };
 
-        self.handlebars.render("functions", &context).map_err(|e| e.into())
+        let instantiation = function(f);
+        let idx = *generics.entry((base, f.file.as_deref())).or_insert_with(|| {
+            entries.push(Function {
+                name: base,
+                link: instantiation.link.clone(),
+                count: 0,
+                symbols: 0,
+                parts: vec![],
+                instantiations: vec![],
+            });
+            entries.len() - 1
+        });
+        let entry = &mut entries[idx];
+        entry.count += instantiation.count;
+        entry.symbols += instantiation.symbols;
+        entry.instantiations.push(instantiation);
     }
+
+    entries
 }
@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+use std::error::Error as StdError;
+
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Serialize;
+
+use crate::model::{FunctionModel, ReportModel};
+use crate::utils;
+use super::{PageContext, RenderContext, Title};
+
+const CODE_REGION: i64 = 0;
+
+#[derive(Serialize, JsonSchema)]
+struct Region {
+    pub line_start: i64,
+    pub column_start: i64,
+    pub line_end: i64,
+    pub column_end: i64,
+    pub count: i64,
+    /// `code`, `expansion`, `skipped`, `gap`, `branch`, or `mcdc`, as
+    /// explained on the legend.
+    pub kind: &'static str,
+    /// Whether the span is in another file the code was expanded from, e.g.
+    /// the definition of a macro.
+    pub expanded: bool,
+    /// `red` or `green` for code regions, whether they ran.
+    pub hit_class: Option<&'static str>,
+}
+
+/// What the `function` template is rendered with.
+#[derive(Serialize, JsonSchema)]
+struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
+    package: Option<&'a str>,
+    name: &'a str,
+    /// Shown name of the file the function is defined in.
+    file: Option<&'a str>,
+    /// Page of that file, if it's reported.
+    file_link: Option<String>,
+    count: i64,
+    /// Number of symbols folded into this entry, e.g. instantiations of a
+    /// generic function.
+    symbols: usize,
+    regions_instrumented: usize,
+    regions_hit: usize,
+    /// By file and position.
+    regions: Vec<Region>,
+}
+
+/// The regions of one function with their counts, linked from the functions
+/// page.
+pub struct RenderFunctionPage<'a> {
+    model: &'a ReportModel,
+    function: &'a FunctionModel,
+    ctx: &'a RenderContext<'a>,
+    pages: Option<&'a HashSet<String>>,
+}
+
+/// Name of a region kind of the export.
+fn region_kind(kind: i64) -> &'static str {
+    match kind {
+        0 => "code",
+        1 => "expansion",
+        2 => "skipped",
+        3 => "gap",
+        4 => "branch",
+        _ => "mcdc",
+    }
+}
+
+impl<'a> RenderFunctionPage<'a> {
+    pub fn new(model: &'a ReportModel, function: &'a FunctionModel, ctx: &'a RenderContext<'a>) -> Self {
+        Self {
+            model, function, ctx, pages: None
+        }
+    }
+
+    /// Only link to the given file pages, e.g. when only some were rendered.
+    /// Borrowed, as every function page is rendered with the same ones.
+    pub fn pages(mut self, pages: &'a HashSet<String>) -> Self {
+        self.pages = Some(pages);
+        self
+    }
+
+    /// Where the page of `function` goes.
+    pub fn page(function: &FunctionModel) -> String {
+        utils::function_page(&function.name)
+    }
+
+    /// JSON schema of what the `function` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let function = self.function;
+
+        let mut regions = function.regions.iter().collect::<Vec<_>>();
+        regions.sort_by_key(|r| (r.file_id != 0, r.file_id, r.line_start, r.column_start, r.line_end, r.column_end, r.region_kind));
+        let code = function.regions.iter().filter(|r| r.region_kind == CODE_REGION && r.file_id == 0);
+
+        let context = Context {
+            page: self.ctx.page(self.model.metadata.package.as_deref(), Title::report(&function.name, self.model)),
+            package: self.model.metadata.package.as_deref(),
+            name: &function.name,
+            file: function.file.as_deref(),
+            file_link: function.file
+                .as_deref()
+                .and_then(|file| self.model.all_files().find(|f| f.name == file))
+                .filter(|file| self.pages.is_none_or(|pages| pages.contains(&file.page)))
+                .map(|file| self.ctx.link(&file.page)),
+            count: function.count,
+            symbols: function.symbols,
+            regions_instrumented: code.clone().count(),
+            regions_hit: code.filter(|r| r.execution_count > 0).count(),
+            regions: regions
+                .into_iter()
+                .map(|r| Region {
+                    line_start: r.line_start,
+                    column_start: r.column_start,
+                    line_end: r.line_end,
+                    column_end: r.column_end,
+                    count: r.execution_count,
+                    kind: region_kind(r.region_kind),
+                    expanded: r.file_id != 0,
+                    hit_class: (r.region_kind == CODE_REGION).then_some(match r.execution_count {
+                        0 => "red",
+                        _ => "green",
+                    }),
+                })
+                .collect(),
+        };
+
+        self.ctx.templates.render("function", &context)
+    }
+}
@@ -0,0 +1,128 @@
+//! Helpers every handlebars template can use, built-in or custom, and the
+//! formatting behind them.  Times are passed to templates as RFC 3339
+//! strings.
+
+use chrono::{DateTime, Datelike, FixedOffset, Utc};
+use handlebars::{self as hbs, Handlebars};
+
+pub use crate::time::Timezone;
+use crate::utils;
+
+fn parse_time(time: &str) -> Result<DateTime<FixedOffset>, String> {
+    DateTime::parse_from_rfc3339(time).map_err(|e| format!("`{}' isn't an RFC 3339 time: {}", time, e))
+}
+
+/// `time` formatted like `strftime(3)` in `timezone`.
+pub fn strftime(time: &str, format: &str, timezone: Timezone) -> Result<String, String> {
+    Ok(timezone.convert(parse_time(time)?).format(format).to_string())
+}
+
+/// How long before (or after) `now` `time` was, e.g. `3 hours ago` or
+/// `in 2 days`, in whole units rounded down.
+pub fn relative_time(time: &str, now: DateTime<FixedOffset>) -> Result<String, String> {
+    const UNITS: [(i64, &str); 6] = [
+        (365 * 24 * 3600, "year"),
+        (30 * 24 * 3600, "month"),
+        (7 * 24 * 3600, "week"),
+        (24 * 3600, "day"),
+        (3600, "hour"),
+        (60, "minute"),
+    ];
+
+    let seconds = now.signed_duration_since(parse_time(time)?).num_seconds();
+    let (amount, unit) = match UNITS.iter().find(|(length, _)| seconds.abs() >= *length) {
+        Some((length, unit)) => (seconds.abs() / length, *unit),
+        None => return Ok("just now".to_string()),
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+    Ok(match seconds > 0 {
+        true => format!("{} {}{} ago", amount, unit, plural),
+        false => format!("in {} {}{}", amount, unit, plural),
+    })
+}
+
+/// ISO 8601 week of `time` in `timezone`, e.g. `2026-W42`.
+pub fn iso_week(time: &str, timezone: Timezone) -> Result<String, String> {
+    let week = timezone.convert(parse_time(time)?).iso_week();
+    Ok(format!("{}-W{:02}", week.year(), week.week()))
+}
+
+/// A number of seconds in the two largest units that apply, e.g. `2h 5m`,
+/// `4.2s`, or `350ms`.
+pub fn duration(seconds: f64) -> String {
+    if !seconds.is_finite() || seconds < 0.0 {
+        return "-".to_string();
+    }
+    if seconds < 1.0 {
+        return format!("{:.0}ms", seconds * 1000.0);
+    }
+    if seconds < 60.0 {
+        return format!("{:.1}s", seconds);
+    }
+    let seconds = seconds as u64;
+    match (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60, seconds % 60) {
+        (0, 0, minutes, seconds) => format!("{}m {}s", minutes, seconds),
+        (0, hours, minutes, _) => format!("{}h {}m", hours, minutes),
+        (days, hours, _, _) => format!("{}d {}h", days, hours),
+    }
+}
+
+fn param<'a>(h: &'a hbs::Helper, idx: usize, what: &str) -> Result<&'a serde_json::Value, hbs::RenderError> {
+    h.param(idx)
+        .map(|p| p.value())
+        .ok_or_else(|| hbs::RenderError::new(format!("{}: {} param not found", h.name(), what)))
+}
+
+fn string_param<'a>(h: &'a hbs::Helper, idx: usize, what: &str) -> Result<&'a str, hbs::RenderError> {
+    param(h, idx, what)?
+        .as_str()
+        .ok_or_else(|| hbs::RenderError::new(format!("{}: {} param must be a string", h.name(), what)))
+}
+
+/// Registers, with times shown in `timezone`:
+///
+/// - `{{ strftime time "%e %b %Y" }}`
+/// - `{{ relative_time time }}`, or `{{ relative_time time since }}` for
+///   how long before `since` rather than now
+/// - `{{ iso_week time }}`
+/// - `{{ duration seconds }}`
+/// - `{{ human_count count }}`
+pub fn register(handlebars: &mut Handlebars, timezone: Timezone) {
+    handlebars.register_helper("strftime",
+      Box::new(move |h: &hbs::Helper, _r: &hbs::Handlebars, _: &hbs::Context, _rc: &mut hbs::RenderContext, out: &mut dyn hbs::Output| -> hbs::HelperResult {
+          let formatted = strftime(string_param(h, 0, "time")?, string_param(h, 1, "format")?, timezone).map_err(hbs::RenderError::new)?;
+          out.write(&formatted).map_err(|e| hbs::RenderError::new(e.to_string()))
+      }));
+
+    handlebars.register_helper("relative_time",
+      Box::new(|h: &hbs::Helper, _r: &hbs::Handlebars, _: &hbs::Context, _rc: &mut hbs::RenderContext, out: &mut dyn hbs::Output| -> hbs::HelperResult {
+          let now = match h.param(1) {
+              Some(_) => parse_time(string_param(h, 1, "since")?).map_err(hbs::RenderError::new)?,
+              None => Utc::now().into(),
+          };
+          let formatted = relative_time(string_param(h, 0, "time")?, now).map_err(hbs::RenderError::new)?;
+          out.write(&formatted).map_err(|e| hbs::RenderError::new(e.to_string()))
+      }));
+
+    handlebars.register_helper("iso_week",
+      Box::new(move |h: &hbs::Helper, _r: &hbs::Handlebars, _: &hbs::Context, _rc: &mut hbs::RenderContext, out: &mut dyn hbs::Output| -> hbs::HelperResult {
+          let formatted = iso_week(string_param(h, 0, "time")?, timezone).map_err(hbs::RenderError::new)?;
+          out.write(&formatted).map_err(|e| hbs::RenderError::new(e.to_string()))
+      }));
+
+    handlebars.register_helper("duration",
+      Box::new(|h: &hbs::Helper, _r: &hbs::Handlebars, _: &hbs::Context, _rc: &mut hbs::RenderContext, out: &mut dyn hbs::Output| -> hbs::HelperResult {
+          let seconds = param(h, 0, "seconds")?
+              .as_f64()
+              .ok_or_else(|| hbs::RenderError::new("duration: seconds param must be a number"))?;
+          out.write(&duration(seconds)).map_err(|e| hbs::RenderError::new(e.to_string()))
+      }));
+
+    handlebars.register_helper("human_count",
+      Box::new(|h: &hbs::Helper, _r: &hbs::Handlebars, _: &hbs::Context, _rc: &mut hbs::RenderContext, out: &mut dyn hbs::Output| -> hbs::HelperResult {
+          let count = param(h, 0, "count")?
+              .as_i64()
+              .ok_or_else(|| hbs::RenderError::new("human_count: count param must be an integer"))?;
+          out.write(&utils::human_count(count)).map_err(|e| hbs::RenderError::new(e.to_string()))
+      }));
+}
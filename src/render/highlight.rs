@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use log::debug;
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxSet};
+
+/// Highlight class of the innermost scope starting with each of these, most
+/// specific first.  The classes are styled in `style.css`.
+const CLASSES: [(&str, &str); 14] = [
+    ("comment", "hl-comment"),
+    ("string", "hl-string"),
+    ("constant.character", "hl-string"),
+    ("constant.numeric", "hl-number"),
+    ("constant", "hl-constant"),
+    ("keyword", "hl-keyword"),
+    ("storage.type", "hl-keyword"),
+    ("storage.modifier", "hl-keyword"),
+    ("entity.name.function", "hl-function"),
+    ("support.function", "hl-function"),
+    ("entity.name.type", "hl-type"),
+    ("support.type", "hl-type"),
+    ("support.macro", "hl-macro"),
+    ("meta.annotation", "hl-attribute"),
+];
+
+/// Highlight classes of a line of source: the number of characters up to
+/// which each run goes, and its class if it has one.
+pub type Runs = Vec<(usize, Option<&'static str>)>;
+
+/// Tokenizes source files with the syntaxes bundled with syntect, picked by
+/// file extension.
+pub struct Highlighter {
+    syntaxes: SyntaxSet,
+    classes: Vec<(Scope, &'static str)>,
+}
+
+impl Default for Highlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Highlighter {
+    pub fn new() -> Self {
+        Self {
+            syntaxes: SyntaxSet::load_defaults_newlines(),
+            classes: CLASSES.iter().map(|(prefix, class)| (Scope::new(prefix).unwrap(), *class)).collect(),
+        }
+    }
+
+    /// The runs of each of `lines` of the file `filename`, or `None` if
+    /// there's no syntax for it or it couldn't be parsed.
+    pub fn runs(&self, filename: &str, lines: &[String]) -> Option<Vec<Runs>> {
+        let extension = Path::new(filename).extension()?.to_str()?;
+        let syntax = self.syntaxes.find_syntax_by_extension(extension)?;
+
+        let mut state = ParseState::new(syntax);
+        let mut stack = ScopeStack::new();
+        let mut cache = HashMap::new();
+        let mut highlighted = Vec::with_capacity(lines.len());
+
+        for line in lines {
+            let text = format!("{}\n", line);
+            let ops = match state.parse_line(&text, &self.syntaxes) {
+                Ok(ops) => ops,
+                Err(e) => {
+                    debug!("Not highlighting {}: {}", filename, e);
+                    return None;
+                }
+            };
+
+            let mut runs: Runs = vec![];
+            let (mut byte, mut chars) = (0, 0);
+            for (pos, op) in ops.iter() {
+                chars += text[byte..*pos].chars().count();
+                byte = *pos;
+                runs.push((chars, self.class(&stack, &mut cache)));
+                stack.apply(op).ok()?;
+            }
+            runs.push((line.chars().count(), self.class(&stack, &mut cache)));
+            highlighted.push(runs);
+        }
+
+        Some(highlighted)
+    }
+
+    fn class(&self, stack: &ScopeStack, cache: &mut HashMap<Scope, Option<&'static str>>) -> Option<&'static str> {
+        stack.as_slice().iter().rev().find_map(|scope| {
+            *cache.entry(*scope).or_insert_with(|| {
+                self.classes.iter().find(|(prefix, _)| prefix.is_prefix_of(*scope)).map(|(_, class)| *class)
+            })
+        })
+    }
+}
+
+/// Escapes `line`, a line of source with segment placeholders inserted into
+/// it, wrapping the text in spans with the classes of `runs`.  Placeholders
+/// are passed through as is, and the highlight spans are closed before each
+/// of them so they nest inside the coverage spans.
+pub fn markup(line: &str, runs: &[(usize, Option<&'static str>)]) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut text = String::new();
+    let mut class = None;
+    let mut run = 0;
+    let mut chars = 0;
+    let mut rest = line;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(len) = placeholder_len(rest) {
+            flush(&mut out, &mut text, class);
+            out.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+
+        while runs.get(run).is_some_and(|(end, _)| *end <= chars) {
+            run += 1;
+        }
+        let c_class = runs.get(run).and_then(|(_, class)| *class);
+        if c_class != class {
+            flush(&mut out, &mut text, class);
+            class = c_class;
+        }
+        text.push(c);
+        chars += 1;
+        rest = &rest[c.len_utf8()..];
+    }
+    flush(&mut out, &mut text, class);
+
+    out
+}
+
+fn placeholder_len(s: &str) -> Option<usize> {
+    if !s.starts_with("{{ start_segment ") && !s.starts_with("{{ end_segment }}") {
+        return None;
+    }
+    s.find(" }}").map(|end| end + 3)
+}
+
+fn flush(out: &mut String, text: &mut String, class: Option<&str>) {
+    if text.is_empty() {
+        return;
+    }
+    let escaped = handlebars::html_escape(text);
+    match class {
+        Some(class) => {
+            out.push_str("<span class=\"");
+            out.push_str(class);
+            out.push_str("\">");
+            out.push_str(&escaped);
+            out.push_str("</span>");
+        }
+        None => out.push_str(&escaped),
+    }
+    text.clear();
+}
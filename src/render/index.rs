@@ -1,25 +1,39 @@
+use std::collections::{HashMap, HashSet};
 use std::error::Error as StdError;
-use std::fs::metadata;
 
-use chrono::{DateTime, offset::Local};
+use schemars::{schema::RootSchema, JsonSchema};
 use serde::Serialize;
 
-use crate::{FileCoverage, FileCoverageSummary, utils};
-use handlebars::Handlebars;
-use std::path::Path;
+use crate::{FileCoverageSummary, Summary};
+use crate::chart;
+use crate::history::History;
+use crate::model::{FileModel, ReportModel};
+use super::{Asset, PageContext, RenderContext, Title};
+use super::directory;
 
-pub(crate) struct RenderIndex<'a> {
-    files: &'a Vec<&'a FileCoverage<'a>>,
-    totals: &'a FileCoverageSummary,
-    package: Option<&'a str>,
-    input_path: &'a Path,
-    handlebars: &'a Handlebars<'a>
+pub struct RenderIndex<'a> {
+    model: &'a ReportModel,
+    ctx: &'a RenderContext<'a>,
+    history: Option<(&'a History, usize)>,
+    pages: Option<HashSet<String>>,
+    links: HashMap<String, String>,
+    tabs: Vec<Tab>,
+    directories: bool,
 }
 
-#[derive(Serialize)]
+/// One of the views of a report built from several labeled inputs.
+#[derive(Clone, Serialize, JsonSchema)]
+pub struct Tab {
+    pub label: String,
+    pub href: String,
+    pub active: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
 struct FileEntry<'a> {
     name: &'a str,
-    link: String,
+    link: Option<String>,
+    sparkline: Option<String>,
     pub lines_count: u64,
     pub lines_covered: u64,
     pub lines_percent: String,
@@ -33,67 +47,292 @@ struct FileEntry<'a> {
     pub functions_percent_n: String,
     pub functions_percent_d: String,
     pub function_hit_class: &'a str,
+
+    /// Monomorphizations of generic functions, and other functions.
+    pub instantiations_count: u64,
+    pub instantiations_covered: u64,
+    pub instantiations_percent: String,
+    pub instantiations_percent_n: String,
+    pub instantiations_percent_d: String,
+    /// Against the function goal.
+    pub instantiation_hit_class: &'a str,
+}
+
+/// Line coverage of recent or older code, from `--commit-age`.
+#[derive(Serialize, JsonSchema)]
+struct AgeEntry<'a> {
+    label: String,
+    covered: u64,
+    count: u64,
+    percent: String,
+    percent_n: String,
+    percent_d: String,
+    hit_class: &'a str,
+}
+
+/// A headline percentage of the totals.
+#[derive(Serialize, JsonSchema)]
+struct Gauge {
+    label: &'static str,
+    covered: u64,
+    count: u64,
+    percent: String,
+    /// `red`, `yellow`, or `green` against the goal for the metric.
+    hit_class: &'static str,
+    /// Inline SVG ring of the percentage, to be included as is.
+    chart: String,
 }
 
-#[derive(Serialize)]
+/// Function coverage of the public API, from `--public-api`.
+#[derive(Serialize, JsonSchema)]
+struct PublicApiEntry {
+    functions_instrumented: u64,
+    functions_hit: u64,
+    functions_hit_percent: String,
+}
+
+/// Line coverage of the changed lines, from `--diff-base`.
+#[derive(Serialize, JsonSchema)]
+struct DiffEntry<'a> {
+    range: &'a str,
+    lines_instrumented: u64,
+    lines_hit: u64,
+    lines_hit_percent: String,
+    hit_class: &'static str,
+}
+
+/// What the `index` template is rendered with.
+#[derive(Serialize, JsonSchema)]
 struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
     title: String,
+    /// The headline percentages in a sentence, for the description and
+    /// Open Graph tags that chat apps show when a link is pasted.
+    description: String,
+    view: Option<&'a str>,
+    tabs: &'a [Tab],
+    page_style: Asset,
+    /// Sorts and filters the file summary.
+    page_script: Asset,
     input_mtime: String,
+    warnings: usize,
     total_line_hit_rate: String,
     total_func_hit_rate: String,
+    /// Lines, functions, and branches (if there are any) as rings.
+    gauges: Vec<Gauge>,
+    dropped_functions: usize,
+    /// Regions that ran in only some of the runs, with `--flaky`.
+    flaky_regions: usize,
+    public_api: Option<PublicApiEntry>,
+    diff: Option<DiffEntry<'a>>,
+    /// Top-level directories, with `--page-layout tree`.
+    directories: Vec<FileEntry<'a>>,
     files: Vec<FileEntry<'a>>,
+    commit_age: Vec<AgeEntry<'a>>,
+    dependencies: Vec<FileEntry<'a>>,
 }
 
 impl<'a> RenderIndex<'a> {
-    pub fn new(files: &'a Vec<&FileCoverage<'a>>, totals: &'a FileCoverageSummary, package: Option<&'a str>, input_path: &'a Path, handlebars: &'a Handlebars<'a>) -> Self {
+    pub fn new(model: &'a ReportModel, ctx: &'a RenderContext<'a>) -> Self {
         Self {
-            files, totals, package, input_path, handlebars
+            model, ctx, history: None, pages: None, links: HashMap::new(), tabs: vec![], directories: false
         }
     }
 
-    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+    /// Render a sparkline of the last `runs` runs next to each file.
+    pub fn history(mut self, history: &'a History, runs: usize) -> Self {
+        self.history = Some((history, runs));
+        self
+    }
 
-        let input_mtime : DateTime<Local> = metadata(self.input_path)?.modified()?.into();
+    /// Only link to the given file pages, e.g. when only some were rendered.
+    pub fn pages(mut self, pages: HashSet<String>) -> Self {
+        self.pages = Some(pages);
+        self
+    }
+
+    /// Link files (by their name in the export) to these pages instead of
+    /// their own, e.g. to share pages between views.
+    pub fn links(mut self, links: HashMap<String, String>) -> Self {
+        self.links = links;
+        self
+    }
+
+    /// List the top-level directories too, linking to their pages, which
+    /// there are with `--page-layout tree`.
+    pub fn directories(mut self) -> Self {
+        self.directories = true;
+        self
+    }
+
+    /// Tabs to switch between the views of a labeled report.
+    pub fn tabs(mut self, tabs: Vec<Tab>) -> Self {
+        self.tabs = tabs;
+        self
+    }
+
+    /// JSON schema of what the `index` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let model = self.model;
 
         let context = Context {
-            title: match self.package {
+            page: self.ctx.page(model.metadata.package.as_deref(), Title::report("Index", model)),
+            title: match model.metadata.package.as_ref() {
                 Some(package) => format!("Code Coverage for {}", package),
-                None => format!("Code Coverage Report")
+                None => "Code Coverage Report".to_string()
+            },
+            description: self.description(),
+            view: self.tabs.iter().find(|tab| tab.active).map(|tab| tab.label.as_str()),
+            tabs: &self.tabs,
+            page_style: self.ctx.assets.stylesheet("index", self.ctx.templates.render("index_css", &())?),
+            page_script: self.ctx.assets.script("index", self.ctx.templates.render("index_js", &())?),
+            input_mtime: model.metadata.input_mtime.to_rfc3339(),
+            warnings: self.ctx.diagnostics.len(),
+            total_line_hit_rate: format!("{:.1}", model.totals.lines.percent),
+            total_func_hit_rate: format!("{:.1}", model.totals.functions.percent),
+            gauges: self.gauges(),
+            dropped_functions: model.dropped_functions.len(),
+            flaky_regions: model.flaky.as_ref().map_or(0, |flaky| flaky.regions.len()),
+            public_api: model.public_api.as_ref().map(|public_api| PublicApiEntry {
+                functions_instrumented: public_api.summary.count,
+                functions_hit: public_api.summary.covered,
+                functions_hit_percent: format!("{:.1}", public_api.summary.percent),
+            }),
+            diff: model.diff.as_ref().map(|diff| DiffEntry {
+                range: &diff.range,
+                lines_instrumented: diff.summary.count,
+                lines_hit: diff.summary.covered,
+                lines_hit_percent: format!("{:.1}", diff.summary.percent),
+                hit_class: self.ctx.goals.lines.color(diff.summary.percent),
+            }),
+            directories: match self.directories {
+                true => directory::entries(model, "").0
+                    .iter()
+                    .map(|d| self.entry(d.name, Some(self.ctx.link(&d.page())), None, &d.summary))
+                    .collect(),
+                false => vec![],
+            },
+            files: model.files.iter().map(|f| self.file_entry(f)).collect(),
+            commit_age: match model.commit_age.as_ref() {
+                Some(age) => vec![
+                    self.age_entry(format!("Written in the last {} days", age.days), &age.recent),
+                    self.age_entry("Older".to_string(), &age.older),
+                ],
+                None => vec![],
             },
-            input_mtime: input_mtime.to_rfc3339(),
-            total_line_hit_rate: format!("{:.1}", self.totals.lines.percent),
-            total_func_hit_rate: format!("{:.1}", self.totals.functions.percent),
-            files: self.files
-                .iter()
-                .map(|f| {
-                    let lines_percent = format!("{:.1}", f.summary.lines.percent);
-                    let lines_percent_vec = lines_percent.splitn(2, ".").into_iter().collect::<Vec<_>>();
-
-                    let functions_percent = format!("{:.1}", f.summary.functions.percent);
-                    let funcs_percent_vec = functions_percent.splitn(2, ".").into_iter().collect::<Vec<_>>();
-
-                    FileEntry {
-                        name: f.filename,
-                        link: utils::sanitize_filename(f.filename),
-
-                        lines_count: f.summary.lines.count,
-                        lines_covered: f.summary.lines.covered,
-                        lines_percent_n: lines_percent_vec[0].into(),
-                        lines_percent_d: lines_percent_vec[1].into(),
-                        lines_percent,
-                        line_hit_class: utils::color_for_percent(f.summary.lines.percent),
-
-                        functions_count: f.summary.functions.count,
-                        functions_covered: f.summary.functions.covered,
-                        functions_percent_n: funcs_percent_vec[0].into(),
-                        functions_percent_d: funcs_percent_vec[1].into(),
-                        functions_percent,
-                        function_hit_class: utils::color_for_percent(f.summary.functions.percent),
-                    }
-                })
-                .collect(),
+            dependencies: model.dependencies.iter().map(|f| self.file_entry(f)).collect(),
         };
 
-        self.handlebars.render("index", &context).map_err(|e| e.into())
+        self.ctx.templates.render("index", &context)
+    }
+
+    /// E.g. "67.6% of lines (23/34) and 62.5% of functions (5/8) covered".
+    fn description(&self) -> String {
+        let totals = &self.model.totals;
+        let mut metrics = vec![("lines", &totals.lines), ("functions", &totals.functions)];
+        if totals.branches.count > 0 {
+            metrics.push(("branches", &totals.branches));
+        }
+
+        let mut metrics = metrics
+            .into_iter()
+            .map(|(metric, summary)| format!("{:.1}% of {} ({}/{})", summary.percent, metric, summary.covered, summary.count))
+            .collect::<Vec<_>>();
+        let last = metrics.pop().unwrap();
+        format!("{} and {} covered", metrics.join(", "), last)
+    }
+
+    fn gauges(&self) -> Vec<Gauge> {
+        let totals = &self.model.totals;
+        let goals = self.ctx.goals;
+        let mut metrics = vec![("Lines", &totals.lines, &goals.lines), ("Functions", &totals.functions, &goals.functions)];
+        if totals.branches.count > 0 {
+            metrics.push(("Branches", &totals.branches, &goals.branches));
+        }
+
+        metrics
+            .into_iter()
+            .map(|(label, summary, goal)| {
+                let hit_class = goal.color(summary.percent);
+                Gauge {
+                    label,
+                    covered: summary.covered,
+                    count: summary.count,
+                    percent: format!("{:.1}", summary.percent),
+                    hit_class,
+                    chart: chart::donut(summary.percent, hit_class),
+                }
+            })
+            .collect()
+    }
+
+    fn age_entry(&self, label: String, summary: &Summary) -> AgeEntry<'a> {
+        let percent = format!("{:.1}", summary.percent);
+        let (percent_n, percent_d) = percent.split_once('.').unwrap_or((&percent, ""));
+
+        AgeEntry {
+            label,
+            covered: summary.covered,
+            count: summary.count,
+            percent_n: percent_n.into(),
+            percent_d: percent_d.into(),
+            hit_class: self.ctx.goals.lines.color(summary.percent),
+            percent,
+        }
+    }
+
+    fn file_entry(&self, f: &'a FileModel) -> FileEntry<'a> {
+        let sparkline = self.history.and_then(|(history, runs)| {
+            chart::sparkline(&history.file_lines_percent(&f.name, runs))
+        });
+
+        let link = Some(self.links.get(&f.filename).unwrap_or(&f.page))
+            .filter(|page| self.pages.as_ref().is_none_or(|pages| pages.contains(*page)))
+            .map(|page| self.ctx.link(page));
+
+        self.entry(&f.name, link, sparkline, &f.summary)
+    }
+
+    fn entry(&self, name: &'a str, link: Option<String>, sparkline: Option<String>, summary: &FileCoverageSummary) -> FileEntry<'a> {
+        let lines_percent = format!("{:.1}", summary.lines.percent);
+        let lines_percent_vec = lines_percent.splitn(2, '.').collect::<Vec<_>>();
+
+        let functions_percent = format!("{:.1}", summary.functions.percent);
+        let funcs_percent_vec = functions_percent.splitn(2, '.').collect::<Vec<_>>();
+
+        let instantiations_percent = format!("{:.1}", summary.instantiations.percent);
+        let insts_percent_vec = instantiations_percent.splitn(2, '.').collect::<Vec<_>>();
+
+        FileEntry {
+            name,
+            link,
+            sparkline,
+
+            lines_count: summary.lines.count,
+            lines_covered: summary.lines.covered,
+            lines_percent_n: lines_percent_vec[0].into(),
+            lines_percent_d: lines_percent_vec[1].into(),
+            lines_percent,
+            line_hit_class: self.ctx.goals.lines.color(summary.lines.percent),
+
+            functions_count: summary.functions.count,
+            functions_covered: summary.functions.covered,
+            functions_percent_n: funcs_percent_vec[0].into(),
+            functions_percent_d: funcs_percent_vec[1].into(),
+            functions_percent,
+            function_hit_class: self.ctx.goals.functions.color(summary.functions.percent),
+
+            instantiations_count: summary.instantiations.count,
+            instantiations_covered: summary.instantiations.covered,
+            instantiations_percent_n: insts_percent_vec[0].into(),
+            instantiations_percent_d: insts_percent_vec[1].into(),
+            instantiations_percent,
+            instantiation_hit_class: self.ctx.goals.functions.color(summary.instantiations.percent),
+        }
     }
 }
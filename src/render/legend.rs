@@ -0,0 +1,89 @@
+use std::error::Error as StdError;
+
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Serialize;
+
+use crate::check::{Level, Metric, Threshold};
+use crate::model::ReportModel;
+use super::{PageContext, RenderContext, Title};
+
+/// Explains what the colors and numbers in the report mean, using the
+/// settings the report was generated with.
+pub struct RenderLegend<'a> {
+    model: &'a ReportModel,
+    ctx: &'a RenderContext<'a>,
+    thresholds: &'a [Threshold],
+}
+
+#[derive(Serialize, JsonSchema)]
+struct GoalEntry {
+    metric: String,
+    medium: String,
+    high: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+struct ThresholdEntry {
+    metric: String,
+    minimum: String,
+    warn: bool,
+}
+
+/// What the `legend` template is rendered with.
+#[derive(Serialize, JsonSchema)]
+struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
+    package: Option<&'a str>,
+    goals: Vec<GoalEntry>,
+    thresholds: Vec<ThresholdEntry>,
+    /// What code that ran looks like in the palette used, e.g. `green`.
+    covered_color: &'static str,
+    /// What code that never ran looks like, e.g. `red`.
+    uncovered_color: &'static str,
+}
+
+impl<'a> RenderLegend<'a> {
+    pub fn new(model: &'a ReportModel, ctx: &'a RenderContext<'a>) -> Self {
+        Self {
+            model, ctx, thresholds: &[]
+        }
+    }
+
+    /// The `--fail-under-*` thresholds the run was checked against.
+    pub fn thresholds(mut self, thresholds: &'a [Threshold]) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// JSON schema of what the `legend` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let goals = [Metric::Lines, Metric::Functions, Metric::Branches]
+            .iter()
+            .map(|metric| {
+                let goal = self.ctx.goals.get(*metric);
+                GoalEntry { metric: metric.to_string(), medium: format!("{}", goal.medium), high: format!("{}", goal.high) }
+            })
+            .collect();
+
+        let thresholds = self.thresholds
+            .iter()
+            .map(|t| ThresholdEntry { metric: t.metric.to_string(), minimum: format!("{}", t.minimum), warn: t.level == Level::Warn })
+            .collect();
+
+        let context = Context {
+            page: self.ctx.page(self.model.metadata.package.as_deref(), Title::report("Legend", self.model)),
+            package: self.model.metadata.package.as_deref(),
+            goals,
+            thresholds,
+            covered_color: self.ctx.palette.covered(),
+            uncovered_color: self.ctx.palette.uncovered(),
+        };
+
+        self.ctx.templates.render("legend", &context)
+    }
+}
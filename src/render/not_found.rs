@@ -0,0 +1,47 @@
+use std::error::Error as StdError;
+
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Serialize;
+
+use crate::model::ReportModel;
+use super::{PageContext, RenderContext, Title};
+
+/// The page static hosts serve for missing paths.  It can be served from any
+/// depth, so every link is absolute: under `--base-url` if one was given,
+/// otherwise from the root of the site.
+pub struct RenderNotFound<'a> {
+    model: &'a ReportModel,
+    ctx: &'a RenderContext<'a>,
+}
+
+/// What the `not_found` template is rendered with.
+#[derive(Serialize, JsonSchema)]
+struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
+    package: Option<&'a str>,
+    base: &'a str,
+}
+
+impl<'a> RenderNotFound<'a> {
+    pub fn new(model: &'a ReportModel, ctx: &'a RenderContext<'a>) -> Self {
+        Self {
+            model, ctx
+        }
+    }
+
+    /// JSON schema of what the `not_found` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let context = Context {
+            page: self.ctx.page(self.model.metadata.package.as_deref(), Title::report("Page Not Found", self.model)),
+            package: self.model.metadata.package.as_deref(),
+            base: self.ctx.hosting.absolute_root(),
+        };
+
+        self.ctx.templates.render("not_found", &context)
+    }
+}
@@ -0,0 +1,54 @@
+use std::error::Error as StdError;
+
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Serialize;
+
+use crate::model::ReportModel;
+use super::{PageContext, RenderContext, Title};
+
+/// What the `omitted` template is rendered with.
+#[derive(Serialize, JsonSchema)]
+struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
+    package: Option<&'a str>,
+    /// How many file pages the preview has.
+    rendered: usize,
+    /// How many files the report has.
+    files: usize,
+}
+
+/// Where the links to pages left out of a `--preview` lead, saying why
+/// they're missing.
+pub struct RenderOmitted<'a> {
+    model: &'a ReportModel,
+    ctx: &'a RenderContext<'a>,
+    rendered: usize,
+}
+
+impl<'a> RenderOmitted<'a> {
+    pub const PAGE: &'static str = "omitted.html";
+
+    /// `rendered` is the number of file pages the preview does have.
+    pub fn new(model: &'a ReportModel, ctx: &'a RenderContext<'a>, rendered: usize) -> Self {
+        Self {
+            model, ctx, rendered
+        }
+    }
+
+    /// JSON schema of what the `omitted` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let context = Context {
+            page: self.ctx.page(self.model.metadata.package.as_deref(), Title::report("Omitted From Preview", self.model)),
+            package: self.model.metadata.package.as_deref(),
+            rendered: self.rendered,
+            files: self.model.all_files().count(),
+        };
+
+        self.ctx.templates.render("omitted", &context)
+    }
+}
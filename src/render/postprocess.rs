@@ -0,0 +1,81 @@
+use std::error::Error as StdError;
+
+use regex::Regex;
+use serde::Deserialize;
+
+/// Transforms the HTML of each source line on the file pages, after it's
+/// been escaped and the region spans have been added.  File pages are
+/// rendered from several threads at once.
+pub trait PostProcessor: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn process(&self, line_html: &str) -> String;
+}
+
+/// Applies `f` to the text between tags of `html`, leaving the tags alone so
+/// nothing matches inside an attribute.
+pub fn map_text<F: Fn(&str) -> String>(html: &str, f: F) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&f(&rest[..start]));
+        let end = rest[start..].find('>').map_or(rest.len(), |end| start + end + 1);
+        out.push_str(&rest[start..end]);
+        rest = &rest[end..];
+    }
+    out.push_str(&f(rest));
+    out
+}
+
+/// Turns references like `TODO(issue-123)` into links.  The URL can refer to
+/// the pattern's capture groups as `$1` or `${name}`.
+pub struct Linkify {
+    pattern: Regex,
+    url: String,
+}
+
+impl Linkify {
+    pub fn new(pattern: &str, url: &str) -> Result<Self, Box<dyn StdError>> {
+        Ok(Self { pattern: Regex::new(pattern)?, url: url.to_string() })
+    }
+}
+
+impl PostProcessor for Linkify {
+    fn name(&self) -> &str {
+        "linkify"
+    }
+
+    fn process(&self, line_html: &str) -> String {
+        map_text(line_html, |text| {
+            self.pattern
+                .replace_all(text, |caps: &regex::Captures| {
+                    let mut url = String::new();
+                    caps.expand(&self.url, &mut url);
+                    format!(r#"<a href="{}">{}</a>"#, url.replace('"', "&quot;"), &caps[0])
+                })
+                .into_owned()
+        })
+    }
+}
+
+/// A post-processor as configured in the config file, e.g.
+///
+/// ```toml
+/// [[postprocess]]
+/// kind = "linkify"
+/// pattern = 'TODO\(issue-(\d+)\)'
+/// url = "https://github.com/org/repo/issues/$1"
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum PostProcessorConfig {
+    Linkify { pattern: String, url: String },
+}
+
+impl PostProcessorConfig {
+    pub fn build(&self) -> Result<Box<dyn PostProcessor>, Box<dyn StdError>> {
+        Ok(match self {
+            PostProcessorConfig::Linkify { pattern, url } => Box::new(Linkify::new(pattern, url)?),
+        })
+    }
+}
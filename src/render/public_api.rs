@@ -0,0 +1,70 @@
+use std::error::Error as StdError;
+
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Serialize;
+
+use crate::model::ReportModel;
+use super::{PageContext, RenderContext, Title};
+
+#[derive(Serialize, JsonSchema)]
+struct Item<'a> {
+    pub name: &'a str,
+    pub file: &'a str,
+    pub line: i64,
+    /// Whether any function was instrumented for the item.
+    pub instrumented: bool,
+    pub count: i64,
+}
+
+/// What the `public_api` template is rendered with.
+#[derive(Serialize, JsonSchema)]
+struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
+    package: Option<&'a str>,
+    functions_instrumented: u64,
+    functions_hit: u64,
+    functions_hit_percent: String,
+    /// `red`, `yellow`, or `green` against the function goal.
+    hit_class: &'static str,
+    items: Vec<Item<'a>>,
+}
+
+/// Lists the public functions and whether they ran, only rendered with
+/// `--public-api`.
+pub struct RenderPublicApi<'a> {
+    model: &'a ReportModel,
+    ctx: &'a RenderContext<'a>,
+}
+
+impl<'a> RenderPublicApi<'a> {
+    pub fn new(model: &'a ReportModel, ctx: &'a RenderContext<'a>) -> Self {
+        Self {
+            model, ctx
+        }
+    }
+
+    /// JSON schema of what the `public_api` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let public_api = self.model.public_api.as_ref().ok_or("no public API to render")?;
+
+        let context = Context {
+            page: self.ctx.page(self.model.metadata.package.as_deref(), Title::report("Public API Coverage", self.model)),
+            package: self.model.metadata.package.as_deref(),
+            functions_instrumented: public_api.summary.count,
+            functions_hit: public_api.summary.covered,
+            functions_hit_percent: format!("{:.1}", public_api.summary.percent),
+            hit_class: self.ctx.goals.functions.color(public_api.summary.percent),
+            items: public_api.items
+                .iter()
+                .map(|item| Item { name: &item.name, file: &item.file, line: item.line, instrumented: item.count.is_some(), count: item.count.unwrap_or(0) })
+                .collect(),
+        };
+
+        self.ctx.templates.render("public_api", &context)
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error as StdError;
+
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Serialize;
+
+use crate::model::{FileModel, FunctionModel, ReportModel};
+use super::{Asset, PageContext, RenderContext, RenderFunctionPage, RenderOmitted, Title};
+
+/// What the `search` template is rendered with.
+#[derive(Serialize, JsonSchema)]
+struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
+    package: Option<&'a str>,
+    /// Name of the script defining the search index, next to the page.
+    index: &'static str,
+    page_script: Asset,
+}
+
+#[derive(Serialize)]
+struct SearchFile<'a> {
+    name: &'a str,
+    /// Missing if the file has no page, e.g. when none was written for it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link: Option<String>,
+    lines_hit_percent: String,
+}
+
+#[derive(Serialize)]
+struct SearchFunction<'a> {
+    /// Demangled name.
+    name: &'a str,
+    /// Mangled name.
+    symbol: &'a str,
+    link: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<&'a str>,
+    /// First line of the function, to link to on the file page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<i64>,
+    count: i64,
+}
+
+#[derive(Serialize)]
+struct SearchIndex<'a> {
+    files: Vec<SearchFile<'a>>,
+    functions: Vec<SearchFunction<'a>>,
+}
+
+/// A page to find files, functions (by their demangled or mangled name),
+/// and lines (as `path:line`) across the report.  It needs no server, the
+/// index is a script defining `window.searchIndex` that the page loads.
+pub struct RenderSearch<'a> {
+    model: &'a ReportModel,
+    ctx: &'a RenderContext<'a>,
+    pages: Option<HashSet<String>>,
+    links: HashMap<String, String>,
+    function_pages: bool,
+}
+
+impl<'a> RenderSearch<'a> {
+    pub const PAGE: &'static str = "search.html";
+    pub const INDEX: &'static str = "search-index.js";
+
+    pub fn new(model: &'a ReportModel, ctx: &'a RenderContext<'a>) -> Self {
+        Self {
+            model, ctx, pages: None, links: HashMap::new(), function_pages: true
+        }
+    }
+
+    /// Only link to the given file pages, e.g. when only some were rendered.
+    pub fn pages(mut self, pages: HashSet<String>) -> Self {
+        self.pages = Some(pages);
+        self
+    }
+
+    /// Link files (by their name in the export) to these pages instead of
+    /// their own.
+    pub fn links(mut self, links: HashMap<String, String>) -> Self {
+        self.links = links;
+        self
+    }
+
+    /// Link every function to the page saying it was left out of the
+    /// preview instead of to its own.
+    pub fn omit_function_pages(mut self) -> Self {
+        self.function_pages = false;
+        self
+    }
+
+    /// JSON schema of what the `search` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
+    fn file_page<'b>(&'b self, file: &'b FileModel) -> Option<&'b str> {
+        Some(self.links.get(&file.filename).unwrap_or(&file.page))
+            .filter(|page| self.pages.as_ref().is_none_or(|pages| pages.contains(*page)))
+            .map(String::as_str)
+    }
+
+    fn function(&self, function: &'a FunctionModel) -> SearchFunction<'a> {
+        SearchFunction {
+            name: &function.name,
+            symbol: &function.symbol,
+            link: match self.function_pages {
+                true => self.ctx.link(&RenderFunctionPage::page(function)),
+                false => self.ctx.link(RenderOmitted::PAGE),
+            },
+            file: function.file.as_deref(),
+            line: function.regions.iter().filter(|r| r.file_id == 0).map(|r| r.line_start).min(),
+            count: function.count,
+        }
+    }
+
+    /// The search index, as a script for the page to load.
+    pub fn index(&self) -> Result<String, Box<dyn StdError>> {
+        let files = self.model
+            .all_files()
+            .map(|file| SearchFile {
+                name: &file.name,
+                link: self.file_page(file).map(|page| self.ctx.link(page)),
+                lines_hit_percent: format!("{:.1}", file.summary.lines.percent),
+            })
+            .collect();
+        let index = SearchIndex {
+            files,
+            functions: self.model.functions.iter().map(|f| self.function(f)).collect(),
+        };
+
+        Ok(format!("window.searchIndex = {};\n", serde_json::to_string(&index)?))
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let context = Context {
+            page: self.ctx.page(self.model.metadata.package.as_deref(), Title::report("Search", self.model)),
+            package: self.model.metadata.package.as_deref(),
+            index: Self::INDEX,
+            page_script: self.ctx.assets.script("search", self.ctx.templates.render("search_js", &())?),
+        };
+
+        self.ctx.templates.render("search", &context)
+    }
+}
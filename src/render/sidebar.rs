@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::FileCoverageSummary;
+use crate::check::Goals;
+use crate::model::ReportModel;
+use super::RenderContext;
+use super::directory::components;
+
+/// A directory or file of the sidebar, with the line coverage of everything
+/// under it.
+struct Entry {
+    name: String,
+    /// Relative to the index.  Directories only have pages of their own with
+    /// `--page-layout tree`.
+    page: Option<String>,
+    directory: bool,
+    percent: Option<String>,
+    hit_class: Option<&'static str>,
+    children: Vec<Entry>,
+}
+
+/// A directory while the tree is being built.
+#[derive(Default)]
+struct Dir<'a> {
+    page: Option<String>,
+    dirs: BTreeMap<&'a str, Dir<'a>>,
+    files: Vec<Entry>,
+    summaries: Vec<&'a FileCoverageSummary>,
+}
+
+/// The tree of directories and files shown next to every page with
+/// `--sidebar`, built once and placed on each page by
+/// [`RenderContext::page`].
+pub struct Sidebar {
+    entries: Vec<Entry>,
+}
+
+/// A directory or file of the sidebar as the page templates see it.
+#[derive(Serialize, JsonSchema)]
+pub struct NavNode {
+    name: String,
+    /// Absent for directories without a page of their own.
+    link: Option<String>,
+    directory: bool,
+    /// Line coverage, absent when there's nothing to measure.
+    percent: Option<String>,
+    hit_class: Option<&'static str>,
+    /// Whether the page shown is under it, to expand it.
+    open: bool,
+    /// Whether it's the page shown.
+    current: bool,
+    /// Subdirectories first, then files, each by name.
+    children: Vec<NavNode>,
+}
+
+impl Sidebar {
+    pub fn new(model: &ReportModel, goals: &Goals) -> Self {
+        let mut root = Dir::default();
+        for file in model.files.iter() {
+            let names = components(file);
+            let pages: Vec<_> = file.page.split('/').collect();
+            let mut dir = &mut root;
+            for (depth, name) in names.iter().enumerate().take(names.len().saturating_sub(1)) {
+                dir = dir.dirs.entry(name).or_default();
+                if depth + 1 < pages.len() {
+                    dir.page = Some(format!("{}/index.html", pages[..=depth].join("/")));
+                }
+                dir.summaries.push(&file.summary);
+            }
+            let measured = file.summary.lines.count > 0;
+            dir.files.push(Entry {
+                name: names.last().map_or_else(|| file.name.clone(), |name| name.to_string()),
+                page: Some(file.page.clone()),
+                directory: false,
+                percent: measured.then(|| format!("{:.1}", file.summary.lines.percent)),
+                hit_class: measured.then(|| goals.lines.color(file.summary.lines.percent)),
+                children: vec![],
+            });
+        }
+
+        Self { entries: Self::children(root, goals) }
+    }
+
+    fn children(dir: Dir, goals: &Goals) -> Vec<Entry> {
+        let mut entries: Vec<_> = dir.dirs
+            .into_iter()
+            .map(|(name, mut dir)| {
+                let summary = FileCoverageSummary::total(std::mem::take(&mut dir.summaries).into_iter());
+                let measured = summary.lines.count > 0;
+                Entry {
+                    name: name.to_string(),
+                    page: dir.page.take(),
+                    directory: true,
+                    percent: measured.then(|| format!("{:.1}", summary.lines.percent)),
+                    hit_class: measured.then(|| goals.lines.color(summary.lines.percent)),
+                    children: Self::children(dir, goals),
+                }
+            })
+            .collect();
+        let mut files = dir.files;
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+        entries.extend(files);
+        entries
+    }
+
+    /// The tree as shown on the page `ctx` is at, expanded down to it.
+    pub fn nav(&self, ctx: &RenderContext) -> Vec<NavNode> {
+        Self::nodes(&self.entries, ctx)
+    }
+
+    fn nodes(entries: &[Entry], ctx: &RenderContext) -> Vec<NavNode> {
+        entries
+            .iter()
+            .map(|entry| {
+                let children = Self::nodes(&entry.children, ctx);
+                let current = entry.page.as_deref() == Some(ctx.page.as_str());
+                NavNode {
+                    name: entry.name.clone(),
+                    link: entry.page.as_deref().map(|page| ctx.link(page)),
+                    directory: entry.directory,
+                    percent: entry.percent.clone(),
+                    hit_class: entry.hit_class,
+                    open: current || children.iter().any(|child| child.open),
+                    current,
+                    children,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,137 @@
+use std::error::Error as StdError;
+
+use rayon::prelude::*;
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Serialize;
+
+use crate::model::{FileModel, ReportModel};
+use super::{PageContext, RenderContext, RenderFile, SourceLine, StyleContext, Title};
+
+#[derive(Serialize, JsonSchema)]
+struct File<'a> {
+    name: &'a str,
+    /// Id of the file's section, for links within the document.
+    anchor: String,
+    lines_instrumented: u64,
+    lines_hit: u64,
+    lines_hit_percent: String,
+    /// `red`, `yellow`, or `green` against the line goal.
+    hit_class: &'static str,
+    functions_instrumented: u64,
+    functions_hit: u64,
+    functions_hit_percent: String,
+    stale: Option<String>,
+    highlighted: bool,
+    /// Empty if the source couldn't be read.
+    contents: Vec<SourceLine>,
+    /// Why the source isn't shown, if it isn't.
+    error: Option<String>,
+}
+
+/// What the `single_file` template is rendered with.
+#[derive(Serialize, JsonSchema)]
+struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
+    package: Option<&'a str>,
+    /// `style.css` and the file page styles, to inline.
+    style: String,
+    lines_instrumented: u64,
+    lines_hit: u64,
+    lines_hit_percent: String,
+    hit_class: &'static str,
+    functions_instrumented: u64,
+    functions_hit: u64,
+    functions_hit_percent: String,
+    files: Vec<File<'a>>,
+}
+
+/// The whole report as one HTML document with the styles inlined and every
+/// file in a collapsed section, written with `--single-file`.
+pub struct RenderSingleFile<'a> {
+    model: &'a ReportModel,
+    ctx: &'a RenderContext<'a>,
+    stylesheet: String,
+}
+
+impl<'a> RenderSingleFile<'a> {
+    /// `stylesheet` is the `style.css` the pages would otherwise link to.
+    pub fn new(model: &'a ReportModel, ctx: &'a RenderContext<'a>, stylesheet: String) -> Self {
+        Self {
+            model, ctx, stylesheet
+        }
+    }
+
+    /// JSON schema of what the `single_file` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
+    /// Id of the section of `file`.
+    pub fn anchor(file: &FileModel) -> String {
+        format!("file-{}", file.page.trim_end_matches(".html"))
+    }
+
+    /// The section of `file`, and the length of its longest line.
+    fn file(&self, file: &'a FileModel) -> (File<'a>, usize) {
+        let annotated = RenderFile::new(self.model, file, self.ctx).annotate();
+        let summary = &file.summary;
+        let mut entry = File {
+            name: &file.name,
+            anchor: Self::anchor(file),
+            lines_instrumented: summary.lines.count,
+            lines_hit: summary.lines.covered,
+            lines_hit_percent: format!("{:.1}", summary.lines.percent),
+            hit_class: self.ctx.goals.lines.color(summary.lines.percent),
+            functions_instrumented: summary.functions.count,
+            functions_hit: summary.functions.covered,
+            functions_hit_percent: format!("{:.1}", summary.functions.percent),
+            stale: None,
+            highlighted: false,
+            contents: vec![],
+            error: None,
+        };
+        match annotated {
+            Ok(annotated) => {
+                entry.stale = annotated.stale;
+                entry.highlighted = annotated.highlighted;
+                entry.contents = annotated.contents;
+                (entry, annotated.max_line_len)
+            }
+            Err(e) => {
+                self.ctx.diagnostics.error(Some(&file.name), format!("No source embedded: {}", e));
+                entry.error = Some(e.to_string());
+                (entry, 0)
+            }
+        }
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let files = self.model.all_files().collect::<Vec<_>>();
+        let (files, widths): (Vec<_>, Vec<_>) = files.into_par_iter().map(|file| self.file(file)).unzip();
+
+        // One file style for every section, wide enough for the widest
+        let longest = files.iter().map(|file| file.contents.len()).max().unwrap_or(1).max(1);
+        let page_style = self.ctx.templates.render("file_css", &StyleContext {
+            max_line_len: widths.into_iter().max().unwrap_or(0),
+            line_count_width: ((longest as f64).log10() + 1_f64).floor() as usize,
+        })?;
+
+        let totals = &self.model.totals;
+        let context = Context {
+            page: self.ctx.page(self.model.metadata.package.as_deref(), Title::report("Index", self.model)),
+            package: self.model.metadata.package.as_deref(),
+            style: format!("{}\n{}", self.stylesheet, page_style),
+            lines_instrumented: totals.lines.count,
+            lines_hit: totals.lines.covered,
+            lines_hit_percent: format!("{:.1}", totals.lines.percent),
+            hit_class: self.ctx.goals.lines.color(totals.lines.percent),
+            functions_instrumented: totals.functions.count,
+            functions_hit: totals.functions.covered,
+            functions_hit_percent: format!("{:.1}", totals.functions.percent),
+            files,
+        };
+
+        self.ctx.templates.render("single_file", &context)
+    }
+}
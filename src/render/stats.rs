@@ -0,0 +1,101 @@
+use std::error::Error as StdError;
+
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Serialize;
+
+use crate::chart;
+use crate::model::ReportModel;
+use crate::stats::{self, Bucket};
+use super::{PageContext, RenderContext, Title};
+
+/// A histogram, as numbers and as a chart.
+#[derive(Serialize, JsonSchema)]
+struct Histogram {
+    /// Label and count of each bar.
+    buckets: Vec<(String, u64)>,
+    /// Inline SVG bar chart of the buckets, to be included as is.
+    chart: String,
+}
+
+impl From<Vec<Bucket>> for Histogram {
+    fn from(buckets: Vec<Bucket>) -> Self {
+        let bars = buckets.iter().map(|b| (b.label.as_str(), b.count)).collect::<Vec<_>>();
+        Self {
+            chart: chart::bars(&bars),
+            buckets: buckets.into_iter().map(|b| (b.label, b.count)).collect(),
+        }
+    }
+}
+
+/// Line coverage of the files last changed in one span of time, from
+/// `--file-age`.
+#[derive(Serialize, JsonSchema)]
+struct AgeEntry<'a> {
+    /// E.g. `This week`.
+    label: &'a str,
+    files: usize,
+    lines_instrumented: u64,
+    lines_hit: u64,
+    lines_hit_percent: String,
+    /// `red`, `yellow`, or `green` against the line goal.
+    hit_class: &'static str,
+}
+
+/// What the `stats` template is rendered with.
+#[derive(Serialize, JsonSchema)]
+struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
+    package: Option<&'a str>,
+    /// Code regions by how often they ran.
+    region_counts: Histogram,
+    /// Files by the decile of their line coverage.
+    file_coverage: Histogram,
+    /// Empty without `--file-age`.
+    file_age: Vec<AgeEntry<'a>>,
+}
+
+/// Histograms of execution counts and file coverage, and coverage by file
+/// age.
+pub struct RenderStats<'a> {
+    model: &'a ReportModel,
+    ctx: &'a RenderContext<'a>,
+}
+
+impl<'a> RenderStats<'a> {
+    pub fn new(model: &'a ReportModel, ctx: &'a RenderContext<'a>) -> Self {
+        Self {
+            model, ctx
+        }
+    }
+
+    /// JSON schema of what the `stats` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let statistics = stats::statistics(self.model);
+
+        let context = Context {
+            page: self.ctx.page(self.model.metadata.package.as_deref(), Title::report("Statistics", self.model)),
+            package: self.model.metadata.package.as_deref(),
+            region_counts: statistics.region_counts.into(),
+            file_coverage: statistics.file_coverage.into(),
+            file_age: self.model.file_age
+                .iter()
+                .flat_map(|age| age.buckets.iter())
+                .map(|bucket| AgeEntry {
+                    label: &bucket.label,
+                    files: bucket.files,
+                    lines_instrumented: bucket.lines.count,
+                    lines_hit: bucket.lines.covered,
+                    lines_hit_percent: format!("{:.1}", bucket.lines.percent),
+                    hit_class: self.ctx.goals.lines.color(bucket.lines.percent),
+                })
+                .collect(),
+        };
+
+        self.ctx.templates.render("stats", &context)
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use handlebars::Handlebars;
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::helpers::{self, Timezone};
+
+/// A template engine the pages can be rendered with.  Renderers go through
+/// [`Templates`], so every engine is handed exactly the same contexts.  Pages
+/// are rendered from several threads at once.
+pub trait TemplateEngine: Send + Sync {
+    fn has_template(&self, name: &str) -> bool;
+
+    fn render(&self, name: &str, context: &Value) -> Result<String, Box<dyn StdError>>;
+}
+
+impl TemplateEngine for Handlebars<'_> {
+    fn has_template(&self, name: &str) -> bool {
+        self.get_template(name).is_some()
+    }
+
+    fn render(&self, name: &str, context: &Value) -> Result<String, Box<dyn StdError>> {
+        Handlebars::render(self, name, context).map_err(|e| e.into())
+    }
+}
+
+#[cfg(feature = "minijinja")]
+impl TemplateEngine for minijinja::Environment<'_> {
+    fn has_template(&self, name: &str) -> bool {
+        self.get_template(name).is_ok()
+    }
+
+    fn render(&self, name: &str, context: &Value) -> Result<String, Box<dyn StdError>> {
+        self.get_template(name)?.render(context).map_err(|e| e.into())
+    }
+}
+
+/// Which engine custom templates are written for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Engine {
+    #[default]
+    Handlebars,
+    /// Jinja syntax, only available when built with the `minijinja` feature.
+    MiniJinja,
+}
+
+impl FromStr for Engine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "handlebars" => Ok(Engine::Handlebars),
+            "minijinja" if cfg!(feature = "minijinja") => Ok(Engine::MiniJinja),
+            "minijinja" => Err("cosmoline was built without the `minijinja' feature".to_string()),
+            _ => Err(format!("Unknown template engine `{}', expected handlebars or minijinja", s)),
+        }
+    }
+}
+
+/// The templates pages are rendered with: custom ones where there are any,
+/// and the built-in handlebars templates for everything else.  A page a
+/// custom template fails to render is rendered with the built-in one
+/// instead, so one broken template doesn't stop the whole report.
+pub struct Templates<'a> {
+    custom: Option<Box<dyn TemplateEngine + 'a>>,
+    builtin: Handlebars<'a>,
+    timezone: Timezone,
+    fallbacks: Mutex<BTreeMap<String, Fallback>>,
+    /// SHA-256 of the custom templates' names and sources.
+    fingerprint: String,
+}
+
+/// Pages a custom template failed to render, and why it failed first.
+#[derive(Clone, Debug)]
+pub struct Fallback {
+    pub error: String,
+    pub pages: usize,
+}
+
+impl<'a> Templates<'a> {
+    pub fn builtin() -> Result<Self, Box<dyn StdError>> {
+        Ok(Self {
+            custom: None,
+            builtin: super::handlebars()?,
+            timezone: Timezone::default(),
+            fallbacks: Mutex::new(BTreeMap::new()),
+            fingerprint: String::new(),
+        })
+    }
+
+    /// Show times in `timezone`.  Custom templates loaded after this get
+    /// it too.
+    pub fn timezone(mut self, timezone: Timezone) -> Self {
+        helpers::register(&mut self.builtin, timezone);
+        self.timezone = timezone;
+        self
+    }
+
+    /// Loads custom templates for `engine` from `dir`.  Every file is a
+    /// template named after its file name up to the first dot, so
+    /// `index.html.j2` replaces the `index` template.
+    pub fn custom_dir(mut self, dir: &Path, engine: Engine) -> Result<Self, Box<dyn StdError>> {
+        let mut templates = vec![];
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let name = match path.file_name().and_then(|n| n.to_str()).and_then(|n| n.split('.').next()) {
+                Some(name) if !name.is_empty() && path.is_file() => name.to_string(),
+                _ => continue,
+            };
+            debug!("Custom template {} from {}", name, path.display());
+            templates.push((name, std::fs::read_to_string(&path)?));
+        }
+        templates.sort();
+        self.fingerprint = crate::manifest::sha256(format!("{:?}", templates));
+
+        self.custom = Some(match engine {
+            Engine::Handlebars => {
+                let mut handlebars = Handlebars::new();
+                handlebars.set_prevent_indent(true);
+                helpers::register(&mut handlebars, self.timezone);
+                for (name, source) in templates {
+                    handlebars.register_template_string(&name, source)?;
+                }
+                Box::new(handlebars)
+            }
+            #[cfg(feature = "minijinja")]
+            Engine::MiniJinja => Box::new(minijinja(templates, self.timezone)?),
+            #[cfg(not(feature = "minijinja"))]
+            Engine::MiniJinja => return Err("cosmoline was built without the `minijinja' feature".into()),
+        });
+        Ok(self)
+    }
+
+    /// Changes whenever the custom templates do, and is empty without any,
+    /// so pages rendered with other templates can be told apart.
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    pub fn render<T: Serialize>(&self, name: &str, context: &T) -> Result<String, Box<dyn StdError>> {
+        let context = serde_json::to_value(context)?;
+        if let Some(custom) = self.custom.as_ref().filter(|custom| custom.has_template(name)) {
+            match custom.render(name, &context) {
+                Ok(output) => return Ok(output),
+                Err(e) => {
+                    let mut fallbacks = self.fallbacks.lock().unwrap_or_else(|e| e.into_inner());
+                    let fallback = fallbacks.entry(name.to_string()).or_insert_with(|| {
+                        warn!("Custom template {} failed, falling back to the built-in one: {}", name, e);
+                        Fallback { error: e.to_string(), pages: 0 }
+                    });
+                    fallback.pages += 1;
+                }
+            }
+        }
+        TemplateEngine::render(&self.builtin, name, &context)
+    }
+
+    /// Custom templates that failed to render a page so far, by name.
+    pub fn fallbacks(&self) -> BTreeMap<String, Fallback> {
+        self.fallbacks.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// The same helpers as the handlebars templates have, as filters:
+/// `{{ generated|strftime("%Y") }}`, `{{ generated|relative_time }}`,
+/// `{{ generated|iso_week }}`, `{{ seconds|duration }}`, and
+/// `{{ count|human_count }}`.
+#[cfg(feature = "minijinja")]
+fn minijinja(templates: Vec<(String, String)>, timezone: Timezone) -> Result<minijinja::Environment<'static>, Box<dyn StdError>> {
+    use minijinja::{AutoEscape, Environment, Error, ErrorKind, State};
+
+    let mut env = Environment::new();
+    // Like handlebars, escape everything regardless of the template name.
+    env.set_auto_escape_callback(|_| AutoEscape::Html);
+
+    let invalid = |e: String| Error::new(ErrorKind::InvalidArguments, e);
+    env.add_filter("strftime", move |_: &State, time: String, format: String| -> Result<String, Error> {
+        helpers::strftime(&time, &format, timezone).map_err(invalid)
+    });
+    env.add_filter("relative_time", move |_: &State, time: String, since: Option<String>| -> Result<String, Error> {
+        let now = match since {
+            Some(since) => chrono::DateTime::parse_from_rfc3339(&since)
+                .map_err(|e| Error::new(ErrorKind::InvalidArguments, "since isn't RFC 3339").with_source(e))?,
+            None => chrono::Utc::now().into(),
+        };
+        helpers::relative_time(&time, now).map_err(invalid)
+    });
+    env.add_filter("iso_week", move |_: &State, time: String| -> Result<String, Error> {
+        helpers::iso_week(&time, timezone).map_err(invalid)
+    });
+    env.add_filter("duration", |_: &State, seconds: f64| -> Result<String, Error> {
+        Ok(helpers::duration(seconds))
+    });
+    env.add_filter("human_count", |_: &State, count: i64| -> Result<String, Error> {
+        Ok(crate::utils::human_count(count))
+    });
+
+    // Templates are loaded once and used until the process exits, so rather
+    // than tying the environment to their lifetime they're leaked.
+    for (name, template) in templates {
+        env.add_template(Box::leak(name.into_boxed_str()), Box::leak(template.into_boxed_str()))?;
+    }
+
+    Ok(env)
+}
@@ -0,0 +1,90 @@
+use std::error::Error as StdError;
+
+use serde::Deserialize;
+
+use crate::model::{FileModel, ReportModel};
+
+const PLACEHOLDERS: [&str; 4] = ["package", "page", "file", "percent"];
+
+/// Format strings for the title and the breadcrumb (what follows the link
+/// back to the index in the header) of every page, as configured in the
+/// config file, e.g.
+///
+/// ```toml
+/// [titles]
+/// page = "{package}: {page} ({percent}%)"
+/// breadcrumb = "{page}"
+/// ```
+///
+/// `{package}` is the package name (or `Code Coverage Report`), `{page}`
+/// what the page shows (e.g. `Legend`, or the name of a file), `{file}` the
+/// name of the file on the pages of a file and empty elsewhere, and
+/// `{percent}` the line coverage of that file or of the whole report.  The
+/// templates keep their own for whichever isn't set.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Titles {
+    pub page: Option<String>,
+    pub breadcrumb: Option<String>,
+}
+
+/// What the placeholders stand for on one page.
+pub struct Title<'a> {
+    pub page: &'a str,
+    pub file: Option<&'a str>,
+    pub percent: f64,
+}
+
+impl<'a> Title<'a> {
+    /// A page about the whole report.
+    pub fn report(page: &'a str, model: &ReportModel) -> Self {
+        Self { page, file: None, percent: model.totals.lines.percent }
+    }
+
+    /// A page about one file.
+    pub fn file(page: &'a str, file: &'a FileModel) -> Self {
+        Self { page, file: Some(&file.name), percent: file.summary.lines.percent }
+    }
+}
+
+impl Titles {
+    /// Fails on placeholders other than the ones above, so a typo doesn't
+    /// end up in every title of the report.
+    pub fn validate(&self) -> Result<(), Box<dyn StdError>> {
+        for format in self.page.iter().chain(self.breadcrumb.iter()) {
+            let mut rest = format.as_str();
+            while let Some(start) = rest.find('{') {
+                let (name, after) = rest[start + 1..].split_once('}').ok_or_else(|| format!("Unclosed placeholder in title `{}'", format))?;
+                if !PLACEHOLDERS.contains(&name) {
+                    return Err(format!("Unknown placeholder `{{{}}}' in title `{}', expected one of {{{}}}", name, format, PLACEHOLDERS.join("}, {")).into());
+                }
+                rest = after;
+            }
+        }
+        Ok(())
+    }
+
+    /// The page title and breadcrumb of a page, `None` where the template's
+    /// own should be used.
+    pub fn format(&self, package: Option<&str>, title: &Title) -> (Option<String>, Option<String>) {
+        let percent = format!("{:.1}", title.percent);
+        let format = |format: &String| {
+            let mut formatted = String::new();
+            let mut rest = format.as_str();
+            while let Some((before, (name, after))) = rest.split_once('{').and_then(|(before, after)| Some((before, after.split_once('}')?))) {
+                formatted.push_str(before);
+                match name {
+                    "package" => formatted.push_str(package.unwrap_or("Code Coverage Report")),
+                    "page" => formatted.push_str(title.page),
+                    "file" => formatted.push_str(title.file.unwrap_or_default()),
+                    "percent" => formatted.push_str(&percent),
+                    _ => formatted.push_str(&format!("{{{}}}", name)),
+                }
+                rest = after;
+            }
+            formatted.push_str(rest);
+            formatted
+        };
+        (self.page.as_ref().map(format), self.breadcrumb.as_ref().map(format))
+    }
+}
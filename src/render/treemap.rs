@@ -0,0 +1,93 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error as StdError;
+
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Serialize;
+
+use crate::chart::{self, Tile};
+use crate::model::ReportModel;
+use crate::treemap::{self, Node};
+use super::{PageContext, RenderContext, Title};
+
+/// What the `treemap` template is rendered with.
+#[derive(Serialize, JsonSchema)]
+struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
+    package: Option<&'a str>,
+    /// Inline SVG of the directories and files, sized by their lines and
+    /// colored by line coverage, to be included as is.
+    treemap: String,
+}
+
+/// The directory hierarchy as a treemap, to show where the large uncovered
+/// parts of a workspace are.
+pub struct RenderTreemap<'a> {
+    model: &'a ReportModel,
+    ctx: &'a RenderContext<'a>,
+    pages: Option<HashSet<String>>,
+    links: HashMap<String, String>,
+}
+
+impl<'a> RenderTreemap<'a> {
+    pub fn new(model: &'a ReportModel, ctx: &'a RenderContext<'a>) -> Self {
+        Self {
+            model, ctx, pages: None, links: HashMap::new()
+        }
+    }
+
+    /// Only link to the given file pages, e.g. when only some were rendered.
+    /// The other files are left without a link.
+    pub fn pages(mut self, pages: HashSet<String>) -> Self {
+        self.pages = Some(pages);
+        self
+    }
+
+    /// Link files (by their name in the export) to these pages instead of
+    /// their own, e.g. to the page saying they were left out of a preview.
+    pub fn links(mut self, links: HashMap<String, String>) -> Self {
+        self.links = links;
+        self
+    }
+
+    /// JSON schema of what the `treemap` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
+    /// `links` are by the page of the file rather than its name, which the
+    /// tree doesn't keep.
+    fn tile(&self, node: Node, links: &HashMap<&str, &str>) -> Tile {
+        let title = match node.path.is_empty() {
+            true => "Total".to_string(),
+            false => node.path,
+        };
+        Tile {
+            label: node.name,
+            title: format!("{}: {} of {} lines, {:.1}%", title, node.lines.covered, node.lines.count, node.lines.percent),
+            size: node.lines.count,
+            class: self.ctx.goals.lines.color(node.lines.percent),
+            href: node.page
+                .map(|page| links.get(page.as_str()).map_or(page, |link| link.to_string()))
+                .filter(|page| self.pages.as_ref().is_none_or(|pages| pages.contains(page)))
+                .map(|page| self.ctx.link(&page)),
+            children: node.children.into_iter().map(|child| self.tile(child, links)).collect(),
+        }
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let links = self.model
+            .all_files()
+            .filter_map(|file| Some((file.page.as_str(), self.links.get(&file.filename)?.as_str())))
+            .collect();
+        let root = self.tile(treemap::hierarchy(self.model), &links);
+
+        let context = Context {
+            page: self.ctx.page(self.model.metadata.package.as_deref(), Title::report("Treemap", self.model)),
+            package: self.model.metadata.package.as_deref(),
+            treemap: chart::treemap(&root),
+        };
+
+        self.ctx.templates.render("treemap", &context)
+    }
+}
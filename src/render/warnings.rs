@@ -0,0 +1,46 @@
+use std::error::Error as StdError;
+
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Serialize;
+
+use crate::diagnostics::Bucket;
+use crate::model::ReportModel;
+use super::{PageContext, RenderContext, Title};
+
+/// Lists the problems found while generating the report, grouped by file.
+pub struct RenderWarnings<'a> {
+    model: &'a ReportModel,
+    ctx: &'a RenderContext<'a>,
+}
+
+/// What the `warnings` template is rendered with.
+#[derive(Serialize, JsonSchema)]
+struct Context<'a> {
+    #[serde(flatten)]
+    page: PageContext,
+    package: Option<&'a str>,
+    buckets: Vec<Bucket>,
+}
+
+impl<'a> RenderWarnings<'a> {
+    pub fn new(model: &'a ReportModel, ctx: &'a RenderContext<'a>) -> Self {
+        Self {
+            model, ctx
+        }
+    }
+
+    /// JSON schema of what the `warnings` template is rendered with.
+    pub fn context_schema() -> RootSchema {
+        schemars::schema_for!(Context)
+    }
+
+    pub fn render(&self) -> Result<String, Box<dyn StdError>> {
+        let context = Context {
+            page: self.ctx.page(self.model.metadata.package.as_deref(), Title::report("Warnings", self.model)),
+            package: self.model.metadata.package.as_deref(),
+            buckets: self.ctx.diagnostics.buckets(),
+        };
+
+        self.ctx.templates.render("warnings", &context)
+    }
+}
@@ -0,0 +1,168 @@
+//! The whole pipeline from an export to a report on disk, for build scripts
+//! and xtasks that want a report without going through the command line:
+//!
+//! ```no_run
+//! # #[cfg(not(feature = "render-html"))] fn main() {}
+//! # #[cfg(feature = "render-html")]
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use cosmoline::filter::Filter;
+//! use cosmoline::report::CoverageReport;
+//!
+//! let json = std::fs::read_to_string("target/coverage.json")?;
+//! let model = CoverageReport::from_llvm_json(&json)?
+//!     .builder()
+//!     .source_dir(".")
+//!     .output_dir("target/coverage")
+//!     .package_name("my-crate")
+//!     .filter(Filter::default().exclude(&["tests/**"])?)
+//!     .write_html()?;
+//! println!("{:.1}% of lines covered", model.totals.lines.percent);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Everything the `cosmoline` binary does beyond this is built from the
+//! [`model`](crate::model) and [`export`](crate::export) modules.
+
+use std::error::Error as StdError;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+
+use crate::CoverageMapping;
+use crate::anonymize::Anonymizer;
+use crate::filter::Filter;
+use crate::input::{InputFormat, LlvmJson};
+use crate::merge::{self, MergeMode};
+use crate::model::{ModelOptions, PageLayout, ReportModel};
+
+/// Coverage data read from one or more exports, merged.  Borrows from the
+/// text it was read from.
+pub struct CoverageReport<'a> {
+    mapping: CoverageMapping<'a>,
+}
+
+impl<'a> CoverageReport<'a> {
+    /// Reads the JSON written by `llvm-cov export`, merging the mappings of
+    /// every binary in it.
+    pub fn from_llvm_json(json: &'a str) -> Result<Self, Box<dyn StdError>> {
+        Ok(Self::from_mappings(LlvmJson.read(json)?, MergeMode::Sum))
+    }
+
+    /// Merges mappings read from any of the [input formats](crate::input).
+    pub fn from_mappings(mappings: Vec<CoverageMapping<'a>>, mode: MergeMode) -> Self {
+        Self { mapping: merge::combine(mappings, mode) }
+    }
+
+    pub fn mapping(&self) -> &CoverageMapping<'a> {
+        &self.mapping
+    }
+
+    pub fn builder(&self) -> ReportBuilder<'_, 'a> {
+        ReportBuilder::new(self)
+    }
+}
+
+/// Where a report goes and what's in it, with the same defaults as the
+/// command line.
+pub struct ReportBuilder<'r, 'a> {
+    report: &'r CoverageReport<'a>,
+    source_dir: PathBuf,
+    output_dir: PathBuf,
+    package: Option<String>,
+    filter: Filter,
+    page_layout: PageLayout,
+}
+
+impl<'r, 'a> ReportBuilder<'r, 'a> {
+    pub fn new(report: &'r CoverageReport<'a>) -> Self {
+        Self {
+            report,
+            source_dir: PathBuf::from("."),
+            output_dir: PathBuf::from("coverage"),
+            package: None,
+            filter: Filter::default(),
+            page_layout: PageLayout::default(),
+        }
+    }
+
+    /// Directory the paths in the export are relative to, the current one by
+    /// default.
+    pub fn source_dir(mut self, source_dir: impl Into<PathBuf>) -> Self {
+        self.source_dir = source_dir.into();
+        self
+    }
+
+    /// Where the report is written, `coverage` by default.  Made if it's
+    /// missing.
+    pub fn output_dir(mut self, output_dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = output_dir.into();
+        self
+    }
+
+    /// Shown in the header of every page.
+    pub fn package_name(mut self, package: &str) -> Self {
+        self.package = Some(package.to_string());
+        self
+    }
+
+    /// Which files are reported.
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// How the pages of the files are named.
+    pub fn page_layout(mut self, page_layout: PageLayout) -> Self {
+        self.page_layout = page_layout;
+        self
+    }
+
+    /// The model every output format is built from.
+    pub fn model(self) -> Result<ReportModel, Box<dyn StdError>> {
+        let options = ModelOptions {
+            package: self.package.as_deref(),
+            filter: self.filter,
+            page_layout: self.page_layout,
+            ..ModelOptions::default()
+        };
+        Ok(ReportModel::build(self.report.mapping(), &options, &Anonymizer::new(false, None), &self.source_dir)?)
+    }
+
+    /// Writes the HTML report, returning the model it was made from, e.g.
+    /// to check the totals against a threshold.
+    #[cfg(feature = "render-html")]
+    pub fn write_html(self) -> Result<ReportModel, Box<dyn StdError>> {
+        use crate::export::{Exporter, Html};
+        use crate::render::{Assets, Highlighter};
+
+        let (source_dir, output_dir) = (self.source_dir.clone(), self.output_dir.clone());
+        let model = self.model()?;
+        create_output_dir(&output_dir)?;
+        let anonymizer = Anonymizer::new(false, None);
+        Html::new(Assets::new(true, true), &anonymizer, &source_dir)?
+            .highlight(Highlighter::new())
+            .export(&model, &output_dir)?;
+        Ok(model)
+    }
+}
+
+/// Makes the output directory if it's missing.
+pub fn create_output_dir(output_path: &Path) -> io::Result<()> {
+    match output_path.exists() {
+        true => {
+            if output_path.is_dir() {
+                info!("Output directory exists at `{}'", output_path.display());
+            } else {
+                error!("Non-directory exists at output `{}'", output_path.display());
+            }
+        }
+        false => {
+            std::fs::create_dir_all(output_path)?;
+            info!("Created missing output directory `{}'", output_path.display());
+        }
+    }
+    Ok(())
+}
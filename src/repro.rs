@@ -0,0 +1,124 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::io;
+use std::path::Path;
+
+use clap::ArgMatches;
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+
+use crate::{stream_export, ExportEntry, FileCoverageSummary};
+use crate::utils;
+
+/// Cuts an `llvm-cov export` down to a single file: its file entry, the
+/// functions defined in it, and totals equal to the file's summary.  The file
+/// may be named as in the export or as shown in the report.  Returns the JSON
+/// of the cut down export.
+///
+/// Works on the raw JSON rather than our own structures so that fields we
+/// don't model (e.g. MC/DC records) survive into the reproducer.  The export
+/// is streamed, so only what's kept is ever held in memory however large the
+/// export is.
+pub fn extract(reader: impl io::Read, filename: &str) -> Result<String, Box<dyn StdError>> {
+    #[derive(Deserialize)]
+    struct File<'a> {
+        #[serde(borrow)]
+        filename: Cow<'a, str>,
+        summary: FileCoverageSummary,
+    }
+
+    #[derive(Deserialize)]
+    struct Function<'a> {
+        #[serde(borrow)]
+        filenames: Vec<Cow<'a, str>>,
+    }
+
+    #[derive(Serialize)]
+    struct Mapping {
+        files: Vec<Box<RawValue>>,
+        functions: Vec<Box<RawValue>>,
+        totals: FileCoverageSummary,
+    }
+
+    #[derive(Serialize)]
+    struct Export<'a> {
+        #[serde(rename = "type")]
+        report_type: &'a str,
+        version: &'a str,
+        data: Vec<Mapping>,
+    }
+
+    // Mappings with the file, and its name in the export, by index.  llvm
+    // writes the files of a mapping before its functions.
+    let mut mappings: BTreeMap<usize, (String, Mapping)> = BTreeMap::new();
+    let header = stream_export(reader, |index, entry| {
+        match entry {
+            ExportEntry::File(raw) => {
+                let file: File = serde_json::from_str(raw.get())?;
+                if file.filename != filename && utils::report_name(&file.filename) != filename {
+                    return Ok(());
+                }
+                let (_, mapping) = mappings.entry(index).or_insert_with(|| {
+                    let mapping = Mapping { files: vec![], functions: vec![], totals: file.summary };
+                    (file.filename.into_owned(), mapping)
+                });
+                mapping.files.push(raw.to_owned());
+            }
+            ExportEntry::Function(raw) => {
+                if let Some((exported, mapping)) = mappings.get_mut(&index) {
+                    let function: Function = serde_json::from_str(raw.get())?;
+                    if function.filenames.iter().any(|name| name == exported) {
+                        mapping.functions.push(raw.to_owned());
+                    }
+                }
+            }
+        }
+        Ok(())
+    })?;
+
+    if mappings.is_empty() {
+        return Err(format!("`{}' is not in the export", filename).into());
+    }
+    let export = Export {
+        report_type: &header.report_type,
+        version: &header.version,
+        data: mappings.into_values().map(|(_, mapping)| mapping).collect(),
+    };
+    Ok(serde_json::to_string(&export)?)
+}
+
+pub fn run(matches: &ArgMatches) -> Result<(), Box<dyn StdError>> {
+    let input_filename = matches.value_of("input").unwrap();
+    let filename = matches.value_of("file").unwrap();
+    let output_path = Path::new(matches.value_of("output").unwrap());
+
+    info!("Reading llvm JSON from: {}", input_filename);
+    let export = extract(std::fs::File::open(input_filename)?, filename)?;
+
+    std::fs::create_dir_all(output_path)?;
+    std::fs::write(output_path.join("coverage.json"), export)?;
+
+    if matches.is_present("with-source") {
+        let source_path = match matches.value_of("source-prefix") {
+            Some(prefix) => Path::new(prefix),
+            None => Path::new(input_filename).parent().unwrap(),
+        };
+        let destination = output_path.join(utils::report_name(filename));
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(source_path.join(filename), &destination)?;
+    }
+
+    println!("Reproducer written to {}", output_path.display());
+    println!(
+        "Check it with: cosmoline -i {} -p {} -o <report>",
+        output_path.join("coverage.json").display(),
+        output_path.display(),
+    );
+
+    Ok(())
+}
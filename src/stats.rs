@@ -0,0 +1,66 @@
+//! How execution counts and coverage are distributed across the report, for
+//! the statistics page.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::model::ReportModel;
+
+const CODE_REGION: i64 = 0;
+
+/// Upper bounds (inclusive) of the execution count buckets, the last one
+/// taking everything above.
+const COUNT_BUCKETS: [(i64, &str); 7] = [
+    (0, "0"),
+    (1, "1"),
+    (9, "2-9"),
+    (99, "10-99"),
+    (999, "100-999"),
+    (9_999, "1000-9999"),
+    (i64::MAX, "10000+"),
+];
+
+#[derive(Debug, Serialize)]
+pub struct Bucket {
+    pub label: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Statistics {
+    /// Code regions of the reported files by how often they ran.
+    pub region_counts: Vec<Bucket>,
+    /// Reported files by the decile of their line coverage, 100% counting
+    /// as 90-100%.  Files without instrumented lines are left out.
+    pub file_coverage: Vec<Bucket>,
+}
+
+pub fn statistics(model: &ReportModel) -> Statistics {
+    // Instantiations of the same generic share their regions
+    let mut regions: BTreeMap<(&str, i64, i64, i64, i64), i64> = BTreeMap::new();
+    for function in model.functions.iter() {
+        let file = match function.file.as_deref() {
+            Some(file) if model.files.iter().any(|f| f.name == file) => file,
+            _ => continue,
+        };
+        for region in function.regions.iter().filter(|r| r.region_kind == CODE_REGION && r.file_id == 0) {
+            let key = (file, region.line_start, region.column_start, region.line_end, region.column_end);
+            *regions.entry(key).or_default() += region.execution_count;
+        }
+    }
+
+    let mut region_counts: Vec<_> = COUNT_BUCKETS.iter().map(|(_, label)| Bucket { label: label.to_string(), count: 0 }).collect();
+    for count in regions.values() {
+        let idx = COUNT_BUCKETS.iter().position(|(max, _)| count <= max).unwrap_or(COUNT_BUCKETS.len() - 1);
+        region_counts[idx].count += 1;
+    }
+
+    let mut file_coverage: Vec<_> = (0..10).map(|i| Bucket { label: format!("{}-{}%", i * 10, i * 10 + 10), count: 0 }).collect();
+    for file in model.files.iter().filter(|f| f.summary.lines.count > 0) {
+        let decile = ((file.summary.lines.percent / 10.0).floor() as usize).min(9);
+        file_coverage[decile].count += 1;
+    }
+
+    Statistics { region_counts, file_coverage }
+}
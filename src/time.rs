@@ -0,0 +1,111 @@
+//! Timestamps as the report shows them.
+
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset, Local, Utc};
+
+use crate::git;
+
+/// What times are shown in, set with `--timezone` or `--utc`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Timezone {
+    /// That of the machine rendering the report.
+    #[default]
+    Local,
+    Utc,
+    /// A fixed offset from UTC, e.g. `+02:00`.
+    Fixed(FixedOffset),
+}
+
+impl Timezone {
+    /// The same instant as it reads in this timezone.
+    pub fn convert(&self, time: DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+        match self {
+            Timezone::Local => {
+                let local = time.with_timezone(&Local);
+                local.with_timezone(local.offset())
+            }
+            Timezone::Utc => time.with_timezone(&Utc).into(),
+            Timezone::Fixed(offset) => time.with_timezone(offset),
+        }
+    }
+}
+
+impl FromStr for Timezone {
+    type Err = String;
+
+    /// Parses `local`, `utc`, or an offset like `+02:00` or `-0530`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid timezone `{}', expected local, utc, or an offset like +02:00", s);
+        match s.to_ascii_lowercase().as_str() {
+            "local" => return Ok(Timezone::Local),
+            "utc" | "z" => return Ok(Timezone::Utc),
+            _ => (),
+        }
+
+        let (sign, offset) = match s.split_at_checked(1) {
+            Some(("+", offset)) => (1, offset),
+            Some(("-", offset)) => (-1, offset),
+            _ => return Err(invalid()),
+        };
+        let digits = offset.replace(':', "");
+        if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let (hours, minutes): (i32, i32) = (digits[..2].parse().map_err(|_| invalid())?, digits[2..].parse().map_err(|_| invalid())?);
+        if minutes >= 60 {
+            return Err(invalid());
+        }
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).map(Timezone::Fixed).ok_or_else(invalid)
+    }
+}
+
+/// Where the time the coverage data is from comes from, set with
+/// `--timestamp-source`.  The mtime of a downloaded artifact says little
+/// about when the data was collected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimestampSource {
+    /// When the input was last modified.
+    #[default]
+    Mtime,
+    /// The commit time of `HEAD` in the source directory.
+    Commit,
+    /// When the report is generated.
+    Now,
+    /// Given on the command line, e.g. by a build system.
+    Fixed(DateTime<FixedOffset>),
+}
+
+impl TimestampSource {
+    /// The time for data read from `path`, which is in the git checkout for
+    /// [`TimestampSource::Commit`].
+    pub fn resolve(&self, path: &Path) -> io::Result<DateTime<FixedOffset>> {
+        Ok(match self {
+            TimestampSource::Mtime => DateTime::<Utc>::from(std::fs::metadata(path)?.modified()?).into(),
+            TimestampSource::Commit => git::commit_time(path).map_err(|e| io::Error::other(e.to_string()))?,
+            TimestampSource::Now => Utc::now().into(),
+            TimestampSource::Fixed(time) => *time,
+        })
+    }
+}
+
+impl FromStr for TimestampSource {
+    type Err = String;
+
+    /// Parses `mtime`, `commit`, `now`, or `fixed=` and an RFC 3339 time.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mtime" => Ok(TimestampSource::Mtime),
+            "commit" => Ok(TimestampSource::Commit),
+            "now" => Ok(TimestampSource::Now),
+            _ => match s.strip_prefix("fixed=") {
+                Some(time) => DateTime::parse_from_rfc3339(time)
+                    .map(TimestampSource::Fixed)
+                    .map_err(|e| format!("Invalid timestamp `{}': {}", time, e)),
+                None => Err(format!("Unknown timestamp source `{}', expected mtime, commit, now, or fixed=<rfc3339>", s)),
+            },
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! The reported files as a tree of directories, sized by their lines, for
+//! the treemap page.
+
+use crate::Summary;
+use crate::model::ReportModel;
+
+#[derive(Debug)]
+pub struct Node {
+    /// Path of the file or directory relative to its parent, several
+    /// components long where directories with nothing but a single
+    /// subdirectory were collapsed into it.
+    pub name: String,
+    /// Path shown in the report.
+    pub path: String,
+    /// Page of the file, `None` for directories.
+    pub page: Option<String>,
+    /// Lines of the file, or of every file under the directory.
+    pub lines: Summary,
+    /// Largest first.
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    fn dir(name: &str, path: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            path: path.to_string(),
+            page: None,
+            lines: Summary::new(0, 0),
+            children: vec![],
+        }
+    }
+
+    fn insert(&mut self, components: &[&str], path: &str, page: &str, lines: &Summary) {
+        match components {
+            [] => (),
+            [name] => self.children.push(Node {
+                name: name.to_string(),
+                path: path.to_string(),
+                page: Some(page.to_string()),
+                lines: Summary::new(lines.count, lines.covered),
+                children: vec![],
+            }),
+            [name, rest @ ..] => {
+                let idx = match self.children.iter().position(|c| c.page.is_none() && c.name == *name) {
+                    Some(idx) => idx,
+                    None => {
+                        let dir_path = match self.path.is_empty() {
+                            true => name.to_string(),
+                            false => format!("{}/{}", self.path, name),
+                        };
+                        self.children.push(Node::dir(name, &dir_path));
+                        self.children.len() - 1
+                    }
+                };
+                self.children[idx].insert(rest, path, page, lines);
+            }
+        }
+    }
+
+    /// Totals the directories, sorts them, and collapses the chains of
+    /// single subdirectories, e.g. `crates/foo/src`.
+    fn finish(&mut self) {
+        for child in self.children.iter_mut() {
+            child.finish();
+            while child.page.is_none() && child.children.len() == 1 && child.children[0].page.is_none() {
+                let only = child.children.remove(0);
+                child.name = format!("{}/{}", child.name, only.name);
+                child.path = only.path;
+                child.children = only.children;
+            }
+        }
+        if self.page.is_none() {
+            self.lines = Summary::total(self.children.iter().map(|c| &c.lines));
+        }
+        self.children.sort_by(|a, b| b.lines.count.cmp(&a.lines.count).then_with(|| a.name.cmp(&b.name)));
+    }
+}
+
+/// The reported files with instrumented lines under a root named after the
+/// package.  Files of a workspace end up under the directories of their
+/// crates.
+pub fn hierarchy(model: &ReportModel) -> Node {
+    let mut root = Node::dir(model.metadata.package.as_deref().unwrap_or_default(), "");
+    for file in model.files.iter().filter(|f| f.summary.lines.count > 0) {
+        let components: Vec<_> = file.name.split('/').filter(|c| !c.is_empty()).collect();
+        root.insert(&components, &file.name, &file.page, &file.summary.lines);
+    }
+    root.finish();
+    root
+}
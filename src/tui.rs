@@ -0,0 +1,353 @@
+//! `cosmoline tui`: browses the files, functions, and annotated sources of a
+//! report in the terminal, for when there's no browser at hand, e.g. over
+//! SSH.
+//!
+//! [`App`] holds what's shown and reacts to keys, separately from the
+//! terminal, so it can be driven and drawn on a test backend.
+
+use std::error::Error as StdError;
+use std::path::{Path, PathBuf};
+
+use clap::ArgMatches;
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+
+use crate::anonymize::Anonymizer;
+use crate::check::Goals;
+use crate::encoding::{self, SourceEncoding};
+use crate::input::Registry;
+use crate::merge::{self, MergeMode};
+use crate::model::{FileModel, FunctionModel, ModelOptions, ReportModel};
+use crate::utils;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum View {
+    Files,
+    Functions,
+    Source,
+}
+
+/// A file opened in the source view.
+struct Source {
+    /// Index into [`ReportModel::all_files`].
+    file: usize,
+    /// Decoded source, or why it couldn't be read.
+    lines: Result<Vec<String>, String>,
+    /// The line under the cursor, from 0.
+    cursor: usize,
+    /// The first line shown.
+    scroll: usize,
+}
+
+pub struct App<'m> {
+    model: &'m ReportModel,
+    files: Vec<&'m FileModel>,
+    source_dir: PathBuf,
+    goals: Goals,
+    view: View,
+    /// The view the source view goes back to.
+    opened_from: View,
+    file_list: ListState,
+    function_list: ListState,
+    /// Functions whose name contains this, ignoring case.
+    search: String,
+    /// Whether keys go to the search.
+    searching: bool,
+    source: Option<Source>,
+    quit: bool,
+}
+
+impl<'m> App<'m> {
+    pub fn new(model: &'m ReportModel, source_dir: &Path) -> Self {
+        Self {
+            model,
+            files: model.all_files().collect(),
+            source_dir: source_dir.to_path_buf(),
+            goals: Goals::default(),
+            view: View::Files,
+            opened_from: View::Files,
+            file_list: ListState::default().with_selected(Some(0)),
+            function_list: ListState::default().with_selected(Some(0)),
+            search: String::new(),
+            searching: false,
+            source: None,
+            quit: false,
+        }
+    }
+
+    pub fn view(&self) -> View {
+        self.view
+    }
+
+    pub fn quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Functions matching the search.
+    fn functions(&self) -> Vec<&'m FunctionModel> {
+        let search = self.search.to_lowercase();
+        self.model.functions.iter().filter(|f| f.name.to_lowercase().contains(&search)).collect()
+    }
+
+    pub fn handle(&mut self, key: KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+
+        if self.searching {
+            match key.code {
+                KeyCode::Enter | KeyCode::Esc => self.searching = false,
+                KeyCode::Backspace => {
+                    self.search.pop();
+                }
+                KeyCode::Char(c) => self.search.push(c),
+                _ => (),
+            }
+            self.function_list.select(Some(0));
+            return;
+        }
+
+        match (self.view, key.code) {
+            (_, KeyCode::Char('q')) => self.quit = true,
+            (View::Files, KeyCode::Tab) => self.view = View::Functions,
+            (View::Functions, KeyCode::Tab) => self.view = View::Files,
+            (View::Functions, KeyCode::Char('/')) => {
+                self.search.clear();
+                self.searching = true;
+            }
+            (View::Files, KeyCode::Enter) => {
+                if let Some(file) = self.file_list.selected().filter(|file| *file < self.files.len()) {
+                    self.open(file, None);
+                }
+            }
+            (View::Functions, KeyCode::Enter) => {
+                let functions = self.functions();
+                let function = match self.function_list.selected().and_then(|idx| functions.get(idx)) {
+                    Some(function) => *function,
+                    None => return,
+                };
+                let file = function.file.as_deref().and_then(|name| self.files.iter().position(|f| f.name == name));
+                let line = function.regions.iter().filter(|r| r.file_id == 0).map(|r| r.line_start).min();
+                if let Some(file) = file {
+                    self.open(file, line);
+                }
+            }
+            (View::Source, KeyCode::Esc) => self.view = self.opened_from,
+            (View::Source, KeyCode::Char('n')) => self.next_uncovered(true),
+            (View::Source, KeyCode::Char('N')) => self.next_uncovered(false),
+            (_, KeyCode::Down) | (_, KeyCode::Char('j')) => self.step(1),
+            (_, KeyCode::Up) | (_, KeyCode::Char('k')) => self.step(-1),
+            (_, KeyCode::PageDown) => self.step(20),
+            (_, KeyCode::PageUp) => self.step(-20),
+            _ => (),
+        }
+    }
+
+    fn step(&mut self, by: isize) {
+        let len = match self.view {
+            View::Files => self.files.len(),
+            View::Functions => self.functions().len(),
+            View::Source => self.source.as_ref().and_then(|s| s.lines.as_ref().ok()).map_or(0, Vec::len),
+        };
+        let moved = |current: usize| (current as isize + by).clamp(0, len.saturating_sub(1) as isize) as usize;
+        match self.view {
+            View::Files => self.file_list.select(Some(moved(self.file_list.selected().unwrap_or(0)))),
+            View::Functions => self.function_list.select(Some(moved(self.function_list.selected().unwrap_or(0)))),
+            View::Source => {
+                if let Some(source) = self.source.as_mut() {
+                    source.cursor = moved(source.cursor);
+                }
+            }
+        }
+    }
+
+    /// Opens the file at `file` in [`Self::files`], with the cursor on
+    /// `line` (from 1).
+    fn open(&mut self, file: usize, line: Option<i64>) {
+        let path = self.source_dir.join(&self.files[file].filename);
+        let lines = std::fs::read(&path)
+            .map(|bytes| encoding::decode(&bytes, SourceEncoding::default()).lines().map(str::to_string).collect())
+            .map_err(|e| format!("{}: {}", path.display(), e));
+        let cursor = line.map_or(0, |line| (line.max(1) - 1) as usize);
+        self.source = Some(Source { file, lines, cursor, scroll: cursor.saturating_sub(5) });
+        self.opened_from = self.view;
+        self.view = View::Source;
+    }
+
+    /// Moves the cursor to the next (or previous) instrumented line that
+    /// never ran.
+    fn next_uncovered(&mut self, forward: bool) {
+        let source = match self.source.as_mut() {
+            Some(source) => source,
+            None => return,
+        };
+        let cursor = source.cursor as i64 + 1;
+        let mut uncovered = self.files[source.file].lines.iter().filter(|l| l.mapped && l.count == 0).map(|l| l.line);
+        let found = match forward {
+            true => uncovered.find(|line| *line > cursor),
+            false => uncovered.rfind(|line| *line < cursor),
+        };
+        if let Some(line) = found {
+            source.cursor = (line - 1) as usize;
+        }
+    }
+
+    fn color(&self, percent: f64) -> Color {
+        match self.goals.lines.color(percent) {
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            _ => Color::Red,
+        }
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame) {
+        let [header, body, footer] = Layout::vertical([Constraint::Length(1), Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+
+        let totals = &self.model.totals;
+        let title = format!(
+            "{}  lines {:.1}% ({}/{})  functions {:.1}% ({}/{})",
+            self.model.metadata.package.as_deref().unwrap_or("Code Coverage Report"),
+            totals.lines.percent,
+            totals.lines.covered,
+            totals.lines.count,
+            totals.functions.percent,
+            totals.functions.covered,
+            totals.functions.count,
+        );
+        frame.render_widget(Paragraph::new(title).style(Style::default().add_modifier(Modifier::BOLD)), header);
+
+        let help = match (self.view, self.searching) {
+            (_, true) => format!("/{}  enter: done  esc: done", self.search),
+            (View::Files, _) => "↑↓: move  enter: open  tab: functions  q: quit".to_string(),
+            (View::Functions, _) => "↑↓: move  enter: open  /: search  tab: files  q: quit".to_string(),
+            (View::Source, _) => "↑↓: move  n/N: next/previous uncovered line  esc: back  q: quit".to_string(),
+        };
+        frame.render_widget(Paragraph::new(help).style(Style::default().add_modifier(Modifier::REVERSED)), footer);
+
+        match self.view {
+            View::Files => {
+                let items = self.files.iter().map(|file| {
+                    let lines = &file.summary.lines;
+                    ListItem::new(Line::from(vec![
+                        Span::styled(format!("{:>6.1}%  ", lines.percent), Style::default().fg(self.color(lines.percent))),
+                        Span::raw(file.name.as_str()),
+                    ]))
+                });
+                let list = List::new(items.collect::<Vec<_>>())
+                    .block(Block::default().borders(Borders::TOP).title("Files"))
+                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+                frame.render_stateful_widget(list, body, &mut self.file_list);
+            }
+            View::Functions => {
+                let items = self.functions().into_iter().map(|function| {
+                    let color = match function.count {
+                        0 => Color::Red,
+                        _ => Color::Green,
+                    };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(format!("{:>6}  ", utils::human_count(function.count)), Style::default().fg(color)),
+                        Span::raw(function.name.as_str()),
+                    ]))
+                });
+                let title = match self.search.is_empty() {
+                    true => "Functions".to_string(),
+                    false => format!("Functions matching `{}'", self.search),
+                };
+                let list = List::new(items.collect::<Vec<_>>())
+                    .block(Block::default().borders(Borders::TOP).title(title))
+                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+                frame.render_stateful_widget(list, body, &mut self.function_list);
+            }
+            View::Source => {
+                let source = match self.source.as_mut() {
+                    Some(source) => source,
+                    None => return,
+                };
+                let file = self.files[source.file];
+                let block = Block::default().borders(Borders::TOP).title(format!(
+                    "{}  lines {:.1}% ({}/{})",
+                    file.name, file.summary.lines.percent, file.summary.lines.covered, file.summary.lines.count
+                ));
+                let lines = match source.lines.as_ref() {
+                    Ok(lines) => lines,
+                    Err(e) => {
+                        frame.render_widget(Paragraph::new(e.as_str()).block(block), body);
+                        return;
+                    }
+                };
+
+                // Keep the cursor in view, below the block's border
+                let height = body.height.saturating_sub(1).max(1) as usize;
+                if source.cursor < source.scroll {
+                    source.scroll = source.cursor;
+                } else if source.cursor >= source.scroll + height {
+                    source.scroll = source.cursor + 1 - height;
+                }
+
+                let shown = lines.iter().enumerate().skip(source.scroll).take(height).map(|(idx, text)| {
+                    let coverage = file.lines.get(idx).filter(|l| l.mapped);
+                    let (count, style) = match coverage {
+                        Some(l) if l.count == 0 => ("0".to_string(), Style::default().fg(Color::Red)),
+                        Some(l) => (utils::human_count(l.count), Style::default().fg(Color::Green)),
+                        None => (String::new(), Style::default()),
+                    };
+                    let style = match idx == source.cursor {
+                        true => style.add_modifier(Modifier::REVERSED),
+                        false => style,
+                    };
+                    Line::from(vec![
+                        Span::styled(format!("{:>6} ", count), style),
+                        Span::styled(format!("{:>5} ", idx + 1), Style::default().fg(Color::DarkGray)),
+                        Span::styled(text.replace('\t', "    "), style),
+                    ])
+                });
+                frame.render_widget(Paragraph::new(shown.collect::<Vec<_>>()).block(block), body);
+            }
+        }
+    }
+}
+
+/// `cosmoline tui`: reads the exports and runs the UI until it's quit.
+pub fn run(matches: &ArgMatches) -> Result<(), Box<dyn StdError>> {
+    let input_filenames = matches.values_of("input").unwrap().collect::<Vec<_>>();
+    let source_dir = match matches.value_of("source-prefix") {
+        Some(prefix) => Path::new(prefix),
+        None => Path::new(input_filenames[0]).parent().unwrap(),
+    };
+
+    let contents = input_filenames
+        .iter()
+        .map(|input_filename| std::fs::read_to_string(input_filename).map_err(|e| format!("{}: {}", input_filename, e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let formats = Registry::builtin();
+    let mut mappings = vec![];
+    for (input_filename, contents) in input_filenames.iter().zip(contents.iter()) {
+        let format = formats
+            .detect(Path::new(input_filename), contents)
+            .ok_or_else(|| format!("Unrecognized input format for `{}'", input_filename))?;
+        mappings.extend(format.read(contents)?);
+    }
+    let coverage = merge::combine(mappings, MergeMode::Sum);
+    let model = ReportModel::build(&coverage, &ModelOptions::default(), &Anonymizer::new(false, None), Path::new(input_filenames[0]))?;
+
+    let mut app = App::new(&model, source_dir);
+    let mut terminal = ratatui::init();
+    let result = (|| -> std::io::Result<()> {
+        while !app.quit() {
+            terminal.draw(|frame| app.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                app.handle(key);
+            }
+        }
+        Ok(())
+    })();
+    ratatui::restore();
+
+    Ok(result?)
+}
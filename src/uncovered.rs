@@ -0,0 +1,83 @@
+//! Every region of code that never ran, for bulk-importing coverage gaps into
+//! an issue tracker or a spreadsheet.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::model::ReportModel;
+use crate::utils::csv_escape;
+
+const CODE_REGION: i64 = 0;
+
+/// File, start line and column, and end line and column.
+pub(crate) type Position<'a> = (&'a str, i64, i64, i64, i64);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct UncoveredRegion {
+    /// Shown name of the file.
+    pub file: String,
+    pub line_start: i64,
+    pub column_start: i64,
+    pub line_end: i64,
+    pub column_end: i64,
+    /// Function the region belongs to.
+    pub function: String,
+}
+
+/// Code regions of reported files that never ran, by file and position.
+/// Regions shared by several functions (e.g. instantiations of a generic
+/// one that weren't folded together) only count as uncovered if none of
+/// them ran it.
+pub fn regions(model: &ReportModel) -> Vec<UncoveredRegion> {
+    region_hits(model)
+        .into_iter()
+        .filter(|(_, (hit, _))| !hit)
+        .map(|((file, line_start, column_start, line_end, column_end), (_, function))| UncoveredRegion {
+            file: file.to_string(),
+            line_start,
+            column_start,
+            line_end,
+            column_end,
+            function: function.to_string(),
+        })
+        .collect()
+}
+
+/// Whether each code region of the reported files ran, and the first function
+/// it was found in.
+pub(crate) fn region_hits(model: &ReportModel) -> BTreeMap<Position<'_>, (bool, &str)> {
+    let reported = model.all_files().map(|f| f.name.as_str()).collect::<Vec<_>>();
+
+    let mut regions: BTreeMap<Position, (bool, &str)> = BTreeMap::new();
+    for function in model.functions.iter() {
+        let file = match function.file.as_deref() {
+            Some(file) if reported.contains(&file) => file,
+            _ => continue,
+        };
+
+        // Other file ids are where macros were expanded from
+        for region in function.regions.iter().filter(|r| r.region_kind == CODE_REGION && r.file_id == 0) {
+            let key = (file, region.line_start, region.column_start, region.line_end, region.column_end);
+            regions.entry(key).or_insert((false, &function.name)).0 |= region.execution_count > 0;
+        }
+    }
+    regions
+}
+
+pub fn csv(regions: &[UncoveredRegion]) -> String {
+    let mut out = String::from("file,start_line,start_column,end_line,end_column,function\n");
+    for r in regions {
+        writeln!(
+            out,
+            "{},{},{},{},{},{}",
+            csv_escape(&r.file),
+            r.line_start,
+            r.column_start,
+            r.line_end,
+            r.column_end,
+            csv_escape(&r.function)
+        )
+        .unwrap();
+    }
+    out
+}
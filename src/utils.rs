@@ -1,53 +1,166 @@
 use serde::{de, Deserialize, Deserializer};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::OnceLock;
 
 /// Cheapie filename escape thing to flaten the paths
 /// so we don't actually need to create the whole hierarchy
-/// when generating the report.
-pub(crate) fn sanitize_filename(input: &str) -> String {
-    format!("{}.html", input.replace("/", "_"))
+/// when generating the report.  Both kinds of path separator become `_` and
+/// anything else that isn't safe in a URL or a filename on every platform is
+/// written as `~` followed by the hex value of each UTF-8 byte.  Names too
+/// long for a filename are cut short, see `fit_filename`.
+pub fn sanitize_filename(input: &str) -> String {
+    let mut output = fit_filename(escape_filename(input), input);
+    output.push_str(".html");
+    output
 }
 
-/// Maps a percent to a color.  Will panic on negative values.
-pub(crate) fn color_for_percent<'a>(percent: f64) -> &'a str {
+/// Page of a file in a tree mirroring the sources, e.g.
+/// `src/render/file.rs.html`, with every component escaped like
+/// `sanitize_filename` does.  Components that are all dots are escaped
+/// entirely so pages stay under the output directory, and so is a file
+/// named `index` so its page doesn't take the place of its directory's.
+pub fn tree_page(input: &str) -> String {
+    let components: Vec<_> = input.split(['/', '\\']).filter(|c| !c.is_empty()).collect();
+    let mut output = components
+        .iter()
+        .enumerate()
+        .map(|(idx, component)| match *component {
+            c if c.chars().all(|c| c == '.') => c.replace('.', "~2e"),
+            "index" if idx == components.len() - 1 => "~69ndex".to_string(),
+            c => fit_filename(escape_filename(c), c),
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    output.push_str(".html");
+    output
+}
+
+/// Page of a function, e.g. `fn@sample~3a~3aclassify.html`, next to the
+/// functions page.  File pages never contain `@`.  Names that would make too
+/// long a filename (say a method of a deeply nested generic type) are cut
+/// short like those of files.
+pub fn function_page(name: &str) -> String {
+    format!("fn@{}.html", fit_filename(escape_filename(name), name))
+}
+
+/// Longest escaped name kept whole.  Most filesystems allow 255 bytes, which
+/// leaves room for the hash, the extension, and the label of a view.
+const MAX_ESCAPED_LEN: usize = 150;
+
+/// `escaped`, the escaped `original`, or if it's longer than
+/// `MAX_ESCAPED_LEN` its start followed by `~~` and a hash of `original` to
+/// tell names with the same start apart.  Escaping never writes `~~`, so
+/// only shortened names contain it.
+fn fit_filename(escaped: String, original: &str) -> String {
+    match escaped.len() > MAX_ESCAPED_LEN {
+        // Escaping leaves nothing but ASCII
+        true => format!("{}~~{}", &escaped[..MAX_ESCAPED_LEN], &crate::manifest::sha256(original)[..16]),
+        false => escaped,
+    }
+}
+
+/// Whether some part of `page` was cut short to fit in a filename, so the
+/// manifest has to say what it shows.
+pub fn is_shortened(page: &str) -> bool {
+    page.contains("~~")
+}
+
+/// The escaping `sanitize_filename` does, without the extension.
+pub fn escape_filename(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '/' | '\\' => output.push('_'),
+            c if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') => output.push(c),
+            c => {
+                let mut bytes = [0; 4];
+                for byte in c.encode_utf8(&mut bytes).bytes() {
+                    output.push_str(&format!("~{:02x}", byte));
+                }
+            }
+        }
+    }
+    output
+}
+
+/// Coverage percentages from this up are shown in yellow.
+pub const YELLOW_PERCENT: f64 = 75.0;
+/// Coverage percentages from this up are shown in green.
+pub const GREEN_PERCENT: f64 = 90.0;
+
+/// Maps a percent to a color.  Anything that isn't a number is treated as
+/// no coverage.
+pub fn color_for_percent<'a>(percent: f64) -> &'a str {
+    color_between(percent, YELLOW_PERCENT, GREEN_PERCENT)
+}
+
+/// Like `color_for_percent`, with the yellow and green cut-offs given.
+pub fn color_between<'a>(percent: f64, yellow: f64, green: f64) -> &'a str {
     match percent {
-        i if i < 75.0 => "red",
-        i if i >= 75.0 && i < 90.0 => "yellow",
-        i if i >= 90.0 => "green",
-        _ => unimplemented!(),
+        i if i >= green => "green",
+        i if i >= yellow => "yellow",
+        _ => "red",
     }
 }
 
+/// Abbreviates an execution count for display, e.g. `1.2k` or `3.4M`.
+/// Counts below a thousand are shown as is.
+pub fn human_count(count: i64) -> String {
+    const UNITS: [&str; 6] = ["k", "M", "G", "T", "P", "E"];
+
+    if count.unsigned_abs() < 1000 {
+        return count.to_string();
+    }
+
+    let mut value = count as f64;
+    for unit in UNITS.iter() {
+        value /= 1000.0;
+        // Round first so that e.g. 999_999 becomes 1.0M rather than 1000.0k
+        if (value * 10.0).round().abs() < 9995.0 {
+            return format!("{:.1}{}", value, unit);
+        }
+    }
+    format!("{:.1}{}", value, UNITS[UNITS.len() - 1])
+}
+
+/// Parses a size in bytes with an optional decimal unit, e.g. `500k`, `50M`,
+/// or `1GB`.
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid size `{}', expected e.g. 500k, 50M, or 1G", input);
+
+    let digits = input.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let multiplier: u64 = match input[digits.len()..].to_ascii_lowercase().trim_end_matches('b') {
+        "" => 1,
+        "k" => 1_000,
+        "m" => 1_000_000,
+        "g" => 1_000_000_000,
+        _ => return Err(invalid()),
+    };
+    digits.parse::<u64>().ok().and_then(|n| n.checked_mul(multiplier)).ok_or_else(invalid)
+}
+
 /// Turns out String::insert_str will panic if we don't know where our character boundaries are e.g.
 /// multibyte characters (e.g. Cyrillic) mean the byte and character boundaries are in different locations.
-pub(crate) trait InsertAtCharacter {
+pub trait InsertAtCharacter {
+    /// Inserts before the character at the 1-based column `index`, or at the
+    /// end of the line if the line is shorter than that.
     fn insert_at_char(&mut self, index: usize, s: &str);
 }
 
 impl InsertAtCharacter for String {
     fn insert_at_char(&mut self, index: usize, s: &str) {
-        let char_indexes = self.char_indices().collect::<Vec<_>>();
-
-        if index >= char_indexes.len() {
-            self.push_str(s)
-        } else {
-            let index = char_indexes[index].0;
-            if index >= self.len() {
-                self.push_str(s)
-            } else {
-                if index > 0 {
-                    self.insert_str(index - 1, s)
-                } else {
-                    self.insert_str(index, s)
-                }
-            }
+        match self.char_indices().nth(index.saturating_sub(1)) {
+            Some((byte, _)) => self.insert_str(byte, s),
+            None => self.push_str(s),
         }
     }
 }
 
 // Ah boilerplate
 // https://github.com/serde-rs/json/issues/317
-pub(crate) fn deser_from_str<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+pub fn deser_from_str<'de, T, D>(deserializer: D) -> Result<T, D::Error>
 where
     T: FromStr,
     T::Err: std::fmt::Display,
@@ -56,3 +169,61 @@ where
     let s = String::deserialize(deserializer)?;
     T::from_str(&s).map_err(de::Error::custom)
 }
+
+/// Escapes text for use in XML attributes and element content.
+pub fn xml_escape(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Quotes a CSV field if it has to be, e.g. function names with commas in
+/// their generic parameters.
+pub fn csv_escape(input: &str) -> Cow<'_, str> {
+    match input.contains([',', '"', '\n', '\r']) {
+        true => Cow::Owned(format!("\"{}\"", input.replace('"', "\"\""))),
+        false => Cow::Borrowed(input),
+    }
+}
+
+/// Whether a file from the coverage mapping belongs in the report.
+pub fn is_source_file(filename: &str) -> bool {
+    filename.starts_with("src/")
+}
+
+/// Cargo's home directory, where registry and git dependencies are unpacked.
+pub fn cargo_home() -> &'static Path {
+    static CARGO_HOME: OnceLock<PathBuf> = OnceLock::new();
+    CARGO_HOME.get_or_init(|| match std::env::var_os("CARGO_HOME") {
+        Some(home) => PathBuf::from(home),
+        None => PathBuf::from(std::env::var_os("HOME").unwrap_or_default()).join(".cargo"),
+    })
+}
+
+/// For a file unpacked from a registry or a git checkout under `CARGO_HOME`,
+/// the path below the registry index or checkout directory, e.g.
+/// `serde-1.0.126/src/de/mod.rs`.
+pub fn dependency_path(filename: &str) -> Option<&str> {
+    let home = cargo_home().to_str()?;
+    let relative = filename.strip_prefix(home)?.trim_start_matches('/');
+
+    let (prefix, skip) = match relative {
+        r if r.starts_with("registry/src/") => ("registry/src/", 1),
+        r if r.starts_with("git/checkouts/") => ("git/checkouts/", 0),
+        _ => return None,
+    };
+
+    let mut rest = &relative[prefix.len()..];
+    for _ in 0..skip {
+        rest = rest.split_once('/')?.1;
+    }
+    Some(rest)
+}
+
+/// The name a file is shown (and linked) under in the report.
+pub fn report_name(filename: &str) -> &str {
+    dependency_path(filename).unwrap_or(filename)
+}
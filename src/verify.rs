@@ -0,0 +1,277 @@
+//! Checks that the links of a rendered report lead somewhere: every page or
+//! asset linked to was written, and every `#anchor` is the id of an element
+//! on the page it points into.  Guards against naming and filtering bugs
+//! that leave dead links behind.  `cosmoline verify` also checks a report
+//! written earlier against its manifest.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use clap::ArgMatches;
+#[allow(unused)]
+use log::{error, warn, info, debug, trace};
+
+use crate::manifest::{self, Manifest};
+
+/// A link that doesn't lead anywhere.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DeadLink {
+    /// Page the link is on, relative to the report directory.
+    pub page: String,
+    /// The link as written.
+    pub href: String,
+    pub problem: Problem,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// Points at a file that wasn't written.
+    MissingFile,
+    /// Points out of the report directory.
+    OutsideReport,
+    /// The page is there but has no element with this id.
+    MissingAnchor(String),
+}
+
+impl fmt::Display for DeadLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.problem {
+            Problem::MissingFile => write!(f, "{}: `{}' leads to a file that doesn't exist", self.page, self.href),
+            Problem::OutsideReport => write!(f, "{}: `{}' leads out of the report", self.page, self.href),
+            Problem::MissingAnchor(anchor) => write!(f, "{}: `{}' leads to a page without `{}'", self.page, self.href, anchor),
+        }
+    }
+}
+
+/// Checks the links on `pages` (relative to `report_path`).  Links to other
+/// sites are left alone, and so are absolute links unless they start with
+/// `base_url`, which stands for the report directory.
+pub fn dead_links(report_path: &Path, pages: &[&str], base_url: Option<&str>) -> io::Result<Vec<DeadLink>> {
+    let mut anchors = Anchors::default();
+    let mut dead = vec![];
+
+    for page in pages {
+        let html = std::fs::read_to_string(report_path.join(page))?;
+        for href in links(&html) {
+            let (target, anchor) = match href.split_once('#') {
+                Some((target, anchor)) => (target, Some(anchor)),
+                None => (href.as_str(), None),
+            };
+            let target = target.split('?').next().unwrap_or_default();
+
+            let target = match resolve(page, target, base_url) {
+                Some(Target::Elsewhere) => continue,
+                Some(Target::Page(target)) => target,
+                None => {
+                    dead.push(DeadLink { page: page.to_string(), href: href.clone(), problem: Problem::OutsideReport });
+                    continue;
+                }
+            };
+            let path = report_path.join(&target);
+            if !path.is_file() {
+                dead.push(DeadLink { page: page.to_string(), href: href.clone(), problem: Problem::MissingFile });
+                continue;
+            }
+
+            if let Some(anchor) = anchor.filter(|anchor| !anchor.is_empty()) {
+                if !anchors.of(&path)?.contains(anchor) {
+                    dead.push(DeadLink {
+                        page: page.to_string(),
+                        href: href.clone(),
+                        problem: Problem::MissingAnchor(anchor.to_string()),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(dead)
+}
+
+/// Files listed in `manifest` that are missing from `report_path` or differ
+/// from what was written, with what's wrong with them.  Reports written
+/// before the manifest recorded hashes are only checked for missing files.
+pub fn changed_files(report_path: &Path, manifest: &Manifest) -> io::Result<Vec<(String, String)>> {
+    let mut changed = vec![];
+    for name in manifest.files.iter() {
+        let path = report_path.join(name);
+        if !path.is_file() {
+            changed.push((name.clone(), "missing".to_string()));
+            continue;
+        }
+        if let Some(recorded) = manifest.hashes.get(name) {
+            if manifest::sha256(std::fs::read(&path)?) != *recorded {
+                changed.push((name.clone(), "changed since it was written".to_string()));
+            }
+        }
+    }
+    Ok(changed)
+}
+
+/// The manifest of the report in `report_path`.
+pub fn manifest(report_path: &Path) -> Result<Manifest, Box<dyn StdError>> {
+    let manifest_path = report_path.join(manifest::FILENAME);
+    match std::fs::read_to_string(&manifest_path) {
+        Ok(json) => Ok(serde_json::from_str(&json).map_err(|e| format!("{}: {}", manifest_path.display(), e))?),
+        Err(e) => Err(format!("No manifest at {}, is this an HTML report? {}", manifest_path.display(), e).into()),
+    }
+}
+
+/// The HTML pages listed in `manifest` that are there to check the links
+/// of, as checked by `cosmoline verify` and `--check-links`.
+pub fn pages<'m>(report_path: &Path, manifest: &'m Manifest) -> Vec<&'m str> {
+    manifest
+        .files
+        .iter()
+        .filter(|name| name.ends_with(".html") && report_path.join(name).is_file())
+        .map(String::as_str)
+        .collect()
+}
+
+/// `cosmoline verify`: checks the files of a report against its manifest and
+/// the links on every page of it.
+pub fn run(matches: &ArgMatches) -> Result<(), Box<dyn StdError>> {
+    let report_path = Path::new(matches.value_of("report").unwrap());
+    let manifest = manifest(report_path)?;
+    if manifest.hashes.is_empty() {
+        info!("The manifest has no hashes, only checking that every file is there");
+    }
+
+    let mut problems = 0;
+    for (name, problem) in changed_files(report_path, &manifest)? {
+        println!("{}: {}", name, problem);
+        problems += 1;
+    }
+
+    let pages = pages(report_path, &manifest);
+    for link in dead_links(report_path, &pages, matches.value_of("base-url"))? {
+        println!("{}", link);
+        problems += 1;
+    }
+
+    match problems {
+        0 => {
+            println!("{} files and the links on {} pages are fine", manifest.files.len(), pages.len());
+            Ok(())
+        }
+        n => Err(format!("{} problems in the report at {}", n, report_path.display()).into()),
+    }
+}
+
+enum Target {
+    /// Another site, or something that isn't a file like `mailto:`.
+    Elsewhere,
+    /// A file relative to the report directory.
+    Page(PathBuf),
+}
+
+/// Where `href` on `page` leads, or `None` if it's above the report
+/// directory.
+fn resolve(page: &str, href: &str, base_url: Option<&str>) -> Option<Target> {
+    let relative = match base_url.and_then(|base_url| href.strip_prefix(base_url)) {
+        Some(below_base) => below_base.to_string(),
+        None if href.starts_with("//") || href.contains("://") || href.starts_with("mailto:") => return Some(Target::Elsewhere),
+        // Site root, only the report directory without a base URL
+        None if href.starts_with('/') => match base_url {
+            Some(_) => return Some(Target::Elsewhere),
+            None => href.trim_start_matches('/').to_string(),
+        },
+        // Anchors on the page itself
+        None if href.is_empty() => page.to_string(),
+        None => match Path::new(page).parent() {
+            Some(dir) => dir.join(href).to_string_lossy().into_owned(),
+            None => href.to_string(),
+        },
+    };
+
+    let mut target = PathBuf::new();
+    for component in Path::new(&relative).components() {
+        match component {
+            Component::Normal(part) => target.push(part),
+            Component::ParentDir => {
+                if !target.pop() {
+                    return None;
+                }
+            }
+            Component::CurDir => (),
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(Target::Page(target))
+}
+
+/// Ids on each page read so far.
+#[derive(Default)]
+struct Anchors {
+    pages: HashMap<PathBuf, HashSet<String>>,
+}
+
+impl Anchors {
+    fn of(&mut self, path: &Path) -> io::Result<&HashSet<String>> {
+        if !self.pages.contains_key(path) {
+            let html = std::fs::read_to_string(path)?;
+            let ids = attributes(&html, "id").into_iter().chain(attributes(&html, "name")).collect();
+            self.pages.insert(path.to_path_buf(), ids);
+        }
+        Ok(&self.pages[path])
+    }
+}
+
+/// Every `href` and `src` on a page.
+fn links(html: &str) -> Vec<String> {
+    let mut links = attributes(html, "href");
+    links.extend(attributes(html, "src"));
+    links
+}
+
+/// Values of every double-quoted `name` attribute in `html`, unescaped.
+/// Good enough for the pages cosmoline writes, which always quote them.
+fn attributes(html: &str, name: &str) -> Vec<String> {
+    let needle = format!(" {}=\"", name);
+    html.match_indices(&needle)
+        .filter_map(|(start, _)| {
+            let value = &html[start + needle.len()..];
+            value.find('"').map(|end| unescape(&value[..end]))
+        })
+        .collect()
+}
+
+/// Undoes the escaping the template engines apply to attribute values.
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                    None => entity.strip_prefix('#').and_then(|dec| dec.parse().ok()).and_then(char::from_u32),
+                },
+            };
+            c.map(|c| (c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,59 @@
+// https://stackoverflow.com/questions/7332179/how-to-recursively-search-all-parentnodes
+function findUpTag(el, tag) {
+    while (el.parentNode) {
+        el = el.parentNode;
+        if (el.tagName.localeCompare(tag, 'en', {sensitivity: 'base'}) === 0) {
+            return el;
+        }
+    }
+    return null;
+}
+
+compare = (selector, direction, isInt) => {
+    return (a,b) => {
+        let a_value, b_value;
+
+        if (isInt === true) {
+            a_value = parseInt(a.querySelector(selector).innerHTML);
+            b_value = parseInt(b.querySelector(selector).innerHTML);
+        } else {
+            a_value = a.querySelector(selector).textContent + '';
+            b_value = b.querySelector(selector).textContent + '';
+        }
+
+        if (a_value > b_value) {
+            return direction;
+        } else if (b_value > a_value) {
+            return -direction;
+        } else {
+            return 0;
+        }
+    }
+}
+
+sortByHits = (selector) => {
+    return function(e) {
+        let table = findUpTag(e.target, 'table');
+
+        if ((typeof(table.getAttribute('data-direction')) === 'undefined') || (table.sortColumn !== e.target)) {
+            table.setAttribute('data-direction', -1);
+            table.sortColumn.className = [... table.sortColumn.classList].filter((c) => c !== 'active').join(' ');
+            table.sortColumn = e.target;
+            table.sortColumn.className = [... table.sortColumn.classList].filter((c) => c !== 'active').join(' ') + ' active';
+        } else {
+            table.setAttribute('data-direction', - table.getAttribute('data-direction'));
+        }
+
+        let body = table.querySelector('tbody');
+
+        Array.from(body.querySelectorAll('tr'))
+            .sort(compare(selector, table.getAttribute('data-direction'), e.target.innerHTML === 'Hits'))
+            .forEach(tr => body.appendChild(tr) );
+    }
+}
+
+document.getElementById('func_names_header').onclick = sortByHits('td:nth-child(0n+1)')
+document.getElementById('func_hits_header').onclick = sortByHits('td:nth-child(0n+2)')
+
+findUpTag(document.getElementById('func_names_header'), 'table').sortColumn = document.getElementById('func_names_header');
+findUpTag(document.getElementById('func_names_header'), 'table').setAttribute('data-direction', 1);
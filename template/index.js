@@ -0,0 +1,52 @@
+// Sorts the file summary by clicking its headers, and filters it by name.
+// The rows carry their counts as `covered/count`, the cells only show the
+// percentages rounded.
+window.addEventListener('load', () => {
+    const tbody = document.getElementById('files');
+    if (!tbody) {
+        return;
+    }
+    const table = tbody.closest('table');
+    const rows = [...tbody.rows];
+    const headers = [...table.querySelectorAll('th[data-sort]')];
+
+    const ratio = (counts) => {
+        let [covered, count] = counts.split('/').map((n) => parseInt(n));
+        return count > 0 ? covered / count : 0;
+    };
+
+    const sortBy = (header) => {
+        let key = header.getAttribute('data-sort');
+        // Ascending first: names from A, percentages least covered first
+        let direction = header.hasAttribute('data-direction')
+            ? -parseInt(header.getAttribute('data-direction'))
+            : 1;
+        headers.forEach((other) => other.removeAttribute('data-direction'));
+        header.setAttribute('data-direction', direction);
+
+        let value = key === 'name'
+            ? (row) => row.getAttribute('data-name')
+            : (row) => ratio(row.getAttribute('data-' + key));
+        rows.sort((a, b) => {
+            let [a_value, b_value] = [value(a), value(b)];
+            if (a_value < b_value) {
+                return -direction;
+            } else if (a_value > b_value) {
+                return direction;
+            }
+            return a.getAttribute('data-name').localeCompare(b.getAttribute('data-name'));
+        });
+        tbody.append(...rows);
+    };
+
+    headers.forEach((header) => header.addEventListener('click', () => sortBy(header)));
+
+    const filter = document.getElementById('file-filter');
+    filter.closest('tr').hidden = false;
+    filter.addEventListener('input', () => {
+        let needle = filter.value.trim().toLowerCase();
+        rows.forEach((row) => {
+            row.hidden = needle !== '' && !row.getAttribute('data-name').toLowerCase().includes(needle);
+        });
+    });
+});
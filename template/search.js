@@ -0,0 +1,84 @@
+// Searches window.searchIndex, written next to the page as search-index.js,
+// so that it works without a server.
+const MAX_RESULTS = 100;
+
+const row = (href, label, detail, hit) => {
+    let tr = document.createElement('tr');
+    let name = document.createElement('td');
+    if (href) {
+        let a = document.createElement('a');
+        a.href = href;
+        a.textContent = label;
+        name.appendChild(a);
+    } else {
+        name.textContent = label;
+    }
+    if (detail) {
+        let small = document.createElement('div');
+        small.className = 'detail';
+        small.textContent = detail;
+        name.appendChild(small);
+    }
+    let hits = document.createElement('td');
+    hits.textContent = hit;
+    tr.appendChild(name);
+    tr.appendChild(hits);
+    return tr;
+};
+
+const search = (query) => {
+    let index = window.searchIndex || { files: [], functions: [] };
+    let results = [];
+    query = query.trim();
+    if (query === '') {
+        return results;
+    }
+
+    // path:line jumps to the line on the file's page
+    let line = query.match(/^(.+):(\d+)$/);
+    if (line) {
+        let path = line[1].toLowerCase();
+        for (let file of index.files) {
+            if (file.link && file.name.toLowerCase().endsWith(path)) {
+                results.push(row(file.link + '#L' + line[2], file.name + ':' + line[2], null, file.lines_hit_percent + '%'));
+            }
+        }
+        return results;
+    }
+
+    let needle = query.toLowerCase();
+    for (let file of index.files) {
+        if (file.name.toLowerCase().includes(needle)) {
+            results.push(row(file.link, file.name, null, file.lines_hit_percent + '%'));
+        }
+    }
+    for (let f of index.functions) {
+        let byName = f.name.toLowerCase().includes(needle);
+        if (byName || f.symbol.toLowerCase().includes(needle)) {
+            let where = f.file ? (f.line ? f.file + ':' + f.line : f.file) : null;
+            let detail = byName ? where : [f.symbol, where].filter((d) => d).join(' · ');
+            results.push(row(f.link, f.name, detail, f.count));
+        }
+    }
+    return results;
+};
+
+const update = () => {
+    let query = document.getElementById('query').value;
+    let tbody = document.getElementById('results');
+    let results = search(query);
+    tbody.replaceChildren(...results.slice(0, MAX_RESULTS));
+    if (results.length > MAX_RESULTS) {
+        tbody.appendChild(row(null, (results.length - MAX_RESULTS) + ' more', null, ''));
+    }
+    history.replaceState(null, '', query ? '#' + encodeURIComponent(query) : location.pathname);
+};
+
+window.addEventListener('load', () => {
+    let input = document.getElementById('query');
+    if (location.hash.length > 1) {
+        input.value = decodeURIComponent(location.hash.substring(1));
+    }
+    input.addEventListener('input', update);
+    update();
+});
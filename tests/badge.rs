@@ -0,0 +1,36 @@
+use cosmoline::badge;
+
+#[test]
+fn coverage_colors() {
+    let high = badge::coverage("coverage", 93.25);
+    assert!(high.contains(r#"aria-label="coverage: 93.2%""#), "{}", high);
+    assert!(high.contains(&format!(r#"fill="{}""#, badge::GREEN)));
+
+    assert!(badge::coverage("coverage", 80.0).contains(&format!(r#"fill="{}""#, badge::YELLOW)));
+    assert!(badge::coverage("coverage", 12.0).contains(&format!(r#"fill="{}""#, badge::RED)));
+}
+
+#[test]
+fn delta_signs() {
+    assert!(badge::delta("coverage Δ", 0.44).contains(">+0.4%<"));
+    assert!(badge::delta("coverage Δ", -1.26).contains(">-1.3%<"));
+    assert!(badge::delta("coverage Δ", -0.01).contains(">±0.0%<"));
+}
+
+#[test]
+fn endpoint() {
+    let json: serde_json::Value = serde_json::from_str(&badge::endpoint("coverage", 93.25)).unwrap();
+    assert_eq!(json, serde_json::json!({
+        "schemaVersion": 1,
+        "label": "coverage",
+        "message": "93.2%",
+        "color": "4c1",
+    }));
+}
+
+#[test]
+fn escaped() {
+    let svg = badge::coverage("a<b", 50.0);
+    assert!(svg.contains("a&lt;b"));
+    assert!(!svg.contains("a<b"));
+}
@@ -0,0 +1,25 @@
+//! The command opening the report.
+
+use std::ffi::OsString;
+use std::path::Path;
+
+use cosmoline::browser;
+
+#[test]
+fn browser_from_the_environment() {
+    let command = browser::command(Some(OsString::from("firefox")), Path::new("report/index.html"));
+    assert_eq!(command.get_program(), "firefox");
+    assert_eq!(command.get_args().collect::<Vec<_>>(), ["report/index.html"]);
+}
+
+#[test]
+fn platform_default() {
+    let command = browser::command(Some(OsString::new()), Path::new("report/index.html"));
+    let expected = match cfg!(target_os = "macos") {
+        true => "open",
+        false if cfg!(windows) => "cmd",
+        false => "xdg-open",
+    };
+    assert_eq!(command.get_program(), expected);
+    assert_eq!(command.get_args().last().unwrap(), "report/index.html");
+}
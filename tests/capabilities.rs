@@ -0,0 +1,11 @@
+use cosmoline::capabilities::capabilities;
+
+#[test]
+fn lists_every_format() {
+    let capabilities = capabilities();
+
+    assert_eq!(capabilities.input_formats, ["llvm", "lcov", "cobertura"]);
+    assert_eq!(capabilities.output_formats, ["cobertura", "github-annotations", "html", "json", "json-summary", "lcov", "markdown", "reachability", "text", "unique"]);
+    assert!(capabilities.templates.contains(&"index"));
+    assert_eq!(capabilities.schemas["manifest.json"], 1);
+}
@@ -0,0 +1,94 @@
+use std::collections::{BTreeSet, HashMap};
+
+use cosmoline::anonymize::Anonymizer;
+use cosmoline::changes::{self, UncoveredChange};
+use cosmoline::export::GithubAnnotations;
+use cosmoline::git::parse_changed_lines;
+
+mod common;
+use common::model;
+
+#[test]
+fn diff_hunks() {
+    let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -3 +3,2 @@ pub fn classify(n: i32) -> &'static str {
+@@ -10,2 +11 @@
+@@ -20,3 +20,0 @@
+diff --git a/src/old.rs b/src/old.rs
+--- a/src/old.rs
++++ /dev/null
+@@ -1,5 +0,0 @@
+";
+    let changed = parse_changed_lines(diff).unwrap();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed["src/lib.rs"].iter().copied().collect::<Vec<_>>(), vec![3, 4, 11]);
+}
+
+#[test]
+fn uncovered_changed_lines() {
+    let _anonymizer = Anonymizer::new(false, None);
+    let model = model();
+
+    let mut changed = HashMap::new();
+    changed.insert("src/lib.rs".to_string(), (1..=10).collect::<BTreeSet<i64>>());
+    let uncovered = changes::uncovered(&model, &changed);
+    assert!(uncovered.contains(&UncoveredChange { file: "src/lib.rs".into(), line_start: 7, line_end: 7 }));
+    assert!(uncovered.iter().all(|c| c.file == "src/lib.rs" && c.line_end <= 10));
+
+    let rdjson: serde_json::Value = serde_json::from_str(&changes::rdjson(&uncovered, "")).unwrap();
+    assert_eq!(rdjson["source"]["name"], "cosmoline");
+    assert_eq!(rdjson["diagnostics"].as_array().unwrap().len(), uncovered.len());
+    assert_eq!(rdjson["diagnostics"][0]["location"]["path"], "src/lib.rs");
+
+    // A crate in a subdirectory of the repository
+    let rdjson: serde_json::Value = serde_json::from_str(&changes::rdjson(&uncovered, "crates/sample/")).unwrap();
+    assert_eq!(rdjson["diagnostics"][0]["location"]["path"], "crates/sample/src/lib.rs");
+}
+
+#[test]
+fn coverage_of_changed_lines() {
+    let _anonymizer = Anonymizer::new(false, None);
+    let model = model();
+
+    let mut changed = HashMap::new();
+    changed.insert("src/lib.rs".to_string(), (1..=10).collect::<BTreeSet<i64>>());
+    // Changed, but not reported
+    changed.insert("README.md".to_string(), (1..=3).collect::<BTreeSet<i64>>());
+    let diff = changes::diff_coverage(&model, "main...HEAD", &changed);
+
+    assert_eq!(diff.range, "main...HEAD");
+    assert_eq!(diff.files.len(), 1);
+    let file = &diff.files[0];
+    assert_eq!((file.name.as_str(), file.page.as_str()), ("src/lib.rs", "src_lib.rs.html"));
+    assert!(file.uncovered.contains(&(7, 7)), "{:?}", file.uncovered);
+    assert_eq!((diff.summary.count, diff.summary.covered), (file.summary.count, file.summary.covered));
+
+    // The same lines --reviewdog points at
+    let uncovered = changes::uncovered(&model, &changed);
+    let runs = uncovered.iter().map(|c| (c.line_start, c.line_end)).collect::<Vec<_>>();
+    assert_eq!(file.uncovered, runs);
+    let lines = runs.iter().map(|(start, end)| end - start + 1).sum::<i64>() as u64;
+    assert_eq!(diff.summary.count - diff.summary.covered, lines);
+}
+
+#[test]
+fn github_annotations() {
+    let mut model = model();
+    assert!(GithubAnnotations::default().commands(&model).is_err());
+
+    let mut changed = HashMap::new();
+    changed.insert("src/lib.rs".to_string(), (1..=10).collect::<BTreeSet<i64>>());
+    model.diff = Some(changes::diff_coverage(&model, "main...HEAD", &changed));
+
+    let commands = GithubAnnotations::default().commands(&model).unwrap();
+    let runs = &model.diff.as_ref().unwrap().files[0].uncovered;
+    assert_eq!(commands.lines().count(), runs.len());
+    assert!(commands.contains("::warning file=src/lib.rs,line=7,endLine=7,title=Uncovered change::Line not covered\n"), "{}", commands);
+
+    // A crate in a subdirectory of the repository
+    let commands = GithubAnnotations::default().path_prefix("crates/sample/").commands(&model).unwrap();
+    assert!(commands.contains("::warning file=crates/sample/src/lib.rs,line=7,"), "{}", commands);
+}
@@ -0,0 +1,60 @@
+//! The charts in `cosmoline::chart` are total: whatever numbers they're given,
+//! they draw something sensible.
+
+use proptest::prelude::*;
+
+use cosmoline::chart::{self, Tile};
+
+fn tiles() -> impl Strategy<Value = Tile> {
+    let leaf = (any::<String>(), 0..1_000_000u64).prop_map(|(label, size)| tile(label, size, vec![]));
+    leaf.prop_recursive(3, 32, 6, |inner| {
+        (any::<String>(), proptest::collection::vec(inner, 0..6)).prop_map(|(label, children)| {
+            let size = children.iter().map(|c| c.size).sum();
+            tile(label, size, children)
+        })
+    })
+}
+
+fn tile(label: String, size: u64, children: Vec<Tile>) -> Tile {
+    Tile { title: label.clone(), label, size, class: "green", href: None, children }
+}
+
+fn drawn(tile: &Tile) -> usize {
+    1 + tile.children.iter().filter(|c| c.size > 0).map(drawn).sum::<usize>()
+}
+
+proptest! {
+    #[test]
+    fn sparkline_is_total(values in proptest::collection::vec(proptest::num::f64::ANY, 0..20)) {
+        let sparkline = chart::sparkline(&values);
+
+        prop_assert_eq!(sparkline.is_some(), values.len() >= 2);
+        if let Some(svg) = sparkline {
+            prop_assert!(!svg.contains("NaN"));
+        }
+    }
+
+    #[test]
+    fn bars_are_total(values in proptest::collection::vec(any::<u64>(), 0..12), label in any::<String>()) {
+        let bars = values.iter().map(|value| (label.as_str(), *value)).collect::<Vec<_>>();
+        let svg = chart::bars(&bars);
+
+        prop_assert!(!svg.contains("NaN") && !svg.contains("=\"-"), "{}", svg);
+        prop_assert_eq!(svg.matches("<rect").count(), values.len());
+    }
+
+    #[test]
+    fn donut_is_total(percent in proptest::num::f64::ANY) {
+        let svg = chart::donut(percent, "green");
+
+        prop_assert!(!svg.contains("NaN") && !svg.contains("inf"), "{}", svg);
+    }
+
+    #[test]
+    fn treemap_is_total(root in tiles()) {
+        let svg = chart::treemap(&root);
+
+        prop_assert!(!svg.contains("NaN") && !svg.contains("inf") && !svg.contains("=\"-"), "{}", svg);
+        prop_assert_eq!(svg.matches("<rect").count(), drawn(&root));
+    }
+}
@@ -0,0 +1,84 @@
+use cosmoline::check::{self, Level, Metric, Threshold};
+
+mod common;
+use common::model;
+
+fn threshold(metric: Metric, minimum: f64, level: Level) -> Threshold {
+    Threshold { metric, minimum, level }
+}
+
+#[test]
+fn summary_of_failed_thresholds() {
+    let model = model();
+    let thresholds = vec![
+        threshold(Metric::Lines, 100.0, Level::Fail),
+        // Only src/shapes.rs is below
+        threshold(Metric::Lines, 60.0, Level::Fail),
+        threshold(Metric::Functions, 0.0, Level::Fail),
+        // Only warns, so it isn't why the run failed
+        threshold(Metric::Branches, 100.0, Level::Warn),
+    ];
+    let mut outcomes = check::evaluate(&thresholds, &model.totals);
+    outcomes.extend(check::evaluate_files(&thresholds, &model.files));
+
+    assert_eq!(
+        check::failure_summary(&outcomes).unwrap(),
+        "Coverage too low: line coverage is below 100.0% for the total and 2 files; line coverage is below 60.0% for 1 file"
+    );
+}
+
+#[test]
+fn nothing_to_summarize_when_thresholds_are_met() {
+    let model = model();
+    let thresholds = vec![threshold(Metric::Lines, 0.0, Level::Fail), threshold(Metric::Branches, 100.0, Level::Warn)];
+
+    assert_eq!(check::failure_summary(&check::evaluate(&thresholds, &model.totals)), None);
+}
+
+#[test]
+fn only_the_totals_fail_the_run_by_default() {
+    let model = model();
+    // The total is above, src/shapes.rs below
+    let thresholds = vec![threshold(Metric::Lines, 60.0, Level::Fail)];
+
+    let outcomes = check::evaluate(&thresholds, &model.totals);
+    assert_eq!(outcomes.len(), 1);
+    assert_eq!(outcomes[0].name, check::TOTAL);
+    assert_eq!(check::failure_summary(&outcomes), None);
+
+    let files = check::evaluate_files(&thresholds, &model.files);
+    assert_eq!(files.len(), model.files.len());
+    assert_eq!(
+        check::failure_summary(&files).unwrap(),
+        "Coverage too low: line coverage is below 60.0% for 1 file"
+    );
+}
+
+#[test]
+fn junit_cases_are_named_by_threshold() {
+    let model = model();
+    let thresholds = vec![threshold(Metric::Lines, 60.0, Level::Fail), threshold(Metric::Lines, 100.0, Level::Warn)];
+
+    let xml = check::junit(&check::evaluate(&thresholds, &model.totals));
+    let total = format!("{:.1}", model.totals.lines.percent);
+    assert_eq!(xml, format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="cosmoline" tests="2" failures="0" skipped="0">
+  <testsuite name="coverage.lines" tests="2" failures="0" skipped="0">
+    <testcase classname="coverage.lines" name="total (fail under 60.0%)"></testcase>
+    <testcase classname="coverage.lines" name="total (warn under 100.0%)"><system-out>line coverage for total is {total}%, below 100.0%</system-out></testcase>
+  </testsuite>
+</testsuites>
+"#,
+        total = total
+    ));
+
+    let mut outcomes = check::evaluate(&thresholds, &model.totals);
+    outcomes.extend(check::evaluate_files(&thresholds, &model.files));
+    let xml = check::junit(&outcomes);
+    let names = xml.match_indices(" name=\"").map(|(i, _)| xml[i + 7..].split('"').next().unwrap()).collect::<Vec<_>>();
+    let cases = &names[2..];
+    assert_eq!(cases.len(), 2 * (1 + model.files.len()));
+    assert_eq!(cases.iter().collect::<std::collections::HashSet<_>>().len(), cases.len());
+    assert!(xml.contains(r#"<testcase classname="coverage.lines" name="src/shapes.rs (fail under 60.0%)"><failure message="line coverage for src/shapes.rs is "#));
+}
@@ -0,0 +1,39 @@
+//! Helpers shared by the integration tests.  Each test only uses some of
+//! them.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use cosmoline::anonymize::Anonymizer;
+use cosmoline::model::{ModelOptions, ReportModel};
+use cosmoline::SummaryReport;
+
+/// A directory of `tests/fixtures`, e.g. `async`.
+pub fn fixture_dir(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+/// The sample crate most tests report on, with its exports.
+pub fn fixture() -> PathBuf {
+    fixture_dir("sample")
+}
+
+/// The model of the llvm export at `input_path`.
+pub fn build(input_path: &Path, options: &ModelOptions) -> ReportModel {
+    let contents = std::fs::read_to_string(input_path).unwrap();
+    let report: SummaryReport = serde_json::from_str(&contents).unwrap();
+    ReportModel::build(&report.data[0], options, &Anonymizer::new(false, None), input_path).unwrap()
+}
+
+/// The model of the sample's `llvm-22.json` with the default options.
+pub fn model() -> ReportModel {
+    build(&fixture().join("llvm-22.json"), &ModelOptions::default())
+}
+
+/// A directory named after `name` and this test process under the system's
+/// temporary one, made if it's missing.
+pub fn output_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cosmoline-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
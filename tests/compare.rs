@@ -0,0 +1,101 @@
+//! Two labeled inputs rendered side by side.
+
+use cosmoline::anonymize::Anonymizer;
+use cosmoline::export::{Exporter, Html};
+use cosmoline::render::Assets;
+
+mod common;
+use common::{fixture, model, output_dir};
+
+#[test]
+fn lines_only_one_label_ran() {
+    let mut fuzz = model();
+    let line = fuzz.files[0].lines.iter_mut().find(|l| l.mapped && l.count > 0).unwrap();
+    line.count = 0;
+    let line = line.line;
+
+    let anonymizer = Anonymizer::new(false, None);
+    let output_path = output_dir("compare");
+    Html::new(Assets::new(true, true), &anonymizer, &fixture())
+        .unwrap()
+        .labels(vec![("unit".to_string(), model()), ("fuzz".to_string(), fuzz)])
+        .compare_labels("unit", "fuzz")
+        .export(&model(), &output_path)
+        .unwrap();
+
+    let page = std::fs::read_to_string(output_path.join("unit~20vs~20fuzz@src_lib.rs.html")).unwrap();
+    assert!(page.contains("1 lines only ran in unit, 0 only in fuzz."));
+    assert_eq!(page.matches(r#"data-ran-in="left""#).count(), 1);
+    assert!(page.contains(&format!(r#"<tr data-ran-in="left">
+                    <td class="count">{}</td>"#, line)));
+
+    let index = std::fs::read_to_string(output_path.join("index.html")).unwrap();
+    assert!(index.contains(r#"href="unit~20vs~20fuzz@index.html">unit vs fuzz</a>"#));
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn regions_show_both_counts() {
+    let mut fuzz = model();
+    let function = fuzz.functions.iter_mut().find(|f| f.name == "sample::classify").unwrap();
+    let region = function.regions.iter_mut().find(|r| r.region_kind == 0 && r.execution_count > 0).unwrap();
+    let count = region.execution_count;
+    region.execution_count = 0;
+
+    let anonymizer = Anonymizer::new(false, None);
+    let output_path = output_dir("compare-regions");
+    Html::new(Assets::new(true, true), &anonymizer, &fixture())
+        .unwrap()
+        .labels(vec![("unit".to_string(), model()), ("fuzz".to_string(), fuzz)])
+        .compare_labels("unit", "fuzz")
+        .export(&model(), &output_path)
+        .unwrap();
+
+    let page = std::fs::read_to_string(output_path.join("unit~20vs~20fuzz@src_lib.rs.html")).unwrap();
+    assert!(page.contains(&format!(
+        r#"<span class="region yellow" data-ran-in="left" data-left="{count}" data-right="0"><span class="counts" title="unit {count}, fuzz 0">{count}&#x2009;|&#x2009;0</span>"#,
+        count = count
+    )));
+    assert!(page.contains(r#"<span class="region green" data-ran-in="both""#));
+    // Regions nobody ran are marked too, lines only by their counts
+    assert!(page.contains(r#"<span class="region red" data-ran-in="neither" data-left="0" data-right="0">"#));
+    assert!(!page.contains("<tr class="));
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn stylesheet_is_an_asset() {
+    let anonymizer = Anonymizer::new(false, None);
+    let output_path = output_dir("compare-stylesheet");
+    Html::new(Assets::new(false, true), &anonymizer, &fixture())
+        .unwrap()
+        .labels(vec![("unit".to_string(), model()), ("fuzz".to_string(), model())])
+        .compare_labels("unit", "fuzz")
+        .export(&model(), &output_path)
+        .unwrap();
+
+    let page = std::fs::read_to_string(output_path.join("unit~20vs~20fuzz@src_lib.rs.html")).unwrap();
+    assert!(!page.contains("<style"));
+    let href = page.split(r#"<link rel="stylesheet" href=""#).nth(2).unwrap().split('"').next().unwrap();
+    assert!(href.starts_with("compare-") && href.ends_with(".css"));
+    assert!(std::fs::read_to_string(output_path.join(href)).unwrap().contains(".compare td.source"));
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn unknown_labels_are_an_error() {
+    let anonymizer = Anonymizer::new(false, None);
+    let output_path = output_dir("compare-unknown");
+    let result = Html::new(Assets::new(true, true), &anonymizer, &fixture())
+        .unwrap()
+        .labels(vec![("unit".to_string(), model())])
+        .compare_labels("unit", "fuzz")
+        .export(&model(), &output_path);
+
+    assert_eq!(result.unwrap_err().to_string(), "No input labeled `fuzz' to compare");
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
@@ -0,0 +1,102 @@
+//! Command line arguments from `cosmoline.toml`.
+
+use std::collections::BTreeMap;
+
+use cosmoline::config::Config;
+
+/// Argument names by long name, as the command line has them.
+fn names() -> BTreeMap<String, String> {
+    [
+        ("input", "input"),
+        ("fail-under-lines", "fail-under-lines"),
+        ("sidebar", "sidebar"),
+        ("no-highlight", "no-highlight"),
+        ("palette", "palette"),
+        ("include", "include"),
+        ("include-glob", "include"),
+        ("output-format", "output-format"),
+        ("emit", "output-format"),
+    ]
+    .iter()
+    .map(|(long, name)| (long.to_string(), name.to_string()))
+    .collect()
+}
+
+#[test]
+fn arguments() {
+    let config = Config::parse(
+        r#"
+input = ["unit.json", "doc.json"]
+fail-under-lines = 80
+goal-lines = "75,90"
+min-ratio = 0.5
+sidebar = true
+no-highlight = false
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        config.args(|_| false).unwrap(),
+        [
+            "--fail-under-lines=80",
+            "--goal-lines=75,90",
+            "--input=unit.json",
+            "--input=doc.json",
+            "--min-ratio=0.5",
+            "--sidebar",
+        ]
+    );
+}
+
+#[test]
+fn command_line_wins() {
+    let config = Config::parse("include = [\"src/**\"]\npalette = \"monochrome\"\n").unwrap();
+    assert_eq!(config.args(|name| name == "include").unwrap(), ["--palette=monochrome"]);
+}
+
+#[test]
+fn invalid() {
+    let config = Config::parse("config = \"other.toml\"\n").unwrap();
+    assert!(config.args(|_| false).is_err());
+
+    let config = Config::parse("[exclude]\nglob = \"tests/**\"\n").unwrap();
+    assert_eq!(
+        config.args(|_| false).unwrap_err(),
+        "`exclude' should be a string, a number, a boolean, or a list of them"
+    );
+}
+
+#[test]
+fn environment() {
+    let vars = [
+        ("COSMOLINE_INPUT", "unit.json\ndoc.json\n"),
+        ("COSMOLINE_FAIL_UNDER_LINES", "80"),
+        ("COSMOLINE_SIDEBAR", "true"),
+        ("COSMOLINE_NO_HIGHLIGHT", "false"),
+        ("COSMOLINE_PALETTE", ""),
+        ("COSMOLINE_CONFIG", "ci.toml"),
+        // Not arguments, e.g. set for another tool
+        ("COSMOLINE_VERSION", "1.2"),
+        ("COSMOLINE_", "x"),
+        ("HOME", "/root"),
+    ];
+    let env = Config::from_env(vars.iter().map(|(var, value)| (var.to_string(), value.to_string())), &names());
+
+    assert_eq!(
+        env.args(|name| name == "sidebar").unwrap(),
+        ["--fail-under-lines=80", "--input=unit.json", "--input=doc.json"]
+    );
+}
+
+#[test]
+fn aliases() {
+    let vars = [("COSMOLINE_EMIT", "lcov"), ("COSMOLINE_INCLUDE_GLOB", "src/**")];
+    let env = Config::from_env(vars.iter().map(|(var, value)| (var.to_string(), value.to_string())), &names());
+    assert_eq!(env.args.keys().collect::<Vec<_>>(), ["include", "output-format"]);
+    // Given on the command line by either name, which clap counts under the argument's
+    assert_eq!(env.args(|name| name == "include").unwrap(), ["--output-format=lcov"]);
+
+    let config = Config::parse("include-glob = [\"src/**\"]\nemit = \"json\"\nunknown = 1\n").unwrap().canonical(&names());
+    assert_eq!(config.args(|name| name == "output-format").unwrap(), ["--include=src/**", "--unknown=1"]);
+}
@@ -0,0 +1,107 @@
+//! `cosmoline diff`, between two exports of the sample crate.
+
+use cosmoline::check::{Goals, Level, Metric, Threshold};
+use cosmoline::diff::{self, FileDelta, Limits};
+use cosmoline::input::{InputFormat, LlvmJson};
+use cosmoline::merge::{self, MergeMode};
+use cosmoline::SummaryReport;
+use serde_json::Value;
+
+mod common;
+
+fn read(fixture: &str) -> String {
+    std::fs::read_to_string(common::fixture().join(fixture)).unwrap()
+}
+
+#[test]
+fn json_deltas_and_verdicts() {
+    let (old, new) = (read("llvm-14.json"), read("llvm-22.json"));
+    let old: SummaryReport = serde_json::from_str(&old).unwrap();
+    let mut new: SummaryReport = serde_json::from_str(&new).unwrap();
+    // A file only in the new export
+    let mut added = new.data[0].files.remove(0);
+    added.filename = "src/added.rs";
+    new.data[0].files.push(added);
+
+    let mut deltas = diff::compare(&old.data[0], &new.data[0]);
+    let total = FileDelta { name: "Total", old: Some(80.0), new: Some(new.data[0].totals.lines.percent) };
+    let limits = Limits {
+        fail_under_lines: Some(Threshold::parse(Metric::Lines, "0", Level::Fail, &Goals::default()).unwrap()),
+        max_decrease: Some(1.0),
+    };
+    let verdicts = diff::verdicts(&deltas, &total, &limits);
+    let json: Value = serde_json::from_str(&diff::json(&deltas, &total, &verdicts).unwrap()).unwrap();
+
+    assert_eq!(json["version"], diff::SCHEMA_VERSION);
+    assert_eq!(json["added"], serde_json::json!(["src/added.rs"]));
+    assert_eq!(json["removed"], serde_json::json!([old.data[0].files[0].filename]));
+    assert_eq!(json["files"].as_array().unwrap().len(), deltas.len());
+    let shapes = json["files"].as_array().unwrap().iter().find(|f| f["name"] == "src/shapes.rs").unwrap();
+    let change = shapes["new"].as_f64().unwrap() - shapes["old"].as_f64().unwrap();
+    assert_eq!(shapes["change"].as_f64(), Some(change));
+
+    // Every file in the new export meets 0%, and only the total's drop from
+    // 80% is measured against the decrease besides the files in both
+    let failed: Vec<_> = json["verdicts"].as_array().unwrap().iter().filter(|v| v["passed"] == false).collect();
+    let dropped = total.old.unwrap() - total.new.unwrap() > 1.0;
+    assert_eq!(failed.iter().any(|v| v["name"] == "Total" && v["check"] == "max-decrease"), dropped);
+    assert!(failed.iter().all(|v| v["check"] == "max-decrease"));
+    assert_eq!(json["passed"], failed.is_empty());
+
+    let both = deltas.iter().filter(|d| d.old.is_some() && d.new.is_some()).count();
+    let present = deltas.iter().filter(|d| d.new.is_some()).count();
+    assert_eq!(verdicts.len(), (1 + present) + (1 + both));
+
+    // Nothing to check, nothing fails
+    deltas.retain(|d| d.new.is_some());
+    assert!(diff::verdicts(&deltas, &total, &Limits::default()).is_empty());
+}
+
+#[test]
+fn every_mapping_of_an_export() {
+    let single = read("llvm-22.json");
+    // The same coverage as if each file came from a test binary of its own
+    let mut export: Value = serde_json::from_str(&single).unwrap();
+    let mut second = export["data"][0].clone();
+    let files = export["data"][0]["files"].as_array_mut().unwrap();
+    second["files"] = Value::Array(files.split_off(1));
+    second["functions"] = Value::Array(vec![]);
+    export["data"].as_array_mut().unwrap().push(second);
+    let split = serde_json::to_string(&export).unwrap();
+
+    // Merging recomputes the summaries, which can differ a little from
+    // llvm's own
+    let single = merge::merge(LlvmJson.read(&single).unwrap(), MergeMode::Sum);
+    let split = diff::read(&split).unwrap();
+    let deltas = diff::compare(&single, &split);
+    assert_eq!(deltas.len(), 2);
+    for delta in deltas {
+        assert_eq!(delta.old, delta.new, "{}", delta.name);
+    }
+    assert_eq!(single.totals.lines.count, split.totals.lines.count);
+    assert_eq!(single.totals.lines.percent, split.totals.lines.percent);
+}
+
+#[test]
+fn total_of_the_compared_files() {
+    let (old, new) = (read("llvm-14.json"), read("llvm-22.json"));
+    let old: SummaryReport = serde_json::from_str(&old).unwrap();
+    let mut new: SummaryReport = serde_json::from_str(&new).unwrap();
+    // Left out of the comparison, and so of its total, though llvm counts it
+    let outside = new.data[0].files.iter_mut().find(|f| f.filename == "src/shapes.rs").unwrap();
+    outside.filename = "tests/shapes.rs";
+
+    let deltas = diff::compare(&old.data[0], &new.data[0]);
+    assert!(deltas.iter().all(|d| d.name != "tests/shapes.rs"));
+
+    let lines = |mapping: &cosmoline::CoverageMapping| {
+        let files = mapping.files.iter().filter(|f| f.filename.starts_with("src/"));
+        let (count, covered) = files.fold((0, 0), |(count, covered), f| (count + f.summary.lines.count, covered + f.summary.lines.covered));
+        covered as f64 * 100.0 / count as f64
+    };
+    let total = diff::total(&old.data[0], &new.data[0]);
+    assert_eq!(total.name, "Total");
+    assert_eq!(total.old, Some(lines(&old.data[0])));
+    assert_eq!(total.new, Some(lines(&new.data[0])));
+    assert_ne!(total.new, Some(new.data[0].totals.lines.percent));
+}
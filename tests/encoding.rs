@@ -0,0 +1,29 @@
+//! Source files decode to every one of their lines, whatever the encoding.
+
+use cosmoline::encoding::{decode, SourceEncoding};
+
+#[test]
+fn auto_keeps_utf8() {
+    assert_eq!(decode("caf\u{e9}\n".as_bytes(), SourceEncoding::Auto), "caf\u{e9}\n");
+}
+
+#[test]
+fn auto_strips_utf8_bom() {
+    assert_eq!(decode(b"\xef\xbb\xbfint x;\n", SourceEncoding::Auto), "int x;\n");
+}
+
+#[test]
+fn auto_falls_back_to_latin1() {
+    assert_eq!(decode(b"/* caf\xe9 */\nint x;\n", SourceEncoding::Auto), "/* caf\u{e9} */\nint x;\n");
+}
+
+#[test]
+fn auto_reads_utf16() {
+    assert_eq!(decode(b"\xff\xfei\x00n\x00t\x00\n\x00", SourceEncoding::Auto), "int\n");
+    assert_eq!(decode(b"\x00i\x00n\x00t\x00\n", SourceEncoding::Auto), "int\n");
+}
+
+#[test]
+fn utf8_replaces_invalid_bytes() {
+    assert_eq!(decode(b"a\xffb\nc\n", SourceEncoding::Utf8), "a\u{fffd}b\nc\n");
+}
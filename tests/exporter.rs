@@ -0,0 +1,338 @@
+//! Formats implemented outside of the crate, the way a third party would, and
+//! built-in ones that need more than a model to set up.
+
+use std::error::Error as StdError;
+use std::fmt::Write;
+use std::path::Path;
+
+use cosmoline::anonymize::Anonymizer;
+use cosmoline::export::{Exporter, Html, Reachability, Registry, Unique};
+use cosmoline::model::{ModelOptions, ReportModel};
+use cosmoline::render::{Assets, Engine, Templates};
+use cosmoline::{verify, SummaryReport};
+
+mod common;
+use common::{build, fixture, model, output_dir};
+
+/// One `file,lines_covered,lines_count` row per file.
+struct Csv;
+
+impl Exporter for Csv {
+    fn name(&self) -> &str {
+        "csv"
+    }
+
+    fn export(&self, model: &ReportModel, output_path: &Path) -> Result<(), Box<dyn StdError>> {
+        let mut out = String::from("file,lines_covered,lines_count\n");
+        for file in model.all_files() {
+            writeln!(out, "{},{},{}", file.name, file.summary.lines.covered, file.summary.lines.count)?;
+        }
+        std::fs::write(output_path.join("coverage.csv"), out)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn third_party_exporter() {
+    let model = model();
+
+    let mut registry = Registry::builtin();
+    registry.register(Csv);
+    assert!(registry.names().any(|name| name == "csv"));
+
+    let output_path = output_dir("csv");
+    registry.get("csv").unwrap().export(&model, &output_path).unwrap();
+
+    let csv = std::fs::read_to_string(output_path.join("coverage.csv")).unwrap();
+    assert_eq!(csv, "file,lines_covered,lines_count\nsrc/lib.rs,15,19\nsrc/shapes.rs,8,15\n");
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn reachability_by_label() {
+    let model = |path: &Path| build(path, &ModelOptions::default());
+    let merged = model(&fixture().join("llvm-22.json"));
+    let labels = vec![
+        ("unit".to_string(), model(&fixture().join("llvm-22.json"))),
+        ("branch".to_string(), model(&fixture().join("llvm-22-branch.json"))),
+    ];
+
+    let output_path = output_dir("reachability");
+    Reachability::new(&labels).export(&merged, &output_path).unwrap();
+
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(output_path.join("reachability.json")).unwrap()).unwrap();
+    assert_eq!(json["labels"], serde_json::json!(["unit", "branch"]));
+    let file = &merged.files[0];
+    for line in file.lines.iter().filter(|l| l.mapped) {
+        let expected = labels
+            .iter()
+            .filter(|(_, model)| model.files.iter().any(|f| f.name == file.name && f.lines.iter().any(|l| l.line == line.line && l.count > 0)))
+            .map(|(label, _)| label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(json["files"][&file.name][line.line.to_string()], serde_json::json!(expected), "line {}", line.line);
+    }
+    assert_eq!(json["files"][&file.name].as_object().unwrap().len(), file.lines.iter().filter(|l| l.mapped).count());
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn unique_coverage_by_label() {
+    // Runs nothing in shapes.rs, and skips a line of lib.rs
+    let mut lib = model();
+    lib.files[0].lines.iter_mut().find(|l| l.mapped && l.count > 0).unwrap().count = 0;
+    for line in lib.files[1].lines.iter_mut() {
+        line.count = 0;
+    }
+    lib.functions.retain(|f| f.file.as_deref() == Some("src/lib.rs"));
+    let labels = vec![("lib".to_string(), lib), ("all".to_string(), model())];
+
+    let output_path = output_dir("unique");
+    Unique::new(&labels).export(&model(), &output_path).unwrap();
+
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(output_path.join("unique.json")).unwrap()).unwrap();
+    let tests = json["tests"].as_array().unwrap();
+    assert_eq!(tests[0]["label"], "all");
+    assert_eq!(tests[0]["unique_lines"], 1 + labels[1].1.files[1].lines.iter().filter(|l| l.mapped && l.count > 0).count());
+    assert_eq!(tests[0]["files"], serde_json::json!(["src/shapes.rs"]));
+    let mut shapes = labels[1].1.functions
+        .iter()
+        .filter(|f| f.file.as_deref() == Some("src/shapes.rs") && f.count > 0)
+        .map(|f| f.name.as_str())
+        .collect::<Vec<_>>();
+    shapes.sort_unstable();
+    assert_eq!(tests[0]["functions"], serde_json::json!(shapes));
+    assert_eq!(tests[1], serde_json::json!({ "label": "lib", "unique_lines": 0, "files": [], "functions": [] }));
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn single_file() {
+    let anonymizer = Anonymizer::new(false, None);
+    let model = model();
+
+    let output_path = output_dir("single-file");
+    Html::new(Assets::new(true, true), &anonymizer, &fixture())
+        .unwrap()
+        .single_file()
+        .export(&model, &output_path)
+        .unwrap();
+
+    let mut written = std::fs::read_dir(&output_path).unwrap().map(|entry| entry.unwrap().file_name()).collect::<Vec<_>>();
+    written.sort();
+    assert_eq!(written, ["index.html", "manifest.json"]);
+
+    let page = std::fs::read_to_string(output_path.join("index.html")).unwrap();
+    assert!(!page.contains("style.css"), "{}", page);
+    assert!(page.contains(r##"<a href="#file-src_lib.rs">src/lib.rs</a>"##), "{}", page);
+    assert!(page.contains(r#"<details class="file" id="file-src_shapes.rs">"#), "{}", page);
+    assert!(page.contains(r#"data-covered="false""#));
+    assert_eq!(verify::dead_links(&output_path, &["index.html"], None).unwrap(), vec![]);
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn json_summary() {
+    let model = model();
+
+    let output_path = output_dir("json-summary");
+    Registry::builtin().get("json-summary").unwrap().export(&model, &output_path).unwrap();
+
+    let summary: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(output_path.join("coverage-summary.json")).unwrap()).unwrap();
+    assert_eq!(summary["version"], 1);
+    assert_eq!(summary["files"].as_object().unwrap().keys().collect::<Vec<_>>(), ["src/lib.rs", "src/shapes.rs"]);
+    assert_eq!(summary["files"]["src/lib.rs"]["lines"]["covered"], 15);
+    assert_eq!(summary["files"]["src/lib.rs"]["lines"]["count"], 19);
+    assert_eq!(summary["totals"]["lines"]["covered"], model.totals.lines.covered);
+    assert!(summary["totals"]["branches"]["percent"].is_number());
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn preview() {
+    let anonymizer = Anonymizer::new(false, None);
+    let model = model();
+
+    let output_path = output_dir("preview");
+    Html::new(Assets::new(true, true), &anonymizer, &fixture())
+        .unwrap()
+        .preview(1)
+        .export(&model, &output_path)
+        .unwrap();
+
+    // Only the less covered of the two files gets a page
+    assert!(output_path.join("src_shapes.rs.html").is_file());
+    assert!(!output_path.join("src_lib.rs.html").exists());
+    assert!(!output_path.join("fn@sample~3a~3aclassify.html").exists());
+
+    let index = std::fs::read_to_string(output_path.join("index.html")).unwrap();
+    assert!(index.contains(r#"<a href="src_shapes.rs.html">src/shapes.rs</a>"#), "{}", index);
+    assert!(index.contains(r#"<a href="omitted.html">src/lib.rs</a>"#), "{}", index);
+    let functions = std::fs::read_to_string(output_path.join("functions.html")).unwrap();
+    assert!(functions.contains(r#"<a href="omitted.html">sample::classify</a>"#), "{}", functions);
+    let manifest = verify::manifest(&output_path).unwrap();
+    assert_eq!(verify::dead_links(&output_path, &verify::pages(&output_path, &manifest), None).unwrap(), vec![]);
+    let treemap = std::fs::read_to_string(output_path.join("treemap.html")).unwrap();
+    assert!(treemap.contains(r#"<a href="src_shapes.rs.html">"#), "{}", treemap);
+    assert!(treemap.contains(r#"<a href="omitted.html">"#), "{}", treemap);
+    assert!(!treemap.contains("src_lib.rs.html"), "{}", treemap);
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn only_changed_files() {
+    let anonymizer = Anonymizer::new(false, None);
+    let model = model();
+
+    let output_path = output_dir("only-changed");
+    Html::new(Assets::new(true, true), &anonymizer, &fixture())
+        .unwrap()
+        .changed_files(vec!["src/shapes.rs".to_string()].into_iter().collect())
+        .export(&model, &output_path)
+        .unwrap();
+    assert!(!output_path.join("src_lib.rs.html").exists());
+
+    // Function pages only link to the file pages that were rendered
+    let classify = std::fs::read_to_string(output_path.join("fn@sample~3a~3aclassify.html")).unwrap();
+    assert!(classify.contains("Defined in src/lib.rs, ran"), "{}", classify);
+    let fetch = std::fs::read_to_string(output_path.join("fn@sample~3a~3ashapes~3a~3afetch.html")).unwrap();
+    assert!(fetch.contains(r#"Defined in <a href="src_shapes.rs.html">src/shapes.rs</a>"#), "{}", fetch);
+
+    let manifest = verify::manifest(&output_path).unwrap();
+    assert_eq!(verify::dead_links(&output_path, &verify::pages(&output_path, &manifest), None).unwrap(), vec![]);
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn search_index() {
+    let anonymizer = Anonymizer::new(false, None);
+    let model = model();
+
+    let output_path = output_dir("search");
+    Html::new(Assets::new(true, true), &anonymizer, &fixture())
+        .unwrap()
+        .preview(1)
+        .export(&model, &output_path)
+        .unwrap();
+
+    let page = std::fs::read_to_string(output_path.join("search.html")).unwrap();
+    assert!(page.contains(r#"<script type="application/javascript" src="search-index.js"></script>"#), "{}", page);
+
+    let script = std::fs::read_to_string(output_path.join("search-index.js")).unwrap();
+    let json = script.strip_prefix("window.searchIndex = ").unwrap().trim_end().trim_end_matches(';');
+    let index: serde_json::Value = serde_json::from_str(json).unwrap();
+    assert_eq!(index["files"][0]["name"], "src/lib.rs");
+    assert_eq!(index["files"][0]["link"], "omitted.html");
+    assert_eq!(index["files"][1]["link"], "src_shapes.rs.html");
+
+    let classify = index["functions"].as_array().unwrap().iter().find(|f| f["name"] == "sample::classify").unwrap();
+    let function = model.functions.iter().find(|f| f.name == "sample::classify").unwrap();
+    assert_eq!(classify["symbol"], function.symbol.as_str());
+    assert_eq!(classify["link"], "omitted.html");
+    assert_eq!(classify["file"], "src/lib.rs");
+    assert_eq!(verify::dead_links(&output_path, &["search.html"], None).unwrap(), vec![]);
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn anonymized_search_index() {
+    let input_path = fixture().join("llvm-22.json");
+    let contents = std::fs::read_to_string(&input_path).unwrap();
+    let report: SummaryReport = serde_json::from_str(&contents).unwrap();
+    let anonymizer = Anonymizer::new(true, None);
+    let model = ReportModel::build(&report.data[0], &ModelOptions::default(), &anonymizer, &input_path).unwrap();
+
+    let output_path = output_dir("search-anonymized");
+    Html::new(Assets::new(true, true), &anonymizer, &fixture()).unwrap().export(&model, &output_path).unwrap();
+
+    // Neither the demangled names nor the mangled symbols give the code away
+    let script = std::fs::read_to_string(output_path.join("search-index.js")).unwrap();
+    for identifier in ["classify", "shapes", "fetch"] {
+        assert!(!script.contains(identifier), "{} in {}", identifier, script);
+    }
+    assert!(script.contains(r#""symbol":"fn_"#), "{}", script);
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn incremental() {
+    let anonymizer = Anonymizer::new(false, None);
+    let model = model();
+
+    let output_path = output_dir("incremental");
+    let template_dir = output_dir("incremental-templates");
+    let export_with = |settings: &str, templates: Templates| {
+        Html::new(Assets::new(true, true), &anonymizer, &fixture())
+            .unwrap()
+            .templates(templates)
+            .incremental(settings)
+            .export(&model, &output_path)
+            .unwrap();
+    };
+    let export = |settings: &str| export_with(settings, Templates::builtin().unwrap());
+    export("--incremental");
+    let manifest = std::fs::read_to_string(output_path.join("manifest.json")).unwrap();
+    let cache: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(output_path.join(".cosmoline-cache.json")).unwrap()).unwrap();
+    assert_eq!(cache["pages"].as_object().unwrap().keys().collect::<Vec<_>>(), ["src_lib.rs.html", "src_shapes.rs.html"]);
+
+    // Unchanged files keep their pages, and the manifest still lists them
+    std::fs::write(output_path.join("src_lib.rs.html"), "kept").unwrap();
+    export("--incremental");
+    assert_eq!(std::fs::read_to_string(output_path.join("src_lib.rs.html")).unwrap(), "kept");
+    assert_eq!(std::fs::read_to_string(output_path.join("manifest.json")).unwrap(), manifest);
+
+    // Other settings render every page again
+    export("--incremental --no-highlight");
+    assert_ne!(std::fs::read_to_string(output_path.join("src_lib.rs.html")).unwrap(), "kept");
+
+    // And so do other custom templates under the same settings
+    let custom = || Templates::builtin().unwrap().custom_dir(&template_dir, Engine::Handlebars).unwrap();
+    std::fs::write(template_dir.join("not_found.html.hbs"), "gone").unwrap();
+    export_with("--incremental", custom());
+    std::fs::write(output_path.join("src_lib.rs.html"), "kept").unwrap();
+    export_with("--incremental", custom());
+    assert_eq!(std::fs::read_to_string(output_path.join("src_lib.rs.html")).unwrap(), "kept");
+    std::fs::write(template_dir.join("not_found.html.hbs"), "really gone").unwrap();
+    export_with("--incremental", custom());
+    assert_ne!(std::fs::read_to_string(output_path.join("src_lib.rs.html")).unwrap(), "kept");
+
+    std::fs::remove_dir_all(output_path).unwrap();
+    std::fs::remove_dir_all(template_dir).unwrap();
+}
+
+#[test]
+fn sortable_index() {
+    let anonymizer = Anonymizer::new(false, None);
+    let model = model();
+
+    let output_path = output_dir("sortable");
+    Html::new(Assets::new(true, false), &anonymizer, &fixture())
+        .unwrap()
+        .export(&model, &output_path)
+        .unwrap();
+
+    let index = std::fs::read_to_string(output_path.join("index.html")).unwrap();
+    // The exact counts to sort by, not the rounded percentages shown
+    assert!(index.contains(r#"<tr data-name="src/lib.rs" data-lines="15/19" data-functions="3/4" data-instantiations="4/5">"#), "{}", index);
+    for key in ["name", "lines", "functions", "instantiations"].iter() {
+        assert!(index.contains(&format!(r#"data-sort="{}""#, key)), "{}", index);
+    }
+    assert!(index.contains(r#"<tbody id="files">"#), "{}", index);
+    assert!(index.contains(r#"<input type="search" id="file-filter" placeholder="Filter files">"#), "{}", index);
+
+    // With --no-inline-js the script is written next to the index
+    let script = index.split(r#"<script type="application/javascript" src=""#).nth(1).unwrap().split('"').next().unwrap();
+    assert!(script.starts_with("index-") && script.ends_with(".js"), "{}", script);
+    let script = std::fs::read_to_string(output_path.join(script)).unwrap();
+    assert!(script.contains("getElementById('file-filter')"), "{}", script);
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
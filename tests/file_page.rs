@@ -0,0 +1,75 @@
+//! The file pages, colored by region or by line.
+
+use cosmoline::anonymize::Anonymizer;
+use cosmoline::export::{Exporter, Html};
+use cosmoline::render::Assets;
+
+mod common;
+use common::{fixture, model, output_dir};
+
+/// Writes the HTML report of the sample fixture, returning the page of
+/// `src/shapes.rs` and the stylesheet.
+fn export(name: &str) -> (String, String) {
+    let anonymizer = Anonymizer::new(false, None);
+    let model = model();
+
+    let output_path = output_dir(&format!("file-page-{}", name));
+    Html::new(Assets::new(false, false), &anonymizer, &fixture()).unwrap().export(&model, &output_path).unwrap();
+
+    let page = std::fs::read_to_string(output_path.join("src_shapes.rs.html")).unwrap();
+    let style = std::fs::read_to_string(output_path.join("style.css")).unwrap();
+    std::fs::remove_dir_all(output_path).unwrap();
+    (page, style)
+}
+
+#[test]
+fn both_views_can_be_picked() {
+    let (page, _) = export("views");
+
+    // Regions by default; the radios come before the containers they style
+    let regions = page.find(r#"<input type="radio" name="view" class="view" id="view-regions" checked>"#).unwrap();
+    let lines = page.find(r#"<input type="radio" name="view" class="view" id="view-lines">"#).unwrap();
+    let container = page.find(r#"<div class="container">"#).unwrap();
+    assert!(regions < lines && lines < container, "{}", page);
+    assert!(page.contains(r#"Color by <label for="view-regions">region</label> or <label for="view-lines">line</label>."#));
+}
+
+#[test]
+fn lines_and_regions_carry_their_counts() {
+    let (page, _) = export("attributes");
+
+    // `assert!(!name.is_empty() && name.len() > 1 || false)` ran, its `false`
+    // didn't
+    let line = page.lines().find(|line| line.contains(r#"id="L32""#)).unwrap();
+    assert!(line.contains(r#"data-line="32" data-count="1" data-covered="true" data-partial="true">"#), "{}", line);
+    assert!(line.contains(
+        r#"<span class='hit' title="0 hits" data-line="32" data-count="0" data-covered="false" data-region-kind="code" data-segment-index="1">false</span>"#
+    ), "{}", line);
+    assert!(line.contains(r#"data-covered="true" data-region-kind="code" data-segment-index="2">name.len() &gt; 1</span>"#), "{}", line);
+    assert!(page.contains("lines ran only in part."));
+
+    // Lines without code have no count, only a number
+    let blank = page.lines().find(|line| line.contains(r#"id="L1""#)).unwrap();
+    assert!(!blank.contains("data-count") && !blank.contains("data-partial"), "{}", blank);
+    // Every region says which segment it is
+    let regions = page.matches("<span class='hit'").count();
+    assert!(regions > 0);
+    assert_eq!(page.matches("data-region-kind=").count(), regions);
+    assert_eq!(page.matches("data-segment-index=").count(), regions);
+}
+
+#[test]
+fn stylesheet_colors_by_the_checked_view() {
+    let (_, style) = export("style");
+
+    for rule in [
+        "#view-regions:checked ~ .container label[for='view-regions']",
+        "#view-lines:checked ~ .container label[for='view-lines']",
+        "#view-regions:checked ~ .container .line[data-partial] .count",
+        "#view-lines:checked ~ .container .hit",
+        "#view-lines:checked ~ .container .line[data-covered='true']",
+        "#view-lines:checked ~ .container .line[data-covered='false']",
+    ] {
+        assert!(style.contains(rule), "{}", rule);
+    }
+}
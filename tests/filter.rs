@@ -0,0 +1,52 @@
+use cosmoline::filter::{Filter, Stage, Verdict};
+
+#[test]
+fn default_reports_src() {
+    let filter = Filter::default();
+
+    assert_eq!(filter.decide("src/lib.rs").verdict, Verdict::Project { crate_name: None });
+    assert_eq!(filter.decide("src/a/b.rs").verdict, Verdict::Project { crate_name: None });
+    assert_eq!(filter.decide("tests/golden.rs").verdict, Verdict::Excluded(Stage::Include));
+}
+
+#[test]
+fn stages_apply_in_order() {
+    let ignore = std::env::temp_dir().join(format!("cosmoline-ignore-{}", std::process::id()));
+    std::fs::write(&ignore, "# generated code\ngenerated/\n*.pb.rs\n").unwrap();
+
+    let filter = Filter::default()
+        .include(&["src/**", "crates/**"])
+        .unwrap()
+        .exclude(&["src/bin/**"])
+        .unwrap()
+        .ignore_file(&ignore)
+        .unwrap()
+        .crate_dir("core", "crates/core")
+        .crate_dir("core_macros", "crates/core/macros");
+
+    assert_eq!(filter.decide("benches/a.rs").verdict, Verdict::Excluded(Stage::Include));
+    assert_eq!(filter.decide("src/bin/main.rs").verdict, Verdict::Excluded(Stage::Exclude));
+    assert_eq!(filter.decide("src/proto/msg.pb.rs").verdict, Verdict::Excluded(Stage::IgnoreFile));
+    assert_eq!(filter.decide("crates/x/src/generated/a.rs").verdict, Verdict::Excluded(Stage::IgnoreFile));
+    assert_eq!(filter.decide("crates/core/src/lib.rs").verdict, Verdict::Project { crate_name: Some("core".into()) });
+    // The longest matching directory wins
+    assert_eq!(
+        filter.decide("crates/core/macros/src/lib.rs").verdict,
+        Verdict::Project { crate_name: Some("core_macros".into()) }
+    );
+
+    let decision = filter.decide("src/bin/main.rs");
+    assert_eq!(decision.steps.last().unwrap(), &(Stage::Exclude, "matches `src/bin/**' from --exclude".to_string()));
+
+    std::fs::remove_file(ignore).unwrap();
+}
+
+#[test]
+fn negated_patterns_are_rejected() {
+    let ignore = std::env::temp_dir().join(format!("cosmoline-ignore-negated-{}", std::process::id()));
+    std::fs::write(&ignore, "*.rs\n!keep.rs\n").unwrap();
+
+    assert!(Filter::default().ignore_file(&ignore).is_err());
+
+    std::fs::remove_file(ignore).unwrap();
+}
@@ -0,0 +1,54 @@
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+pub async fn fetch(n: u32) -> u32 {
+    let doubled = double(n).await;
+    if doubled > 10 {
+        doubled - 10
+    } else {
+        doubled
+    }
+}
+
+async fn double(n: u32) -> u32 {
+    n * 2
+}
+
+pub async fn never_polled() -> u32 {
+    1
+}
+
+pub fn total(values: &[u32]) -> u32 {
+    values.iter().map(|v| v + 1).filter(|v| *v > 2).sum()
+}
+
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    fn noop(_: *const ()) {}
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+    let waker = unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) };
+    let mut context = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fetch_works() {
+        assert_eq!(block_on(fetch(3)), 6);
+        assert_eq!(block_on(fetch(7)), 4);
+        assert_eq!(total(&[1, 2, 3]), 7);
+        drop(never_polled());
+    }
+}
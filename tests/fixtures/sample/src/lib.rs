@@ -0,0 +1,31 @@
+pub mod shapes;
+
+pub fn classify(n: i32) -> &'static str {
+    if n < 0 {
+        "negative"
+    } else if n == 0 {
+        "zero"
+    } else {
+        "positive"
+    }
+}
+
+pub fn never_called(x: u8) -> u8 {
+    x.wrapping_mul(3)
+}
+
+pub fn apply<F: Fn(i32) -> i32>(f: F, v: i32) -> i32 {
+    f(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_works() {
+        assert_eq!(classify(5), "positive");
+        assert_eq!(classify(-1), "negative");
+        assert_eq!(apply(|x| x + 1, 1), 2);
+    }
+}
@@ -0,0 +1,34 @@
+pub trait Area {
+    fn area(&self) -> f64;
+}
+
+pub struct Square(pub f64);
+pub struct Circle(pub f64);
+
+impl Area for Square {
+    fn area(&self) -> f64 {
+        self.0 * self.0
+    }
+}
+
+impl Area for Circle {
+    fn area(&self) -> f64 {
+        3.14159 * self.0 * self.0
+    }
+}
+
+pub async fn fetch(v: u32) -> u32 {
+    v + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square() {
+        assert_eq!(Square(2.0).area(), 4.0);
+        let name = "квадрат";
+        assert!(!name.is_empty() && name.len() > 1 || false);
+    }
+}
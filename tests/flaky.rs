@@ -0,0 +1,29 @@
+use cosmoline::flaky;
+
+mod common;
+use common::model;
+
+#[test]
+fn identical_runs_are_not_flaky() {
+    let flaky = flaky::compare(&model(), &[model(), model(), model()]);
+
+    assert_eq!(flaky.runs, 3);
+    assert!(flaky.regions.is_empty());
+}
+
+#[test]
+fn regions_that_ran_in_some_runs() {
+    let mut unlucky = model();
+    let function = unlucky.functions.iter_mut().find(|f| f.name == "sample::classify").unwrap();
+    let region = function.regions.iter_mut().find(|r| r.region_kind == 0 && r.execution_count > 0).unwrap();
+    region.execution_count = 0;
+    let (line_start, column_start) = (region.line_start, region.column_start);
+
+    let flaky = flaky::compare(&model(), &[model(), unlucky, model()]);
+
+    assert_eq!(flaky.regions.len(), 1);
+    let region = &flaky.regions[0];
+    assert_eq!((region.file.as_str(), region.page.as_deref()), ("src/lib.rs", Some("src_lib.rs.html")));
+    assert_eq!((region.line_start, region.column_start), (line_start, column_start));
+    assert_eq!((region.function.as_str(), region.covered_runs), ("sample::classify", 2));
+}
@@ -0,0 +1,63 @@
+use cosmoline::anonymize::Anonymizer;
+use cosmoline::encoding::SourceEncoding;
+use cosmoline::freshness;
+use cosmoline::fs::FileLimiter;
+use cosmoline::input;
+use cosmoline::manifest::Manifest;
+use cosmoline::model::{ModelOptions, ReportModel};
+
+mod common;
+use common::{fixture, model};
+
+#[test]
+fn edited_sources_are_stale() {
+    let model = model();
+
+    let files = FileLimiter::default();
+    assert!(freshness::stale_sources(&model, &fixture(), SourceEncoding::default(), &files).is_empty());
+
+    let lib = model.files.iter().find(|f| f.name == "src/lib.rs").unwrap();
+    let source = std::fs::read_to_string(fixture().join("src/lib.rs")).unwrap();
+    assert_eq!(freshness::mismatch(lib, &source), None);
+
+    let truncated = source.lines().take(3).collect::<Vec<_>>().join("\n");
+    assert!(freshness::mismatch(lib, &truncated).unwrap().contains("but the source has 3 lines"));
+
+    let shortened = source.lines().map(|l| l.get(..2).unwrap_or(l)).collect::<Vec<_>>().join("\n");
+    assert!(freshness::mismatch(lib, &shortened).unwrap().contains("bytes long"));
+}
+
+#[test]
+fn line_based_formats_have_no_columns() {
+    let input_path = fixture().join("lcov.info");
+    let contents = std::fs::read_to_string(&input_path).unwrap();
+    let mappings = input::Registry::builtin().get("lcov").unwrap().read(&contents).unwrap();
+    let model = ReportModel::build(&mappings[0], &ModelOptions::default(), &Anonymizer::new(false, None), &input_path).unwrap();
+
+    // Every region ends at the end of its line, however long it is
+    let files = FileLimiter::default();
+    assert!(freshness::stale_sources(&model, &fixture(), SourceEncoding::default(), &files).is_empty());
+
+    // Lines are still checked
+    let lib = model.files.iter().find(|f| f.name == "src/lib.rs").unwrap();
+    let source = std::fs::read_to_string(fixture().join("src/lib.rs")).unwrap();
+    let truncated = source.lines().take(3).collect::<Vec<_>>().join("\n");
+    assert!(freshness::mismatch(lib, &truncated).unwrap().contains("but the source has 3 lines"));
+}
+
+#[test]
+fn hashes_from_an_earlier_manifest() {
+    let model = model();
+    let hashes = freshness::source_hashes(&model, &fixture(), &FileLimiter::default());
+    assert_eq!(hashes.keys().collect::<Vec<_>>(), vec!["src/lib.rs", "src/shapes.rs"]);
+    assert_eq!(hashes["src/lib.rs"].len(), 64);
+
+    let mut manifest = Manifest { sources: hashes.clone(), ..Manifest::default() };
+    assert!(freshness::changed_since(&manifest, &hashes).is_empty());
+
+    manifest.sources.insert("src/lib.rs".to_string(), "0".repeat(64));
+    manifest.sources.remove("src/shapes.rs");
+    let changed = freshness::changed_since(&manifest, &hashes);
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].0, "src/lib.rs");
+}
@@ -0,0 +1,160 @@
+use cosmoline::model::{ModelOptions, ReportModel};
+
+mod common;
+use common::{build, fixture, fixture_dir};
+
+fn model(options: &ModelOptions) -> ReportModel {
+    build(&fixture_dir("async").join("llvm-22.json"), options)
+}
+
+fn function<'m>(model: &'m ReportModel, name: &str) -> Option<&'m cosmoline::model::FunctionModel> {
+    model.functions.iter().find(|f| f.name == name)
+}
+
+#[test]
+fn async_bodies_are_folded() {
+    let model = model(&ModelOptions { fold_closures: false, ..ModelOptions::default() });
+
+    let fetch = function(&model, "async_sample::fetch").unwrap();
+    assert_eq!((fetch.count, fetch.symbols), (2, 2));
+    assert!(function(&model, "async_sample::fetch::{closure#0}").is_none());
+    // Called, but the future was never polled
+    assert_eq!(function(&model, "async_sample::never_polled").unwrap().count, 0);
+    // Closures written inside a fn stay as they are
+    assert!(function(&model, "async_sample::total::{closure#0}").is_some());
+
+    assert_eq!(model.functions.len(), 10);
+    assert_eq!((model.files[0].summary.functions.count, model.files[0].summary.functions.covered), (10, 8));
+    assert_eq!(model.totals.functions.count, 10);
+}
+
+#[test]
+fn closures_are_attributed_to_their_function() {
+    let model = model(&ModelOptions::default());
+
+    let total = function(&model, "async_sample::total").unwrap();
+    assert_eq!((total.count, total.symbols), (1, 3));
+    let parts = total.parts.iter().map(|p| (p.name.as_str(), p.count)).collect::<Vec<_>>();
+    assert_eq!(parts, vec![("async_sample::total::{closure#0}", 3), ("async_sample::total::{closure#1}", 3)]);
+    assert_eq!(function(&model, "async_sample::never_polled").unwrap().parts.len(), 1);
+
+    assert_eq!(model.functions.len(), 8);
+    assert_eq!((model.files[0].summary.functions.count, model.files[0].summary.functions.covered), (8, 6));
+}
+
+#[test]
+fn folding_can_be_turned_off() {
+    let model = model(&ModelOptions { fold_async: false, fold_closures: false, ..ModelOptions::default() });
+
+    assert_eq!(function(&model, "async_sample::never_polled").unwrap().count, 1);
+    assert_eq!(function(&model, "async_sample::never_polled::{closure#0}").unwrap().count, 0);
+    assert_eq!(model.functions.len(), 13);
+    assert_eq!(model.files[0].summary.functions.count, 13);
+}
+
+#[test]
+fn methods_are_grouped_by_impl() {
+    let model = common::model();
+
+    let impls = model.impls.iter().map(|i| (i.name.as_str(), i.functions.count, i.functions.covered)).collect::<Vec<_>>();
+    assert_eq!(
+        impls,
+        vec![
+            ("impl sample::shapes::Area for sample::shapes::Circle", 1, 0),
+            ("impl sample::shapes::Area for sample::shapes::Square", 1, 1),
+        ]
+    );
+    assert_eq!(function(&model, "sample::classify").unwrap().impl_block, None);
+}
+
+#[test]
+fn renames_are_matched_against_the_baseline() {
+    let baseline = common::model();
+    let mut model = common::model();
+
+    let classify = model.functions.iter_mut().find(|f| f.name == "sample::classify").unwrap();
+    let baseline_count = classify.count;
+    classify.name = "sample::classify_shape".to_string();
+    classify.count = 0;
+    model.compare_baseline(&baseline);
+
+    let renamed = model.renamed_functions.iter().map(|r| (r.old_name.as_str(), r.name.as_str())).collect::<Vec<_>>();
+    assert_eq!(renamed, vec![("sample::classify", "sample::classify_shape")]);
+    assert_eq!(model.renamed_functions[0].baseline_count, baseline_count);
+
+    // It stopped running, so it's dropped under its new name
+    assert_eq!(model.dropped_functions.len(), 1);
+    assert_eq!(model.dropped_functions[0].name, "sample::classify_shape");
+    assert_eq!(model.dropped_functions[0].renamed_from.as_deref(), Some("sample::classify"));
+}
+
+#[test]
+fn unrelated_functions_are_not_renames() {
+    let baseline = common::model();
+    let mut model = common::model();
+
+    let classify = model.functions.iter_mut().find(|f| f.name == "sample::classify").unwrap();
+    classify.name = "sample::totally_different_thing".to_string();
+    classify.regions.iter_mut().for_each(|r| r.line_start += 200);
+    model.compare_baseline(&baseline);
+
+    assert!(model.renamed_functions.is_empty());
+}
+
+#[test]
+fn instantiations_know_their_generic_function() {
+    let model = common::model();
+
+    let apply = model.functions.iter().find(|f| f.name.starts_with("sample::apply::<")).unwrap();
+    assert_eq!(apply.generic.as_deref(), Some("sample::apply"));
+    assert_eq!(function(&model, "sample::classify").unwrap().generic, None);
+    // The type of an impl isn't a generic argument of the method
+    assert!(model.functions.iter().filter(|f| f.impl_block.is_some()).all(|f| f.generic.is_none()));
+}
+
+/// The function summary of each file, as `(name, count, covered)`.
+fn summaries(model: &ReportModel) -> Vec<(&str, u64, u64)> {
+    model.files.iter().map(|f| (f.name.as_str(), f.summary.functions.count, f.summary.functions.covered)).collect()
+}
+
+#[test]
+fn folding_fixes_up_the_summaries() {
+    let sample = fixture().join("llvm-22.json");
+    let unfolded = build(&sample, &ModelOptions { fold_async: false, fold_closures: false, ..ModelOptions::default() });
+    assert_eq!(summaries(&unfolded), vec![("src/lib.rs", 5, 4), ("src/shapes.rs", 5, 2)]);
+    assert_eq!((unfolded.totals.functions.count, unfolded.totals.functions.covered), (10, 6));
+
+    let model = build(&sample, &ModelOptions::default());
+    // The test's closure ran along with the test, so only one of the two
+    // covered symbols is left
+    let test = function(&model, "sample::tests::classify_works").unwrap();
+    assert_eq!(test.parts.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), vec!["sample::tests::classify_works::{closure#0}"]);
+    // The body of an async fn that never ran, neither did the fn
+    let fetch = function(&model, "sample::shapes::fetch").unwrap();
+    assert_eq!((fetch.count, fetch.symbols), (0, 2));
+
+    assert_eq!(summaries(&model), vec![("src/lib.rs", 4, 3), ("src/shapes.rs", 4, 2)]);
+    assert_eq!((model.totals.functions.count, model.totals.functions.covered), (8, 5));
+    // Nothing's counted twice, or left out
+    assert_eq!(model.totals.functions.count, model.functions.len() as u64);
+    assert_eq!(model.totals.functions.covered, model.functions.iter().filter(|f| f.count > 0).count() as u64);
+}
+
+#[test]
+fn folding_a_polled_async_body_keeps_the_fn_covered() {
+    let unfolded = model(&ModelOptions { fold_async: false, fold_closures: false, ..ModelOptions::default() });
+    let model = model(&ModelOptions::default());
+
+    // `never_polled` was called, but only counts once its body ran
+    let never_polled = function(&model, "async_sample::never_polled").unwrap();
+    assert_eq!((never_polled.count, never_polled.symbols), (0, 2));
+    assert_eq!(function(&model, "async_sample::fetch").unwrap().count, 2);
+
+    assert_eq!((unfolded.totals.functions.count, unfolded.totals.functions.covered), (13, 11));
+    // Two closures and three async bodies are folded, four of them ran, and
+    // `never_polled` no longer counts as covered
+    assert_eq!((model.totals.functions.count, model.totals.functions.covered), (8, 6));
+    assert_eq!(summaries(&model), vec![(model.files[0].name.as_str(), 8, 6)]);
+    assert_eq!(model.totals.functions.count, model.functions.len() as u64);
+    assert_eq!(model.totals.functions.covered, model.functions.iter().filter(|f| f.count > 0).count() as u64);
+}
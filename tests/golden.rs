@@ -0,0 +1,146 @@
+//! Renders every export in `tests/fixtures/sample` (each from a different llvm
+//! release) and compares the HTML and LCOV output with the known-good copies
+//! in `tests/golden/<fixture>`.
+//!
+//! After an intentional change to the output regenerate the golden files with
+//!
+//! ```text
+//! cargo test --test golden -- --bless
+//! ```
+//!
+//! and review the diff like any other change.
+
+use std::collections::BTreeSet;
+use std::error::Error as StdError;
+use std::path::{Path, PathBuf};
+
+use chrono::TimeZone;
+
+use cosmoline::anonymize::Anonymizer;
+use cosmoline::export::{Exporter, Html, Lcov};
+use cosmoline::model::{ModelOptions, ReportModel};
+use cosmoline::render::Assets;
+use cosmoline::SummaryReport;
+
+fn root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn render(input_path: &Path, output_path: &Path) -> Result<(), Box<dyn StdError>> {
+    let source_path = input_path.parent().unwrap();
+    let contents = std::fs::read_to_string(input_path)?;
+    let report: SummaryReport = serde_json::from_str(&contents)?;
+
+    let anonymizer = Anonymizer::new(false, None);
+    let mut model = ReportModel::build(&report.data[0], &ModelOptions::default(), &anonymizer, input_path)?;
+    // The fixture's mtime depends on when it was checked out
+    model.metadata.input_mtime = chrono::Local.timestamp_opt(0, 0).unwrap().into();
+
+    Html::new(Assets::new(true, true), &anonymizer, source_path)?.export(&model, output_path)?;
+    Lcov.export(&model, output_path)?;
+
+    Ok(())
+}
+
+fn files(dir: &Path) -> BTreeSet<String> {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect(),
+        Err(_) => BTreeSet::new(),
+    }
+}
+
+/// Describes how `actual` differs from `golden`, if it does.
+fn compare(golden: &Path, actual: &Path) -> Vec<String> {
+    let mut problems = vec![];
+
+    let (golden_files, actual_files) = (files(golden), files(actual));
+    for missing in golden_files.difference(&actual_files) {
+        problems.push(format!("{} was not written", missing));
+    }
+    for extra in actual_files.difference(&golden_files) {
+        problems.push(format!("{} is not in {}", extra, golden.display()));
+    }
+
+    for name in golden_files.intersection(&actual_files) {
+        let expected = std::fs::read_to_string(golden.join(name)).unwrap();
+        let found = std::fs::read_to_string(actual.join(name)).unwrap();
+        if expected == found {
+            continue;
+        }
+
+        let line = expected
+            .lines()
+            .zip(found.lines())
+            .position(|(e, f)| e != f)
+            .unwrap_or_else(|| expected.lines().count().min(found.lines().count()));
+        problems.push(format!(
+            "{} differs at line {}\n    expected: {}\n       found: {}",
+            name,
+            line + 1,
+            expected.lines().nth(line).unwrap_or("<end of file>"),
+            found.lines().nth(line).unwrap_or("<end of file>"),
+        ));
+    }
+
+    problems
+}
+
+fn main() {
+    // Timestamps in the report are shown in local time
+    std::env::set_var("TZ", "UTC");
+
+    let bless = std::env::args().any(|arg| arg == "--bless");
+
+    let fixture_dir = root().join("tests/fixtures/sample");
+    let mut fixtures: Vec<PathBuf> = std::fs::read_dir(&fixture_dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().map(|ext| ext == "json").unwrap_or(false))
+        .collect();
+    fixtures.sort();
+
+    let mut failed = 0;
+    for fixture in fixtures.iter() {
+        let name = fixture.file_stem().unwrap().to_string_lossy();
+        let golden = root().join("tests/golden").join(&*name);
+        let output = std::env::temp_dir().join(format!("cosmoline-golden-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&output);
+        std::fs::create_dir_all(&output).unwrap();
+
+        if let Err(e) = render(fixture, &output) {
+            println!("golden {} ... FAILED\n    {}", name, e);
+            failed += 1;
+            continue;
+        }
+
+        if bless {
+            let _ = std::fs::remove_dir_all(&golden);
+            std::fs::create_dir_all(&golden).unwrap();
+            for file in files(&output) {
+                std::fs::copy(output.join(&file), golden.join(&file)).unwrap();
+            }
+            println!("golden {} ... blessed", name);
+        } else {
+            let problems = compare(&golden, &output);
+            match problems.is_empty() {
+                true => println!("golden {} ... ok", name),
+                false => {
+                    println!("golden {} ... FAILED", name);
+                    for problem in problems {
+                        println!("    {}", problem);
+                    }
+                    failed += 1;
+                }
+            }
+        }
+
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+
+    if failed > 0 {
+        println!("\n{} of {} fixtures failed; if the change is intended run `cargo test --test golden -- --bless`", failed, fixtures.len());
+        std::process::exit(1);
+    }
+}
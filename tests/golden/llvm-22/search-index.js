@@ -0,0 +1 @@
+window.searchIndex = {"files":[{"name":"src/lib.rs","link":"src_lib.rs.html","lines_hit_percent":"78.9"},{"name":"src/shapes.rs","link":"src_shapes.rs.html","lines_hit_percent":"53.3"}],"functions":[{"name":"<sample::shapes::Circle as sample::shapes::Area>::area","symbol":"_RNvXs_NtCsjmtxFF4qXEV_6sample6shapesNtB4_6CircleNtB4_4Area4area","link":"fn@~3csample~3a~3ashapes~3a~3aCircle~20as~20sample~3a~3ashapes~3a~3aArea~3e~3a~3aarea.html","file":"src/shapes.rs","line":15,"count":0},{"name":"<sample::shapes::Square as sample::shapes::Area>::area","symbol":"_RNvXNtCsjmtxFF4qXEV_6sample6shapesNtB2_6SquareNtB2_4Area4area","link":"fn@~3csample~3a~3ashapes~3a~3aSquare~20as~20sample~3a~3ashapes~3a~3aArea~3e~3a~3aarea.html","file":"src/shapes.rs","line":9,"count":1},{"name":"sample::apply::<sample::tests::classify_works::{closure#0}>","symbol":"_RINvCsjmtxFF4qXEV_6sample5applyNCNvNtB2_5testss_14classify_works0EB2_","link":"fn@sample~3a~3aapply~3a~3a~3csample~3a~3atests~3a~3aclassify_works~3a~3a~7bclosure~230~7d~3e.html","file":"src/lib.rs","line":17,"count":1},{"name":"sample::classify","symbol":"_RNvCsjmtxFF4qXEV_6sample8classify","link":"fn@sample~3a~3aclassify.html","file":"src/lib.rs","line":3,"count":2},{"name":"sample::never_called","symbol":"_RNvCsjmtxFF4qXEV_6sample12never_called","link":"fn@sample~3a~3anever_called.html","file":"src/lib.rs","line":13,"count":0},{"name":"sample::shapes::fetch","symbol":"_RNvNtCsjmtxFF4qXEV_6sample6shapes5fetch","link":"fn@sample~3a~3ashapes~3a~3afetch.html","file":"src/shapes.rs","line":20,"count":0},{"name":"sample::shapes::tests::square","symbol":"_RNvNtNtCsjmtxFF4qXEV_6sample6shapes5testss_6square","link":"fn@sample~3a~3ashapes~3a~3atests~3a~3asquare.html","file":"src/shapes.rs","line":29,"count":1},{"name":"sample::tests::classify_works","symbol":"_RNvNtCsjmtxFF4qXEV_6sample5testss_14classify_works","link":"fn@sample~3a~3atests~3a~3aclassify_works.html","file":"src/lib.rs","line":26,"count":1}]};
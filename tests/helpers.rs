@@ -0,0 +1,117 @@
+//! The template helpers, as functions and as used from templates, and the
+//! timezone times are shown in.
+
+use chrono::{DateTime, Offset};
+use cosmoline::anonymize::Anonymizer;
+use cosmoline::model::{ModelOptions, ReportModel};
+use cosmoline::render::helpers::{self, Timezone};
+use cosmoline::render::{Engine, Templates};
+use cosmoline::SummaryReport;
+use proptest::prelude::*;
+use serde_json::json;
+
+mod common;
+use common::fixture;
+
+const TIME: &str = "2026-10-16T21:30:00+02:00";
+
+#[test]
+fn strftime_in_timezones() {
+    let offset: Timezone = "-05:30".parse().unwrap();
+
+    assert_eq!(helpers::strftime(TIME, "%Y-%m-%d %H:%M %:z", Timezone::Utc).unwrap(), "2026-10-16 19:30 +00:00");
+    assert_eq!(helpers::strftime(TIME, "%Y-%m-%d %H:%M", offset).unwrap(), "2026-10-16 14:00");
+    assert_eq!(helpers::strftime(TIME, "%H:%M", "+0900".parse().unwrap()).unwrap(), "04:30");
+    assert!(helpers::strftime("yesterday", "%Y", Timezone::Utc).is_err());
+}
+
+#[test]
+fn timezones() {
+    assert_eq!("local".parse::<Timezone>(), Ok(Timezone::Local));
+    assert_eq!("UTC".parse::<Timezone>(), Ok(Timezone::Utc));
+    for invalid in ["", "+2", "+25:00", "+02:60", "Europe/Berlin", "02:00"] {
+        assert!(invalid.parse::<Timezone>().is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn input_mtime_in_timezone() {
+    let input_path = fixture().join("llvm-22.json");
+    let contents = std::fs::read_to_string(&input_path).unwrap();
+    let report: SummaryReport = serde_json::from_str(&contents).unwrap();
+    let build = |timezone| {
+        let options = ModelOptions { timezone, ..ModelOptions::default() };
+        ReportModel::build(&report.data[0], &options, &Anonymizer::new(false, None), &input_path).unwrap()
+    };
+
+    let utc = build(Timezone::Utc).metadata.input_mtime;
+    let offset = build("+05:30".parse().unwrap()).metadata.input_mtime;
+    assert_eq!(utc.offset().fix().local_minus_utc(), 0);
+    assert_eq!(offset.offset().fix().local_minus_utc(), 5 * 3600 + 30 * 60);
+    assert_eq!(utc, offset);
+}
+
+#[test]
+fn relative_times() {
+    let now = DateTime::parse_from_rfc3339(TIME).unwrap();
+    let relative = |time: &str| helpers::relative_time(time, now).unwrap();
+
+    assert_eq!(relative("2026-10-16T21:29:30+02:00"), "just now");
+    assert_eq!(relative("2026-10-16T20:29:00+02:00"), "1 hour ago");
+    assert_eq!(relative("2026-10-16T16:30:00+00:00"), "3 hours ago");
+    assert_eq!(relative("2026-10-13T21:30:00+02:00"), "3 days ago");
+    assert_eq!(relative("2026-10-02T21:30:00+02:00"), "2 weeks ago");
+    assert_eq!(relative("2024-10-16T21:30:00+02:00"), "2 years ago");
+    assert_eq!(relative("2026-10-16T21:35:00+02:00"), "in 5 minutes");
+}
+
+#[test]
+fn iso_weeks() {
+    assert_eq!(helpers::iso_week(TIME, Timezone::Utc).unwrap(), "2026-W42");
+    // Still Sunday in UTC, already Monday further east
+    assert_eq!(helpers::iso_week("2027-01-03T23:30:00+00:00", Timezone::Utc).unwrap(), "2026-W53");
+    assert_eq!(helpers::iso_week("2027-01-03T23:30:00+00:00", "+01:00".parse().unwrap()).unwrap(), "2027-W01");
+}
+
+#[test]
+fn durations() {
+    assert_eq!(helpers::duration(0.35), "350ms");
+    assert_eq!(helpers::duration(4.21), "4.2s");
+    assert_eq!(helpers::duration(125.0), "2m 5s");
+    assert_eq!(helpers::duration(7500.0), "2h 5m");
+    assert_eq!(helpers::duration(90000.0), "1d 1h");
+    assert_eq!(helpers::duration(-1.0), "-");
+}
+
+#[test]
+fn helpers_in_templates() {
+    let dir = std::env::temp_dir().join(format!("cosmoline-helpers-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("legend.html.hbs"),
+        r#"{{ strftime time "%H:%M" }} {{ relative_time time since }} {{ iso_week time }} {{ duration seconds }}"#,
+    )
+    .unwrap();
+    let templates = Templates::builtin().unwrap().timezone(Timezone::Utc).custom_dir(&dir, Engine::Handlebars).unwrap();
+
+    let context = json!({ "time": TIME, "since": "2026-10-17T19:30:00Z", "seconds": 65 });
+    assert_eq!(templates.render("legend", &context).unwrap(), "19:30 1 day ago 2026-W42 1m 5s");
+
+    // Wrong arguments are an error rather than a panic, and the page is
+    // rendered with the built-in template instead
+    for context in [json!({ "time": 1, "seconds": 1 }), json!({ "time": TIME, "since": TIME, "seconds": "a minute" }), json!({})] {
+        assert!(templates.render("legend", &context).is_ok(), "{}", context);
+    }
+    assert_eq!(templates.fallbacks()["legend"].pages, 3);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+proptest! {
+    #[test]
+    fn duration_is_total(seconds in proptest::num::f64::ANY) {
+        let duration = helpers::duration(seconds);
+
+        prop_assert!(!duration.is_empty() && !duration.contains("NaN") && !duration.contains("inf"), "{}", duration);
+    }
+}
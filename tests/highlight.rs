@@ -0,0 +1,67 @@
+//! Syntax highlighting of the file pages.
+
+use cosmoline::anonymize::Anonymizer;
+use cosmoline::export::{Exporter, Html};
+use cosmoline::render::{markup, Assets, Highlighter};
+
+mod common;
+use common::{fixture, model};
+
+fn lines(source: &str) -> Vec<String> {
+    source.lines().map(str::to_string).collect()
+}
+
+#[test]
+fn rust_tokens() {
+    let highlighter = Highlighter::new();
+    let source = lines("// a < b\nfn main() { let s = \"x\"; }");
+    let runs = highlighter.runs("src/main.rs", &source).unwrap();
+
+    assert_eq!(markup(&source[0], &runs[0]), r#"<span class="hl-comment">// a &lt; b</span>"#);
+    let main = markup(&source[1], &runs[1]);
+    assert!(main.contains(r#"<span class="hl-keyword">fn</span>"#), "{}", main);
+    assert!(main.contains(r#"<span class="hl-function">main</span>"#), "{}", main);
+    assert!(main.contains(r#"<span class="hl-string">&quot;x&quot;</span>"#), "{}", main);
+}
+
+#[test]
+fn unknown_syntax() {
+    let highlighter = Highlighter::new();
+    assert!(highlighter.runs("notes.unknown-extension", &lines("fn main() {}")).is_none());
+    assert!(highlighter.runs("Makefile-without-extension", &lines("all:")).is_none());
+}
+
+#[test]
+fn spans_nest_inside_segments() {
+    let highlighter = Highlighter::new();
+    let source = lines("let x = 1; // one");
+    let runs = highlighter.runs("lib.rs", &source).unwrap();
+
+    // The segment ends in the middle of the comment, the highlight span is
+    // closed before it and opened again after
+    let line = "{{ start_segment 0 1 1 code }}let x = 1; // o{{ end_segment }}ne";
+    let html = markup(line, &runs[0]);
+    assert!(html.starts_with("{{ start_segment 0 1 1 code }}<span class=\"hl-keyword\">let</span>"), "{}", html);
+    assert!(html.ends_with(r#"<span class="hl-comment">// o</span>{{ end_segment }}<span class="hl-comment">ne</span>"#), "{}", html);
+}
+
+#[test]
+fn file_pages() {
+    let anonymizer = Anonymizer::new(false, None);
+    let model = model();
+
+    let output_path = std::env::temp_dir().join(format!("cosmoline-highlight-{}", std::process::id()));
+    std::fs::create_dir_all(&output_path).unwrap();
+    Html::new(Assets::new(true, true), &anonymizer, &fixture())
+        .unwrap()
+        .highlight(Highlighter::new())
+        .export(&model, &output_path)
+        .unwrap();
+
+    let page = std::fs::read_to_string(output_path.join("src_lib.rs.html")).unwrap();
+    assert!(page.contains(r#"<div class="container highlighted">"#));
+    assert!(page.contains(r#"<span class="hl-keyword">pub</span>"#), "{}", page);
+    assert!(page.contains(r#"data-segment-index="#));
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
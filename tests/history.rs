@@ -0,0 +1,135 @@
+//! Retention and compaction of the history store.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use cosmoline::history::{FileRun, History, Retention, Run, SCHEMA_VERSION};
+
+mod common;
+use common::output_dir;
+
+fn run(timestamp: DateTime<Utc>, lines_percent: f64) -> Run {
+    let mut files = BTreeMap::new();
+    files.insert("src/lib.rs".to_string(), FileRun { lines_percent, functions_percent: 50.0 });
+    Run { timestamp, lines_percent, functions_percent: 50.0, files }
+}
+
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 6, 30, 12, 0, 0).unwrap()
+}
+
+fn percents(history: &History) -> Vec<f64> {
+    history.runs.iter().map(|run| run.lines_percent).collect()
+}
+
+#[test]
+fn parse_retention() {
+    assert_eq!("90d".parse(), Ok(Retention::Days(90)));
+    assert_eq!("12w".parse(), Ok(Retention::Days(84)));
+    assert_eq!("30".parse(), Ok(Retention::Runs(30)));
+    assert_eq!("0d".parse(), Ok(Retention::Days(0)));
+    for invalid in ["", "d", "w", "-5d", "-3", "1.5w", "90 d", "90y", "ninety"] {
+        assert_eq!(
+            invalid.parse::<Retention>(),
+            Err(format!("Invalid history retention `{}', expected e.g. 90d, 12w, or 30", invalid)),
+        );
+    }
+}
+
+#[test]
+fn prune_compacts_identical_runs_into_the_latest() {
+    let mut history = History {
+        version: SCHEMA_VERSION,
+        runs: vec![
+            run(now() - Duration::days(4), 50.0),
+            run(now() - Duration::days(3), 50.0),
+            run(now() - Duration::days(2), 60.0),
+            run(now() - Duration::days(1), 60.0),
+            run(now(), 50.0),
+        ],
+    };
+
+    assert_eq!(history.prune(Retention::Runs(10), now()), 2);
+    assert_eq!(percents(&history), [50.0, 60.0, 50.0]);
+    // Each run that's kept is the latest of those it stands for
+    let timestamps = history.runs.iter().map(|run| run.timestamp).collect::<Vec<_>>();
+    assert_eq!(timestamps, [now() - Duration::days(3), now() - Duration::days(1), now()]);
+
+    // A run only differing in a file isn't the same
+    let mut changed = run(now() + Duration::hours(1), 50.0);
+    changed.files.get_mut("src/lib.rs").unwrap().functions_percent = 75.0;
+    history.runs.push(changed);
+    assert_eq!(history.prune(Retention::Runs(10), now()), 0);
+    assert_eq!(history.runs.len(), 4);
+}
+
+#[test]
+fn prune_keeps_the_retention_window() {
+    let history = || History {
+        version: SCHEMA_VERSION,
+        runs: vec![
+            run(now() - Duration::days(20), 10.0),
+            run(now() - Duration::days(8), 20.0),
+            run(now() - Duration::days(6), 30.0),
+            run(now(), 40.0),
+        ],
+    };
+
+    let mut days = history();
+    assert_eq!(days.prune("1w".parse().unwrap(), now()), 2);
+    assert_eq!(percents(&days), [30.0, 40.0]);
+
+    let mut runs = history();
+    assert_eq!(runs.prune("3".parse().unwrap(), now()), 1);
+    assert_eq!(percents(&runs), [20.0, 30.0, 40.0]);
+
+    // Compacted before counting, so identical runs don't take up the window
+    let mut compacted = history();
+    compacted.runs.push(run(now(), 40.0));
+    assert_eq!(compacted.prune(Retention::Runs(2), now()), 3);
+    assert_eq!(percents(&compacted), [30.0, 40.0]);
+}
+
+#[test]
+fn prune_always_keeps_the_newest_run() {
+    let history = || History {
+        version: SCHEMA_VERSION,
+        runs: vec![run(now() - Duration::days(1), 10.0), run(now() - Duration::seconds(1), 20.0)],
+    };
+
+    // The run just recorded is older than `now` by the time it's pruned
+    let mut days = history();
+    assert_eq!(days.prune("0d".parse().unwrap(), now()), 1);
+    assert_eq!(percents(&days), [20.0]);
+
+    let mut runs = history();
+    assert_eq!(runs.prune("0".parse().unwrap(), now()), 1);
+    assert_eq!(percents(&runs), [20.0]);
+
+    let mut empty = History::default();
+    assert_eq!(empty.prune(Retention::Runs(0), now()), 0);
+}
+
+#[test]
+fn the_store_is_versioned() {
+    let output_path = output_dir("history-version");
+
+    // A new store is written with the current version
+    let mut history = History::load(&output_path).unwrap();
+    history.runs.push(run(now(), 10.0));
+    history.save(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(History::path(&output_path)).unwrap()).unwrap();
+    assert_eq!(json["version"], SCHEMA_VERSION);
+    assert_eq!(percents(&History::load(&output_path).unwrap()), [10.0]);
+
+    // Stores from before the version was written are version 1
+    std::fs::write(History::path(&output_path), r#"{"runs":[]}"#).unwrap();
+    assert_eq!(History::load(&output_path).unwrap().version, 1);
+
+    // Other versions aren't read
+    std::fs::write(History::path(&output_path), r#"{"version":2,"runs":[]}"#).unwrap();
+    let error = History::load(&output_path).unwrap_err().to_string();
+    assert!(error.ends_with("is history version 2, this build reads version 1"), "{}", error);
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
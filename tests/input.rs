@@ -0,0 +1,70 @@
+//! The line-based input formats, read back from what the exporters write.
+
+use cosmoline::export::{Cobertura, Exporter, Lcov};
+use cosmoline::input;
+use cosmoline::model::{ModelOptions, ReportModel};
+
+mod common;
+use common::{build, fixture, output_dir};
+
+fn model() -> ReportModel {
+    build(&fixture().join("llvm-22-branch.json"), &ModelOptions::default())
+}
+
+#[test]
+fn lcov_round_trip() {
+    let model = model();
+
+    let output_path = output_dir("lcov-input");
+    Lcov.export(&model, &output_path).unwrap();
+    let tracefile = std::fs::read_to_string(output_path.join("lcov.info")).unwrap();
+
+    let formats = input::Registry::builtin();
+    let lcov = formats.detect(&output_path.join("lcov.info"), &tracefile).unwrap();
+    assert_eq!(lcov.name(), "lcov");
+    let mappings = lcov.read(&tracefile).unwrap();
+
+    let read = &mappings[0];
+    assert_eq!(read.files.len(), model.files.len());
+    for (read, file) in read.files.iter().zip(model.files.iter()) {
+        assert_eq!(read.filename, file.name);
+        // llvm's own totals count some lines twice, the tracefile doesn't
+        let mapped = file.lines.iter().filter(|l| l.mapped).collect::<Vec<_>>();
+        assert_eq!(read.summary.lines.count, mapped.len() as u64);
+        assert_eq!(read.summary.lines.covered, mapped.iter().filter(|l| l.count > 0).count() as u64);
+        assert_eq!(read.branches.len(), file.branches.len());
+    }
+    assert_eq!(read.functions.len(), model.functions.len());
+    assert_eq!(read.totals.functions.covered, model.totals.functions.covered);
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn cobertura_round_trip() {
+    let model = model();
+
+    let output_path = output_dir("cobertura-input");
+    Cobertura.export(&model, &output_path).unwrap();
+    let xml = std::fs::read_to_string(output_path.join("cobertura.xml")).unwrap();
+
+    let formats = input::Registry::builtin();
+    let cobertura = formats.detect(&output_path.join("cobertura.xml"), &xml).unwrap();
+    assert_eq!(cobertura.name(), "cobertura");
+    let mappings = cobertura.read(&xml).unwrap();
+
+    let read = &mappings[0];
+    assert_eq!(read.files.len(), model.files.len());
+    for (read, file) in read.files.iter().zip(model.files.iter()) {
+        assert_eq!(read.filename, file.name);
+        let mapped = file.lines.iter().filter(|l| l.mapped).collect::<Vec<_>>();
+        assert_eq!(read.summary.lines.count, mapped.len() as u64);
+        assert_eq!(read.summary.lines.covered, mapped.iter().filter(|l| l.count > 0).count() as u64);
+        // Branches make it through as the arms hit on each line
+        let arms_hit = file.branches.iter().map(|b| (b.execution_count > 0) as u64 + (b.false_execution_count > 0) as u64).sum::<u64>();
+        assert_eq!(read.summary.branches.count, file.branches.len() as u64 * 2);
+        assert_eq!(read.summary.branches.covered, arms_hit);
+    }
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
@@ -0,0 +1,90 @@
+use cosmoline::merge::{self, MergeMode};
+use cosmoline::SummaryReport;
+
+mod common;
+use common::fixture;
+
+fn contents(name: &str) -> String {
+    std::fs::read_to_string(fixture().join(name)).unwrap()
+}
+
+#[test]
+fn summing_an_export_with_itself_doubles_counts() {
+    let contents = contents("llvm-22.json");
+    let unit: SummaryReport = serde_json::from_str(&contents).unwrap();
+    let doc: SummaryReport = serde_json::from_str(&contents).unwrap();
+    let original: SummaryReport = serde_json::from_str(&contents).unwrap();
+    let original = &original.data[0];
+
+    let merged = merge::merge(unit.data.into_iter().chain(doc.data).collect(), MergeMode::Sum);
+
+    assert_eq!(merged.files.len(), original.files.len());
+    assert_eq!(merged.functions.len(), original.functions.len());
+    for (merged, original) in merged.files.iter().zip(original.files.iter()) {
+        assert_eq!(merged.filename, original.filename);
+        let counts = |file: &cosmoline::FileCoverage| file.segments.iter().map(|s| s.count).collect::<Vec<_>>();
+        assert_eq!(counts(merged), counts(original).iter().map(|c| c * 2).collect::<Vec<_>>());
+        // Coverage is the same, only the counts went up
+        let lines = original.line_coverage().into_iter().filter(|l| l.mapped).collect::<Vec<_>>();
+        assert_eq!(merged.summary.lines.count, lines.len() as u64);
+        assert_eq!(merged.summary.lines.covered, lines.iter().filter(|l| l.count > 0).count() as u64);
+    }
+}
+
+#[test]
+fn max_keeps_the_highest_count() {
+    let contents = contents("llvm-22.json");
+    let unit: SummaryReport = serde_json::from_str(&contents).unwrap();
+    let doc: SummaryReport = serde_json::from_str(&contents).unwrap();
+    let original: SummaryReport = serde_json::from_str(&contents).unwrap();
+
+    let merged = merge::merge(unit.data.into_iter().chain(doc.data).collect(), MergeMode::Max);
+
+    let counts = |mapping: &cosmoline::CoverageMapping| mapping.functions.iter().map(|f| (f.name.to_string(), f.count)).collect::<Vec<_>>();
+    assert_eq!(counts(&merged), counts(&original.data[0]));
+}
+
+#[test]
+fn latest_takes_the_counts_of_the_last_input() {
+    let contents = contents("llvm-22.json");
+    let unit: SummaryReport = serde_json::from_str(&contents).unwrap();
+    let mut rerun: SummaryReport = serde_json::from_str(&contents).unwrap();
+    let original: SummaryReport = serde_json::from_str(&contents).unwrap();
+    let original = &original.data[0];
+    // Nothing ran in the last input
+    for file in rerun.data[0].files.iter_mut() {
+        file.segments.iter_mut().for_each(|s| s.count = 0);
+    }
+    for function in rerun.data[0].functions.iter_mut() {
+        function.count = 0;
+        function.regions.iter_mut().for_each(|r| r.execution_count = 0);
+    }
+
+    let merged = merge::combine(unit.data.into_iter().chain(rerun.data).collect(), MergeMode::Latest);
+
+    assert!(merged.functions.iter().all(|f| f.count == 0));
+    for (merged, original) in merged.files.iter().zip(original.files.iter()) {
+        assert!(merged.segments.iter().all(|s| s.count == 0));
+        // Recomputed from the merged data rather than kept from either input
+        let mapped = original.line_coverage().into_iter().filter(|l| l.mapped).count() as u64;
+        assert_eq!(merged.summary.lines.count, mapped);
+        assert_eq!(merged.summary.lines.covered, 0);
+        assert_eq!(merged.summary.functions.covered, 0);
+    }
+    assert_eq!(merged.totals.lines.covered, 0);
+}
+
+#[test]
+fn a_single_input_keeps_llvms_summaries() {
+    let contents = contents("llvm-22.json");
+    let single: SummaryReport = serde_json::from_str(&contents).unwrap();
+    let original: SummaryReport = serde_json::from_str(&contents).unwrap();
+    let original = &original.data[0];
+
+    let combined = merge::combine(single.data, MergeMode::Latest);
+    let summaries = |mapping: &cosmoline::CoverageMapping| {
+        mapping.files.iter().map(|f| (f.summary.lines.count, f.summary.lines.covered)).collect::<Vec<_>>()
+    };
+    assert_eq!(summaries(&combined), summaries(original));
+    assert_eq!(combined.totals.lines.count, original.totals.lines.count);
+}
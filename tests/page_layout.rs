@@ -0,0 +1,130 @@
+//! File pages in directories mirroring the sources, with `--page-layout tree`.
+
+use std::collections::BTreeMap;
+
+use cosmoline::anonymize::Anonymizer;
+use cosmoline::export::{Exporter, Html};
+use cosmoline::manifest::Manifest;
+use cosmoline::model::{ModelOptions, PageLayout, ReportModel};
+use cosmoline::render::Assets;
+use cosmoline::{verify, SummaryReport};
+
+mod common;
+use common::{build, fixture, output_dir};
+
+fn model(page_layout: PageLayout) -> ReportModel {
+    build(&fixture().join("llvm-22-branch.json"), &ModelOptions { page_layout, ..ModelOptions::default() })
+}
+
+#[test]
+fn pages_mirror_the_sources() {
+    let model = model(PageLayout::Tree);
+    assert_eq!(model.files.iter().map(|f| f.page.as_str()).collect::<Vec<_>>(), ["src/lib.rs.html", "src/shapes.rs.html"]);
+
+    let anonymizer = Anonymizer::new(false, None);
+    let output_path = output_dir("page-layout");
+    Html::new(Assets::new(true, true), &anonymizer, &fixture()).unwrap().export(&model, &output_path).unwrap();
+
+    // Links from pages a directory down go back up
+    let file = std::fs::read_to_string(output_path.join("src/lib.rs.html")).unwrap();
+    assert!(file.contains(r#"<link rel="stylesheet" href="../style.css">"#));
+    assert!(file.contains(r#"<a href="../index.html">"#));
+
+    let index = std::fs::read_to_string(output_path.join("index.html")).unwrap();
+    assert!(index.contains(r#"<td><a href="src/index.html">src</a></td>"#));
+    assert!(index.contains(r#"<a href="src/lib.rs.html">src/lib.rs</a>"#));
+
+    let dir = std::fs::read_to_string(output_path.join("src/index.html")).unwrap();
+    assert!(dir.contains(r#"<a href="../src/shapes.rs.html">shapes.rs</a>"#));
+    let branches = &model.files.iter().find(|f| f.name == "src/shapes.rs").unwrap().summary.branches;
+    assert!(dir.contains(&format!("{} / {}", branches.covered, branches.count)));
+    let totals = &model.totals;
+    for summary in [&totals.lines, &totals.functions, &totals.branches] {
+        assert!(dir.contains(&format!("{:.1}%</td>\n                    <td class=\"blue\">{} / {}</td>", summary.percent, summary.covered, summary.count)), "{}", dir);
+    }
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn flat_pages_have_no_directories() {
+    let model = model(PageLayout::Flat);
+    assert!(model.files.iter().all(|f| !f.page.contains('/')));
+
+    let anonymizer = Anonymizer::new(false, None);
+    let output_path = output_dir("page-layout-flat");
+    Html::new(Assets::new(true, true), &anonymizer, &fixture()).unwrap().export(&model, &output_path).unwrap();
+
+    assert!(!output_path.join("src").exists());
+    let index = std::fs::read_to_string(output_path.join("index.html")).unwrap();
+    assert!(!index.contains("Directories"));
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn sidebar_on_every_page() {
+    let model = model(PageLayout::Tree);
+    let anonymizer = Anonymizer::new(false, None);
+    let output_path = output_dir("page-layout-sidebar");
+    Html::new(Assets::new(true, true), &anonymizer, &fixture()).unwrap().sidebar().export(&model, &output_path).unwrap();
+
+    // Expanded down to the page shown, with links relative to it
+    let file = std::fs::read_to_string(output_path.join("src/shapes.rs.html")).unwrap();
+    assert!(file.contains(r#"<details open><summary><a href="../src/index.html">src</a>"#), "{}", file);
+    assert!(file.contains(r#"<li class="current"><a href="../src/shapes.rs.html">shapes.rs</a>"#), "{}", file);
+
+    let index = std::fs::read_to_string(output_path.join("index.html")).unwrap();
+    assert!(index.contains(r#"<details><summary><a href="src/index.html">src</a>"#), "{}", index);
+    assert!(std::fs::read_to_string(output_path.join("legend.html")).unwrap().contains(r#"<nav class="sidebar">"#));
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn function_pages() {
+    // Function pages stay next to the functions page with either layout
+    let model = model(PageLayout::Tree);
+    let anonymizer = Anonymizer::new(false, None);
+    let output_path = output_dir("page-layout-functions");
+    Html::new(Assets::new(true, true), &anonymizer, &fixture()).unwrap().export(&model, &output_path).unwrap();
+
+    let functions = std::fs::read_to_string(output_path.join("functions.html")).unwrap();
+    assert!(functions.contains(r#"<a href="fn@sample~3a~3aclassify.html">sample::classify</a>"#), "{}", functions);
+    assert!(verify::dead_links(&output_path, &["functions.html"], None).unwrap().is_empty());
+
+    let page = std::fs::read_to_string(output_path.join("fn@sample~3a~3aclassify.html")).unwrap();
+    assert!(page.contains(r#"Defined in <a href="src/lib.rs.html">src/lib.rs</a>"#), "{}", page);
+    assert!(page.contains(r#"<td class="red" title="0 hits">0</td>"#), "{}", page);
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn long_paths_are_shortened() {
+    let long = format!("src/{}shapes.rs", "nested_module/".repeat(20));
+    let contents = std::fs::read_to_string(fixture().join("llvm-22.json")).unwrap().replace("src/shapes.rs", &long);
+    let report: SummaryReport = serde_json::from_str(&contents).unwrap();
+    let model = ReportModel::build(&report.data[0], &ModelOptions::default(), &Anonymizer::new(false, None), &fixture()).unwrap();
+
+    let page = &model.files.iter().find(|f| f.name == long).unwrap().page;
+    assert!(page.len() < 200 && page.ends_with(".html"), "{}", page);
+
+    // The sources, with the file moved to the long path
+    let source_path = output_dir("page-layout-long-sources");
+    std::fs::create_dir_all(source_path.join(&long).parent().unwrap()).unwrap();
+    std::fs::copy(fixture().join("src/lib.rs"), source_path.join("src/lib.rs")).unwrap();
+    std::fs::copy(fixture().join("src/shapes.rs"), source_path.join(&long)).unwrap();
+
+    let anonymizer = Anonymizer::new(false, None);
+    let output_path = output_dir("page-layout-long");
+    Html::new(Assets::new(true, true), &anonymizer, &source_path).unwrap().export(&model, &output_path).unwrap();
+
+    assert!(output_path.join(page).is_file());
+    assert!(verify::dead_links(&output_path, &["index.html"], None).unwrap().is_empty());
+    let manifest: Manifest = serde_json::from_str(&std::fs::read_to_string(output_path.join("manifest.json")).unwrap()).unwrap();
+    assert_eq!(manifest.pages, BTreeMap::from([(page.clone(), long.clone())]));
+
+    std::fs::remove_dir_all(source_path).unwrap();
+    std::fs::remove_dir_all(output_path).unwrap();
+}
@@ -0,0 +1,48 @@
+//! Coverage colors with `--palette`, and `--print-of-record`.
+
+use cosmoline::render::{stylesheet, Palette, Templates};
+
+#[test]
+fn names() {
+    for palette in [Palette::RedGreen, Palette::BlueOrange, Palette::Monochrome] {
+        assert_eq!(palette.name().parse::<Palette>().unwrap(), palette);
+    }
+    assert_eq!("".parse::<Palette>().unwrap_err(), "Unknown palette `', expected red-green, blue-orange, or monochrome");
+}
+
+#[test]
+fn default_colors_only() {
+    let templates = Templates::builtin().unwrap();
+    let style = stylesheet(&templates, Palette::RedGreen, false).unwrap();
+    assert!(style.contains("--uncovered: #ee6a6f;"));
+    assert!(!style.contains("/* --palette"));
+    assert!(!style.contains("repeating-linear-gradient"));
+}
+
+#[test]
+fn palettes_override_colors_and_hatch() {
+    let templates = Templates::builtin().unwrap();
+    let style = stylesheet(&templates, Palette::BlueOrange, false).unwrap();
+    // Later declarations win
+    assert!(style.rfind("--uncovered: #e69f00;").unwrap() > style.find("--uncovered: #ee6a6f;").unwrap());
+    assert!(style.contains("repeating-linear-gradient"));
+
+    let style = stylesheet(&templates, Palette::Monochrome, false).unwrap();
+    assert!(style.contains("/* --palette monochrome */"));
+    assert!(!style.contains("#e69f00"));
+    assert!(style.contains("text-decoration: underline wavy;"));
+}
+
+#[test]
+fn print_of_record() {
+    let templates = Templates::builtin().unwrap();
+    let screen = stylesheet(&templates, Palette::BlueOrange, false).unwrap();
+    let print = stylesheet(&templates, Palette::BlueOrange, true).unwrap();
+
+    // Only adds print rules after the screen theme
+    assert!(print.starts_with(&screen));
+    let rules = &print[screen.len()..];
+    assert!(rules.contains("@media print {"));
+    assert!(rules.contains("background-image: repeating-linear-gradient"));
+    assert!(!screen.contains("@media print"));
+}
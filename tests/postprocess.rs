@@ -0,0 +1,37 @@
+use cosmoline::config::Config;
+use cosmoline::render::{map_text, Linkify, PostProcessor};
+
+#[test]
+fn linkify_references() {
+    let linkify = Linkify::new(r"TODO\(issue-(\d+)\)", "https://example.com/issues/$1").unwrap();
+    let html = r#"<span class='hit' title="1 hits">// TODO(issue-123): &lt;fix&gt;</span>"#;
+
+    assert_eq!(
+        linkify.process(html),
+        r#"<span class='hit' title="1 hits">// <a href="https://example.com/issues/123">TODO(issue-123)</a>: &lt;fix&gt;</span>"#
+    );
+}
+
+#[test]
+fn tags_are_left_alone() {
+    assert_eq!(map_text(r#"a<b title="a">a</b>"#, |t| t.to_uppercase()), r#"A<b title="a">A</b>"#);
+}
+
+#[test]
+fn config_file() {
+    let config = Config::parse(
+        r#"
+[[postprocess]]
+kind = "linkify"
+pattern = 'FIXME\((\w+)\)'
+url = "https://example.com/$1"
+"#,
+    )
+    .unwrap();
+    let postprocessors = config.postprocessors().unwrap();
+    assert_eq!(postprocessors.len(), 1);
+    assert_eq!(postprocessors[0].name(), "linkify");
+
+    assert!(Config::parse("[[postprocess]]\nkind = \"shout\"\n").is_err());
+    assert!(Config::parse("[[postprocess]]\nkind = \"linkify\"\npattern = '('\nurl = ''\n").unwrap().postprocessors().is_err());
+}
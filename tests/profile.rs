@@ -0,0 +1,33 @@
+//! Timing the phases of a run and reading its peak memory use.
+
+use cosmoline::profile::{self, Profile};
+
+#[test]
+fn peak_rss_from_proc_status() {
+    let status = "Name:\tcosmoline\nVmPeak:\t  204800 kB\nVmHWM:\t   51200 kB\nVmRSS:\t   40960 kB\n";
+    assert_eq!(profile::parse_peak_rss(status), Some(51200 * 1024));
+    assert_eq!(profile::parse_peak_rss("Name:\tcosmoline\n"), None);
+    assert_eq!(profile::parse_peak_rss("VmHWM:\tlots\n"), None);
+}
+
+#[test]
+fn phases_in_order() {
+    let mut profile = Profile::start();
+    profile.lap("parse");
+    profile.lap("model");
+    profile.lap("render html");
+    profile.finish();
+
+    assert_eq!(profile.phases.iter().map(|phase| phase.name.as_str()).collect::<Vec<_>>(), ["parse", "model", "render html"]);
+    let phases = profile.phases.iter().map(|phase| phase.seconds).sum::<f64>();
+    assert!(phases <= profile.total_seconds);
+    let summary = profile.summary();
+    assert!(summary.contains("render html: "), "{}", summary);
+    assert!(summary.contains("peak RSS: "), "{}", summary);
+
+    let json: serde_json::Value = serde_json::to_value(&profile).unwrap();
+    assert_eq!(json["phases"][1]["name"], "model");
+    if cfg!(target_os = "linux") {
+        assert!(json["peak_rss"].as_u64().unwrap() > 0);
+    }
+}
@@ -0,0 +1,45 @@
+use cosmoline::anonymize::Anonymizer;
+use cosmoline::model::{ModelOptions, ReportModel};
+use cosmoline::public_api;
+
+mod common;
+use common::{build, fixture_dir};
+
+fn model() -> ReportModel {
+    build(&fixture_dir("async").join("llvm-22.json"), &ModelOptions::default())
+}
+
+#[test]
+fn public_functions_are_matched() {
+    let json = std::fs::read_to_string(fixture_dir("async").join("rustdoc.json")).unwrap();
+    let items = public_api::parse_rustdoc(&json).unwrap();
+    let mut paths = items.iter().map(|item| item.path.as_str()).collect::<Vec<_>>();
+    paths.sort_unstable();
+    // Not `double`, which is private, nor anything from other crates
+    assert_eq!(paths, vec!["async_sample::block_on", "async_sample::fetch", "async_sample::never_polled", "async_sample::total"]);
+
+    let public_api = public_api::public_api(&model(), &items, &Anonymizer::new(false, None));
+    let counts = public_api.items.iter().map(|item| (item.name.as_str(), item.line, item.count)).collect::<Vec<_>>();
+    assert_eq!(
+        counts,
+        vec![
+            ("async_sample::fetch", 5, Some(2)),
+            ("async_sample::never_polled", 18, Some(0)),
+            ("async_sample::total", 22, Some(1)),
+            ("async_sample::block_on::<async_sample::fetch::{closure#0}>", 26, Some(2)),
+        ]
+    );
+    assert_eq!((public_api.summary.count, public_api.summary.covered), (4, 3));
+}
+
+#[test]
+fn missing_items_are_not_instrumented() {
+    let json = r#"{"index": {"1": {"crate_id": 0, "name": "gone", "visibility": "public",
+        "span": {"filename": "src/other.rs", "begin": [1, 1], "end": [3, 2]}, "inner": {"function": {"has_body": true}}}}}"#;
+    let items = public_api::parse_rustdoc(json).unwrap();
+
+    let public_api = public_api::public_api(&model(), &items, &Anonymizer::new(false, None));
+    assert_eq!(public_api.items[0].name, "gone");
+    assert_eq!(public_api.items[0].count, None);
+    assert_eq!((public_api.summary.count, public_api.summary.covered), (1, 0));
+}
@@ -0,0 +1,56 @@
+//! Reports written through the library rather than the command line.
+
+use cosmoline::filter::Filter;
+use cosmoline::model::PageLayout;
+use cosmoline::report::CoverageReport;
+
+mod common;
+use common::fixture;
+
+#[test]
+fn write_html() {
+    let json = std::fs::read_to_string(fixture().join("llvm-22.json")).unwrap();
+    let report = CoverageReport::from_llvm_json(&json).unwrap();
+    let output_path = std::env::temp_dir().join(format!("cosmoline-report-{}", std::process::id())).join("nested");
+
+    let model = report
+        .builder()
+        .source_dir(fixture())
+        .output_dir(&output_path)
+        .package_name("sample")
+        .page_layout(PageLayout::Tree)
+        .write_html()
+        .unwrap();
+
+    assert_eq!(model.metadata.package.as_deref(), Some("sample"));
+    assert!(output_path.join("src/lib.rs.html").is_file());
+    let index = std::fs::read_to_string(output_path.join("index.html")).unwrap();
+    assert!(index.contains("sample"));
+    // Shown when a link to the report is unfurled
+    assert!(index.contains(r#"<meta property="og:title" content="Code Coverage for sample">"#), "{}", index);
+    assert!(index.contains(r#"<meta name="description" content="67.6% of lines (23/34) and 62.5% of functions (5/8) covered">"#));
+
+    std::fs::remove_dir_all(output_path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn filters() {
+    let json = std::fs::read_to_string(fixture().join("llvm-22.json")).unwrap();
+    let report = CoverageReport::from_llvm_json(&json).unwrap();
+
+    let all = report.builder().source_dir(fixture()).model().unwrap();
+    let filtered = report
+        .builder()
+        .source_dir(fixture())
+        .filter(Filter::default().exclude(&["src/shapes.rs"]).unwrap())
+        .model()
+        .unwrap();
+
+    assert_eq!(all.files.len(), 2);
+    assert_eq!(filtered.files.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(), ["src/lib.rs"]);
+}
+
+#[test]
+fn not_llvm_json() {
+    assert!(CoverageReport::from_llvm_json("SF:src/lib.rs\nend_of_record\n").is_err());
+}
@@ -0,0 +1,56 @@
+//! Cutting an export down to one file, streamed rather than parsed whole.
+
+use std::path::PathBuf;
+
+use cosmoline::{stream_export, ExportEntry, SummaryReport};
+use cosmoline::repro;
+
+mod common;
+use common::fixture;
+
+fn export() -> PathBuf {
+    fixture().join("llvm-22.json")
+}
+
+#[test]
+fn streamed_entries_match_the_parsed_export() {
+    let contents = std::fs::read_to_string(export()).unwrap();
+    let report: SummaryReport = serde_json::from_str(&contents).unwrap();
+
+    let (mut files, mut functions) = (vec![0; report.data.len()], vec![0; report.data.len()]);
+    let header = stream_export(contents.as_bytes(), |index, entry| {
+        match entry {
+            ExportEntry::File(_) => files[index] += 1,
+            ExportEntry::Function(_) => functions[index] += 1,
+        }
+        Ok(())
+    }).unwrap();
+
+    assert_eq!(header.report_type, report.report_type);
+    assert_eq!(header.version, report.version.to_string());
+    for (index, mapping) in report.data.iter().enumerate() {
+        assert_eq!(files[index], mapping.files.len());
+        assert_eq!(functions[index], mapping.functions.len());
+    }
+}
+
+#[test]
+fn extract_keeps_one_file() {
+    let export = repro::extract(std::fs::File::open(export()).unwrap(), "src/shapes.rs").unwrap();
+    let report: SummaryReport = serde_json::from_str(&export).unwrap();
+
+    assert_eq!(report.data.len(), 1);
+    let mapping = &report.data[0];
+    assert_eq!(mapping.files.len(), 1);
+    assert_eq!(mapping.files[0].filename, "src/shapes.rs");
+    assert!(!mapping.functions.is_empty());
+    assert!(mapping.functions.iter().all(|function| function.filenames[0] == "src/shapes.rs"));
+    assert_eq!(mapping.totals.lines.count, mapping.files[0].summary.lines.count);
+    assert_eq!(mapping.totals.lines.covered, mapping.files[0].summary.lines.covered);
+}
+
+#[test]
+fn extract_missing_file() {
+    let error = repro::extract(std::fs::File::open(export()).unwrap(), "src/missing.rs").unwrap_err();
+    assert!(error.to_string().contains("src/missing.rs"));
+}
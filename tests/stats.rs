@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use chrono::{TimeZone, Utc};
+
+use cosmoline::age;
+use cosmoline::git::parse_last_modified;
+use cosmoline::{stats, uncovered};
+
+mod common;
+use common::model;
+
+#[test]
+fn regions_by_execution_count() {
+    let model = model();
+    let statistics = stats::statistics(&model);
+
+    let counts = &statistics.region_counts;
+    assert_eq!(counts.iter().map(|b| b.label.as_str()).collect::<Vec<_>>(), ["0", "1", "2-9", "10-99", "100-999", "1000-9999", "10000+"]);
+    assert_eq!(counts[0].count as usize, uncovered::regions(&model).len());
+    assert!(counts.iter().skip(1).map(|b| b.count).sum::<u64>() > 0);
+}
+
+#[test]
+fn files_by_coverage_decile() {
+    let model = model();
+    let statistics = stats::statistics(&model);
+
+    assert_eq!(statistics.file_coverage.len(), 10);
+    assert_eq!(statistics.file_coverage.iter().map(|b| b.count).sum::<u64>(), 2);
+    for file in model.files.iter() {
+        let decile = (file.summary.lines.percent / 10.0) as usize;
+        assert!(statistics.file_coverage[decile.min(9)].count > 0, "{} at {}%", file.name, file.summary.lines.percent);
+    }
+}
+
+#[test]
+fn last_change_of_each_file() {
+    let log = "\u{1}1700000300\n\nsrc/lib.rs\n\u{1}1700000200\n\nsrc/lib.rs\nsrc/shapes.rs\n";
+    let modified = parse_last_modified(log).unwrap();
+    assert_eq!(modified.len(), 2);
+    assert_eq!(modified["src/lib.rs"], 1_700_000_300);
+    assert_eq!(modified["src/shapes.rs"], 1_700_000_200);
+}
+
+#[test]
+fn files_by_age() {
+    let model = model();
+    let now = Utc.with_ymd_and_hms(2024, 6, 30, 12, 0, 0).unwrap();
+    let day = 24 * 60 * 60;
+    let modified = HashMap::from([
+        ("src/lib.rs".to_string(), now.timestamp() - 2 * day),
+        ("src/shapes.rs".to_string(), now.timestamp() - 60 * day),
+    ]);
+
+    let buckets = age::bucket_files(&model.files, &modified, now.timestamp()).buckets;
+    let labels = buckets.iter().map(|b| (b.label.as_str(), b.files)).collect::<Vec<_>>();
+    assert_eq!(labels, vec![("This week", 1), ("This month", 0), ("This quarter", 1), ("Older", 0)]);
+
+    let lib = model.files.iter().find(|f| f.filename == "src/lib.rs").unwrap();
+    assert_eq!(buckets[0].lines.count, lib.summary.lines.count);
+    assert_eq!(buckets[0].lines.covered, lib.summary.lines.covered);
+}
@@ -0,0 +1,79 @@
+//! Custom templates, which replace the built-in ones by name.
+
+use std::path::PathBuf;
+
+use cosmoline::render::{Engine, Templates};
+use serde_json::json;
+
+fn template_dir(name: &str, templates: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cosmoline-templates-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (filename, source) in templates {
+        std::fs::write(dir.join(filename), source).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn handlebars_replaces_builtin() {
+    let dir = template_dir("handlebars", &[("legend.html.hbs", "{{ package }}: {{ human_count count }}")]);
+    let templates = Templates::builtin().unwrap().custom_dir(&dir, Engine::Handlebars).unwrap();
+
+    let output = templates.render("legend", &json!({ "package": "<pkg>", "count": 1234 })).unwrap();
+    assert_eq!(output, "&lt;pkg&gt;: 1.2k");
+
+    // Everything that isn't replaced is still rendered with the built-ins
+    assert!(templates.render("not_found", &json!({ "root": "", "base": "/" })).unwrap().contains("index.html"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+#[cfg(feature = "minijinja")]
+fn minijinja_receives_the_same_context() {
+    let dir = template_dir("minijinja", &[("legend.html.j2", "{{ package }}: {{ count|human_count }}")]);
+    let templates = Templates::builtin().unwrap().custom_dir(&dir, Engine::MiniJinja).unwrap();
+
+    let output = templates.render("legend", &json!({ "package": "<pkg>", "count": 1234 })).unwrap();
+    assert_eq!(output, "&lt;pkg&gt;: 1.2k");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+#[cfg(not(feature = "minijinja"))]
+fn minijinja_needs_the_feature() {
+    assert!("minijinja".parse::<Engine>().is_err());
+}
+
+#[test]
+fn failing_template_falls_back_to_builtin() {
+    let dir = template_dir("fallback", &[("not_found.html.hbs", "{{ no_such_helper package }}")]);
+    let templates = Templates::builtin().unwrap().custom_dir(&dir, Engine::Handlebars).unwrap();
+
+    let context = json!({ "root": "", "base": "/" });
+    assert!(templates.render("not_found", &context).unwrap().contains("index.html"));
+    assert!(templates.render("not_found", &context).unwrap().contains("index.html"));
+
+    let fallbacks = templates.fallbacks();
+    assert_eq!(fallbacks.keys().collect::<Vec<_>>(), ["not_found"]);
+    assert_eq!(fallbacks["not_found"].pages, 2);
+    assert!(fallbacks["not_found"].error.contains("no_such_helper"), "{}", fallbacks["not_found"].error);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fingerprint_follows_the_sources() {
+    assert_eq!(Templates::builtin().unwrap().fingerprint(), "");
+
+    let dir = template_dir("fingerprint", &[("legend.html.hbs", "{{ package }}")]);
+    let fingerprint = |dir: &PathBuf| Templates::builtin().unwrap().custom_dir(dir, Engine::Handlebars).unwrap().fingerprint().to_string();
+    let before = fingerprint(&dir);
+    assert_eq!(fingerprint(&dir), before);
+
+    std::fs::write(dir.join("legend.html.hbs"), "{{ package }}!").unwrap();
+    assert_ne!(fingerprint(&dir), before);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
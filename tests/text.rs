@@ -0,0 +1,24 @@
+use cosmoline::anonymize::Anonymizer;
+use cosmoline::export::{Exporter, Text};
+
+mod common;
+use common::{fixture, model};
+
+#[test]
+fn annotated_sources() {
+    let anonymizer = Anonymizer::new(false, None);
+    let model = model();
+
+    let output_path = std::env::temp_dir().join(format!("cosmoline-text-{}", std::process::id()));
+    std::fs::create_dir_all(&output_path).unwrap();
+    Text::new(&anonymizer, &fixture()).export(&model, &output_path).unwrap();
+
+    let annotated = std::fs::read_to_string(output_path.join("text/src/lib.rs.txt")).unwrap();
+    let lines = annotated.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "    1|       |pub mod shapes;");
+    assert_eq!(lines[2], "    3|      2|pub fn classify(n: i32) -> &'static str {");
+    assert_eq!(lines[6], "    7|      0|        \"zero\"");
+    assert!(output_path.join("text/src/shapes.rs.txt").exists());
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
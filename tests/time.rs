@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use cosmoline::time::TimestampSource;
+
+mod common;
+use common::fixture;
+
+#[test]
+fn timestamp_sources() {
+    assert_eq!("mtime".parse(), Ok(TimestampSource::Mtime));
+    assert_eq!("commit".parse(), Ok(TimestampSource::Commit));
+    assert_eq!("now".parse(), Ok(TimestampSource::Now));
+    assert_eq!(
+        "fixed=2026-10-16T21:30:00+02:00".parse(),
+        Ok(TimestampSource::Fixed(DateTime::parse_from_rfc3339("2026-10-16T21:30:00+02:00").unwrap()))
+    );
+    for invalid in ["", "ctime", "fixed=", "fixed=yesterday", "2026-10-16T21:30:00+02:00"] {
+        assert!(invalid.parse::<TimestampSource>().is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn resolved_timestamps() {
+    let fixture = fixture().join("llvm-22.json");
+    let fixed = DateTime::parse_from_rfc3339("2026-10-16T21:30:00+02:00").unwrap();
+
+    assert_eq!(TimestampSource::Fixed(fixed).resolve(&fixture).unwrap(), fixed);
+    let before = Utc::now();
+    assert!(TimestampSource::Now.resolve(&fixture).unwrap() >= before);
+    assert!(TimestampSource::Mtime.resolve(&fixture).unwrap() <= Utc::now());
+    assert!(TimestampSource::Mtime.resolve(&fixture.with_extension("missing")).is_err());
+}
@@ -0,0 +1,70 @@
+//! Page titles and breadcrumbs from the `[titles]` table of the config file.
+
+use cosmoline::anonymize::Anonymizer;
+use cosmoline::config::Config;
+use cosmoline::export::{Exporter, Html};
+use cosmoline::render::Assets;
+
+mod common;
+use common::{fixture, model, output_dir};
+
+#[test]
+fn configured_titles_on_every_page() {
+    let config = Config::parse(
+        r#"
+[titles]
+page = "{package} | {page} | {file} | {percent}%"
+breadcrumb = "<{page}>"
+"#,
+    )
+    .unwrap();
+
+    let model = model();
+    let anonymizer = Anonymizer::new(false, None);
+    let output_path = output_dir("titles");
+    Html::new(Assets::new(true, true), &anonymizer, &fixture())
+        .unwrap()
+        .titles(config.titles)
+        .export(&model, &output_path)
+        .unwrap();
+
+    let read = |page: &str| std::fs::read_to_string(output_path.join(page)).unwrap();
+    let file = &model.files[0];
+    let page = read(&file.page);
+    let title = format!("<title>Code Coverage Report | {name} | {name} | {:.1}%</title>", file.summary.lines.percent, name = file.name);
+    assert!(page.contains(&title), "{}", page);
+    assert!(page.contains(&format!("&#x00BB; &lt;{}&gt;", file.name)));
+
+    let legend = read("legend.html");
+    assert!(legend.contains(&format!("<title>Code Coverage Report | Legend |  | {:.1}%</title>", model.totals.lines.percent)));
+    assert!(legend.contains("&#x00BB; &lt;Legend&gt;"));
+    assert!(read("index.html").contains("<title>Code Coverage Report | Index | "));
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn unset_titles_keep_the_templates() {
+    let config = Config::parse("[titles]\nbreadcrumb = \"{page}!\"\n").unwrap();
+
+    let anonymizer = Anonymizer::new(false, None);
+    let output_path = output_dir("titles-unset");
+    Html::new(Assets::new(true, true), &anonymizer, &fixture())
+        .unwrap()
+        .titles(config.titles)
+        .export(&model(), &output_path)
+        .unwrap();
+
+    let stats = std::fs::read_to_string(output_path.join("stats.html")).unwrap();
+    assert!(stats.contains("<title>Statistics</title>"));
+    assert!(stats.contains("&#x00BB; Statistics!"));
+
+    std::fs::remove_dir_all(output_path).unwrap();
+}
+
+#[test]
+fn unknown_placeholders_are_an_error() {
+    assert!(Config::parse("[titles]\npage = \"{pakage}\"\n").is_err());
+    assert!(Config::parse("[titles]\npage = \"{page\"\n").is_err());
+    assert!(Config::parse("[titles]\nheading = \"{page}\"\n").is_err());
+}
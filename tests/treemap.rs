@@ -0,0 +1,50 @@
+use cosmoline::treemap::{self, Node};
+
+mod common;
+use common::model;
+
+fn leaves(node: &Node) -> Vec<&Node> {
+    match node.page {
+        Some(_) => vec![node],
+        None => node.children.iter().flat_map(leaves).collect(),
+    }
+}
+
+fn check_totals(node: &Node) {
+    if node.page.is_none() {
+        assert_eq!(node.lines.count, node.children.iter().map(|c| c.lines.count).sum::<u64>(), "{}", node.path);
+        assert_eq!(node.lines.covered, node.children.iter().map(|c| c.lines.covered).sum::<u64>(), "{}", node.path);
+        assert!(node.children.windows(2).all(|pair| pair[0].lines.count >= pair[1].lines.count), "{}", node.path);
+        node.children.iter().for_each(check_totals);
+    }
+}
+
+#[test]
+fn files_under_their_directories() {
+    let model = model();
+    let root = treemap::hierarchy(&model);
+
+    let mut files: Vec<_> = leaves(&root).iter().map(|leaf| (leaf.path.clone(), leaf.page.clone().unwrap())).collect();
+    files.sort();
+    let mut expected: Vec<_> = model.files.iter().filter(|f| f.summary.lines.count > 0).map(|f| (f.name.clone(), f.page.clone())).collect();
+    expected.sort();
+    assert_eq!(files, expected);
+
+    assert_eq!(root.lines.count, model.files.iter().map(|f| f.summary.lines.count).sum::<u64>());
+    check_totals(&root);
+}
+
+#[test]
+fn single_subdirectories_are_collapsed() {
+    let model = model();
+    let root = treemap::hierarchy(&model);
+
+    fn check(node: &Node) {
+        for child in node.children.iter().filter(|c| c.page.is_none()) {
+            assert!(child.children.len() > 1 || child.children.iter().all(|c| c.page.is_some()), "{}", child.path);
+            assert!(child.path.ends_with(&child.name), "{} {}", child.path, child.name);
+            check(child);
+        }
+    }
+    check(&root);
+}
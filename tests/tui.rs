@@ -0,0 +1,70 @@
+//! `cosmoline tui`, driven by key presses and drawn on a test backend.
+
+use cosmoline::tui::{App, View};
+use ratatui::backend::TestBackend;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::Terminal;
+
+mod common;
+use common::{fixture, model};
+
+fn press(app: &mut App, keys: &[KeyCode]) {
+    for key in keys {
+        app.handle(KeyEvent::from(*key));
+    }
+}
+
+/// What's on the screen, a line of text per row.
+fn screen(app: &mut App) -> Vec<String> {
+    let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+    terminal.draw(|frame| app.draw(frame)).unwrap();
+    let buffer = terminal.backend().buffer();
+    (0..buffer.area.height)
+        .map(|y| (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect::<String>().trim_end().to_string())
+        .collect()
+}
+
+#[test]
+fn files_and_sources() {
+    let model = model();
+    let mut app = App::new(&model, &fixture());
+
+    let files = screen(&mut app);
+    assert!(files[0].contains("lines 67.6% (23/34)"), "{:#?}", files);
+    assert!(files.iter().any(|row| row.ends_with("%  src/lib.rs")), "{:#?}", files);
+
+    // The first line that never ran
+    press(&mut app, &[KeyCode::Enter, KeyCode::Char('n')]);
+    assert_eq!(app.view(), View::Source);
+    let source = screen(&mut app);
+    assert!(source[1].contains("src/lib.rs"), "{:#?}", source);
+    assert!(source.iter().any(|row| row.starts_with("     0     7 ")), "{:#?}", source);
+
+    press(&mut app, &[KeyCode::Esc, KeyCode::Char('q')]);
+    assert_eq!(app.view(), View::Files);
+    assert!(app.quit());
+}
+
+#[test]
+fn search_functions() {
+    let model = model();
+    let mut app = App::new(&model, &fixture());
+
+    press(&mut app, &[KeyCode::Tab, KeyCode::Char('/')]);
+    for c in "NEVER".chars() {
+        press(&mut app, &[KeyCode::Char(c)]);
+    }
+    press(&mut app, &[KeyCode::Enter]);
+    let functions = screen(&mut app);
+    assert!(functions[1].contains("Functions matching `NEVER'"), "{:#?}", functions);
+    assert!(functions[2].ends_with("0  sample::never_called"), "{:#?}", functions);
+    assert!(functions[3].is_empty(), "{:#?}", functions);
+
+    // Opens where the function starts
+    press(&mut app, &[KeyCode::Enter]);
+    assert_eq!(app.view(), View::Source);
+    let source = screen(&mut app);
+    assert!(source.iter().any(|row| row.contains("fn never_called")), "{:#?}", source);
+    press(&mut app, &[KeyCode::Esc]);
+    assert_eq!(app.view(), View::Functions);
+}
@@ -0,0 +1,41 @@
+use cosmoline::uncovered::{self, UncoveredRegion};
+
+mod common;
+use common::model;
+
+#[test]
+fn regions_that_never_ran() {
+    let regions = uncovered::regions(&model());
+
+    assert_eq!(regions.len(), 13);
+    assert_eq!(
+        regions[0],
+        UncoveredRegion {
+            file: "src/lib.rs".into(),
+            line_start: 7,
+            column_start: 9,
+            line_end: 7,
+            column_end: 15,
+            function: "sample::classify".into(),
+        }
+    );
+    // The body of an async fn is part of the fn
+    assert!(regions.iter().any(|r| r.function == "sample::shapes::fetch" && r.line_start > 20));
+}
+
+#[test]
+fn csv_quotes_function_names() {
+    let regions = vec![UncoveredRegion {
+        file: "src/lib.rs".into(),
+        line_start: 1,
+        column_start: 2,
+        line_end: 3,
+        column_end: 4,
+        function: "<HashMap<K, V> as \"Foo\">::bar".into(),
+    }];
+
+    assert_eq!(
+        uncovered::csv(&regions),
+        "file,start_line,start_column,end_line,end_column,function\nsrc/lib.rs,1,2,3,4,\"<HashMap<K, V> as \"\"Foo\"\">::bar\"\n"
+    );
+}
@@ -0,0 +1,133 @@
+//! The helpers in `cosmoline::utils` are total: they never panic, whatever
+//! the coverage data or source files throw at them.
+
+use proptest::prelude::*;
+
+use cosmoline::utils::{self, InsertAtCharacter};
+
+proptest! {
+    #[test]
+    fn color_for_percent_is_total(percent in proptest::num::f64::ANY) {
+        prop_assert!(["red", "yellow", "green"].contains(&utils::color_for_percent(percent)));
+    }
+
+    #[test]
+    fn sanitize_filename_is_flat_and_portable(input in any::<String>()) {
+        let output = utils::sanitize_filename(&input);
+
+        prop_assert!(output.ends_with(".html"));
+        prop_assert!(output.chars().all(|c| c.is_ascii_alphanumeric() || "._-~".contains(c)));
+        prop_assert!(output.len() < 200, "{}", output);
+        prop_assert_eq!(utils::is_shortened(&output), output != format!("{}.html", utils::escape_filename(&input)));
+    }
+
+    #[test]
+    fn sanitize_filename_keeps_distinct_names_apart(a in "[^/\\\\_]*", b in "[^/\\\\_]*") {
+        prop_assume!(a != b);
+        prop_assert_ne!(utils::sanitize_filename(&a), utils::sanitize_filename(&b));
+    }
+
+    #[test]
+    fn function_page_is_flat_and_short(input in any::<String>()) {
+        let output = utils::function_page(&input);
+
+        prop_assert!(output.starts_with("fn@") && output.ends_with(".html"));
+        prop_assert!(output.chars().all(|c| c.is_ascii_alphanumeric() || "._-~@".contains(c)));
+        prop_assert!(output.len() < 200, "{}", output);
+    }
+
+    #[test]
+    fn tree_page_stays_under_the_output_directory(input in any::<String>()) {
+        let output = utils::tree_page(&input);
+
+        prop_assert!(output.ends_with(".html"));
+        prop_assert!(output.chars().all(|c| c.is_ascii_alphanumeric() || "._-~/".contains(c)));
+        prop_assert!(output.split('/').all(|c| !c.is_empty() && c.chars().any(|c| c != '.')), "{}", output);
+        prop_assert!(output.split('/').all(|c| c.len() < 200), "{}", output);
+        prop_assert_ne!(output.rsplit('/').next(), Some("index.html"));
+    }
+
+    #[test]
+    fn insert_at_char_keeps_every_character(line in any::<String>(), index in 0usize..200, insert in any::<String>()) {
+        let mut output = line.clone();
+        output.insert_at_char(index, &insert);
+
+        prop_assert_eq!(output.chars().count(), line.chars().count() + insert.chars().count());
+        prop_assert!(output.contains(&insert));
+    }
+
+    #[test]
+    fn insert_at_char_goes_before_the_column(line in any::<String>(), index in 1usize..200, insert in any::<String>()) {
+        let mut output = line.clone();
+        output.insert_at_char(index, &insert);
+
+        let before = line.chars().take(index - 1).collect::<String>();
+        prop_assert_eq!(output, format!("{}{}{}", before, insert, &line[before.len()..]));
+    }
+
+    #[test]
+    fn human_count_is_short(count in any::<i64>()) {
+        let output = utils::human_count(count);
+
+        prop_assert!(output.trim_start_matches('-').chars().count() <= 6, "{} became {}", count, output);
+    }
+
+    #[test]
+    fn xml_escape_leaves_no_markup(input in any::<String>()) {
+        let output = utils::xml_escape(&input);
+
+        prop_assert!(!output.contains(|c| "<>\"'".contains(c)));
+        let unescaped = output
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&");
+        prop_assert_eq!(unescaped, input);
+    }
+
+    #[test]
+    fn path_helpers_are_total(filename in any::<String>()) {
+        let name = utils::report_name(&filename);
+
+        prop_assert!(filename.ends_with(name));
+        prop_assert_eq!(utils::dependency_path(&filename).is_some(), name != filename);
+    }
+}
+
+#[test]
+fn insert_at_char_respects_multibyte_characters() {
+    let mut line = String::from("let ж = 1;");
+    line.insert_at_char(5, "[");
+    line.insert_at_char(7, "]");
+    assert_eq!(line, "let [ж] = 1;");
+}
+
+#[test]
+fn insert_at_char_before_the_last_character() {
+    let mut line = String::from("if x {");
+    line.insert_at_char(6, "[");
+    assert_eq!(line, "if x [{");
+    line.insert_at_char(9, "]");
+    assert_eq!(line, "if x [{]");
+}
+
+#[test]
+fn human_count_abbreviates() {
+    assert_eq!(utils::human_count(0), "0");
+    assert_eq!(utils::human_count(999), "999");
+    assert_eq!(utils::human_count(1_234), "1.2k");
+    assert_eq!(utils::human_count(999_999), "1.0M");
+    assert_eq!(utils::human_count(3_400_000), "3.4M");
+    assert_eq!(utils::human_count(-12_345), "-12.3k");
+}
+
+#[test]
+fn parse_size_units() {
+    assert_eq!(utils::parse_size("1234"), Ok(1234));
+    assert_eq!(utils::parse_size("500k"), Ok(500_000));
+    assert_eq!(utils::parse_size("50M"), Ok(50_000_000));
+    assert_eq!(utils::parse_size("1GB"), Ok(1_000_000_000));
+    assert!(utils::parse_size("12x").is_err());
+    assert!(utils::parse_size("M").is_err());
+}
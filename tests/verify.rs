@@ -0,0 +1,105 @@
+use std::path::PathBuf;
+
+use cosmoline::manifest::{self, Manifest};
+use cosmoline::verify::{self, DeadLink, Problem};
+
+fn report(name: &str, pages: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cosmoline-verify-{}-{}", name, std::process::id()));
+    for (page, html) in pages {
+        let path = dir.join(page);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, html).unwrap();
+    }
+    dir
+}
+
+fn dead(page: &str, href: &str, problem: Problem) -> DeadLink {
+    DeadLink { page: page.into(), href: href.into(), problem }
+}
+
+#[test]
+fn links_that_lead_nowhere() {
+    let dir = report("dead", &[
+        (
+            "index.html",
+            r##"<link rel="stylesheet" href="style.css">
+            <a href="src/lib.rs.html">lib</a> <a href="src/gone.rs.html">gone</a>
+            <a href="functions.html#dropped">dropped</a> <a href="functions.html#impls">impls</a>
+            <a href="#top">top</a> <a href="../elsewhere.html">up</a>
+            <a href="https://example.com/">site</a> <script src="index-0123.js"></script>"##,
+        ),
+        ("style.css", ""),
+        ("src/lib.rs.html", r#"<a href="../index.html">back</a>"#),
+        ("functions.html", r#"<h2 id="dropped">Dropped</h2>"#),
+    ]);
+
+    let found = verify::dead_links(&dir, &["index.html", "src/lib.rs.html"], None).unwrap();
+    assert_eq!(
+        found,
+        vec![
+            dead("index.html", "src/gone.rs.html", Problem::MissingFile),
+            dead("index.html", "functions.html#impls", Problem::MissingAnchor("impls".into())),
+            dead("index.html", "#top", Problem::MissingAnchor("top".into())),
+            dead("index.html", "../elsewhere.html", Problem::OutsideReport),
+            dead("index.html", "index-0123.js", Problem::MissingFile),
+        ]
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn absolute_links_under_the_base_url() {
+    let dir = report("absolute", &[
+        (
+            "index.html",
+            r#"<a href="https://example.com/coverage/src_lib.rs.html">lib</a>
+            <a href="https://example.com/coverage/gone.html">gone</a>
+            <a href="/elsewhere.html">site</a> <a href="legend.html?x&amp;y">legend</a>"#,
+        ),
+        ("src_lib.rs.html", ""),
+        ("legend.html", ""),
+    ]);
+
+    let found = verify::dead_links(&dir, &["index.html"], Some("https://example.com/coverage/")).unwrap();
+    assert_eq!(found, vec![dead("index.html", "https://example.com/coverage/gone.html", Problem::MissingFile)]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn files_that_differ_from_the_manifest() {
+    let dir = report("manifest", &[("index.html", "<p>index</p>"), ("style.css", "p {}")]);
+    let mut manifest = Manifest {
+        files: vec!["index.html".into(), "style.css".into(), "functions.html".into()],
+        ..Manifest::default()
+    };
+
+    // Without hashes only missing files are found
+    assert_eq!(verify::changed_files(&dir, &manifest).unwrap(), vec![("functions.html".into(), "missing".into())]);
+
+    manifest.hashes.insert("index.html".into(), manifest::sha256("<p>index</p>"));
+    manifest.hashes.insert("style.css".into(), manifest::sha256("p { color: red }"));
+    assert_eq!(
+        verify::changed_files(&dir, &manifest).unwrap(),
+        vec![("style.css".into(), "changed since it was written".into()), ("functions.html".into(), "missing".into())]
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn every_page_in_the_manifest_is_checked() {
+    let dir = report("pages", &[("index.html", ""), ("treemap.html", ""), ("style.css", "")]);
+    let manifest = Manifest {
+        files: vec!["index.html".into(), "treemap.html".into(), "style.css".into(), "gone.html".into()],
+        ..Manifest::default()
+    };
+    std::fs::write(dir.join(manifest::FILENAME), serde_json::to_string(&manifest).unwrap()).unwrap();
+
+    let manifest = verify::manifest(&dir).unwrap();
+    assert_eq!(verify::pages(&dir, &manifest), vec!["index.html", "treemap.html"]);
+    assert!(verify::manifest(&dir.join("elsewhere")).is_err());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}